@@ -0,0 +1,7 @@
+-- File size in bytes. Together with fs_modified_ts, this is a cheap fingerprint
+-- for detecting files that have changed since the last scan.
+-- Nullable because files scanned before this migration don't have a recorded size.
+
+ALTER TABLE pictures ADD COLUMN fs_file_size_bytes INTEGER;
+
+ALTER TABLE videos ADD COLUMN fs_file_size_bytes INTEGER;
@@ -0,0 +1,259 @@
+-- Multiple library roots, such as a local directory, a NAS mount, and an external drive.
+-- Every picture and video belongs to a library root and its paths are relative to that root.
+-- Root paths are user settings, so only root names are stored here.
+-- Items in an unavailable root (unmounted or no longer configured) are hidden rather than deleted.
+--
+-- The same relative path can exist in more than one root, so the pictures and videos
+-- tables are rebuilt to make paths unique per root rather than globally.
+-- Note that foreign key enforcement is disabled while migrating so dropping the old
+-- tables doesn't cascade deletes to dependent tables.
+
+CREATE TABLE library_roots (
+        root_id      INTEGER PRIMARY KEY UNIQUE NOT NULL, -- unique ID for library root
+        name         TEXT UNIQUE NOT NULL, -- user visible name, such as 'NAS'
+        is_available BOOLEAN NOT NULL DEFAULT TRUE CHECK (is_available IN (0, 1)) -- root mounted and configured?
+);
+
+-- Root for the single pictures directory from before multiple roots were supported.
+INSERT INTO library_roots (root_id, name) VALUES (1, 'Pictures');
+
+DROP VIEW visual;
+DROP VIEW pictures_cleanup;
+DROP VIEW videos_cleanup;
+
+CREATE TABLE pictures_new (
+        picture_id         INTEGER PRIMARY KEY UNIQUE NOT NULL, -- unique ID for picture
+        root_id            INTEGER NOT NULL, -- library root holding picture
+        picture_path_b64   TEXT NOT NULL, -- path to picture relative to root (base64 encoded)
+        picture_path_lossy TEXT NOT NULL, --path to picture. Human readable for debugging.
+        thumbnail_path     TEXT UNIQUE, -- path to picture thumbnail. Not b64 as we only build UTF8 paths.
+        fs_created_ts      DATETIME, -- UTC timestamp of file system creation time
+        fs_modified_ts     DATETIME, -- UTC timestamp of file system modification time
+        fs_file_size_bytes INTEGER, -- file size in bytes
+        exif_created_ts    DATETIME, -- UTC timestamp for EXIF original creation date
+        exif_modified_ts   DATETIME, -- UTC timestamp for EXIF original modification date
+        is_selfie          BOOLEAN CHECK (is_selfie IN (0, 1)), -- front camera?
+        link_path_b64      TEXT NOT NULL, -- picture path minus suffix, for linking picture/photo siblings. Base64 encoded.
+        link_path_lossy    TEXT NOT NULL, -- link path. Human readable for debugging.
+        content_id         TEXT,
+        metadata_version   INTEGER NOT NULL DEFAULT 0, -- code version that scanned metadata
+        orientation        INTEGER, -- EXIF orientation (1..8)
+        is_broken          BOOLEAN CHECK (is_broken IN (0, 1)),
+        is_raw             BOOLEAN NOT NULL DEFAULT FALSE CHECK (is_raw IN (0, 1)), -- camera RAW file?
+
+        UNIQUE (root_id, picture_path_b64),
+        FOREIGN KEY (root_id) REFERENCES library_roots (root_id) ON DELETE CASCADE
+);
+
+INSERT INTO pictures_new (
+        picture_id, root_id, picture_path_b64, picture_path_lossy, thumbnail_path,
+        fs_created_ts, fs_modified_ts, fs_file_size_bytes, exif_created_ts, exif_modified_ts,
+        is_selfie, link_path_b64, link_path_lossy, content_id, metadata_version,
+        orientation, is_broken, is_raw
+)
+SELECT
+        picture_id, 1, picture_path_b64, picture_path_lossy, thumbnail_path,
+        fs_created_ts, fs_modified_ts, fs_file_size_bytes, exif_created_ts, exif_modified_ts,
+        is_selfie, link_path_b64, link_path_lossy, content_id, metadata_version,
+        orientation, is_broken, is_raw
+FROM pictures;
+
+DROP TABLE pictures;
+ALTER TABLE pictures_new RENAME TO pictures;
+
+CREATE INDEX pic_live_photo_idx ON pictures(root_id, link_path_b64, content_id);
+
+CREATE TABLE videos_new (
+        video_id           INTEGER PRIMARY KEY UNIQUE NOT NULL, -- unique ID for video
+        root_id            INTEGER NOT NULL, -- library root holding video
+        video_path_b64     TEXT NOT NULL, -- path to video relative to root (base64 encoded)
+        video_path_lossy   TEXT NOT NULL, -- human readable path to video for debugging
+        link_path_b64      TEXT NOT NULL, -- base64 encoded video path minus suffix for linking with sibling photos
+        link_path_lossy    TEXT NOT NULL, -- human readable link path for debugging
+        thumbnail_path     TEXT UNIQUE, -- path to thumbnail. Not b64 as we only build UTF8 paths.
+        fs_created_ts      DATETIME, -- UTC timestamp of file system creation time
+        fs_modified_ts     DATETIME, -- UTC timestamp of file system modification time
+        fs_file_size_bytes INTEGER, -- file size in bytes
+        stream_created_ts  DATETIME, -- UTC creation timestamp from video stream metadata
+        duration_millis    INTEGER, -- Duration in milliseconds of video
+        video_codec        TEXT, -- Video codec.
+        transcoded_path    TEXT, -- path to transcoded video. Not b64 as we only build UTF8 paths.
+        content_id         TEXT, -- iOS ID for linking with sibling photos
+        metadata_version   INTEGER NOT NULL DEFAULT 0, -- code version that scanned metadata
+        rotation           INTEGER, -- display matrix rotation in degrees
+        is_broken          BOOLEAN CHECK (is_broken IN (0, 1)),
+
+        UNIQUE (root_id, video_path_b64),
+        FOREIGN KEY (root_id) REFERENCES library_roots (root_id) ON DELETE CASCADE
+);
+
+INSERT INTO videos_new (
+        video_id, root_id, video_path_b64, video_path_lossy, link_path_b64, link_path_lossy,
+        thumbnail_path, fs_created_ts, fs_modified_ts, fs_file_size_bytes, stream_created_ts,
+        duration_millis, video_codec, transcoded_path, content_id, metadata_version,
+        rotation, is_broken
+)
+SELECT
+        video_id, 1, video_path_b64, video_path_lossy, link_path_b64, link_path_lossy,
+        thumbnail_path, fs_created_ts, fs_modified_ts, fs_file_size_bytes, stream_created_ts,
+        duration_millis, video_codec, transcoded_path, content_id, metadata_version,
+        rotation, is_broken
+FROM videos;
+
+DROP TABLE videos;
+ALTER TABLE videos_new RENAME TO videos;
+
+CREATE INDEX vid_live_photo_idx ON videos(root_id, link_path_b64, content_id);
+
+CREATE VIEW pictures_cleanup AS
+
+SELECT picture_id, 'cache' AS root_name, 'picture thumbnail' AS description, thumbnail_path AS path
+FROM pictures
+
+UNION
+
+SELECT picture_id, 'cache' AS root_name, 'motion photo video' AS description, video_path AS path
+FROM motion_photos
+WHERE video_path IS NOT NULL
+
+UNION
+
+SELECT picture_id, 'cache' AS root_name, 'motion photo transcoded video' AS description, transcoded_path AS path
+FROM motion_photos
+
+WHERE transcoded_path IS NOT NULL
+
+UNION
+
+SELECT picture_id, 'data' AS root_name, 'face bounds' AS description, bounds_path AS path FROM pictures_faces
+
+UNION
+
+SELECT picture_id, 'data' AS root_name, 'face thumbnail' AS description, thumbnail_path AS path FROM pictures_faces;
+
+CREATE VIEW videos_cleanup AS
+
+SELECT video_id, 'cache' AS root_name, 'video thumbnail' AS description, thumbnail_path AS path
+FROM videos
+
+UNION
+
+SELECT video_id, 'cache' AS root_name, 'video transcode' AS description, transcoded_path AS path
+FROM videos
+WHERE transcoded_path IS NOT NULL;
+
+CREATE VIEW visual AS
+SELECT
+  -- Unique ID
+  COALESCE(pictures.picture_id, 'x') || '_' || COALESCE(videos.video_id, 'x') AS visual_id,
+  COALESCE(pictures.root_id, videos.root_id) AS root_id,
+  COALESCE(pictures.link_path_b64, videos.link_path_b64) AS link_path_b64,
+
+  pictures.picture_id,
+  pictures.picture_path_b64,
+  pictures.picture_path_lossy, -- for debug only. Never read in Fotema.
+  pictures.orientation AS picture_orientation,
+
+-- If the thumbnail path is absent in the database, then compute the path we know it
+-- will have. Eventually the thumbnail generation background process will create the file
+-- and it will show up in the UI without having to refresh the data.
+  CASE pictures.picture_id
+        WHEN NOT NULL THEN pictures.thumbnail_path
+        ELSE 'photo_thumbnails/' || printf('%04d', pictures.picture_id / 1000) || '/' || CAST(pictures.picture_id AS TEXT) || '_200x200.png'
+  END AS picture_thumbnail,
+
+  pictures.is_selfie,
+
+  -- RAW sibling of a JPEG.
+  (SELECT raw_pictures.picture_id
+    FROM pictures AS raw_pictures
+    WHERE raw_pictures.is_raw IS TRUE
+    AND pictures.is_raw IS FALSE
+    AND raw_pictures.root_id = pictures.root_id
+    AND raw_pictures.link_path_b64 = pictures.link_path_b64
+    AND COALESCE(raw_pictures.is_broken, FALSE) IS FALSE
+    LIMIT 1
+  ) AS raw_picture_id,
+
+  (SELECT raw_pictures.picture_path_b64
+    FROM pictures AS raw_pictures
+    WHERE raw_pictures.is_raw IS TRUE
+    AND pictures.is_raw IS FALSE
+    AND raw_pictures.root_id = pictures.root_id
+    AND raw_pictures.link_path_b64 = pictures.link_path_b64
+    AND COALESCE(raw_pictures.is_broken, FALSE) IS FALSE
+    LIMIT 1
+  ) AS raw_picture_path_b64,
+
+  videos.video_id,
+  videos.video_path_b64,
+  videos.video_path_lossy, -- for debug only. Never read in Fotema.
+
+-- If the thumbnail path is absent in the database, then compute the path we know it
+-- will have. Eventually the thumbnail generation background process will create the file
+-- and it will show up in the UI without having to refresh the data.
+  CASE videos.video_id
+        WHEN NOT NULL THEN videos.thumbnail_path
+        ELSE 'video_thumbnails/' || printf('%04d', videos.video_id / 1000) || '/' || CAST(videos.video_id AS TEXT) || '_200x200.png'
+  END AS video_thumbnail,
+
+  COALESCE(videos.video_codec, motion_photos.video_codec) AS video_codec,
+
+  COALESCE(videos.video_codec, motion_photos.video_codec) IN ('hevc') AS is_transcode_required,
+
+  COALESCE(videos.transcoded_path, motion_photos.transcoded_path) AS video_transcoded_path,
+
+  COALESCE(videos.rotation, motion_photos.rotation) AS video_rotation,
+
+  -- An iOS live photo is a photo and a video linked with a content ID.
+  -- However, we only really need the video part, and short (<3 seconds)
+  -- videos are possibly live photos that have a missing or misnamed photo.
+  CASE
+        WHEN videos.content_id IS NOT NULL THEN true
+        WHEN videos.duration_millis <= 3000 THEN true
+        WHEN motion_photos.video_path IS NOT NULL THEN true
+        ELSE false
+  END AS is_live_photo,
+
+  COALESCE(videos.duration_millis, motion_photos.duration_millis) as duration_millis,
+
+  motion_photos.video_path AS motion_photo_video_path,
+
+  pictures_geo.longitude AS longitude,
+  pictures_geo.latitude AS latitude,
+
+  -- Timestamp to order visual items by.
+  -- Prefer embedded metadata over file system metadata.
+  COALESCE(
+    pictures.exif_created_ts,
+    videos.stream_created_ts,
+    pictures.exif_modified_ts,
+    pictures.fs_created_ts,
+    videos.fs_created_ts,
+    pictures.fs_modified_ts,
+    videos.fs_modified_ts,
+    CURRENT_TIMESTAMP
+  ) AS ordering_ts
+FROM
+  pictures
+  FULL OUTER JOIN videos USING (root_id, link_path_b64, content_id)
+  FULL OUTER JOIN motion_photos USING (picture_id)
+  FULL OUTER JOIN pictures_geo USING (picture_id)
+WHERE COALESCE(pictures.is_broken, FALSE) IS FALSE
+AND COALESCE(videos.is_broken, FALSE) IS FALSE
+-- Hide items in library roots that are unmounted or no longer configured.
+AND COALESCE(pictures.root_id, videos.root_id) IN (
+  SELECT root_id FROM library_roots WHERE is_available IS TRUE
+)
+-- Hide RAW files that are grouped with a JPEG sibling.
+AND NOT EXISTS (
+  SELECT 1
+  FROM pictures AS jpeg_pictures
+  WHERE pictures.is_raw IS TRUE
+  AND jpeg_pictures.is_raw IS FALSE
+  AND jpeg_pictures.root_id = pictures.root_id
+  AND jpeg_pictures.link_path_b64 = pictures.link_path_b64
+  AND COALESCE(jpeg_pictures.is_broken, FALSE) IS FALSE
+)
+ORDER BY
+  ordering_ts ASC;
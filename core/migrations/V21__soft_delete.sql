@@ -0,0 +1,132 @@
+-- Soft delete pictures and videos whose files are missing.
+-- Clean tasks mark missing items as deleted instead of removing them straight away,
+-- so that a file system glitch doesn't lose metadata, face assignments, or people.
+-- Deleted items are hidden and are purged once a grace period has passed.
+-- If a deleted file reappears, then the next scan restores it.
+
+ALTER TABLE pictures ADD COLUMN deleted_ts DATETIME; -- UTC timestamp of when file was found missing
+ALTER TABLE videos ADD COLUMN deleted_ts DATETIME; -- UTC timestamp of when file was found missing
+
+DROP VIEW visual;
+
+CREATE VIEW visual AS
+SELECT
+  -- Unique ID
+  COALESCE(pictures.picture_id, 'x') || '_' || COALESCE(videos.video_id, 'x') AS visual_id,
+  COALESCE(pictures.root_id, videos.root_id) AS root_id,
+  COALESCE(pictures.link_path_b64, videos.link_path_b64) AS link_path_b64,
+
+  pictures.picture_id,
+  pictures.picture_path_b64,
+  pictures.picture_path_lossy, -- for debug only. Never read in Fotema.
+  pictures.orientation AS picture_orientation,
+
+-- If the thumbnail path is absent in the database, then compute the path we know it
+-- will have. Eventually the thumbnail generation background process will create the file
+-- and it will show up in the UI without having to refresh the data.
+  CASE pictures.picture_id
+        WHEN NOT NULL THEN pictures.thumbnail_path
+        ELSE 'photo_thumbnails/' || printf('%04d', pictures.picture_id / 1000) || '/' || CAST(pictures.picture_id AS TEXT) || '_200x200.png'
+  END AS picture_thumbnail,
+
+  pictures.is_selfie,
+
+  -- RAW sibling of a JPEG.
+  (SELECT raw_pictures.picture_id
+    FROM pictures AS raw_pictures
+    WHERE raw_pictures.is_raw IS TRUE
+    AND pictures.is_raw IS FALSE
+    AND raw_pictures.root_id = pictures.root_id
+    AND raw_pictures.link_path_b64 = pictures.link_path_b64
+    AND COALESCE(raw_pictures.is_broken, FALSE) IS FALSE
+    AND raw_pictures.deleted_ts IS NULL
+    LIMIT 1
+  ) AS raw_picture_id,
+
+  (SELECT raw_pictures.picture_path_b64
+    FROM pictures AS raw_pictures
+    WHERE raw_pictures.is_raw IS TRUE
+    AND pictures.is_raw IS FALSE
+    AND raw_pictures.root_id = pictures.root_id
+    AND raw_pictures.link_path_b64 = pictures.link_path_b64
+    AND COALESCE(raw_pictures.is_broken, FALSE) IS FALSE
+    AND raw_pictures.deleted_ts IS NULL
+    LIMIT 1
+  ) AS raw_picture_path_b64,
+
+  videos.video_id,
+  videos.video_path_b64,
+  videos.video_path_lossy, -- for debug only. Never read in Fotema.
+
+-- If the thumbnail path is absent in the database, then compute the path we know it
+-- will have. Eventually the thumbnail generation background process will create the file
+-- and it will show up in the UI without having to refresh the data.
+  CASE videos.video_id
+        WHEN NOT NULL THEN videos.thumbnail_path
+        ELSE 'video_thumbnails/' || printf('%04d', videos.video_id / 1000) || '/' || CAST(videos.video_id AS TEXT) || '_200x200.png'
+  END AS video_thumbnail,
+
+  COALESCE(videos.video_codec, motion_photos.video_codec) AS video_codec,
+
+  COALESCE(videos.video_codec, motion_photos.video_codec) IN ('hevc') AS is_transcode_required,
+
+  COALESCE(videos.transcoded_path, motion_photos.transcoded_path) AS video_transcoded_path,
+
+  COALESCE(videos.rotation, motion_photos.rotation) AS video_rotation,
+
+  -- An iOS live photo is a photo and a video linked with a content ID.
+  -- However, we only really need the video part, and short (<3 seconds)
+  -- videos are possibly live photos that have a missing or misnamed photo.
+  CASE
+        WHEN videos.content_id IS NOT NULL THEN true
+        WHEN videos.duration_millis <= 3000 THEN true
+        WHEN motion_photos.video_path IS NOT NULL THEN true
+        ELSE false
+  END AS is_live_photo,
+
+  COALESCE(videos.duration_millis, motion_photos.duration_millis) as duration_millis,
+
+  motion_photos.video_path AS motion_photo_video_path,
+
+  pictures_geo.longitude AS longitude,
+  pictures_geo.latitude AS latitude,
+
+  -- Timestamp to order visual items by.
+  -- Prefer embedded metadata over file system metadata.
+  COALESCE(
+    pictures.exif_created_ts,
+    videos.stream_created_ts,
+    pictures.exif_modified_ts,
+    pictures.fs_created_ts,
+    videos.fs_created_ts,
+    pictures.fs_modified_ts,
+    videos.fs_modified_ts,
+    CURRENT_TIMESTAMP
+  ) AS ordering_ts
+FROM
+  pictures
+  FULL OUTER JOIN videos USING (root_id, link_path_b64, content_id)
+  FULL OUTER JOIN motion_photos USING (picture_id)
+  FULL OUTER JOIN pictures_geo USING (picture_id)
+WHERE COALESCE(pictures.is_broken, FALSE) IS FALSE
+AND COALESCE(videos.is_broken, FALSE) IS FALSE
+-- Hide items whose files are missing and are waiting to be purged.
+AND pictures.deleted_ts IS NULL
+AND videos.deleted_ts IS NULL
+-- Hide items in library roots that are unmounted or no longer configured.
+AND COALESCE(pictures.root_id, videos.root_id) IN (
+  SELECT root_id FROM library_roots WHERE is_available IS TRUE
+)
+-- Hide RAW files that are grouped with a JPEG sibling.
+AND NOT EXISTS (
+  SELECT 1
+  FROM pictures AS jpeg_pictures
+  WHERE pictures.is_raw IS TRUE
+  AND jpeg_pictures.is_raw IS FALSE
+  AND jpeg_pictures.root_id = pictures.root_id
+  AND jpeg_pictures.link_path_b64 = pictures.link_path_b64
+  AND COALESCE(jpeg_pictures.is_broken, FALSE) IS FALSE
+  AND jpeg_pictures.deleted_ts IS NULL
+)
+ORDER BY
+  ordering_ts ASC;
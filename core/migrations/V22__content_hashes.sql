@@ -0,0 +1,17 @@
+-- Hashes of file content for finding duplicate pictures and videos,
+-- such as the same photo copied into a backup folder.
+CREATE TABLE pictures_hashes (
+        picture_id     INTEGER PRIMARY KEY UNIQUE NOT NULL, -- unique ID for picture
+        content_hash   TEXT NOT NULL, -- hex encoded hash of file content
+        FOREIGN KEY (picture_id) REFERENCES pictures (picture_id) ON DELETE CASCADE
+);
+
+CREATE INDEX pictures_hashes_content_hash_idx ON pictures_hashes (content_hash);
+
+CREATE TABLE videos_hashes (
+        video_id       INTEGER PRIMARY KEY UNIQUE NOT NULL, -- unique ID for video
+        content_hash   TEXT NOT NULL, -- hex encoded hash of file content
+        FOREIGN KEY (video_id) REFERENCES videos (video_id) ON DELETE CASCADE
+);
+
+CREATE INDEX videos_hashes_content_hash_idx ON videos_hashes (content_hash);
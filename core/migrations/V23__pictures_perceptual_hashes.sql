@@ -0,0 +1,7 @@
+-- Perceptual hashes of picture thumbnails for grouping bursts and lightly
+-- edited copies into stacks.
+CREATE TABLE pictures_perceptual_hashes (
+        picture_id   INTEGER PRIMARY KEY UNIQUE NOT NULL, -- unique ID for picture
+        dhash        INTEGER NOT NULL, -- 64-bit difference hash of thumbnail
+        FOREIGN KEY (picture_id) REFERENCES pictures (picture_id) ON DELETE CASCADE
+);
@@ -0,0 +1,52 @@
+-- Metadata read from XMP sidecars and XMP embedded in pictures and videos,
+-- such as written by darktable, digiKam, or Lightroom.
+CREATE TABLE pictures_xmp (
+        picture_id    INTEGER PRIMARY KEY UNIQUE NOT NULL, -- unique ID for picture
+        rating        INTEGER, -- xmp:Rating. -1 for rejected, 0 for unrated, 1 to 5 stars
+        title         TEXT, -- dc:title
+        description   TEXT, -- dc:description
+        FOREIGN KEY (picture_id) REFERENCES pictures (picture_id) ON DELETE CASCADE
+);
+
+CREATE TABLE videos_xmp (
+        video_id      INTEGER PRIMARY KEY UNIQUE NOT NULL, -- unique ID for video
+        rating        INTEGER, -- xmp:Rating. -1 for rejected, 0 for unrated, 1 to 5 stars
+        title         TEXT, -- dc:title
+        description   TEXT, -- dc:description
+        FOREIGN KEY (video_id) REFERENCES videos (video_id) ON DELETE CASCADE
+);
+
+-- dc:subject keywords
+CREATE TABLE pictures_keywords (
+        picture_id    INTEGER NOT NULL, -- unique ID for picture
+        keyword       TEXT NOT NULL,
+        PRIMARY KEY (picture_id, keyword),
+        FOREIGN KEY (picture_id) REFERENCES pictures (picture_id) ON DELETE CASCADE
+);
+
+CREATE INDEX pictures_keywords_keyword_idx ON pictures_keywords (keyword);
+
+CREATE TABLE videos_keywords (
+        video_id      INTEGER NOT NULL, -- unique ID for video
+        keyword       TEXT NOT NULL,
+        PRIMARY KEY (video_id, keyword),
+        FOREIGN KEY (video_id) REFERENCES videos (video_id) ON DELETE CASCADE
+);
+
+CREATE INDEX videos_keywords_keyword_idx ON videos_keywords (keyword);
+
+-- MWG regions, such as faces named in another tool.
+-- Coordinates are normalized and x and y are the centre of the region.
+CREATE TABLE pictures_xmp_regions (
+        region_id     INTEGER PRIMARY KEY UNIQUE NOT NULL, -- unique ID for region
+        picture_id    INTEGER NOT NULL, -- unique ID for picture
+        name          TEXT, -- mwg-rs:Name, such as a person's name
+        region_type   TEXT, -- mwg-rs:Type, such as 'Face' or 'Pet'
+        x             REAL NOT NULL,
+        y             REAL NOT NULL,
+        width         REAL NOT NULL,
+        height        REAL NOT NULL,
+        FOREIGN KEY (picture_id) REFERENCES pictures (picture_id) ON DELETE CASCADE
+);
+
+CREATE INDEX pictures_xmp_regions_picture_id_idx ON pictures_xmp_regions (picture_id);
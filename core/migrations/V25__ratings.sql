@@ -0,0 +1,15 @@
+-- Star ratings and favourites set in Fotema.
+-- A rating set here takes precedence over a rating read from XMP.
+CREATE TABLE pictures_ratings (
+        picture_id    INTEGER PRIMARY KEY UNIQUE NOT NULL, -- unique ID for picture
+        rating        INTEGER CHECK (rating BETWEEN 0 AND 5), -- 0 for unrated, 1 to 5 stars
+        is_favourite  BOOLEAN NOT NULL DEFAULT FALSE,
+        FOREIGN KEY (picture_id) REFERENCES pictures (picture_id) ON DELETE CASCADE
+);
+
+CREATE TABLE videos_ratings (
+        video_id      INTEGER PRIMARY KEY UNIQUE NOT NULL, -- unique ID for video
+        rating        INTEGER CHECK (rating BETWEEN 0 AND 5), -- 0 for unrated, 1 to 5 stars
+        is_favourite  BOOLEAN NOT NULL DEFAULT FALSE,
+        FOREIGN KEY (video_id) REFERENCES videos (video_id) ON DELETE CASCADE
+);
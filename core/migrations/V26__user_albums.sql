@@ -0,0 +1,27 @@
+-- Albums created by the user, as opposed to albums derived from metadata.
+CREATE TABLE user_albums (
+        album_id      INTEGER PRIMARY KEY UNIQUE NOT NULL, -- unique ID for album
+        name          TEXT NOT NULL,
+        created_ts    DATETIME NOT NULL,
+        cover_item_id INTEGER, -- item to show as album cover. First item if null.
+        FOREIGN KEY (cover_item_id) REFERENCES user_album_items (item_id) ON DELETE SET NULL
+);
+
+-- Pictures and videos in user albums.
+-- Both the picture and the video of a live photo are recorded, so that the item
+-- stays in the album if the live photo is later paired, or unpaired, with its video.
+CREATE TABLE user_album_items (
+        item_id       INTEGER PRIMARY KEY UNIQUE NOT NULL, -- unique ID for album item
+        album_id      INTEGER NOT NULL,
+        position      INTEGER NOT NULL, -- manual sort order, ascending
+        picture_id    INTEGER,
+        video_id      INTEGER,
+        FOREIGN KEY (album_id) REFERENCES user_albums (album_id) ON DELETE CASCADE,
+        FOREIGN KEY (picture_id) REFERENCES pictures (picture_id) ON DELETE CASCADE,
+        FOREIGN KEY (video_id) REFERENCES videos (video_id) ON DELETE CASCADE,
+        CHECK (picture_id IS NOT NULL OR video_id IS NOT NULL)
+);
+
+-- Nulls are distinct, so a video without a picture doesn't clash with another.
+CREATE UNIQUE INDEX user_album_items_picture_idx ON user_album_items (album_id, picture_id);
+CREATE UNIQUE INDEX user_album_items_video_idx ON user_album_items (album_id, video_id);
@@ -0,0 +1,8 @@
+-- Albums defined by a saved query, rather than by membership.
+-- Queries are re-evaluated whenever the library is loaded.
+CREATE TABLE smart_albums (
+        smart_album_id INTEGER PRIMARY KEY UNIQUE NOT NULL, -- unique ID for smart album
+        name           TEXT NOT NULL,
+        query          TEXT NOT NULL, -- query expression. See smart_album::Query.
+        created_ts     DATETIME NOT NULL
+);
@@ -0,0 +1,14 @@
+-- Full-text search index over pictures and videos.
+-- File paths are stored base64 encoded, so the index is rebuilt by search::Repository
+-- whenever the library is loaded rather than by triggers.
+CREATE VIRTUAL TABLE visual_search USING fts5(
+        visual_id UNINDEXED, -- visual ID of picture or video
+        file_name,   -- file names of picture and video
+        folder,      -- name of containing folder
+        people,      -- names of recognized people and named XMP regions
+        tags,        -- XMP keywords
+        title,       -- XMP title
+        description, -- XMP description
+        places,      -- names of places where picture or video was captured
+        tokenize = 'unicode61 remove_diacritics 2'
+);
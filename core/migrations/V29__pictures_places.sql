@@ -0,0 +1,25 @@
+-- Place names found by reverse geocoding the GPS location of a picture
+CREATE TABLE pictures_places (
+        picture_id         INTEGER PRIMARY KEY UNIQUE NOT NULL, -- unique ID for picture
+        country            TEXT, -- country name. NULL if no city is near the location.
+        region             TEXT, -- first-level administrative division, such as a state
+        city               TEXT, -- nearest city or town
+        FOREIGN KEY (picture_id) REFERENCES pictures (picture_id) ON DELETE CASCADE
+);
+
+-- Places must be found again when GPS locations change.
+CREATE TRIGGER pictures_geo_insert_places AFTER INSERT ON pictures_geo
+BEGIN
+  DELETE FROM pictures_places WHERE picture_id = NEW.picture_id;
+END;
+
+CREATE TRIGGER pictures_geo_update_places AFTER UPDATE ON pictures_geo
+WHEN OLD.latitude IS NOT NEW.latitude OR OLD.longitude IS NOT NEW.longitude
+BEGIN
+  DELETE FROM pictures_places WHERE picture_id = NEW.picture_id;
+END;
+
+CREATE TRIGGER pictures_geo_delete_places AFTER DELETE ON pictures_geo
+BEGIN
+  DELETE FROM pictures_places WHERE picture_id = OLD.picture_id;
+END;
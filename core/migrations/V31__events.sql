@@ -0,0 +1,25 @@
+-- Pictures and videos taken close together in time and place, such as trips and parties.
+CREATE TABLE events (
+        event_id      INTEGER PRIMARY KEY UNIQUE NOT NULL, -- unique ID for event
+        name          TEXT, -- chosen by the user, or the city most items were taken in
+        created_ts    DATETIME NOT NULL
+);
+
+-- Pictures and videos in events.
+-- Both the picture and the video of a live photo are recorded, so that the item
+-- stays in the event if the live photo is later paired, or unpaired, with its video.
+CREATE TABLE event_items (
+        item_id       INTEGER PRIMARY KEY UNIQUE NOT NULL, -- unique ID for event item
+        event_id      INTEGER NOT NULL,
+        picture_id    INTEGER,
+        video_id      INTEGER,
+        FOREIGN KEY (event_id) REFERENCES events (event_id) ON DELETE CASCADE,
+        FOREIGN KEY (picture_id) REFERENCES pictures (picture_id) ON DELETE CASCADE,
+        FOREIGN KEY (video_id) REFERENCES videos (video_id) ON DELETE CASCADE,
+        CHECK (picture_id IS NOT NULL OR video_id IS NOT NULL)
+);
+
+-- A picture or video is in at most one event.
+-- Nulls are distinct, so a video without a picture doesn't clash with another.
+CREATE UNIQUE INDEX event_items_picture_idx ON event_items (picture_id);
+CREATE UNIQUE INDEX event_items_video_idx ON event_items (video_id);
@@ -0,0 +1,5 @@
+-- Locations set by the user, such as by picking a point on the map,
+-- rather than read from EXIF or QuickTime metadata.
+-- Rescanning metadata never overwrites a location set by the user.
+ALTER TABLE pictures_geo ADD COLUMN is_user_set BOOLEAN NOT NULL DEFAULT FALSE CHECK (is_user_set IN (0, 1));
+ALTER TABLE videos_geo ADD COLUMN is_user_set BOOLEAN NOT NULL DEFAULT FALSE CHECK (is_user_set IN (0, 1));
@@ -0,0 +1,6 @@
+-- Groups of unknown faces that likely belong to the same unnamed person.
+-- Recomputed by each clustering pass, so a cluster is only ever a suggestion.
+-- A cluster is identified by the lowest face ID in it.
+ALTER TABLE pictures_faces ADD COLUMN cluster_id INTEGER;
+
+CREATE INDEX pictures_faces_cluster_idx ON pictures_faces (cluster_id);
@@ -0,0 +1,5 @@
+-- SFace feature vector of each face, stored as little-endian 32-bit floats.
+-- Computed once after a face is detected, so that recognizing and clustering faces
+-- doesn't need to run the face recognition model again.
+-- NULL until computed, or if it couldn't be computed.
+ALTER TABLE pictures_faces ADD COLUMN embedding BLOB;
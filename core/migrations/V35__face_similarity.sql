@@ -0,0 +1,4 @@
+-- Cosine similarity (-1.0 to 1.0) between a face matched by face recognition and the
+-- nearest confirmed face of the person it was matched with.
+-- Used to rank unconfirmed faces for review. NULL for faces matched before this was recorded.
+ALTER TABLE pictures_faces ADD COLUMN similarity DECIMAL;
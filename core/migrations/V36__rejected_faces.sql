@@ -0,0 +1,9 @@
+-- Faces the user has said are not a person, so face recognition never
+-- suggests them for that person again.
+CREATE TABLE people_rejected_faces (
+        person_id     INTEGER NOT NULL, -- person the face was wrongly matched with
+        face_id       INTEGER NOT NULL, -- face that isn't the person
+        PRIMARY KEY (person_id, face_id),
+        FOREIGN KEY (person_id) REFERENCES people (person_id) ON DELETE CASCADE,
+        FOREIGN KEY (face_id) REFERENCES pictures_faces (face_id) ON DELETE CASCADE
+);
@@ -0,0 +1,5 @@
+-- Last known path of each library root, so that a root can be found by its directory
+-- as well as by its name. Root paths are still user settings, so these are recorded
+-- whenever roots are synced with the settings, and are null until then.
+ALTER TABLE library_roots ADD COLUMN root_path_b64 TEXT; -- path to root (base64 encoded)
+ALTER TABLE library_roots ADD COLUMN root_path_lossy TEXT; -- path to root. Human readable for debugging.
@@ -0,0 +1,735 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Headless command-line front end to the Fotema core library.
+//! Runs the same processing pipeline as the desktop app, but without needing a display.
+
+use anyhow::*;
+use chrono::TimeDelta;
+use futures::executor::block_on;
+use gio::glib;
+use rayon::prelude::*;
+
+use fotema_core::database;
+use fotema_core::library;
+use fotema_core::machine_learning::face_embedder::FaceEmbedder;
+use fotema_core::machine_learning::face_extractor::FaceExtractor;
+use fotema_core::machine_learning::face_recognizer::FaceRecognizer;
+use fotema_core::people;
+use fotema_core::photo;
+use fotema_core::photo::gpx;
+use fotema_core::scan;
+use fotema_core::scan::MediaKind;
+use fotema_core::video;
+use fotema_core::visual;
+
+use std::env;
+use std::panic;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::result::Result::Ok;
+use std::sync::{Arc, Mutex};
+
+use tracing::{error, info, warn};
+use tracing_subscriber::filter::{EnvFilter, LevelFilter};
+
+/// Same application ID as the desktop app so both share a database and cache.
+const APP_ID: &str = "app.fotema.Fotema";
+
+const USAGE: &str = "Usage: fotema-cli [OPTIONS] <COMMAND>
+
+Commands:
+  scan       Scan library for new and changed photos and videos
+  enrich     Extract photo and video metadata
+  thumbnail  Generate photo and video thumbnails
+  motion     Extract videos from motion photos
+  faces      Detect faces and recognize people
+  clean      Hide deleted files and purge those deleted over 30 days ago
+  list       Print all photos and videos in library
+  shift-time <FOLDER> <SHIFT>
+             Shift capture time of photos in a folder, such as +1h or -1d2h30m
+  geotag <GPX>...
+             Locate photos without GPS by matching capture times to GPX tracks
+  all        Run scan, enrich, thumbnail, clean, motion, and faces in order
+
+Options:
+  --library <DIR>    Pictures library directory (required). Repeat for each library root
+  --data-dir <DIR>   Data directory holding the database [default: $XDG_DATA_HOME/app.fotema.Fotema]
+  --cache-dir <DIR>  Cache directory for thumbnails [default: $XDG_CACHE_HOME/app.fotema.Fotema]
+  --yes              Clean even if a large share of the library is missing
+  --write-to <MODE>  Write changed metadata to 'Sidecar' or 'File' [default: Sidecar]
+  --clock-offset <SHIFT>
+                     Added to camera capture times to match GPX times, such as -2h [default: 0s]
+  --tolerance <SHIFT>
+                     Furthest time from a GPX track point to geotag a photo [default: 5m]
+  --write            Write geotags to photos as well as the database, as set by --write-to
+  -h, --help         Print help";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Scan,
+    Enrich,
+    Thumbnail,
+    Motion,
+    Faces,
+    Clean,
+    List,
+    ShiftTime,
+    Geotag,
+    All,
+}
+
+impl Command {
+    fn parse(name: &str) -> Option<Command> {
+        match name {
+            "scan" => Some(Command::Scan),
+            "enrich" => Some(Command::Enrich),
+            "thumbnail" => Some(Command::Thumbnail),
+            "motion" => Some(Command::Motion),
+            "faces" => Some(Command::Faces),
+            "clean" => Some(Command::Clean),
+            "list" => Some(Command::List),
+            "shift-time" => Some(Command::ShiftTime),
+            "geotag" => Some(Command::Geotag),
+            "all" => Some(Command::All),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Args {
+    command: Command,
+    library_dirs: Vec<PathBuf>,
+    data_dir: PathBuf,
+    cache_dir: PathBuf,
+    is_confirmed: bool,
+    write_target: photo::WriteTarget,
+    clock_offset: TimeDelta,
+    tolerance: TimeDelta,
+    is_write_back: bool,
+    operands: Vec<String>,
+}
+
+impl Args {
+    fn parse(args: impl Iterator<Item = String>) -> Result<Args> {
+        let mut command = None;
+        let mut library_dirs = Vec::new();
+        let mut data_dir = None;
+        let mut cache_dir = None;
+        let mut is_confirmed = false;
+        let mut write_target = photo::WriteTarget::default();
+        let mut clock_offset = TimeDelta::zero();
+        let mut tolerance = TimeDelta::minutes(5);
+        let mut is_write_back = false;
+        let mut operands = Vec::new();
+
+        let mut args = args;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--library" => library_dirs.extend(args.next().map(PathBuf::from)),
+                "--data-dir" => data_dir = args.next().map(PathBuf::from),
+                "--cache-dir" => cache_dir = args.next().map(PathBuf::from),
+                "--yes" => is_confirmed = true,
+                "--write-to" => {
+                    let mode = args.next().unwrap_or_default();
+                    write_target = mode
+                        .parse()
+                        .map_err(|_| anyhow!("Unknown write mode: {}", mode))?;
+                }
+                "--clock-offset" => {
+                    clock_offset = photo::edit::parse_time_shift(&args.next().unwrap_or_default())?;
+                }
+                "--tolerance" => {
+                    tolerance = photo::edit::parse_time_shift(&args.next().unwrap_or_default())?;
+                    if tolerance < TimeDelta::zero() {
+                        bail!("--tolerance must not be negative");
+                    }
+                }
+                "--write" => is_write_back = true,
+                name => {
+                    if command.is_some() {
+                        operands.push(name.to_string());
+                        continue;
+                    }
+                    command = Some(
+                        Command::parse(name).ok_or_else(|| anyhow!("Unknown command: {}", name))?,
+                    );
+                }
+            }
+        }
+
+        let command = command.ok_or_else(|| anyhow!("No command given"))?;
+        match command {
+            Command::ShiftTime if operands.len() != 2 => {
+                bail!("shift-time needs a folder and a time shift")
+            }
+            Command::ShiftTime => {}
+            Command::Geotag if operands.is_empty() => {
+                bail!("geotag needs at least one GPX file")
+            }
+            Command::Geotag => {}
+            _ if !operands.is_empty() => bail!("Unexpected argument: {}", operands[0]),
+            _ => {}
+        }
+        if library_dirs.is_empty() {
+            bail!("--library is required");
+        }
+        let data_dir = data_dir.unwrap_or_else(|| glib::user_data_dir().join(APP_ID));
+        let cache_dir = cache_dir.unwrap_or_else(|| glib::user_cache_dir().join(APP_ID));
+
+        Ok(Args {
+            command,
+            library_dirs,
+            data_dir,
+            cache_dir,
+            is_confirmed,
+            write_target,
+            clock_offset,
+            tolerance,
+            is_write_back,
+            operands,
+        })
+    }
+}
+
+/// Repositories and processors shared by all commands.
+struct Cli {
+    roots: library::LibraryRoots,
+    data_dir: PathBuf,
+    cache_dir: PathBuf,
+    is_confirmed: bool,
+    write_target: photo::WriteTarget,
+    clock_offset: TimeDelta,
+    tolerance: TimeDelta,
+    is_write_back: bool,
+    operands: Vec<String>,
+    photo_repo: photo::Repository,
+    video_repo: video::Repository,
+    visual_repo: visual::Repository,
+    people_repo: people::Repository,
+}
+
+impl Cli {
+    fn open(args: &Args) -> Result<Cli> {
+        let library_dirs = &args.library_dirs;
+        let data_dir = &args.data_dir;
+        let cache_dir = &args.cache_dir;
+
+        std::fs::create_dir_all(data_dir)?;
+        std::fs::create_dir_all(cache_dir)?;
+
+        let db_path = data_dir.join("pictures.sqlite");
+        let con = database::setup(&db_path)?;
+        let con = Arc::new(Mutex::new(con));
+
+        // Roots are found by directory, so the same directory is the same root whether
+        // scanned by the CLI or the desktop app, and roots only configured in the
+        // desktop app stay available.
+        let roots = library::Repository::open(con.clone())?.find_by_paths(library_dirs)?;
+
+        let photo_repo = photo::Repository::open(&roots, cache_dir, data_dir, con.clone())?;
+        let video_repo = video::Repository::open(&roots, cache_dir, data_dir, con.clone())?;
+        let visual_repo = visual::Repository::open(&roots, cache_dir, con.clone())?;
+        let people_repo = people::Repository::open(data_dir, con.clone())?;
+
+        Ok(Cli {
+            roots,
+            data_dir: PathBuf::from(data_dir),
+            cache_dir: PathBuf::from(cache_dir),
+            is_confirmed: args.is_confirmed,
+            write_target: args.write_target,
+            clock_offset: args.clock_offset,
+            tolerance: args.tolerance,
+            is_write_back: args.is_write_back,
+            operands: args.operands.clone(),
+            photo_repo,
+            video_repo,
+            visual_repo,
+            people_repo,
+        })
+    }
+
+    fn run(&mut self, command: Command) -> Result<()> {
+        match command {
+            Command::Scan => self.scan(),
+            Command::Enrich => self.enrich(),
+            Command::Thumbnail => self.thumbnail(),
+            Command::Motion => self.motion(),
+            Command::Faces => self.faces(),
+            Command::Clean => self.clean(),
+            Command::List => self.list(),
+            Command::ShiftTime => self.shift_time(),
+            Command::Geotag => self.geotag(),
+            Command::All => {
+                // Same order as the desktop app's bootstrap process.
+                self.scan()?;
+                self.enrich()?;
+                self.thumbnail()?;
+                self.clean()?;
+                self.motion()?;
+                self.faces()
+            }
+        }
+    }
+
+    fn scan(&mut self) -> Result<()> {
+        for root in self.roots.iter() {
+            if !root.is_available() {
+                warn!(
+                    "Skipping unavailable library root {}: {:?}",
+                    root.name, root.path
+                );
+                continue;
+            }
+
+            // Walk the library root once for both photos and videos.
+            let scanner = scan::Scanner::build(&root.path)?;
+            let mut files = scanner.scan_all()?;
+
+            let pics = files.remove(&MediaKind::Photo).unwrap_or_default();
+            info!("Found {} photos to add to database", pics.len());
+            let pic_summary = self.photo_repo.add_all(root, &pics)?;
+
+            let vids = files.remove(&MediaKind::Video).unwrap_or_default();
+            info!("Found {} videos to add to database", vids.len());
+            let vid_summary = self.video_repo.add_all(root, &vids)?;
+
+            println!("{} photos: {}", root.name, pic_summary);
+            println!("{} videos: {}", root.name, vid_summary);
+        }
+        Ok(())
+    }
+
+    fn enrich(&mut self) -> Result<()> {
+        let unprocessed = self.photo_repo.find_need_metadata_update()?;
+        let photo_count = unprocessed.len();
+        let metadatas = unprocessed
+            .par_iter()
+            .flat_map(|pic| photo::metadata::from_path(&pic.path).map(|m| (pic.picture_id, m)))
+            .collect();
+        self.photo_repo.add_metadatas(metadatas)?;
+
+        let unprocessed = self.video_repo.find_need_metadata_update()?;
+        let video_count = unprocessed.len();
+        let metadatas = unprocessed
+            .par_iter()
+            .flat_map(|vid| video::metadata::from_path(&vid.path).map(|m| (vid.video_id, m)))
+            .collect();
+        self.video_repo.add_metadata(metadatas)?;
+
+        println!(
+            "Enriched {} photos and {} videos.",
+            photo_count, video_count
+        );
+        Ok(())
+    }
+
+    fn thumbnail(&mut self) -> Result<()> {
+        let thumbnailer = photo::Thumbnailer::build(&self.cache_dir)?;
+
+        let mut pics: Vec<photo::model::Picture> = self
+            .photo_repo
+            .all()?
+            .into_iter()
+            .filter(|pic| pic.path.exists())
+            .filter(|pic| !pic.thumbnail_path.as_ref().is_some_and(|p| p.exists()))
+            .collect();
+
+        // should be ascending time order from database, so reverse to process newest items first
+        pics.reverse();
+
+        pics.par_iter().for_each(|pic| {
+            // Careful! panic::catch_unwind returns Ok(Err) if the evaluated expression returns
+            // an error but doesn't panic.
+            let result = panic::catch_unwind(|| {
+                block_on(async { thumbnailer.thumbnail(&pic.picture_id, &pic.path).await })
+                    .and_then(|thumbnail_path| {
+                        self.photo_repo
+                            .clone()
+                            .add_thumbnail(&pic.picture_id, &thumbnail_path)
+                    })
+            });
+
+            if !matches!(result, Ok(Ok(_))) {
+                error!(
+                    "Failed generate or add thumbnail: Photo path: {:?}",
+                    pic.path
+                );
+                let _ = self.photo_repo.clone().mark_broken(&pic.picture_id);
+            }
+        });
+
+        let thumbnailer = video::Thumbnailer::build(&self.cache_dir)?;
+
+        let mut vids: Vec<video::Video> = self
+            .video_repo
+            .all()?
+            .into_iter()
+            .filter(|vid| vid.path.exists())
+            .filter(|vid| !vid.thumbnail_path.as_ref().is_some_and(|p| p.exists()))
+            .collect();
+
+        vids.reverse();
+
+        vids.par_iter().for_each(|vid| {
+            let result =
+                thumbnailer
+                    .thumbnail(&vid.video_id, &vid.path)
+                    .and_then(|thumbnail_path| {
+                        self.video_repo
+                            .clone()
+                            .add_thumbnail(&vid.video_id, &thumbnail_path)
+                    });
+
+            if let Err(e) = result {
+                error!(
+                    "Failed generate or add thumbnail: {:?}: Video path: {:?}",
+                    e, vid.path
+                );
+                let _ = self.video_repo.clone().mark_broken(&vid.video_id);
+            }
+        });
+
+        println!(
+            "Generated {} photo and {} video thumbnails.",
+            pics.len(),
+            vids.len()
+        );
+        Ok(())
+    }
+
+    fn motion(&mut self) -> Result<()> {
+        let extractor = photo::MotionPhotoExtractor::build(&self.cache_dir)?;
+
+        let pics: Vec<photo::model::Picture> = self
+            .photo_repo
+            .find_need_motion_photo_extract()?
+            .into_iter()
+            .filter(|pic| pic.path.exists())
+            .collect();
+
+        pics.par_iter().for_each(|pic| {
+            let mut repo = self.photo_repo.clone();
+            let result = match extractor.extract(&pic.picture_id, &pic.path) {
+                Ok(opt_video) => repo.add_motion_photo_video(&pic.picture_id, opt_video),
+                Err(e) => {
+                    error!(
+                        "Failed extracting motion photo: {:?}: Photo path: {:?}",
+                        e, pic.path
+                    );
+                    repo.mark_broken(&pic.picture_id)
+                }
+            };
+
+            if let Err(e) = result {
+                error!(
+                    "Failed updating database: {:?}: Photo path: {:?}",
+                    e, pic.path
+                );
+            }
+        });
+
+        println!("Processed {} motion photos.", pics.len());
+        Ok(())
+    }
+
+    fn faces(&mut self) -> Result<()> {
+        let unprocessed: Vec<(photo::PictureId, PathBuf)> = self
+            .photo_repo
+            .find_need_face_scan()?
+            .into_iter()
+            .filter(|(_, path)| path.exists())
+            .collect();
+
+        if !unprocessed.is_empty() {
+            let extractor = FaceExtractor::build(&self.data_dir)?;
+
+            unprocessed.par_iter().for_each(|(picture_id, path)| {
+                let mut repo = self.people_repo.clone();
+                let result = block_on(async { extractor.extract_faces(picture_id, path).await })
+                    .and_then(|faces| repo.add_face_scans(picture_id, &faces));
+
+                if let Err(e) = result {
+                    error!(
+                        "Failed detecting faces: Photo path: {:?}. Error: {:?}",
+                        path, e
+                    );
+                    let _ = repo.mark_face_scan_broken(picture_id);
+                }
+            });
+        }
+
+        println!("Detected faces in {} photos.", unprocessed.len());
+
+        let need_embedding = self.people_repo.find_need_embedding()?;
+
+        if !need_embedding.is_empty() {
+            let embedder = FaceEmbedder::build(&self.cache_dir)?;
+
+            need_embedding.par_iter().for_each(|face| {
+                let result = embedder.embed(face).and_then(|face_embedding| {
+                    self.people_repo
+                        .clone()
+                        .add_embedding(face.face_id, &face_embedding)
+                });
+                if let Err(e) = result {
+                    error!(
+                        "Failed computing embedding of face {}: {:?}",
+                        face.face_id, e
+                    );
+                }
+            });
+        }
+
+        println!("Computed embeddings of {} faces.", need_embedding.len());
+
+        let people = self.people_repo.find_people_for_recognition()?;
+        let Some(min_recognized_at) = people.iter().map(|x| x.recognized_at).min() else {
+            return Ok(());
+        };
+
+        let unknown_faces: Vec<people::model::DetectedFace> = self
+            .people_repo
+            .find_unknown_faces()?
+            .into_iter()
+            .filter(|face| face.detected_at > min_recognized_at)
+            .collect();
+
+        if !unknown_faces.is_empty() {
+            let recognizer = FaceRecognizer::build(people.clone());
+
+            unknown_faces.into_par_iter().for_each(|face| {
+                if let Some((person_id, similarity)) = recognizer.recognize(&face) {
+                    info!(
+                        "Face {} looks like person {} with similarity {}",
+                        face.face_id, person_id, similarity
+                    );
+                    let result = self.people_repo.clone().mark_as_person_unconfirmed(
+                        face.face_id,
+                        person_id,
+                        similarity,
+                    );
+                    if let Err(e) = result {
+                        error!("Failed marking face {} as person: {:?}", face.face_id, e);
+                    }
+                }
+            });
+        }
+
+        for person in people {
+            self.people_repo
+                .mark_face_recognition_complete(person.person_id)?;
+        }
+
+        Ok(())
+    }
+
+    fn clean(&mut self) -> Result<()> {
+        let policy = library::CleanPolicy::default();
+
+        // Files in an unmounted library root are missing, but not deleted.
+        let is_available = self.roots.available_filter();
+
+        let all_pics: Vec<photo::model::Picture> = self
+            .photo_repo
+            .all()?
+            .into_iter()
+            .filter(|pic| is_available(&pic.path))
+            .collect();
+        let pic_count = all_pics.len();
+        let pics: Vec<_> = all_pics.iter().filter(|pic| !pic.path.exists()).collect();
+
+        let all_vids: Vec<video::Video> = self
+            .video_repo
+            .all()?
+            .into_iter()
+            .filter(|vid| is_available(&vid.path))
+            .collect();
+        let vid_count = all_vids.len();
+        let vids: Vec<_> = all_vids.iter().filter(|vid| !vid.path.exists()).collect();
+
+        if !self.is_confirmed
+            && (policy.is_confirmation_required_per_root(
+                &self.roots,
+                all_pics.iter().map(|pic| pic.path.as_path()),
+                pics.iter().map(|pic| pic.path.as_path()),
+            ) || policy.is_confirmation_required_per_root(
+                &self.roots,
+                all_vids.iter().map(|vid| vid.path.as_path()),
+                vids.iter().map(|vid| vid.path.as_path()),
+            ))
+        {
+            bail!(
+                "{} of {} photos and {} of {} videos are missing. \
+                Check your library is mounted, or run again with --yes to clean anyway.",
+                pics.len(),
+                pic_count,
+                vids.len(),
+                vid_count
+            );
+        }
+
+        // Soft delete now, purge after the grace period.
+        for pic in &pics {
+            self.photo_repo.mark_deleted(pic.picture_id)?;
+        }
+        for vid in &vids {
+            self.video_repo.mark_deleted(vid.video_id)?;
+        }
+
+        let expired_pics: Vec<_> = self
+            .photo_repo
+            .find_deleted_before(policy.purge_before())?
+            .into_iter()
+            .filter(|pic| is_available(&pic.path) && !pic.path.exists())
+            .collect();
+
+        for pic in &expired_pics {
+            for path in self.photo_repo.find_files_to_cleanup(pic.picture_id)? {
+                if let Err(e) = std::fs::remove_file(&path) {
+                    error!("Failed deleting {:?} with {}", path, e);
+                }
+            }
+            self.photo_repo.remove(pic.picture_id)?;
+        }
+
+        let expired_vids: Vec<_> = self
+            .video_repo
+            .find_deleted_before(policy.purge_before())?
+            .into_iter()
+            .filter(|vid| is_available(&vid.path) && !vid.path.exists())
+            .collect();
+
+        for vid in &expired_vids {
+            for path in self.video_repo.find_files_to_cleanup(vid.video_id)? {
+                if let Err(e) = std::fs::remove_file(&path) {
+                    error!("Failed deleting {:?} with {}", path, e);
+                }
+            }
+            self.video_repo.remove(vid.video_id)?;
+        }
+
+        println!(
+            "Marked {} photos and {} videos as deleted.",
+            pics.len(),
+            vids.len()
+        );
+        println!(
+            "Purged {} photos and {} videos.",
+            expired_pics.len(),
+            expired_vids.len()
+        );
+        Ok(())
+    }
+
+    fn shift_time(&mut self) -> Result<()> {
+        let folder = PathBuf::from(&self.operands[0]);
+        let delta = photo::edit::parse_time_shift(&self.operands[1])?;
+
+        let mut editor = photo::Editor::new(self.photo_repo.clone(), self.write_target);
+        let count = editor.shift_time(&folder, delta)?;
+
+        println!("Shifted capture time of {} photos by {}.", count, delta);
+        Ok(())
+    }
+
+    fn geotag(&mut self) -> Result<()> {
+        let mut track = gpx::Track::default();
+        for path in &self.operands {
+            track.extend(gpx::Track::open(&PathBuf::from(path))?);
+        }
+        info!("Loaded {} track points", track.len());
+
+        let pics = self.photo_repo.find_need_geotag()?;
+        let edits: Vec<(photo::PictureId, photo::MetadataEdit)> = track
+            .locate(&pics, self.clock_offset, self.tolerance)
+            .into_iter()
+            .map(|(picture_id, location)| {
+                let edit = photo::MetadataEdit {
+                    location: Some(location),
+                    ..Default::default()
+                };
+                (picture_id, edit)
+            })
+            .collect();
+
+        let count = if self.is_write_back {
+            let mut editor = photo::Editor::new(self.photo_repo.clone(), self.write_target);
+            editor.edit_each(edits)?
+        } else {
+            let count = edits.len();
+            self.photo_repo.update_metadata(edits)?;
+            count
+        };
+
+        println!(
+            "Geotagged {} of {} photos without a location.",
+            count,
+            pics.len()
+        );
+        Ok(())
+    }
+
+    fn list(&self) -> Result<()> {
+        for visual in self.visual_repo.all()? {
+            let kind = if visual.is_motion_photo() || visual.is_live_photo {
+                "motion"
+            } else if visual.is_video_only() {
+                "video"
+            } else {
+                "photo"
+            };
+
+            let path = visual
+                .path()
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_default();
+
+            println!(
+                "{}\t{}\t{}\t{}",
+                visual.visual_id,
+                visual.ordering_ts.format("%Y-%m-%d %H:%M:%S"),
+                kind,
+                path
+            );
+        }
+        Ok(())
+    }
+}
+
+fn main() -> ExitCode {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::WARN.into())
+        .from_env_lossy(); // picks up RUST_LOG
+
+    tracing_subscriber::fmt()
+        .with_env_filter(env_filter)
+        .with_writer(std::io::stderr)
+        .compact()
+        .init();
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|a| a == "-h" || a == "--help") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    let args = match Args::parse(args.into_iter()) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let result = Cli::open(&args).and_then(|mut cli| cli.run(args.command));
+
+    if let Err(e) = result {
+        eprintln!("Error: {:?}", e);
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
+}
@@ -11,13 +11,24 @@ refinery::embed_migrations!("migrations");
 
 pub fn setup(database_path: &path::Path) -> Result<Connection> {
     let mut con = Connection::open(database_path)?;
-    migrations::runner().run(&mut con)?;
+    migrate(&mut con)?;
     Ok(con)
 }
 
 // for testing
 pub fn setup_in_memory() -> Result<Connection> {
     let mut con = Connection::open_in_memory()?;
-    migrations::runner().run(&mut con)?;
+    migrate(&mut con)?;
     Ok(con)
 }
+
+fn migrate(con: &mut Connection) -> Result<()> {
+    // Some migrations rebuild tables by copying them and dropping the original.
+    // Foreign keys must be disabled so that dropping a table doesn't cascade deletes
+    // to dependent tables. This pragma is a no-op inside a transaction, so must be set
+    // before running the migrations.
+    con.pragma_update(None, "foreign_keys", false)?;
+    migrations::runner().run(con)?;
+    con.pragma_update(None, "foreign_keys", true)?;
+    Ok(())
+}
@@ -0,0 +1,131 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::ContentHash;
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Size of each sampled block of a large file.
+const BLOCK_SIZE: u64 = 1024 * 1024;
+
+/// Files up to this size are hashed in full. Larger files are sampled.
+const FULL_HASH_LIMIT: u64 = 4 * BLOCK_SIZE;
+
+/// Computes a fast hash of file content.
+///
+/// Small files are hashed in full. Large files, such as videos, are sampled by hashing
+/// the file size and a block from the start, middle, and end of the file. Copies of a file
+/// always have the same hash and it is very unlikely that different photos or videos
+/// of exactly the same size will match in all three blocks.
+pub fn hash(path: &Path) -> Result<ContentHash> {
+    let mut file = File::open(path)?;
+    let size = file.metadata()?.len();
+
+    let mut hasher = Sha256::new();
+    hasher.update(size.to_le_bytes());
+
+    if size <= FULL_HASH_LIMIT {
+        std::io::copy(&mut file, &mut hasher)?;
+    } else {
+        let mut block = vec![0; BLOCK_SIZE as usize];
+        for offset in [0, (size - BLOCK_SIZE) / 2, size - BLOCK_SIZE] {
+            file.seek(SeekFrom::Start(offset))?;
+            file.read_exact(&mut block)?;
+            hasher.update(&block);
+        }
+    }
+
+    Ok(ContentHash::new(format!("{:x}", hasher.finalize())))
+}
+
+/// Checks that two files have exactly the same content by comparing every byte.
+/// Large files are only sampled by [`hash`], so this must pass before a copy is removed.
+pub fn is_same_content(a: &Path, b: &Path) -> Result<bool> {
+    let a = File::open(a)?;
+    let b = File::open(b)?;
+
+    if a.metadata()?.len() != b.metadata()?.len() {
+        return Ok(false);
+    }
+
+    let mut a = BufReader::new(a);
+    let mut b = BufReader::new(b);
+    let mut a_block = vec![0; BLOCK_SIZE as usize];
+    let mut b_block = vec![0; BLOCK_SIZE as usize];
+
+    loop {
+        let len = read_block(&mut a, &mut a_block)?;
+        if len != read_block(&mut b, &mut b_block)? || a_block[..len] != b_block[..len] {
+            return Ok(false);
+        }
+        if len == 0 {
+            return Ok(true);
+        }
+    }
+}
+
+/// Fills as much of a block as possible, returning fewer bytes only at the end of the file.
+fn read_block(reader: &mut impl Read, block: &mut [u8]) -> Result<usize> {
+    let mut len = 0;
+    while len < block.len() {
+        match reader.read(&mut block[len..])? {
+            0 => break,
+            n => len += n,
+        }
+    }
+    Ok(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copies_have_same_hash() {
+        let dir = tempfile::tempdir().unwrap();
+        let small = vec![7u8; 1000];
+        let mut large = vec![7u8; (FULL_HASH_LIMIT + BLOCK_SIZE) as usize];
+
+        std::fs::write(dir.path().join("a.jpg"), &small).unwrap();
+        std::fs::write(dir.path().join("b.jpg"), &small).unwrap();
+        std::fs::write(dir.path().join("a.mp4"), &large).unwrap();
+        std::fs::write(dir.path().join("b.mp4"), &large).unwrap();
+
+        // Change the middle of the file, which is sampled.
+        let middle = large.len() / 2;
+        large[middle] = 8;
+        std::fs::write(dir.path().join("c.mp4"), &large).unwrap();
+
+        let hash_of = |name: &str| hash(&dir.path().join(name)).unwrap();
+
+        assert_eq!(hash_of("a.jpg"), hash_of("b.jpg"));
+        assert_eq!(hash_of("a.mp4"), hash_of("b.mp4"));
+        assert_ne!(hash_of("a.jpg"), hash_of("a.mp4"));
+        assert_ne!(hash_of("a.mp4"), hash_of("c.mp4"));
+    }
+
+    #[test]
+    fn same_content_compares_every_byte() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut large = vec![7u8; (FULL_HASH_LIMIT + BLOCK_SIZE) as usize];
+
+        std::fs::write(dir.path().join("a.mp4"), &large).unwrap();
+        std::fs::write(dir.path().join("b.mp4"), &large).unwrap();
+
+        // Change a byte that isn't sampled, so the hashes still match.
+        large[BLOCK_SIZE as usize + 1] = 8;
+        std::fs::write(dir.path().join("c.mp4"), &large).unwrap();
+        std::fs::write(dir.path().join("d.mp4"), &large[1..]).unwrap();
+
+        let path = |name: &str| dir.path().join(name);
+
+        assert_eq!(hash(&path("a.mp4")).unwrap(), hash(&path("c.mp4")).unwrap());
+        assert!(is_same_content(&path("a.mp4"), &path("b.mp4")).unwrap());
+        assert!(!is_same_content(&path("a.mp4"), &path("c.mp4")).unwrap());
+        assert!(!is_same_content(&path("a.mp4"), &path("d.mp4")).unwrap());
+    }
+}
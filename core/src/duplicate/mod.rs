@@ -0,0 +1,11 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+pub mod hash;
+pub mod model;
+pub mod repo;
+
+pub use model::ContentHash;
+pub use model::DuplicateGroup;
+pub use repo::Repository;
@@ -0,0 +1,47 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::VisualId;
+use std::fmt::Display;
+
+/// Hex encoded hash of file content. Files with the same hash are copies of each other.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ContentHash(String);
+
+impl ContentHash {
+    pub fn new(hash: String) -> Self {
+        Self(hash)
+    }
+
+    pub fn hash(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for ContentHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Visual items that are copies of the same file.
+#[derive(Debug, Clone)]
+pub struct DuplicateGroup {
+    pub content_hash: ContentHash,
+
+    /// Copies in order of preference for keeping. The first copy is the oldest file.
+    pub visual_ids: Vec<VisualId>,
+}
+
+impl DuplicateGroup {
+    /// The copy to keep when resolving duplicates.
+    pub fn keep(&self) -> &VisualId {
+        &self.visual_ids[0]
+    }
+
+    /// The copies that can be removed when resolving duplicates.
+    pub fn extras(&self) -> &[VisualId] {
+        &self.visual_ids[1..]
+    }
+}
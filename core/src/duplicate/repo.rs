@@ -0,0 +1,162 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::model::{ContentHash, DuplicateGroup};
+use crate::photo::PictureId;
+use crate::video::VideoId;
+use crate::visual::VisualId;
+use anyhow::*;
+use rusqlite;
+use rusqlite::params;
+use std::result::Result::Ok;
+use std::sync::{Arc, Mutex};
+
+/// Repository of content hashes for finding duplicate pictures and videos.
+#[derive(Debug, Clone)]
+pub struct Repository {
+    /// Connection to backing Sqlite database.
+    con: Arc<Mutex<rusqlite::Connection>>,
+}
+
+impl Repository {
+    pub fn open(con: Arc<Mutex<rusqlite::Connection>>) -> Result<Repository> {
+        Ok(Repository { con })
+    }
+
+    pub fn add_picture_hash(
+        &mut self,
+        picture_id: PictureId,
+        content_hash: &ContentHash,
+    ) -> Result<()> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare_cached(
+            "INSERT INTO pictures_hashes (
+                picture_id,
+                content_hash
+            ) VALUES (
+                ?1, ?2
+            ) ON CONFLICT (picture_id) DO UPDATE SET
+                content_hash = ?2",
+        )?;
+
+        stmt.execute(params![picture_id.id(), content_hash.hash()])?;
+
+        Ok(())
+    }
+
+    pub fn add_video_hash(&mut self, video_id: VideoId, content_hash: &ContentHash) -> Result<()> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare_cached(
+            "INSERT INTO videos_hashes (
+                video_id,
+                content_hash
+            ) VALUES (
+                ?1, ?2
+            ) ON CONFLICT (video_id) DO UPDATE SET
+                content_hash = ?2",
+        )?;
+
+        stmt.execute(params![video_id.id(), content_hash.hash()])?;
+
+        Ok(())
+    }
+
+    /// Gets groups of visible items that have the same content.
+    /// A visual item with both a picture and a video, such as a live photo, is
+    /// compared using the picture.
+    pub fn find_duplicates(&self) -> Result<Vec<DuplicateGroup>> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare(
+            "WITH hashed AS (
+                SELECT
+                    visual.visual_id,
+                    pictures_hashes.content_hash,
+                    COALESCE(pictures.fs_created_ts, pictures.fs_modified_ts) AS fs_ts,
+                    pictures.picture_path_lossy AS path_lossy
+                FROM visual
+                JOIN pictures_hashes USING (picture_id)
+                JOIN pictures USING (picture_id)
+                UNION ALL
+                SELECT
+                    visual.visual_id,
+                    videos_hashes.content_hash,
+                    COALESCE(videos.fs_created_ts, videos.fs_modified_ts) AS fs_ts,
+                    videos.video_path_lossy AS path_lossy
+                FROM visual
+                JOIN videos_hashes USING (video_id)
+                JOIN videos USING (video_id)
+                WHERE visual.picture_id IS NULL
+            )
+            SELECT
+                visual_id,
+                content_hash
+            FROM hashed
+            WHERE content_hash IN (
+                SELECT content_hash
+                FROM hashed
+                GROUP BY content_hash
+                HAVING COUNT(*) > 1
+            )
+            ORDER BY content_hash, fs_ts ASC, path_lossy ASC",
+        )?;
+
+        let rows: Vec<(VisualId, ContentHash)> = stmt
+            .query_map([], |row| {
+                let visual_id = row.get(0).map(VisualId::new)?;
+                let content_hash = row.get(1).map(ContentHash::new)?;
+                Ok((visual_id, content_hash))
+            })?
+            .flatten()
+            .collect();
+
+        // Rows are ordered by hash, so copies are adjacent.
+        let mut groups: Vec<DuplicateGroup> = vec![];
+        for (visual_id, content_hash) in rows {
+            match groups.last_mut() {
+                Some(group) if group.content_hash == content_hash => {
+                    group.visual_ids.push(visual_id);
+                }
+                _ => groups.push(DuplicateGroup {
+                    content_hash,
+                    visual_ids: vec![visual_id],
+                }),
+            }
+        }
+
+        Ok(groups)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestLibrary;
+    use chrono::prelude::*;
+
+    #[test]
+    fn find_duplicates_groups_copies() {
+        let library = TestLibrary::new();
+        let ts = Utc::now();
+        let ids = library.add_pictures(&[
+            ("a.jpg", Some(ts)),
+            ("backup/a.jpg", Some(ts + chrono::TimeDelta::seconds(1))),
+            ("b.jpg", Some(ts + chrono::TimeDelta::seconds(2))),
+        ]);
+
+        let mut repo = Repository::open(library.con.clone()).unwrap();
+        let a = ContentHash::new("a".into());
+        let b = ContentHash::new("b".into());
+        repo.add_picture_hash(ids[0], &a).unwrap();
+        repo.add_picture_hash(ids[1], &a).unwrap();
+        repo.add_picture_hash(ids[2], &b).unwrap();
+
+        let groups = repo.find_duplicates().unwrap();
+        assert_eq!(1, groups.len());
+        assert_eq!(a, groups[0].content_hash);
+        assert_eq!(2, groups[0].visual_ids.len());
+
+        // Oldest file is kept.
+        assert_eq!(&VisualId::new(format!("{}_x", ids[0])), groups[0].keep());
+    }
+}
@@ -0,0 +1,13 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+pub mod model;
+pub mod policy;
+pub mod repo;
+
+pub use model::Event;
+pub use model::EventId;
+pub use model::NewEvent;
+pub use policy::EventPolicy;
+pub use repo::Repository;
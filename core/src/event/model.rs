@@ -0,0 +1,74 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::user_album::AlbumItem;
+use crate::visual::Visual;
+use chrono::{DateTime, Utc};
+use std::fmt::Display;
+
+/// Database ID of event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EventId(i64);
+
+impl EventId {
+    pub fn new(id: i64) -> Self {
+        Self(id)
+    }
+
+    pub fn id(&self) -> i64 {
+        self.0
+    }
+}
+
+impl Display for EventId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Pictures and videos taken close together in time and place, such as a trip or a party.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub event_id: EventId,
+
+    /// Name chosen by the user, or the city most items were taken in. None if neither.
+    pub name: Option<String>,
+
+    /// Time of first item.
+    pub start_ts: DateTime<Utc>,
+
+    /// Time of last item.
+    pub end_ts: DateTime<Utc>,
+
+    /// Items in ascending time order.
+    pub items: Vec<AlbumItem>,
+}
+
+impl Event {
+    /// Item to show as event cover.
+    pub fn cover(&self) -> Option<&AlbumItem> {
+        self.items.first()
+    }
+
+    pub fn contains(&self, visual: &Visual) -> bool {
+        self.items.iter().any(|item| item.matches(visual))
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+/// An event found by an event policy that has not yet been saved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewEvent {
+    pub name: Option<String>,
+
+    /// Items in ascending time order.
+    pub items: Vec<AlbumItem>,
+}
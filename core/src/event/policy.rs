@@ -0,0 +1,265 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::model::{Event, NewEvent};
+use crate::user_album::AlbumItem;
+use crate::visual::Visual;
+use chrono::TimeDelta;
+use h3o::{CellIndex, Resolution};
+use std::collections::HashSet;
+
+/// Groups pictures and videos taken close together in time and place into events,
+/// such as trips and parties.
+#[derive(Debug, Clone)]
+pub struct EventPolicy {
+    /// Longest time between consecutive items in an event. Long enough to span a night.
+    pub max_gap: TimeDelta,
+
+    /// Longest time between consecutive items in an event that were taken far apart.
+    pub max_gap_far_apart: TimeDelta,
+
+    /// Resolution of cells used to measure the distance between items.
+    /// Cells are roughly 17km apart.
+    pub resolution: Resolution,
+
+    /// Most cells between items that are not far apart.
+    pub max_distance: i32,
+
+    /// Fewest items in an event.
+    pub min_len: usize,
+}
+
+impl Default for EventPolicy {
+    fn default() -> Self {
+        Self {
+            max_gap: TimeDelta::hours(16),
+            max_gap_far_apart: TimeDelta::hours(2),
+            resolution: Resolution::Five,
+            max_distance: 4,
+            min_len: 5,
+        }
+    }
+}
+
+impl EventPolicy {
+    /// Finds events in visual items, which must be in ascending time order.
+    /// Items already in an event are skipped, so that events the user has
+    /// renamed, merged, or split are kept as they are.
+    pub fn detect(&self, visuals: &[Visual], events: &[Event]) -> Vec<NewEvent> {
+        let picture_ids: HashSet<i64> = events
+            .iter()
+            .flat_map(|event| event.items.iter())
+            .filter_map(|item| item.picture_id.map(|id| id.id()))
+            .collect();
+
+        let video_ids: HashSet<i64> = events
+            .iter()
+            .flat_map(|event| event.items.iter())
+            .filter_map(|item| item.video_id.map(|id| id.id()))
+            .collect();
+
+        let unassigned = visuals.iter().filter(|v| {
+            !v.picture_id
+                .is_some_and(|id| picture_ids.contains(&id.id()))
+                && !v.video_id.is_some_and(|id| video_ids.contains(&id.id()))
+        });
+
+        let mut groups: Vec<Vec<&Visual>> = Vec::new();
+
+        // Location of the last item in the current group that has a location.
+        let mut last_cell: Option<CellIndex> = None;
+
+        for visual in unassigned {
+            let cell = visual.location.map(|x| x.to_cell(self.resolution));
+
+            let is_same_event =
+                groups
+                    .last()
+                    .and_then(|group| group.last())
+                    .is_some_and(|previous| {
+                        let gap = visual.ordering_ts - previous.ordering_ts;
+                        gap <= self.max_gap_far_apart
+                            || (gap <= self.max_gap && !self.is_far_apart(last_cell, cell))
+                    });
+
+            if is_same_event {
+                if let Some(group) = groups.last_mut() {
+                    group.push(visual);
+                }
+            } else {
+                groups.push(vec![visual]);
+                last_cell = None;
+            }
+
+            last_cell = cell.or(last_cell);
+        }
+
+        groups
+            .into_iter()
+            .filter(|group| group.len() >= self.min_len)
+            .map(|group| NewEvent {
+                name: Self::most_visited_city(&group),
+                items: group.into_iter().map(AlbumItem::of).collect(),
+            })
+            .collect()
+    }
+
+    fn is_far_apart(&self, a: Option<CellIndex>, b: Option<CellIndex>) -> bool {
+        let (Some(a), Some(b)) = (a, b) else {
+            return false;
+        };
+
+        // Distance can't be measured between cells that are very far apart.
+        match a.grid_distance(b) {
+            Ok(distance) => distance > self.max_distance,
+            Err(_) => true,
+        }
+    }
+
+    fn most_visited_city(visuals: &[&Visual]) -> Option<String> {
+        let mut counts: Vec<(&str, usize)> = Vec::new();
+
+        let cities = visuals
+            .iter()
+            .filter_map(|v| v.place.as_ref())
+            .map(|place| place.city.as_str());
+
+        for city in cities {
+            match counts.iter_mut().find(|(name, _)| *name == city) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((city, 1)),
+            }
+        }
+
+        // Reversed so the first city visited wins a tie.
+        counts
+            .into_iter()
+            .rev()
+            .max_by_key(|(_, count)| *count)
+            .map(|(city, _)| city.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::EventId;
+    use crate::photo::PictureId;
+    use crate::place::Place;
+    use crate::visual::VisualId;
+    use chrono::prelude::*;
+    use h3o::LatLng;
+    use std::path::PathBuf;
+
+    const HOUR: i64 = 60 * 60;
+
+    fn photo(id: i64, secs: i64, location: Option<(f64, f64, &str)>) -> Visual {
+        Visual {
+            visual_id: VisualId::new(format!("{}_x", id)),
+            parent_path: PathBuf::from("/"),
+            thumbnail_path: None,
+            video_id: None,
+            video_path: None,
+            video_transcoded_path: None,
+            video_duration: None,
+            video_orientation: None,
+            picture_id: Some(PictureId::new(id)),
+            picture_path: Some(PathBuf::from(format!("/{}.jpg", id))),
+            picture_orientation: None,
+            raw_picture_id: None,
+            raw_picture_path: None,
+            motion_photo_video_path: None,
+            ordering_ts: DateTime::from_timestamp(secs, 0).unwrap(),
+            is_selfie: None,
+            is_live_photo: false,
+            is_transcode_required: None,
+            location: location.map(|(lat, lng, _)| LatLng::new(lat, lng).unwrap()),
+            place: location.map(|(_, _, city)| Place {
+                country: "Portugal".into(),
+                region: None,
+                city: city.into(),
+            }),
+            stack_id: None,
+            stack_count: 1,
+            rating: None,
+            is_favourite: false,
+            title: None,
+            description: None,
+            keywords: Vec::new(),
+            regions: Vec::new(),
+            person_ids: Vec::new(),
+        }
+    }
+
+    fn ids(event: &NewEvent) -> Vec<i64> {
+        event
+            .items
+            .iter()
+            .filter_map(|item| item.picture_id.map(|id| id.id()))
+            .collect()
+    }
+
+    #[test]
+    fn splits_on_time_gaps_and_distance() {
+        let lisbon = Some((38.72, -9.14, "Lisbon"));
+        let porto = Some((41.15, -8.61, "Porto"));
+
+        let policy = EventPolicy {
+            min_len: 2,
+            ..EventPolicy::default()
+        };
+
+        let visuals = vec![
+            // Weekend in Porto, with a night between photos
+            photo(1, 0, porto),
+            photo(2, HOUR, None),
+            photo(3, 14 * HOUR, porto),
+            // Moving on to Lisbon soon after isn't a new event
+            photo(4, 15 * HOUR, lisbon),
+            // Days later in Lisbon
+            photo(5, 100 * HOUR, lisbon),
+            photo(6, 101 * HOUR, lisbon),
+            // Hours later and far away in Porto
+            photo(7, 110 * HOUR, porto),
+            photo(8, 111 * HOUR, porto),
+            // Too small to be an event
+            photo(9, 500 * HOUR, porto),
+        ];
+
+        let events = policy.detect(&visuals, &[]);
+
+        assert_eq!(3, events.len());
+        assert_eq!(vec![1, 2, 3, 4], ids(&events[0]));
+        assert_eq!(vec![5, 6], ids(&events[1]));
+        assert_eq!(vec![7, 8], ids(&events[2]));
+
+        // Named after the most visited city
+        assert_eq!(Some("Porto".to_string()), events[0].name);
+        assert_eq!(Some("Lisbon".to_string()), events[1].name);
+    }
+
+    #[test]
+    fn skips_items_already_in_events() {
+        let policy = EventPolicy {
+            min_len: 2,
+            ..EventPolicy::default()
+        };
+
+        let visuals: Vec<Visual> = (1..=4).map(|id| photo(id, id * HOUR, None)).collect();
+
+        let existing = Event {
+            event_id: EventId::new(1),
+            name: Some("Party".into()),
+            start_ts: visuals[0].ordering_ts,
+            end_ts: visuals[1].ordering_ts,
+            items: visuals[0..2].iter().map(AlbumItem::of).collect(),
+        };
+
+        let events = policy.detect(&visuals, &[existing]);
+
+        assert_eq!(1, events.len());
+        assert_eq!(vec![3, 4], ids(&events[0]));
+        assert_eq!(None, events[0].name);
+    }
+}
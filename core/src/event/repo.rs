@@ -0,0 +1,289 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::model::{Event, EventId, NewEvent};
+use crate::photo::PictureId;
+use crate::user_album::AlbumItem;
+use crate::video::VideoId;
+use anyhow::*;
+use chrono::*;
+use rusqlite;
+use rusqlite::params;
+use rusqlite::Transaction;
+use std::collections::HashMap;
+use std::result::Result::Ok;
+use std::sync::{Arc, Mutex};
+
+/// Repository of events found in the library.
+#[derive(Debug, Clone)]
+pub struct Repository {
+    /// Connection to backing Sqlite database.
+    con: Arc<Mutex<rusqlite::Connection>>,
+}
+
+impl Repository {
+    pub fn open(con: Arc<Mutex<rusqlite::Connection>>) -> Result<Repository> {
+        Ok(Repository { con })
+    }
+
+    /// Gets all events in ascending time order, with items in ascending time order.
+    /// Events without any items are skipped.
+    pub fn all(&self) -> Result<Vec<Event>> {
+        let con = self.con.lock().unwrap();
+
+        let mut stmt = con.prepare("SELECT event_id, name FROM events")?;
+
+        let result = stmt.query_map([], |row| {
+            let event_id: i64 = row.get("event_id")?;
+            let name: Option<String> = row.get("name")?;
+            Ok((event_id, name))
+        })?;
+
+        let names: HashMap<i64, Option<String>> = result.flatten().collect();
+
+        let mut stmt = con.prepare(
+            "SELECT
+                    event_items.event_id,
+                    event_items.picture_id,
+                    event_items.video_id,
+                    visual.ordering_ts
+                FROM visual
+                INNER JOIN event_items
+                    ON event_items.picture_id = visual.picture_id
+                    OR event_items.video_id = visual.video_id
+                ORDER BY visual.ordering_ts ASC",
+        )?;
+
+        let result = stmt.query_map([], |row| {
+            let event_id: i64 = row.get("event_id")?;
+            let item = AlbumItem {
+                picture_id: row.get::<_, Option<i64>>("picture_id")?.map(PictureId::new),
+                video_id: row.get::<_, Option<i64>>("video_id")?.map(VideoId::new),
+            };
+            let ordering_ts: DateTime<Utc> = row.get("ordering_ts")?;
+            Ok((event_id, item, ordering_ts))
+        })?;
+
+        let mut items: HashMap<i64, Vec<(AlbumItem, DateTime<Utc>)>> = HashMap::new();
+
+        for (event_id, item, ordering_ts) in result.flatten() {
+            let event_items = items.entry(event_id).or_default();
+            // An unpaired live photo is two visual items, but one event item.
+            if !event_items.iter().any(|(x, _)| *x == item) {
+                event_items.push((item, ordering_ts));
+            }
+        }
+
+        let mut events: Vec<Event> = items
+            .into_iter()
+            .filter_map(|(event_id, items)| {
+                let start_ts = items.first()?.1;
+                let end_ts = items.last()?.1;
+                Some(Event {
+                    event_id: EventId::new(event_id),
+                    name: names.get(&event_id).cloned().flatten(),
+                    start_ts,
+                    end_ts,
+                    items: items.into_iter().map(|(item, _)| item).collect(),
+                })
+            })
+            .collect();
+
+        events.sort_by_key(|event| event.start_ts);
+
+        Ok(events)
+    }
+
+    /// Saves newly found events and removes events that no longer have any items.
+    pub fn add_all(&mut self, events: &[NewEvent]) -> Result<()> {
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+        {
+            for event in events {
+                Self::insert(&tx, event.name.as_deref(), &event.items)?;
+            }
+
+            let mut delete_empty = tx.prepare_cached(
+                "DELETE FROM events
+                WHERE event_id NOT IN (SELECT event_id FROM event_items)",
+            )?;
+            delete_empty.execute([])?;
+        }
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    pub fn rename(&mut self, event_id: EventId, name: &str) -> Result<()> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare_cached("UPDATE events SET name = ?2 WHERE event_id = ?1")?;
+
+        stmt.execute(params![event_id.id(), name])?;
+
+        Ok(())
+    }
+
+    /// Moves all items of another event into an event and deletes the other event.
+    /// The event keeps its name, unless it doesn't have one.
+    pub fn merge(&mut self, event_id: EventId, other_id: EventId) -> Result<()> {
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+        {
+            let mut update_name = tx.prepare_cached(
+                "UPDATE events
+                SET name = (SELECT name FROM events WHERE event_id = ?2)
+                WHERE event_id = ?1
+                AND name IS NULL",
+            )?;
+            update_name.execute(params![event_id.id(), other_id.id()])?;
+
+            let mut move_items =
+                tx.prepare_cached("UPDATE event_items SET event_id = ?1 WHERE event_id = ?2")?;
+            move_items.execute(params![event_id.id(), other_id.id()])?;
+
+            let mut delete = tx.prepare_cached("DELETE FROM events WHERE event_id = ?1")?;
+            delete.execute(params![other_id.id()])?;
+        }
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    /// Moves items out of an event into a new event with the same name.
+    pub fn split(&mut self, event_id: EventId, items: &[AlbumItem]) -> Result<EventId> {
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+        let new_event_id = {
+            let mut name = tx.prepare_cached("SELECT name FROM events WHERE event_id = ?1")?;
+            let name: Option<String> = name.query_row([event_id.id()], |row| row.get(0))?;
+
+            let mut delete = tx.prepare_cached(
+                "DELETE FROM event_items
+                WHERE event_id = ?1
+                AND (picture_id = ?2 OR video_id = ?3)",
+            )?;
+
+            for item in items {
+                delete.execute(params![
+                    event_id.id(),
+                    item.picture_id.map(|x| x.id()),
+                    item.video_id.map(|x| x.id()),
+                ])?;
+            }
+
+            Self::insert(&tx, name.as_deref(), items)?
+        };
+        tx.commit()?;
+
+        Ok(new_event_id)
+    }
+
+    /// Inserts an event. Items already in another event are skipped.
+    fn insert(tx: &Transaction<'_>, name: Option<&str>, items: &[AlbumItem]) -> Result<EventId> {
+        let mut insert_event = tx.prepare_cached(
+            "INSERT INTO events (
+                name,
+                created_ts
+            ) VALUES (
+                ?1, ?2
+            )",
+        )?;
+
+        insert_event.execute(params![name, Utc::now()])?;
+
+        let event_id = tx.last_insert_rowid();
+
+        let mut insert_item = tx.prepare_cached(
+            "INSERT OR IGNORE INTO event_items (
+                event_id,
+                picture_id,
+                video_id
+            ) VALUES (
+                ?1, ?2, ?3
+            )",
+        )?;
+
+        for item in items {
+            insert_item.execute(params![
+                event_id,
+                item.picture_id.map(|x| x.id()),
+                item.video_id.map(|x| x.id()),
+            ])?;
+        }
+
+        Ok(EventId::new(event_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestLibrary;
+
+    #[test]
+    fn renames_merges_and_splits_events() {
+        let library = TestLibrary::new();
+
+        // Files are a minute apart so they have a stable time order.
+        let files: Vec<_> = ["a.jpg", "b.jpg", "c.jpg", "d.jpg"]
+            .into_iter()
+            .enumerate()
+            .map(|(index, name)| (name, DateTime::from_timestamp(index as i64 * 60, 0)))
+            .collect();
+
+        let items: Vec<AlbumItem> = library
+            .add_pictures(&files)
+            .into_iter()
+            .map(|picture_id| AlbumItem {
+                picture_id: Some(picture_id),
+                video_id: None,
+            })
+            .collect();
+
+        let mut repo = Repository::open(library.con.clone()).unwrap();
+
+        // An item can't be in two events.
+        repo.add_all(&[
+            NewEvent {
+                name: None,
+                items: items[0..2].to_vec(),
+            },
+            NewEvent {
+                name: Some("Porto".into()),
+                items: items[1..4].to_vec(),
+            },
+        ])
+        .unwrap();
+
+        let events = repo.all().unwrap();
+        assert_eq!(2, events.len());
+        assert_eq!(None, events[0].name);
+        assert_eq!(items[0..2], events[0].items);
+        assert_eq!(Some("Porto".to_string()), events[1].name);
+        assert_eq!(items[2..4], events[1].items);
+        assert_eq!(events[1].start_ts + TimeDelta::minutes(1), events[1].end_ts);
+
+        // Merging into an unnamed event takes the other name.
+        repo.merge(events[0].event_id, events[1].event_id).unwrap();
+        let events = repo.all().unwrap();
+        assert_eq!(1, events.len());
+        assert_eq!(Some("Porto".to_string()), events[0].name);
+        assert_eq!(items, events[0].items);
+
+        repo.rename(events[0].event_id, "Weekend in Porto").unwrap();
+        let new_event_id = repo.split(events[0].event_id, &items[2..4]).unwrap();
+
+        let events = repo.all().unwrap();
+        assert_eq!(2, events.len());
+        assert_eq!(items[0..2], events[0].items);
+        assert_eq!(new_event_id, events[1].event_id);
+        assert_eq!(Some("Weekend in Porto".to_string()), events[1].name);
+        assert_eq!(items[2..4], events[1].items);
+
+        // Events emptied by splitting are removed.
+        repo.split(events[0].event_id, &items[0..2]).unwrap();
+        repo.add_all(&[]).unwrap();
+        assert_eq!(2, repo.all().unwrap().len());
+    }
+}
@@ -3,13 +3,25 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 pub mod database;
+pub mod duplicate;
+pub mod event;
+pub mod library;
 pub mod machine_learning;
 pub mod path_encoding;
 pub mod people;
 pub mod photo;
+pub mod place;
+pub mod rating;
+pub mod scan;
+pub mod search;
+pub mod smart_album;
+#[cfg(test)]
+mod test_support;
 pub mod time;
+pub mod user_album;
 pub mod video;
 pub mod visual;
+pub mod xmp;
 
 pub use people::model::FaceId;
 pub use people::model::PersonId;
@@ -0,0 +1,167 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::model::{LibraryRoots, RootId};
+use chrono::prelude::*;
+use chrono::TimeDelta;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Safeguards for cleaning up pictures and videos whose files are missing.
+///
+/// A missing file might be a deleted file, but it might also be a file on a network
+/// share that has dropped out or a drive that is half-mounted. So missing items are first
+/// soft deleted (hidden) and only purged from the database after a grace period. If many
+/// items go missing at once, then the user must confirm before they are soft deleted.
+#[derive(Debug, Clone)]
+pub struct CleanPolicy {
+    /// Largest share (0.0 to 1.0) of the library that can be soft deleted in one
+    /// clean without confirmation.
+    pub max_unconfirmed_share: f64,
+
+    /// Number of missing items that can always be soft deleted without confirmation,
+    /// so that deleting a few items from a small library doesn't need confirmation.
+    pub min_unconfirmed_count: usize,
+
+    /// How long soft deleted items are kept before being purged.
+    pub grace_period: TimeDelta,
+}
+
+impl Default for CleanPolicy {
+    fn default() -> Self {
+        Self {
+            max_unconfirmed_share: 0.1,
+            min_unconfirmed_count: 20,
+            grace_period: TimeDelta::days(30),
+        }
+    }
+}
+
+impl CleanPolicy {
+    /// Must the user confirm soft deleting `missing_count` items from a library
+    /// of `library_count` items?
+    pub fn is_confirmation_required(&self, missing_count: usize, library_count: usize) -> bool {
+        if missing_count <= self.min_unconfirmed_count {
+            return false;
+        }
+
+        let share = missing_count as f64 / library_count.max(1) as f64;
+        share > self.max_unconfirmed_share
+    }
+
+    /// Must the user confirm soft deleting the `missing` items from the `library` items?
+    /// Each library root is judged against its own size, so a root that has dropped out
+    /// isn't outweighed by the other roots. Items in unavailable roots are never soft
+    /// deleted, so they are ignored.
+    pub fn is_confirmation_required_per_root<'a>(
+        &self,
+        roots: &LibraryRoots,
+        library: impl IntoIterator<Item = &'a Path>,
+        missing: impl IntoIterator<Item = &'a Path>,
+    ) -> bool {
+        let available: HashSet<RootId> = roots.available().map(|root| root.root_id).collect();
+        let library_counts = count_by_root(roots, &available, library);
+        let missing_counts = count_by_root(roots, &available, missing);
+
+        missing_counts.iter().any(|(root_id, missing_count)| {
+            let library_count = library_counts.get(root_id).copied().unwrap_or_default();
+            self.is_confirmation_required(*missing_count, library_count)
+        })
+    }
+
+    /// Items soft deleted before this time can be purged.
+    pub fn purge_before(&self) -> DateTime<Utc> {
+        Utc::now() - self.grace_period
+    }
+}
+
+/// Count of paths in each available root.
+fn count_by_root<'a>(
+    roots: &LibraryRoots,
+    available: &HashSet<RootId>,
+    paths: impl IntoIterator<Item = &'a Path>,
+) -> HashMap<RootId, usize> {
+    let mut counts = HashMap::new();
+    for path in paths {
+        if let Some((root, _)) = roots.relativize(path) {
+            if available.contains(&root.root_id) {
+                *counts.entry(root.root_id).or_default() += 1;
+            }
+        }
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::LibraryRoot;
+    use std::path::PathBuf;
+
+    #[test]
+    fn confirmation_required_for_large_share() {
+        let policy = CleanPolicy::default();
+
+        // A few deletions from a small library.
+        assert!(!policy.is_confirmation_required(5, 10));
+
+        // Everything missing, such as an unmounted drive.
+        assert!(policy.is_confirmation_required(1000, 1000));
+
+        // Under the share of a large library.
+        assert!(!policy.is_confirmation_required(50, 1000));
+        assert!(policy.is_confirmation_required(101, 1000));
+    }
+
+    #[test]
+    fn confirmation_required_per_root() {
+        let policy = CleanPolicy::default();
+
+        // A mounted root must be non-empty.
+        let big = tempfile::tempdir().unwrap();
+        let small = tempfile::tempdir().unwrap();
+        let unmounted = tempfile::tempdir().unwrap();
+        std::fs::write(big.path().join("a.jpg"), b"").unwrap();
+        std::fs::write(small.path().join("a.jpg"), b"").unwrap();
+
+        let roots = LibraryRoots::new(
+            [big.path(), small.path(), unmounted.path()]
+                .into_iter()
+                .enumerate()
+                .map(|(index, path)| LibraryRoot {
+                    root_id: RootId::new(index as i64),
+                    name: format!("root{}", index),
+                    path: path.to_path_buf(),
+                })
+                .collect(),
+        );
+
+        let files = |dir: &Path, count: usize| -> Vec<PathBuf> {
+            (0..count)
+                .map(|index| dir.join(format!("{}.jpg", index)))
+                .collect()
+        };
+        let big_files = files(big.path(), 1000);
+        let small_files = files(small.path(), 30);
+        let unmounted_files = files(unmounted.path(), 30);
+
+        let library: Vec<&Path> = big_files
+            .iter()
+            .chain(&small_files)
+            .chain(&unmounted_files)
+            .map(PathBuf::as_path)
+            .collect();
+
+        // Everything in the small root missing is a small share of the whole library.
+        let missing = small_files.iter().map(PathBuf::as_path);
+        assert!(policy.is_confirmation_required_per_root(&roots, library.clone(), missing));
+
+        // Missing files in an unmounted root are never cleaned.
+        let missing = unmounted_files.iter().map(PathBuf::as_path);
+        assert!(!policy.is_confirmation_required_per_root(&roots, library.clone(), missing));
+
+        let missing = big_files[0..50].iter().map(PathBuf::as_path);
+        assert!(!policy.is_confirmation_required_per_root(&roots, library, missing));
+    }
+}
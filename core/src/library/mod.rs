@@ -0,0 +1,15 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+pub mod clean;
+pub mod model;
+pub mod repo;
+
+pub use clean::CleanPolicy;
+pub use model::LibraryRoot;
+pub use model::LibraryRoots;
+pub use model::RootConfig;
+pub use model::RootId;
+pub use model::DEFAULT_ROOT_NAME;
+pub use repo::Repository;
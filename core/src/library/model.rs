@@ -0,0 +1,189 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+/// Name of the library root holding pictures and videos scanned before
+/// Fotema supported multiple library roots.
+pub const DEFAULT_ROOT_NAME: &str = "Pictures";
+
+/// Database ID of library root
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RootId(i64);
+
+impl RootId {
+    pub fn new(id: i64) -> Self {
+        Self(id)
+    }
+
+    pub fn id(&self) -> i64 {
+        self.0
+    }
+}
+
+impl Display for RootId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A library root as configured by the user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RootConfig {
+    /// User visible name, such as "NAS". Unique across roots.
+    pub name: String,
+
+    /// Directory holding pictures and videos.
+    pub path: PathBuf,
+}
+
+impl RootConfig {
+    /// Root named after its directory.
+    pub fn from_path(path: &Path) -> Self {
+        let name = path
+            .file_name()
+            .map(|x| x.to_string_lossy().to_string())
+            .unwrap_or_else(|| DEFAULT_ROOT_NAME.into());
+
+        Self {
+            name,
+            path: PathBuf::from(path),
+        }
+    }
+
+    /// Root named after its directory, with a numeric suffix if that name is already taken.
+    pub fn from_path_unique(path: &Path, is_taken: impl Fn(&str) -> bool) -> Self {
+        let mut root = Self::from_path(path);
+        let base_name = root.name.clone();
+        let mut suffix = 2;
+        while is_taken(&root.name) {
+            root.name = format!("{} {}", base_name, suffix);
+            suffix += 1;
+        }
+        root
+    }
+
+    /// Is the directory this root, inside this root, or does it contain this root?
+    /// Overlapping roots would scan the same files twice.
+    pub fn overlaps(&self, path: &Path) -> bool {
+        let canonical = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.into());
+        let (a, b) = (canonical(&self.path), canonical(path));
+        a.starts_with(&b) || b.starts_with(&a)
+    }
+}
+
+/// A directory tree of pictures and videos.
+/// All paths in the database are relative to a library root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LibraryRoot {
+    pub root_id: RootId,
+
+    pub name: String,
+
+    pub path: PathBuf,
+}
+
+impl LibraryRoot {
+    /// Is the root mounted?
+    /// A mount point for an unmounted removable drive or network share is often
+    /// left behind as an empty directory, so an empty root is considered unavailable.
+    /// This stops the pictures and videos of an unmounted root from being cleaned up.
+    pub fn is_available(&self) -> bool {
+        std::fs::read_dir(&self.path).is_ok_and(|mut entries| entries.next().is_some())
+    }
+}
+
+/// All configured library roots.
+#[derive(Debug, Clone, Default)]
+pub struct LibraryRoots(Vec<LibraryRoot>);
+
+impl LibraryRoots {
+    pub fn new(roots: Vec<LibraryRoot>) -> Self {
+        Self(roots)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &LibraryRoot> {
+        self.0.iter()
+    }
+
+    /// Roots that are currently mounted.
+    pub fn available(&self) -> impl Iterator<Item = &LibraryRoot> {
+        self.0.iter().filter(|root| root.is_available())
+    }
+
+    pub fn get(&self, root_id: RootId) -> Option<&LibraryRoot> {
+        self.0.iter().find(|root| root.root_id == root_id)
+    }
+
+    /// Full path for a path relative to a root.
+    pub fn resolve(&self, root_id: RootId, relative_path: &Path) -> Option<PathBuf> {
+        self.get(root_id).map(|root| root.path.join(relative_path))
+    }
+
+    /// Root holding a full path and the path relative to that root.
+    /// If roots are nested, then the innermost root is chosen.
+    pub fn relativize<'a>(&self, path: &'a Path) -> Option<(&LibraryRoot, &'a Path)> {
+        self.0
+            .iter()
+            .filter_map(|root| path.strip_prefix(&root.path).ok().map(|rel| (root, rel)))
+            .min_by_key(|(_, rel)| rel.components().count())
+    }
+
+    /// Predicate for whether a full path is in a root that is currently mounted.
+    /// Availability is checked once when the predicate is built, so it is cheap
+    /// to call for every picture or video in the library.
+    pub fn available_filter(&self) -> impl Fn(&Path) -> bool + '_ {
+        let available: HashSet<RootId> = self.available().map(|root| root.root_id).collect();
+        move |path| {
+            self.relativize(path)
+                .is_some_and(|(root, _)| available.contains(&root.root_id))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn root(id: i64, path: &str) -> LibraryRoot {
+        LibraryRoot {
+            root_id: RootId::new(id),
+            name: format!("root{}", id),
+            path: PathBuf::from(path),
+        }
+    }
+
+    #[test]
+    fn relativize_chooses_innermost_root() {
+        let roots = LibraryRoots::new(vec![
+            root(1, "/home/me/Pictures"),
+            root(2, "/home/me/Pictures/NAS"),
+        ]);
+
+        let (r, rel) = roots
+            .relativize(Path::new("/home/me/Pictures/NAS/2024/a.jpg"))
+            .unwrap();
+        assert_eq!(RootId::new(2), r.root_id);
+        assert_eq!(Path::new("2024/a.jpg"), rel);
+
+        let (r, rel) = roots
+            .relativize(Path::new("/home/me/Pictures/b.jpg"))
+            .unwrap();
+        assert_eq!(RootId::new(1), r.root_id);
+        assert_eq!(Path::new("b.jpg"), rel);
+
+        assert!(roots.relativize(Path::new("/media/c.jpg")).is_none());
+    }
+
+    #[test]
+    fn names_new_root_uniquely() {
+        let taken = ["Pictures", "Pictures 2"];
+        let root = RootConfig::from_path_unique(Path::new("/media/Pictures"), |name| {
+            taken.contains(&name)
+        });
+        assert_eq!("Pictures 3", root.name);
+    }
+}
@@ -0,0 +1,366 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::model::{LibraryRoot, LibraryRoots, RootConfig, RootId};
+use crate::path_encoding;
+use anyhow::*;
+use rusqlite;
+use rusqlite::params;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::result::Result::Ok;
+use std::sync::{Arc, Mutex};
+
+/// Repository of library roots.
+/// Root names and IDs are stored in the database, but root paths are user settings.
+#[derive(Debug, Clone)]
+pub struct Repository {
+    /// Connection to backing Sqlite database.
+    con: Arc<Mutex<rusqlite::Connection>>,
+}
+
+impl Repository {
+    pub fn open(con: Arc<Mutex<rusqlite::Connection>>) -> Result<Repository> {
+        Ok(Repository { con })
+    }
+
+    /// Adds any newly configured roots and records which roots are available.
+    /// Roots that are in the database but no longer configured are marked unavailable,
+    /// so their pictures and videos are hidden but not deleted.
+    pub fn sync(&mut self, configs: &[RootConfig]) -> Result<LibraryRoots> {
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+
+        let mut roots = Vec::with_capacity(configs.len());
+
+        // Create a scope to make borrowing of tx not be an error.
+        {
+            tx.execute("UPDATE library_roots SET is_available = FALSE", [])?;
+
+            let mut insert_stmt = tx.prepare_cached(
+                "INSERT INTO library_roots (name) VALUES (?1)
+                ON CONFLICT (name) DO NOTHING",
+            )?;
+
+            let mut select_stmt =
+                tx.prepare_cached("SELECT root_id FROM library_roots WHERE name = ?1")?;
+
+            let mut update_stmt = tx.prepare_cached(
+                "UPDATE library_roots
+                SET
+                    is_available = ?2,
+                    root_path_b64 = ?3,
+                    root_path_lossy = ?4
+                WHERE root_id = ?1",
+            )?;
+
+            for config in configs {
+                insert_stmt.execute([&config.name])?;
+
+                let root_id = select_stmt.query_row([&config.name], |row| row.get(0))?;
+
+                let root = LibraryRoot {
+                    root_id: RootId::new(root_id),
+                    name: config.name.clone(),
+                    path: config.path.clone(),
+                };
+
+                update_stmt.execute(params![
+                    root.root_id.id(),
+                    root.is_available(),
+                    path_encoding::to_base64(&root.path),
+                    root.path.to_string_lossy(),
+                ])?;
+
+                roots.push(root);
+            }
+        }
+
+        tx.commit()?;
+        Ok(LibraryRoots::new(roots))
+    }
+
+    /// Finds roots by directory rather than by name, for tools such as the CLI that
+    /// are given directories instead of the user's settings. Directories that aren't
+    /// yet a root are added. Roots that weren't given are left alone, because they
+    /// are still configured elsewhere.
+    pub fn find_by_paths(&mut self, dirs: &[PathBuf]) -> Result<LibraryRoots> {
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+
+        let mut roots = Vec::with_capacity(dirs.len());
+
+        // Create a scope to make borrowing of tx not be an error.
+        {
+            let known = Self::known_roots(&tx)?;
+
+            let mut names: HashSet<String> =
+                known.iter().map(|(_, name, _)| name.clone()).collect();
+
+            let mut known: Vec<LibraryRoot> = known
+                .into_iter()
+                .filter_map(|(root_id, name, path)| {
+                    Some(LibraryRoot {
+                        root_id,
+                        name,
+                        path: path?,
+                    })
+                })
+                .collect();
+
+            let mut insert_stmt = tx.prepare_cached(
+                "INSERT INTO library_roots (
+                    name,
+                    root_path_b64,
+                    root_path_lossy
+                ) VALUES (
+                    ?1, ?2, ?3
+                )",
+            )?;
+
+            let mut update_stmt = tx.prepare_cached(
+                "UPDATE library_roots
+                SET
+                    is_available = ?2
+                WHERE root_id = ?1",
+            )?;
+
+            for dir in dirs {
+                let root = match known.iter().find(|root| is_same_dir(&root.path, dir)) {
+                    Some(root) => LibraryRoot {
+                        path: dir.clone(),
+                        ..root.clone()
+                    },
+                    None => {
+                        let config = RootConfig::from_path_unique(dir, |name| names.contains(name));
+                        insert_stmt.execute(params![
+                            config.name,
+                            path_encoding::to_base64(dir),
+                            dir.to_string_lossy(),
+                        ])?;
+
+                        let root = LibraryRoot {
+                            root_id: RootId::new(tx.last_insert_rowid()),
+                            name: config.name,
+                            path: dir.clone(),
+                        };
+                        names.insert(root.name.clone());
+                        known.push(root.clone());
+                        root
+                    }
+                };
+
+                update_stmt.execute(params![root.root_id.id(), root.is_available()])?;
+
+                roots.push(root);
+            }
+        }
+
+        tx.commit()?;
+        Ok(LibraryRoots::new(roots))
+    }
+
+    /// Config for a directory the user is adding as a root.
+    /// A directory that was a root before gets its old name back, so its pictures
+    /// and videos reappear. Otherwise, the root is named after its directory with a
+    /// suffix if any root has that name, even one that is no longer configured,
+    /// so it never takes over the pictures and videos of a removed root.
+    /// A directory that overlaps a configured root is rejected.
+    pub fn new_root_config(&self, path: &Path, configured: &[RootConfig]) -> Result<RootConfig> {
+        if let Some(root) = configured.iter().find(|root| root.overlaps(path)) {
+            bail!(
+                "{:?} overlaps library root {}: {:?}",
+                path,
+                root.name,
+                root.path
+            );
+        }
+
+        let con = self.con.lock().unwrap();
+        let known = Self::known_roots(&con)?;
+
+        let is_configured = |name: &str| configured.iter().any(|root| root.name == name);
+
+        let previous = known.iter().find(|(_, name, known_path)| {
+            !is_configured(name)
+                && known_path
+                    .as_ref()
+                    .is_some_and(|known_path| is_same_dir(known_path, path))
+        });
+
+        if let Some((_, name, _)) = previous {
+            return Ok(RootConfig {
+                name: name.clone(),
+                path: path.into(),
+            });
+        }
+
+        Ok(RootConfig::from_path_unique(path, |name| {
+            is_configured(name) || known.iter().any(|(_, known_name, _)| known_name == name)
+        }))
+    }
+
+    /// Every root in the database with its last known path, including roots
+    /// that are no longer configured.
+    fn known_roots(con: &rusqlite::Connection) -> Result<Vec<(RootId, String, Option<PathBuf>)>> {
+        let mut stmt =
+            con.prepare_cached("SELECT root_id, name, root_path_b64 FROM library_roots")?;
+
+        let result = stmt
+            .query_map([], |row| {
+                let root_id = row.get(0).map(RootId::new)?;
+                let name: String = row.get(1)?;
+                let path: Option<String> = row.get(2)?;
+                Ok((root_id, name, path))
+            })?
+            .flatten()
+            .map(|(root_id, name, path)| {
+                let path = path.and_then(|p| path_encoding::from_base64(&p).ok());
+                (root_id, name, path)
+            })
+            .collect();
+
+        Ok(result)
+    }
+}
+
+/// Do two paths refer to the same directory, even if one is relative or through a symlink?
+fn is_same_dir(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database;
+
+    fn is_available(repo: &Repository, root_id: RootId) -> bool {
+        repo.con
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT is_available FROM library_roots WHERE root_id = ?1",
+                [root_id.id()],
+                |row| row.get(0),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn finds_roots_by_path() {
+        let pictures = tempfile::tempdir().unwrap();
+        let nas = tempfile::tempdir().unwrap();
+        let other = tempfile::tempdir().unwrap();
+        for dir in [&pictures, &nas, &other] {
+            std::fs::write(dir.path().join("a.jpg"), b"").unwrap();
+        }
+
+        let con = Arc::new(Mutex::new(database::setup_in_memory().unwrap()));
+        let mut repo = Repository::open(con).unwrap();
+
+        // Roots as configured in the app, with names unrelated to their directories.
+        let app_roots = repo
+            .sync(&[
+                RootConfig {
+                    name: "Pictures".into(),
+                    path: pictures.path().into(),
+                },
+                RootConfig {
+                    name: "NAS".into(),
+                    path: nas.path().into(),
+                },
+            ])
+            .unwrap();
+        let app_roots: Vec<LibraryRoot> = app_roots.iter().cloned().collect();
+
+        // Unmounting the NAS while the app isn't running.
+        std::fs::remove_file(nas.path().join("a.jpg")).unwrap();
+
+        let roots = repo
+            .find_by_paths(&[pictures.path().into(), other.path().into()])
+            .unwrap();
+        let roots: Vec<LibraryRoot> = roots.iter().cloned().collect();
+
+        assert_eq!(app_roots[0], roots[0]);
+        assert_ne!(app_roots[0].root_id, roots[1].root_id);
+        assert_ne!(app_roots[1].root_id, roots[1].root_id);
+        assert_eq!(other.path(), roots[1].path);
+
+        // The NAS is still configured in the app, so isn't marked as unavailable.
+        assert!(is_available(&repo, roots[0].root_id));
+        assert!(is_available(&repo, roots[1].root_id));
+        assert!(is_available(&repo, app_roots[1].root_id));
+
+        // The same directory is found again rather than added twice.
+        let again = repo.find_by_paths(&[other.path().into()]).unwrap();
+        assert_eq!(Some(&roots[1]), again.iter().next());
+    }
+
+    #[test]
+    fn new_root_never_reuses_removed_root() {
+        let parent = tempfile::tempdir().unwrap();
+        let old = parent.path().join("old").join("Photos");
+        let new = parent.path().join("new").join("Photos");
+        std::fs::create_dir_all(&old).unwrap();
+        std::fs::create_dir_all(&new).unwrap();
+
+        let con = Arc::new(Mutex::new(database::setup_in_memory().unwrap()));
+        let mut repo = Repository::open(con).unwrap();
+
+        let pictures = RootConfig {
+            name: "Pictures".into(),
+            path: parent.path().join("Pictures"),
+        };
+        repo.sync(&[pictures.clone(), RootConfig::from_path(&old)])
+            .unwrap();
+
+        // Photos has been removed from the settings.
+        repo.sync(&[pictures.clone()]).unwrap();
+        let configured = [pictures];
+
+        // A different directory with the same name gets a name of its own.
+        let config = repo.new_root_config(&new, &configured).unwrap();
+        assert_eq!("Photos 2", config.name);
+
+        // The removed directory gets its old name back.
+        let config = repo.new_root_config(&old, &configured).unwrap();
+        assert_eq!("Photos", config.name);
+        assert_eq!(old, config.path);
+    }
+
+    #[test]
+    fn new_root_must_not_overlap_configured_root() {
+        let parent = tempfile::tempdir().unwrap();
+        let pictures = parent.path().join("Pictures");
+        let holidays = pictures.join("Holidays");
+        let other = parent.path().join("Other");
+        std::fs::create_dir_all(&holidays).unwrap();
+        std::fs::create_dir_all(&other).unwrap();
+
+        let con = Arc::new(Mutex::new(database::setup_in_memory().unwrap()));
+        let repo = Repository::open(con).unwrap();
+
+        let configured = [RootConfig::from_path(&pictures)];
+
+        // Inside a configured root.
+        assert!(repo.new_root_config(&holidays, &configured).is_err());
+
+        // Containing a configured root.
+        assert!(repo.new_root_config(parent.path(), &configured).is_err());
+
+        // The same directory through a different path.
+        assert!(repo
+            .new_root_config(&holidays.join(".."), &configured)
+            .is_err());
+
+        // A sibling directory that only shares a prefix of the name.
+        let pictures_2 = parent.path().join("Pictures 2");
+        std::fs::create_dir_all(&pictures_2).unwrap();
+        assert!(repo.new_root_config(&pictures_2, &configured).is_ok());
+        assert!(repo.new_root_config(&other, &configured).is_ok());
+    }
+}
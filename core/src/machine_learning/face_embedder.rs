@@ -0,0 +1,105 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+
+use opencv::core::Mat;
+use opencv::imgcodecs;
+use opencv::objdetect::FaceRecognizerSF;
+use opencv::prelude::*;
+
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT};
+
+use tracing::info;
+
+use crate::people::embedding;
+use crate::people::model::DetectedFace;
+
+/// Computes face embeddings with the OpenCV SFace face recognition model.
+/// Embeddings are computed once for each detected face and then stored, so that
+/// recognizing and clustering faces are just comparisons of embeddings.
+pub struct FaceEmbedder {
+    /// Path to OpenCV face recognition model
+    model_path: PathBuf,
+}
+
+impl FaceEmbedder {
+    const MODEL_URL: &'static str =
+        "https://github.com/blissd/fotema-opencv_zoo/raw/fotema-1.0/models/face_recognition_sface/face_recognition_sface_2021dec.onnx";
+
+    pub fn build(cache_dir: &Path) -> Result<Self> {
+        let model_path = {
+            let base_path = cache_dir.join("opencv_models");
+            std::fs::create_dir_all(&base_path)?;
+            base_path.join("face_recognition_sface_2021dec.onnx")
+        };
+
+        Self::download_model(Self::MODEL_URL, &model_path)?;
+
+        Ok(Self { model_path })
+    }
+
+    /// Computes the normalized embedding of a face.
+    pub fn embed(&self, face: &DetectedFace) -> Result<Vec<f32>> {
+        // WARNING cannot re-use recognizer. MUST use a separate one for each face.
+        let mut face_recognizer =
+            FaceRecognizerSF::create_def(&self.model_path.to_string_lossy(), "")?;
+
+        let face_img = imgcodecs::imread_def(&face.face_path.to_string_lossy())?;
+
+        let face_landmarks = face.landmarks_as_mat();
+
+        let mut aligned_face = Mat::default();
+        face_recognizer.align_crop(&face_img, &face_landmarks, &mut aligned_face)?;
+
+        let mut face_features = Mat::default();
+        face_recognizer.feature(&aligned_face, &mut face_features)?;
+
+        Ok(embedding::normalize(face_features.data_typed::<f32>()?))
+    }
+
+    fn download_model(url: &str, destination: &Path) -> Result<()> {
+        if destination.exists() {
+            info!("Face recognition model already downloaded.");
+            return Ok(());
+        }
+
+        info!("Downloading face recognition model from {}", url);
+        info!("Model is approximately 40MB.");
+
+        let headers = {
+            let mut headers = HeaderMap::new();
+            headers.insert(ACCEPT, HeaderValue::from_static("*/*"));
+            headers
+        };
+
+        let client = reqwest::blocking::Client::new();
+        let mut response = client.get(url).headers(headers).send()?;
+
+        if response.status().is_success() {
+            let tmp_path = destination.with_extension("tmp");
+            let tmp_file = File::create(&tmp_path)?;
+            let mut writer = BufWriter::new(tmp_file);
+            while let Ok(bytes_read) = response.copy_to(&mut writer) {
+                if bytes_read == 0 {
+                    break;
+                }
+            }
+            info!("Face recognition model successfully downloaded.");
+            std::fs::rename(tmp_path, destination)?;
+
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Failed to download face recognition model: {}",
+                response.status()
+            ))
+        }
+    }
+}
@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use crate::photo::model::PictureId;
+use crate::photo::raw;
 use anyhow::*;
 
 use super::nms::Nms;
@@ -350,6 +351,18 @@ impl FaceExtractor {
     }
 
     async fn open_image(source_path: &Path) -> Result<DynamicImage> {
+        // Camera RAW files are read through their embedded JPEG preview.
+        let preview_file = if raw::is_raw(source_path) {
+            Some(raw::extract_preview_file(source_path)?)
+        } else {
+            None
+        };
+
+        let source_path = preview_file
+            .as_ref()
+            .map(|f| f.path())
+            .unwrap_or(source_path);
+
         let file = gio::File::for_path(source_path);
 
         let mut loader = glycin::Loader::new(file);
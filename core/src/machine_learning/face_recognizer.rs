@@ -2,153 +2,62 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use std::fs::File;
-use std::io::BufWriter;
-use std::path::Path;
-use std::path::PathBuf;
-
-use anyhow::{anyhow, Result};
-
-use opencv::core::Mat;
-use opencv::imgcodecs;
-use opencv::objdetect::{FaceRecognizerSF, FaceRecognizerSF_DisType};
-use opencv::prelude::*;
-
-use reqwest::header::{HeaderMap, HeaderValue, ACCEPT};
-
-use tracing::info;
-
+use crate::people::embedding;
 use crate::people::model::{DetectedFace, PersonForRecognition, PersonId};
 
+/// Recognizes faces of known people by comparing face embeddings.
 pub struct FaceRecognizer {
-    /// Person recognition data and a opencv matrix of aligned face features.
-    people: Vec<(PersonForRecognition, Mat)>,
-
-    /// Path to OpenCV face recognition model
-    model_path: PathBuf,
+    /// Person recognition data and normalized embeddings of their confirmed faces.
+    people: Vec<(PersonForRecognition, Vec<Vec<f32>>)>,
 }
 
 impl FaceRecognizer {
-    //const COSINE_SIMILAR_THRESH: f64 = 0.363;
-    const L2NORM_SIMILAR_THRESH: f64 = 1.128;
-
-    const MODEL_URL: &'static str =
-        "https://github.com/blissd/fotema-opencv_zoo/raw/fotema-1.0/models/face_recognition_sface/face_recognition_sface_2021dec.onnx";
-
-    pub fn build(cache_dir: &Path, people: Vec<PersonForRecognition>) -> Result<Self> {
-        let model_path = {
-            let base_path = cache_dir.join("opencv_models");
-            std::fs::create_dir_all(&base_path)?;
-            base_path.join("face_recognition_sface_2021dec.onnx")
-        };
-
-        Self::download_model(Self::MODEL_URL, &model_path)?;
-
-        let mut recognizer = Self {
-            people: vec![],
-            model_path,
-        };
-
-        for person in people {
-            // WARNING cannot re-use recognizer. MUST use a separate one for each person.
-            let mut opencv_face_recognizer =
-                FaceRecognizerSF::create_def(&recognizer.model_path.to_string_lossy(), "")?;
-
-            let face_img = imgcodecs::imread_def(&person.face.face_path.to_string_lossy())?;
-
-            let face_landarks = person.face.landmarks_as_mat();
-
-            let mut aligned_face = Mat::default();
-            opencv_face_recognizer.align_crop(&face_img, &face_landarks, &mut aligned_face)?;
-
-            // Run feature extraction with given aligned_face
-            let mut face_features = Mat::default();
-            opencv_face_recognizer.feature(&aligned_face, &mut face_features)?;
-
-            recognizer.people.push((person, face_features));
-        }
+    /// Same as an L2 distance of 1.128 between normalized embeddings.
+    const COSINE_SIMILAR_THRESH: f32 = 0.363;
+
+    /// People without a face embedding can't be recognized and are skipped.
+    pub fn build(people: Vec<PersonForRecognition>) -> Self {
+        let people = people
+            .into_iter()
+            .filter(|person| !person.embeddings.is_empty())
+            .map(|person| {
+                let embeddings = person
+                    .embeddings
+                    .iter()
+                    .map(|x| embedding::normalize(x))
+                    .collect();
+                (person, embeddings)
+            })
+            .collect();
 
-        Ok(recognizer)
+        Self { people }
     }
 
-    pub fn recognize(&self, unknown_face: &DetectedFace) -> Result<Option<PersonId>> {
-        let mut face_recognizer =
-            FaceRecognizerSF::create_def(&self.model_path.to_string_lossy(), "")?;
-
-        let face_img = imgcodecs::imread_def(&unknown_face.face_path.to_string_lossy())?;
-
-        let face_landmarks = unknown_face.landmarks_as_mat();
-
-        let mut aligned_face = Mat::default();
-        face_recognizer.align_crop(&face_img, &face_landmarks, &mut aligned_face)?;
+    /// Finds the person with a confirmed face most similar to an unknown face,
+    /// and how similar the faces are. People who the face has been rejected for are skipped.
+    /// Comparing against every confirmed face, rather than one face per person,
+    /// recognizes people from different angles and at different ages.
+    /// Faces without an embedding are never recognized.
+    pub fn recognize(&self, unknown_face: &DetectedFace) -> Option<(PersonId, f32)> {
+        let face_embedding = embedding::normalize(unknown_face.embedding.as_ref()?);
 
-        let mut face_features = Mat::default();
-        face_recognizer.feature(&aligned_face, &mut face_features)?;
-
-        let best_person_and_score = self
+        let (person, score) = self
             .people
             .iter()
             .filter(|(p, _)| p.recognized_at <= unknown_face.detected_at)
-            .map(|(person, person_face_features)| {
-                let l2_score = face_recognizer.match_(
-                    &person_face_features,
-                    &face_features,
-                    FaceRecognizerSF_DisType::FR_NORM_L2.into(),
-                );
-                (
-                    person,
-                    l2_score.unwrap_or(Self::L2NORM_SIMILAR_THRESH + 100.0),
-                )
+            .filter(|(p, _)| !p.rejected_face_ids.contains(&unknown_face.face_id))
+            .flat_map(|(person, person_embeddings)| {
+                person_embeddings
+                    .iter()
+                    .map(|x| (person, embedding::similarity(x, &face_embedding)))
             })
-            // FIXME do we need to filter out NaNs?
-            .min_by_key(|x| (x.1 * 10000.0) as i32); // f64 doesn't implement Ord.
-
-        if let Some((person, l2_score)) = best_person_and_score {
-            // The internet said the l2norm should give better results than the cosine.
-            if l2_score <= Self::L2NORM_SIMILAR_THRESH {
-                return Ok(Some(person.person_id));
-            }
-        }
-
-        Ok(None)
-    }
-
-    fn download_model(url: &str, destination: &Path) -> Result<()> {
-        if destination.exists() {
-            info!("Face recognition model already downloaded.");
-            return Ok(());
-        }
+            .filter(|(_, score)| !score.is_nan())
+            .max_by(|a, b| a.1.total_cmp(&b.1))?;
 
-        info!("Downloading face recognition model from {}", url);
-        info!("Model is approximately 40MB.");
-
-        let headers = {
-            let mut headers = HeaderMap::new();
-            headers.insert(ACCEPT, HeaderValue::from_static("*/*"));
-            headers
-        };
-
-        let client = reqwest::blocking::Client::new();
-        let mut response = client.get(url).headers(headers).send()?;
-
-        if response.status().is_success() {
-            let tmp_path = destination.with_extension("tmp");
-            let tmp_file = File::create(&tmp_path)?;
-            let mut writer = BufWriter::new(tmp_file);
-            while let Ok(bytes_read) = response.copy_to(&mut writer) {
-                if bytes_read == 0 {
-                    break;
-                }
-            }
-            info!("Face recognition model successfully downloaded.");
-            std::fs::rename(tmp_path, destination)?;
-
-            Ok(())
+        if score >= Self::COSINE_SIMILAR_THRESH {
+            Some((person.person_id, score))
         } else {
-            Err(anyhow!(
-                "Failed to download face recognition model: {}",
-                response.status()
-            ))
+            None
         }
     }
 }
@@ -157,14 +66,14 @@ impl FaceRecognizer {
 mod tests {
     use super::*;
     use crate::people::model::{FaceId, Rect};
+    use chrono::{DateTime, Utc};
     use std::path::PathBuf;
 
-    #[test]
-    fn test_recognize() {
-        let person_face = DetectedFace {
-
-            face_id: FaceId::new(1),
-            face_path: PathBuf::from("/var/home/david/.var/app/app.fotema.Fotema.Devel/cache/app.fotema.Fotema.Devel/photo_faces/0003/3027/0_blaze_face_640_original.png"),
+    fn face(id: i64, detected_at: DateTime<Utc>, face_embedding: Option<Vec<f32>>) -> DetectedFace {
+        DetectedFace {
+            face_id: FaceId::new(id),
+            face_path: PathBuf::from(format!("{}_original.png", id)),
+            detected_at,
             bounds: Rect {
                 x: 0.,
                 y: 0.,
@@ -179,8 +88,84 @@ mod tests {
             left_mouth_corner: (10., 20.),
 
             confidence: 0.98,
+            embedding: face_embedding,
+        }
+    }
+
+    #[test]
+    fn test_recognize() {
+        let recognized_at = DateTime::from_timestamp(0, 0).unwrap();
+        let detected_at = DateTime::from_timestamp(60, 0).unwrap();
+
+        let person = |id: i64, embeddings: Vec<Vec<f32>>| PersonForRecognition {
+            person_id: PersonId::new(id),
+            recognized_at,
+            embeddings,
+            rejected_face_ids: vec![],
         };
 
-        let _ = FaceRecognizer::build(&person_face).unwrap();
+        let recognizer = FaceRecognizer::build(vec![
+            person(1, vec![vec![1.0, 0.0, 0.0]]),
+            person(2, vec![vec![0.0, 1.0, 0.0]]),
+            person(3, vec![]),
+        ]);
+
+        let recognize = |face: &DetectedFace| recognizer.recognize(face).map(|(id, _)| id);
+
+        // Embeddings are compared by direction, not length.
+        let unknown = face(10, detected_at, Some(vec![0.0, 2.0, 0.0]));
+        assert_eq!(
+            Some((PersonId::new(2), 1.0)),
+            recognizer.recognize(&unknown)
+        );
+
+        // Too far from anyone.
+        let unknown = face(11, detected_at, Some(vec![0.0, 0.0, 1.0]));
+        assert_eq!(None, recognize(&unknown));
+
+        let unknown = face(12, detected_at, None);
+        assert_eq!(None, recognize(&unknown));
+
+        // Faces detected before the last recognition have already been compared.
+        let unknown = face(
+            13,
+            recognized_at - chrono::TimeDelta::seconds(1),
+            Some(vec![1.0, 0.0, 0.0]),
+        );
+        assert_eq!(None, recognize(&unknown));
+    }
+
+    #[test]
+    fn test_recognize_nearest_confirmed_face() {
+        let recognized_at = DateTime::from_timestamp(0, 0).unwrap();
+        let detected_at = DateTime::from_timestamp(60, 0).unwrap();
+
+        let recognizer = FaceRecognizer::build(vec![
+            PersonForRecognition {
+                person_id: PersonId::new(1),
+                recognized_at,
+                // Facing forward, and in profile.
+                embeddings: vec![vec![1.0, 0.0, 0.0], vec![0.0, 0.0, 1.0]],
+                rejected_face_ids: vec![FaceId::new(11)],
+            },
+            PersonForRecognition {
+                person_id: PersonId::new(2),
+                recognized_at,
+                embeddings: vec![vec![0.6, 0.8, 0.0]],
+                rejected_face_ids: vec![],
+            },
+        ]);
+
+        // Closer to person 2 than to the first face of person 1,
+        // but closer still to the second face of person 1.
+        let unknown = face(10, detected_at, Some(vec![0.3, 0.3, 0.9]));
+        let (person_id, score) = recognizer.recognize(&unknown).unwrap();
+        assert_eq!(PersonId::new(1), person_id);
+        assert!(score > 0.9);
+
+        // The user has said this face isn't person 1.
+        let unknown = face(11, detected_at, Some(vec![0.3, 0.3, 0.9]));
+        let (person_id, _) = recognizer.recognize(&unknown).unwrap();
+        assert_eq!(PersonId::new(2), person_id);
     }
 }
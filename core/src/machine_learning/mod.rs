@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 //pub mod blaze_face;
+pub mod face_embedder;
 pub mod face_extractor;
 pub mod face_recognizer;
 pub mod nms;
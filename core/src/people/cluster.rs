@@ -0,0 +1,137 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::embedding::{distance, normalize};
+use super::model::FaceId;
+use std::collections::HashMap;
+
+/// Groups unknown faces that likely belong to the same person, so that the user
+/// can name a whole group of faces at once.
+#[derive(Debug, Clone)]
+pub struct ClusterPolicy {
+    /// Largest L2 distance between normalized SFace features of faces in a cluster.
+    /// Stricter than the face recognition threshold because clusters chain faces
+    /// together, so a loose threshold would merge different people.
+    pub max_distance: f32,
+
+    /// Fewest faces in a cluster.
+    pub min_len: usize,
+}
+
+impl Default for ClusterPolicy {
+    fn default() -> Self {
+        Self {
+            max_distance: 1.0,
+            min_len: 3,
+        }
+    }
+}
+
+impl ClusterPolicy {
+    /// Clusters faces by their feature vectors.
+    /// Faces are in the same cluster if a chain of faces, each close to the next,
+    /// links them together. Clusters are in descending size order.
+    pub fn cluster(&self, faces: &[(FaceId, Vec<f32>)]) -> Vec<Vec<FaceId>> {
+        let features: Vec<Vec<f32>> = faces.iter().map(|(_, x)| normalize(x)).collect();
+
+        // Disjoint set of face indices. Each face starts in a cluster of its own.
+        let mut parents: Vec<usize> = (0..faces.len()).collect();
+
+        for i in 0..features.len() {
+            for j in (i + 1)..features.len() {
+                if distance(&features[i], &features[j]) <= self.max_distance {
+                    let a = find(&mut parents, i);
+                    let b = find(&mut parents, j);
+                    parents[a.max(b)] = a.min(b);
+                }
+            }
+        }
+
+        let mut clusters: HashMap<usize, Vec<FaceId>> = HashMap::new();
+        for (index, (face_id, _)) in faces.iter().enumerate() {
+            let root = find(&mut parents, index);
+            clusters.entry(root).or_default().push(*face_id);
+        }
+
+        let mut clusters: Vec<Vec<FaceId>> = clusters
+            .into_values()
+            .filter(|cluster| cluster.len() >= self.min_len)
+            .collect();
+
+        // Largest first, with ties broken by face ID so the order is stable.
+        clusters.sort_by_key(|cluster| (std::cmp::Reverse(cluster.len()), cluster[0].id()));
+
+        clusters
+    }
+}
+
+fn find(parents: &mut [usize], mut index: usize) -> usize {
+    while parents[index] != index {
+        parents[index] = parents[parents[index]];
+        index = parents[index];
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn face(id: i64, features: &[f32]) -> (FaceId, Vec<f32>) {
+        (FaceId::new(id), features.to_vec())
+    }
+
+    fn ids(cluster: &[FaceId]) -> Vec<i64> {
+        cluster.iter().map(|x| x.id()).collect()
+    }
+
+    #[test]
+    fn clusters_close_faces() {
+        let policy = ClusterPolicy {
+            min_len: 2,
+            ..ClusterPolicy::default()
+        };
+
+        let faces = vec![
+            face(1, &[1.0, 0.0, 0.0]),
+            face(2, &[0.0, 1.0, 0.0]),
+            // Features are compared by direction, not length.
+            face(3, &[2.0, 0.1, 0.0]),
+            face(4, &[0.1, 0.9, 0.0]),
+            face(5, &[0.9, 0.3, 0.0]),
+            // Too far from anyone else to be in a cluster.
+            face(6, &[0.0, 0.0, 1.0]),
+        ];
+
+        let clusters = policy.cluster(&faces);
+
+        assert_eq!(2, clusters.len());
+        assert_eq!(vec![1, 3, 5], ids(&clusters[0]));
+        assert_eq!(vec![2, 4], ids(&clusters[1]));
+
+        // Too small with the default policy.
+        let clusters = ClusterPolicy::default().cluster(&faces);
+        assert_eq!(1, clusters.len());
+        assert_eq!(vec![1, 3, 5], ids(&clusters[0]));
+    }
+
+    #[test]
+    fn chains_faces_together() {
+        let policy = ClusterPolicy {
+            max_distance: 0.5,
+            min_len: 2,
+        };
+
+        // First and last faces are too far apart, but are linked by the middle face.
+        let faces = vec![
+            face(1, &[1.0, 0.0]),
+            face(2, &[1.0, 0.45]),
+            face(3, &[1.0, 1.0]),
+        ];
+
+        let clusters = policy.cluster(&faces);
+        assert_eq!(1, clusters.len());
+        assert_eq!(vec![1, 2, 3], ids(&clusters[0]));
+    }
+}
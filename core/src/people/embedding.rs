@@ -0,0 +1,63 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Face embeddings are SFace feature vectors. Faces of the same person have
+//! embeddings that are close together.
+
+/// Scales an embedding to unit length, because SFace compares embeddings by direction.
+pub fn normalize(embedding: &[f32]) -> Vec<f32> {
+    let norm = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        return embedding.to_vec();
+    }
+    embedding.iter().map(|x| x / norm).collect()
+}
+
+/// L2 distance between normalized embeddings.
+pub fn distance(a: &[f32], b: &[f32]) -> f32 {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| (x - y) * (x - y))
+        .sum::<f32>()
+        .sqrt()
+}
+
+/// Cosine similarity between normalized embeddings, from -1.0 to 1.0.
+pub fn similarity(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Encodes an embedding for the database as little-endian floats.
+pub fn to_bytes(embedding: &[f32]) -> Vec<u8> {
+    embedding.iter().flat_map(|x| x.to_le_bytes()).collect()
+}
+
+/// Decodes an embedding from the database.
+pub fn from_bytes(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|x| f32::from_le_bytes([x[0], x[1], x[2], x[3]]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_and_compares_embeddings() {
+        let embedding = vec![0.5, -1.25, 3.0];
+        assert_eq!(embedding, from_bytes(&to_bytes(&embedding)));
+
+        let a = normalize(&[3.0, 0.0]);
+        let b = normalize(&[0.0, 0.5]);
+        assert_eq!(vec![1.0, 0.0], a);
+        assert_eq!(0.0, distance(&a, &normalize(&[2.0, 0.0])));
+        assert_eq!(2.0f32.sqrt(), distance(&a, &b));
+
+        assert_eq!(1.0, similarity(&a, &a));
+        assert_eq!(0.0, similarity(&a, &b));
+        assert_eq!(-1.0, similarity(&a, &normalize(&[-1.0, 0.0])));
+    }
+}
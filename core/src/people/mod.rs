@@ -2,10 +2,16 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+pub mod cluster;
+pub mod embedding;
 pub mod model;
 pub mod repo;
 
+pub use cluster::ClusterPolicy;
+pub use model::ClusterId;
+pub use model::FaceCluster;
 pub use model::FaceId;
 pub use model::Person;
 pub use model::PersonId;
+pub use model::SuggestedFace;
 pub use repo::Repository;
@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use crate::photo::model::Orientation;
+use crate::photo::model::PictureId;
 use chrono::{DateTime, Utc};
 use opencv::core::Mat;
 use std::fmt::Display;
@@ -57,6 +58,39 @@ impl Display for PersonId {
     }
 }
 
+/// Database ID of a cluster of faces, which is the lowest face ID in the cluster.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClusterId(i64);
+
+impl ClusterId {
+    pub fn new(id: i64) -> Self {
+        Self(id)
+    }
+
+    /// FIXME replace this with a To/From SQL implementation.
+    pub fn id(&self) -> i64 {
+        self.0
+    }
+}
+
+impl Display for ClusterId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Unknown faces that likely belong to the same unnamed person.
+#[derive(Debug, Clone)]
+pub struct FaceCluster {
+    pub cluster_id: ClusterId,
+
+    /// Thumbnail of the most confidently detected face in the cluster.
+    pub thumbnail_path: PathBuf,
+
+    /// Faces in cluster.
+    pub face_ids: Vec<FaceId>,
+}
+
 #[derive(Debug, Clone)]
 pub struct Rect {
     pub x: f32,
@@ -101,6 +135,9 @@ pub struct DetectedFace {
     pub left_mouth_corner: (f32, f32),
 
     pub confidence: f32,
+
+    /// SFace feature vector, if it has been computed.
+    pub embedding: Option<Vec<f32>>,
 }
 
 impl DetectedFace {
@@ -139,6 +176,24 @@ pub struct PersonForRecognition {
     /// Time of last recognition
     pub recognized_at: DateTime<Utc>,
 
-    /// "Best" confirmed face for person.
-    pub face: DetectedFace,
+    /// Embeddings of all confirmed faces of person, so that the person can be
+    /// recognized at different ages, with or without glasses, and so on.
+    pub embeddings: Vec<Vec<f32>>,
+
+    /// Faces the user has said are not the person.
+    pub rejected_face_ids: Vec<FaceId>,
+}
+
+/// A face that face recognition has matched with a person, but that the user
+/// hasn't confirmed.
+#[derive(Debug, Clone)]
+pub struct SuggestedFace {
+    pub face: Face,
+
+    /// Picture the face is in.
+    pub picture_id: PictureId,
+
+    /// Cosine similarity between the face and the nearest confirmed face of the person.
+    /// None for faces matched before similarities were recorded.
+    pub similarity: Option<f32>,
 }
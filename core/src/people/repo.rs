@@ -5,17 +5,22 @@
 use crate::photo::model::PictureId;
 
 use crate::machine_learning::face_extractor;
+use crate::people::embedding;
 use crate::people::model;
 use crate::people::model::PersonForRecognition;
 use crate::people::model::Rect;
+use crate::people::ClusterId;
 use crate::people::FaceId;
 use crate::people::PersonId;
 use crate::photo::model::Orientation;
+use crate::smart_album::Query;
 
 use anyhow::*;
 use rusqlite;
 use rusqlite::params;
+use rusqlite::OptionalExtension;
 use rusqlite::Row;
+use rusqlite::Transaction;
 use std::path::{Path, PathBuf};
 use std::result::Result::Ok;
 use std::sync::{Arc, Mutex};
@@ -155,7 +160,9 @@ impl Repository {
             let mut stmt = tx.prepare_cached(
                 "UPDATE pictures_faces
                 SET
-                    is_confirmed = FALSE
+                    is_confirmed = FALSE,
+                    person_id = NULL,
+                    similarity = NULL
                 WHERE person_id = ?1",
             )?;
             stmt.execute(params![person_id.id(),])?;
@@ -205,18 +212,81 @@ impl Repository {
         Ok(result)
     }
 
-    /// All known people that must have a face recognition performed.
-    /// Select the best face for recognition, where "best" is the face with
-    /// the highest confidence for a face that the user has confirmed is a particular person.
+    /// All known people that must have a face recognition performed,
+    /// with the embeddings of every face the user has confirmed is that person,
+    /// and the faces the user has rejected as not that person.
+    /// People without any confirmed faces that have an embedding are skipped.
     pub fn find_people_for_recognition(&self) -> Result<Vec<model::PersonForRecognition>> {
         let con = self.con.lock().unwrap();
 
-        // NOTE: this is non-standard SQL that might not work in DBs that aren't SQLite.
         let mut stmt = con.prepare(
             "SELECT
                 person_id,
                 recognized_at,
+                embedding
+            FROM pictures_faces AS faces
+            INNER JOIN people USING (person_id)
+            WHERE faces.is_confirmed = TRUE
+            AND faces.embedding IS NOT NULL
+            ORDER BY person_id, face_id",
+        )?;
+
+        let result = stmt.query_map([], |row| {
+            let person_id = row.get("person_id").map(PersonId::new)?;
+            let recognized_at = row.get("recognized_at")?;
+            let face_embedding = row
+                .get("embedding")
+                .map(|x: Vec<u8>| embedding::from_bytes(&x))?;
+            Ok((person_id, recognized_at, face_embedding))
+        })?;
+
+        let mut people: Vec<model::PersonForRecognition> = Vec::new();
+
+        for (person_id, recognized_at, face_embedding) in result.flatten() {
+            match people.last_mut() {
+                Some(person) if person.person_id == person_id => {
+                    person.embeddings.push(face_embedding);
+                }
+                _ => people.push(PersonForRecognition {
+                    person_id,
+                    recognized_at,
+                    embeddings: vec![face_embedding],
+                    rejected_face_ids: vec![],
+                }),
+            }
+        }
+
+        let mut stmt = con.prepare(
+            "SELECT
+                person_id,
+                face_id
+            FROM people_rejected_faces
+            ORDER BY person_id, face_id",
+        )?;
+
+        let rejected = stmt.query_map([], |row| {
+            let person_id = row.get("person_id").map(PersonId::new)?;
+            let face_id = row.get("face_id").map(FaceId::new)?;
+            Ok((person_id, face_id))
+        })?;
+
+        for (person_id, face_id) in rejected.flatten() {
+            if let Some(person) = people.iter_mut().find(|p| p.person_id == person_id) {
+                person.rejected_face_ids.push(face_id);
+            }
+        }
+
+        Ok(people)
+    }
+
+    /// Find new faces as candidates for face recognition for a given person.
+    /// Only returns faces that haven't been recognized before for the person.
+    pub fn find_unknown_faces(&self) -> Result<Vec<model::DetectedFace>> {
+        let con = self.con.lock().unwrap();
 
+        // NOTE: this is non-standard SQL that might not work in DBs that aren't SQLite.
+        let mut stmt = con.prepare(
+            "SELECT
                 face_id,
                 detected_at,
 
@@ -242,27 +312,26 @@ impl Repository {
                 left_mouth_corner_x,
                 left_mouth_corner_y,
 
-                max(confidence) AS confidence
+                confidence,
+
+                embedding
             FROM  pictures_faces AS faces
-            INNER JOIN people USING (person_id)
-            WHERE faces.is_confirmed = TRUE
-            GROUP BY faces.person_id",
+            WHERE faces.person_id IS NULL
+            AND faces.is_ignored = FALSE",
         )?;
 
-        let result: Vec<model::PersonForRecognition> = stmt
-            .query_map([], |row| self.to_person_for_recognition(row))?
+        let result: Vec<model::DetectedFace> = stmt
+            .query_map([], |row| self.to_detected_face(row))?
             .flatten()
             .collect();
 
         Ok(result)
     }
 
-    /// Find new faces as candidates for face recognition for a given person.
-    /// Only returns faces that haven't been recognized before for the person.
-    pub fn find_unknown_faces(&self) -> Result<Vec<model::DetectedFace>> {
+    /// Finds faces that don't have an embedding yet, such as newly detected faces.
+    pub fn find_need_embedding(&self) -> Result<Vec<model::DetectedFace>> {
         let con = self.con.lock().unwrap();
 
-        // NOTE: this is non-standard SQL that might not work in DBs that aren't SQLite.
         let mut stmt = con.prepare(
             "SELECT
                 face_id,
@@ -290,9 +359,11 @@ impl Repository {
                 left_mouth_corner_x,
                 left_mouth_corner_y,
 
-                confidence
+                confidence,
+
+                embedding
             FROM  pictures_faces AS faces
-            WHERE faces.person_id IS NULL
+            WHERE faces.embedding IS NULL
             AND faces.is_ignored = FALSE",
         )?;
 
@@ -304,6 +375,233 @@ impl Repository {
         Ok(result)
     }
 
+    pub fn add_embedding(&mut self, face_id: FaceId, face_embedding: &[f32]) -> Result<()> {
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+
+        {
+            let mut stmt = tx.prepare_cached(
+                "UPDATE pictures_faces
+                SET
+                    embedding = ?2
+                WHERE face_id = ?1",
+            )?;
+
+            stmt.execute(params![face_id.id(), embedding::to_bytes(face_embedding)])?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Replaces the clusters of unknown faces with those found by the latest clustering pass.
+    pub fn save_clusters(&mut self, clusters: &[Vec<FaceId>]) -> Result<()> {
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+
+        {
+            let mut clear = tx.prepare_cached("UPDATE pictures_faces SET cluster_id = NULL")?;
+            clear.execute([])?;
+
+            let mut stmt = tx.prepare_cached(
+                "UPDATE pictures_faces
+                SET
+                    cluster_id = ?2
+                WHERE face_id = ?1",
+            )?;
+
+            for cluster in clusters {
+                let Some(cluster_id) = cluster.iter().map(|x| x.id()).min() else {
+                    continue;
+                };
+
+                for face_id in cluster {
+                    stmt.execute(params![face_id.id(), cluster_id])?;
+                }
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Clusters of faces that are still unknown, largest first.
+    /// Faces that have since been associated with a person or ignored are left out.
+    pub fn all_clusters(&self) -> Result<Vec<model::FaceCluster>> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare(
+            "SELECT
+                cluster_id,
+                face_id,
+                thumbnail_path
+            FROM pictures_faces
+            WHERE cluster_id IS NOT NULL
+            AND person_id IS NULL
+            AND is_ignored = FALSE
+            ORDER BY cluster_id ASC, confidence DESC",
+        )?;
+
+        let result = stmt.query_map([], |row| {
+            let cluster_id = row.get("cluster_id").map(ClusterId::new)?;
+            let face_id = row.get("face_id").map(FaceId::new)?;
+            let thumbnail_path = row
+                .get("thumbnail_path")
+                .map(|p: String| self.data_dir_base_path.join(p))?;
+            Ok((cluster_id, face_id, thumbnail_path))
+        })?;
+
+        let mut clusters: Vec<model::FaceCluster> = Vec::new();
+
+        for (cluster_id, face_id, thumbnail_path) in result.flatten() {
+            match clusters.last_mut() {
+                Some(cluster) if cluster.cluster_id == cluster_id => {
+                    cluster.face_ids.push(face_id);
+                }
+                _ => clusters.push(model::FaceCluster {
+                    cluster_id,
+                    thumbnail_path,
+                    face_ids: vec![face_id],
+                }),
+            }
+        }
+
+        clusters.sort_by_key(|cluster| std::cmp::Reverse(cluster.face_ids.len()));
+
+        Ok(clusters)
+    }
+
+    /// Add a new named person from all the unknown faces in a cluster.
+    pub fn add_person_from_cluster(
+        &mut self,
+        cluster_id: ClusterId,
+        name: &str,
+    ) -> Result<PersonId> {
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+
+        let person_id = {
+            let mut insert_person = tx.prepare_cached(
+                "INSERT INTO people (name, thumbnail_path)
+                SELECT ?2, thumbnail_path
+                FROM pictures_faces
+                WHERE cluster_id = ?1
+                AND person_id IS NULL
+                AND is_ignored = FALSE
+                ORDER BY confidence DESC
+                LIMIT 1",
+            )?;
+
+            let count = insert_person.execute(params![cluster_id.id(), name])?;
+            ensure!(count == 1, "No unknown faces in cluster {}", cluster_id);
+
+            let person_id = PersonId::new(tx.last_insert_rowid());
+            Self::assign_cluster(&tx, cluster_id, person_id)?;
+            person_id
+        };
+
+        tx.commit()?;
+        Ok(person_id)
+    }
+
+    /// User is marking all the unknown faces in a cluster as an existing person.
+    pub fn mark_cluster_as_person(
+        &mut self,
+        cluster_id: ClusterId,
+        person_id: PersonId,
+    ) -> Result<()> {
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+
+        Self::assign_cluster(&tx, cluster_id, person_id)?;
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn assign_cluster(
+        tx: &Transaction<'_>,
+        cluster_id: ClusterId,
+        person_id: PersonId,
+    ) -> Result<()> {
+        let mut stmt = tx.prepare_cached(
+            "UPDATE pictures_faces
+            SET
+                person_id = ?2,
+                is_confirmed = TRUE,
+                cluster_id = NULL
+            WHERE cluster_id = ?1
+            AND person_id IS NULL
+            AND is_ignored = FALSE",
+        )?;
+
+        stmt.execute(params![cluster_id.id(), person_id.id()])?;
+
+        Self::reset_recognition(tx, person_id)?;
+
+        Ok(())
+    }
+
+    /// Faces that face recognition has matched with a person, but that the user hasn't
+    /// confirmed, from most to least similar to the person.
+    pub fn find_suggested_faces(&self, person_id: PersonId) -> Result<Vec<model::SuggestedFace>> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare(
+            "SELECT
+                faces.face_id AS face_id,
+                faces.thumbnail_path AS face_thumbnail_path,
+                faces.picture_id AS picture_id,
+                faces.similarity AS similarity,
+                pictures.orientation
+            FROM pictures_faces AS faces
+            INNER JOIN pictures USING (picture_id)
+            WHERE faces.person_id = ?1
+            AND faces.is_confirmed = FALSE
+            AND faces.is_ignored = FALSE
+            ORDER BY faces.similarity IS NULL, faces.similarity DESC, faces.face_id ASC",
+        )?;
+
+        let result = stmt
+            .query_map([person_id.id()], |row| {
+                let (face, _) = self.to_face_and_person(row)?;
+                let picture_id = row.get("picture_id").map(PictureId::new)?;
+                let similarity = row.get("similarity")?;
+                Ok(model::SuggestedFace {
+                    face,
+                    picture_id,
+                    similarity,
+                })
+            })?
+            .flatten()
+            .collect();
+
+        Ok(result)
+    }
+
+    /// Faces of a person, confirmed faces first.
+    pub fn find_faces_for_person(&self, person_id: PersonId) -> Result<Vec<model::Face>> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare(
+            "SELECT
+                faces.face_id AS face_id,
+                faces.thumbnail_path AS face_thumbnail_path,
+                pictures.orientation
+            FROM pictures_faces AS faces
+            INNER JOIN pictures USING (picture_id)
+            WHERE faces.person_id = ?1
+            AND faces.is_ignored = FALSE
+            ORDER BY faces.is_confirmed DESC, faces.face_id ASC",
+        )?;
+
+        let result = stmt
+            .query_map([person_id.id()], |row| {
+                self.to_face_and_person(row).map(|(face, _)| face)
+            })?
+            .flatten()
+            .collect();
+
+        Ok(result)
+    }
+
     /// Finds all pictures that feature a known person.
     pub fn find_pictures_for_person(&self, person_id: PersonId) -> Result<Vec<PictureId>> {
         let con = self.con.lock().unwrap();
@@ -335,7 +633,8 @@ impl Repository {
                 SET
                     is_ignored = TRUE,
                     is_confirmed = FALSE,
-                    person_id = NULL
+                    person_id = NULL,
+                    similarity = NULL
                 WHERE face_id = ?1",
             )?;
 
@@ -526,6 +825,8 @@ impl Repository {
             )?;
 
             stmt.execute(params![face_id.id(), person_id.id(),])?;
+
+            Self::reset_recognition(&tx, person_id)?;
         }
 
         tx.commit()?;
@@ -537,6 +838,7 @@ impl Repository {
         &mut self,
         face_id: FaceId,
         person_id: PersonId,
+        similarity: f32,
     ) -> Result<()> {
         let mut con = self.con.lock().unwrap();
         let tx = con.transaction()?;
@@ -546,11 +848,12 @@ impl Repository {
                 "UPDATE pictures_faces
                 SET
                     person_id = ?2,
-                    is_confirmed = FALSE
+                    is_confirmed = FALSE,
+                    similarity = ?3
                 WHERE face_id = ?1",
             )?;
 
-            stmt.execute(params![face_id.id(), person_id.id(),])?;
+            stmt.execute(params![face_id.id(), person_id.id(), similarity])?;
         }
 
         tx.commit()?;
@@ -576,26 +879,378 @@ impl Repository {
         Ok(())
     }
 
+    /// User is saying a face isn't the person it is associated with.
+    /// The face will never be suggested for that person again.
     pub fn mark_not_person(&mut self, face_id: FaceId) -> Result<()> {
         let mut con = self.con.lock().unwrap();
         let tx = con.transaction()?;
 
         {
+            let person_id: Option<PersonId> = tx
+                .query_row(
+                    "SELECT person_id FROM pictures_faces WHERE face_id = ?1",
+                    [face_id.id()],
+                    |row| row.get::<_, Option<i64>>(0),
+                )
+                .optional()?
+                .flatten()
+                .map(PersonId::new);
+
+            if let Some(person_id) = person_id {
+                Self::reject(&tx, face_id, person_id)?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// User is confirming many faces suggested by face recognition are a person.
+    /// Faces that are no longer suggested for the person are left alone.
+    pub fn confirm_faces(&mut self, person_id: PersonId, face_ids: &[FaceId]) -> Result<()> {
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+
+        {
+            let mut stmt = tx.prepare_cached(
+                "UPDATE pictures_faces
+                SET
+                    is_confirmed = TRUE
+                WHERE face_id = ?1
+                AND person_id = ?2",
+            )?;
+
+            for face_id in face_ids {
+                stmt.execute(params![face_id.id(), person_id.id()])?;
+            }
+
+            Self::reset_recognition(&tx, person_id)?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// User is rejecting many faces suggested by face recognition for a person.
+    /// The faces will never be suggested for that person again.
+    pub fn reject_faces(&mut self, person_id: PersonId, face_ids: &[FaceId]) -> Result<()> {
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+
+        for face_id in face_ids {
+            Self::reject(&tx, *face_id, person_id)?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn reject(tx: &Transaction<'_>, face_id: FaceId, person_id: PersonId) -> Result<()> {
+        let mut stmt = tx.prepare_cached(
+            "INSERT OR IGNORE INTO people_rejected_faces (person_id, face_id)
+            VALUES (?1, ?2)",
+        )?;
+
+        stmt.execute(params![person_id.id(), face_id.id()])?;
+
+        let mut stmt = tx.prepare_cached(
+            "UPDATE pictures_faces
+            SET
+                person_id = NULL,
+                is_confirmed = FALSE,
+                similarity = NULL
+            WHERE face_id = ?1
+            AND person_id = ?2",
+        )?;
+
+        stmt.execute(params![face_id.id(), person_id.id()])?;
+
+        Ok(())
+    }
+
+    /// Merge two people that are the same person. All faces of the first person are moved
+    /// to the second person, who keeps their name and thumbnail. The first person is deleted.
+    pub fn merge_people(&mut self, from: PersonId, into: PersonId) -> Result<()> {
+        ensure!(from != into, "Can't merge person {} with themselves", from);
+
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+
+        {
+            // A face that isn't one person isn't the other person either.
+            let mut stmt = tx.prepare_cached(
+                "INSERT OR IGNORE INTO people_rejected_faces (person_id, face_id)
+                SELECT ?2, face_id
+                FROM people_rejected_faces
+                WHERE person_id = ?1",
+            )?;
+            stmt.execute(params![from.id(), into.id()])?;
+
+            let mut stmt = tx.prepare_cached(
+                "UPDATE pictures_faces
+                SET
+                    person_id = ?2
+                WHERE person_id = ?1",
+            )?;
+            stmt.execute(params![from.id(), into.id()])?;
+
+            // Drop suggestions the user has rejected for either person.
             let mut stmt = tx.prepare_cached(
                 "UPDATE pictures_faces
                 SET
                     person_id = NULL,
-                    is_confirmed = FALSE
-                WHERE face_id = ?1",
+                    similarity = NULL
+                WHERE person_id = ?1
+                AND is_confirmed = FALSE
+                AND face_id IN (
+                    SELECT face_id FROM people_rejected_faces WHERE person_id = ?1
+                )",
             )?;
+            stmt.execute(params![into.id()])?;
 
-            stmt.execute(params![face_id.id(),])?;
+            // Faces confirmed as the first person are no longer rejected by the merged person.
+            let mut stmt = tx.prepare_cached(
+                "DELETE FROM people_rejected_faces
+                WHERE person_id = ?1
+                AND face_id IN (
+                    SELECT face_id FROM pictures_faces
+                    WHERE person_id = ?1
+                    AND is_confirmed = TRUE
+                )",
+            )?;
+            stmt.execute(params![into.id()])?;
+
+            let mut stmt =
+                tx.prepare_cached("DELETE FROM people_rejected_faces WHERE person_id = ?1")?;
+            stmt.execute(params![from.id()])?;
+
+            // Faces not yet compared with the first person must be compared with the merged person.
+            let mut stmt = tx.prepare_cached(
+                "UPDATE people
+                SET
+                    recognized_at = MIN(
+                        recognized_at,
+                        (SELECT recognized_at FROM people WHERE person_id = ?1)
+                    )
+                WHERE person_id = ?2",
+            )?;
+            stmt.execute(params![from.id(), into.id()])?;
+
+            // Smart albums of the first person must now match the merged person.
+            let mut stmt = tx.prepare_cached("SELECT smart_album_id, query FROM smart_albums")?;
+            let smart_albums: Vec<(i64, String)> = stmt
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .flatten()
+                .collect();
+
+            let mut stmt =
+                tx.prepare_cached("UPDATE smart_albums SET query = ?2 WHERE smart_album_id = ?1")?;
+
+            for (smart_album_id, query) in smart_albums {
+                // Invalid queries are skipped when loading smart albums, so are left alone.
+                let Ok(query) = query.parse::<Query>() else {
+                    continue;
+                };
+
+                let replaced = query.replace_person(from, into);
+                if replaced != query {
+                    stmt.execute(params![smart_album_id, replaced.to_string()])?;
+                }
+            }
+
+            let mut stmt = tx.prepare_cached("DELETE FROM people WHERE person_id = ?1")?;
+            stmt.execute(params![from.id()])?;
         }
 
         tx.commit()?;
         Ok(())
     }
 
+    /// Split faces from the people they are associated with, and associate them with
+    /// an existing person.
+    pub fn move_faces(&mut self, face_ids: &[FaceId], person_id: PersonId) -> Result<()> {
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+
+        let owners = Self::detach_faces(&tx, face_ids)?;
+        Self::attach_faces(&tx, face_ids, person_id)?;
+
+        for owner in owners.into_iter().filter(|x| *x != person_id) {
+            Self::fix_person_thumbnail(&tx, owner)?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Split faces from the people they are associated with, and associate them with
+    /// a new person.
+    pub fn add_person_from_faces(&mut self, face_ids: &[FaceId], name: &str) -> Result<PersonId> {
+        ensure!(!face_ids.is_empty(), "No faces for new person");
+
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+
+        let person_id = {
+            let owners = Self::detach_faces(&tx, face_ids)?;
+
+            // Owners must stop using a moved face as their thumbnail
+            // before the new person can use it.
+            for owner in owners {
+                Self::fix_person_thumbnail(&tx, owner)?;
+            }
+
+            let mut stmt = tx.prepare_cached(
+                "SELECT
+                    thumbnail_path,
+                    confidence,
+                    EXISTS (
+                        SELECT 1 FROM people WHERE people.thumbnail_path = faces.thumbnail_path
+                    ) AS is_used
+                FROM pictures_faces AS faces
+                WHERE face_id = ?1",
+            )?;
+
+            let mut thumbnail: Option<(bool, f32, String)> = None;
+            for face_id in face_ids {
+                let (thumbnail_path, confidence, is_used): (String, f32, bool) = stmt
+                    .query_row([face_id.id()], |row| {
+                        Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+                    })?;
+
+                // Prefer thumbnails that no one else is using, then the clearest faces.
+                let is_better = match thumbnail {
+                    Some((used, conf, _)) => (!is_used, confidence) > (!used, conf),
+                    None => true,
+                };
+
+                if is_better {
+                    thumbnail = Some((is_used, confidence, thumbnail_path));
+                }
+            }
+
+            let Some((_, _, thumbnail_path)) = thumbnail else {
+                bail!("No faces for new person");
+            };
+
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO people (name, thumbnail_path)
+                VALUES (?1, ?2)",
+            )?;
+            stmt.execute(params![name, thumbnail_path])?;
+
+            let person_id = PersonId::new(tx.last_insert_rowid());
+            Self::attach_faces(&tx, face_ids, person_id)?;
+            person_id
+        };
+
+        tx.commit()?;
+        Ok(person_id)
+    }
+
+    /// Disassociate faces from people, returning the people.
+    fn detach_faces(tx: &Transaction<'_>, face_ids: &[FaceId]) -> Result<Vec<PersonId>> {
+        let mut owners: Vec<PersonId> = vec![];
+
+        let mut select = tx.prepare_cached(
+            "SELECT person_id FROM pictures_faces WHERE face_id = ?1 AND person_id IS NOT NULL",
+        )?;
+
+        let mut update = tx.prepare_cached(
+            "UPDATE pictures_faces
+            SET
+                person_id = NULL,
+                is_confirmed = FALSE,
+                similarity = NULL
+            WHERE face_id = ?1",
+        )?;
+
+        for face_id in face_ids {
+            let owner = select
+                .query_row([face_id.id()], |row| row.get(0).map(PersonId::new))
+                .optional()?;
+
+            if let Some(owner) = owner {
+                if !owners.contains(&owner) {
+                    owners.push(owner);
+                }
+            }
+
+            update.execute([face_id.id()])?;
+        }
+
+        Ok(owners)
+    }
+
+    /// Confirm faces are a person, even if previously rejected.
+    fn attach_faces(tx: &Transaction<'_>, face_ids: &[FaceId], person_id: PersonId) -> Result<()> {
+        let mut update = tx.prepare_cached(
+            "UPDATE pictures_faces
+            SET
+                person_id = ?2,
+                is_confirmed = TRUE,
+                cluster_id = NULL
+            WHERE face_id = ?1",
+        )?;
+
+        let mut unreject = tx.prepare_cached(
+            "DELETE FROM people_rejected_faces
+            WHERE face_id = ?1
+            AND person_id = ?2",
+        )?;
+
+        for face_id in face_ids {
+            update.execute(params![face_id.id(), person_id.id()])?;
+            unreject.execute(params![face_id.id(), person_id.id()])?;
+        }
+
+        Self::reset_recognition(tx, person_id)?;
+
+        Ok(())
+    }
+
+    /// Face recognition only compares a person with faces detected since it last ran
+    /// for them, so a person with new confirmed faces must be compared with every face again.
+    fn reset_recognition(tx: &Transaction<'_>, person_id: PersonId) -> Result<()> {
+        let mut stmt = tx.prepare_cached(
+            "UPDATE people
+            SET
+                recognized_at = '1970-01-01 00:00:00'
+            WHERE person_id = ?1",
+        )?;
+
+        stmt.execute(params![person_id.id()])?;
+
+        Ok(())
+    }
+
+    /// If a person's thumbnail is no longer one of their faces, then use their clearest
+    /// remaining face instead. People without any faces keep their thumbnail.
+    fn fix_person_thumbnail(tx: &Transaction<'_>, person_id: PersonId) -> Result<()> {
+        let mut stmt = tx.prepare_cached(
+            "UPDATE people
+            SET
+                thumbnail_path = (
+                    SELECT thumbnail_path
+                    FROM pictures_faces
+                    WHERE person_id = ?1
+                    ORDER BY is_confirmed DESC, confidence DESC
+                    LIMIT 1
+                )
+            WHERE person_id = ?1
+            AND EXISTS (SELECT 1 FROM pictures_faces WHERE person_id = ?1)
+            AND NOT EXISTS (
+                SELECT 1 FROM pictures_faces
+                WHERE pictures_faces.person_id = ?1
+                AND pictures_faces.thumbnail_path = people.thumbnail_path
+            )",
+        )?;
+
+        stmt.execute(params![person_id.id()])?;
+
+        Ok(())
+    }
+
     pub fn set_person_thumbnail(&mut self, person_id: PersonId, face_id: FaceId) -> Result<()> {
         let mut con = self.con.lock().unwrap();
         let tx = con.transaction()?;
@@ -720,6 +1375,10 @@ impl Repository {
 
         let detected_at = row.get("detected_at")?;
 
+        let embedding = row
+            .get("embedding")
+            .map(|x: Option<Vec<u8>>| x.map(|x| embedding::from_bytes(&x)))?;
+
         let face = model::DetectedFace {
             face_id,
             face_path,
@@ -731,25 +1390,369 @@ impl Repository {
             left_mouth_corner: (left_mouth_corner_x, left_mouth_corner_y),
             confidence,
             detected_at,
+            embedding,
         };
 
         std::result::Result::Ok(face)
     }
+}
 
-    fn to_person_for_recognition(
-        &self,
-        row: &Row<'_>,
-    ) -> rusqlite::Result<model::PersonForRecognition> {
-        let person_id = row.get("person_id").map(PersonId::new)?;
-        let recognized_at = row.get("recognized_at")?;
-        let face = self.to_detected_face(row)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smart_album;
+    use crate::test_support::TestLibrary;
+    use chrono::DateTime;
+
+    /// Repository with a picture containing faces with the given confidences.
+    fn setup(confidences: &[f32]) -> (TestLibrary, Repository, Vec<FaceId>) {
+        let library = TestLibrary::new();
+        let picture_id = library.add_pictures(&[("a.jpg", None)])[0];
+
+        let face_ids = confidences
+            .iter()
+            .enumerate()
+            .map(|(index, confidence)| {
+                library.add_face(
+                    picture_id,
+                    &format!("face_{}_thumbnail.png", index),
+                    &format!("face_{}_bounds.png", index),
+                    *confidence,
+                )
+            })
+            .collect();
 
-        let person = PersonForRecognition {
-            person_id,
-            recognized_at,
-            face,
+        let repo = Repository::open(library.path(), library.con.clone()).unwrap();
+        (library, repo, face_ids)
+    }
+
+    #[test]
+    fn names_and_merges_clusters() {
+        let (library, mut repo, faces) = setup(&[0.7, 0.9, 0.8, 0.6, 0.9, 0.5]);
+
+        repo.save_clusters(&[vec![faces[1], faces[0], faces[2]], vec![faces[3], faces[4]]])
+            .unwrap();
+
+        let clusters = repo.all_clusters().unwrap();
+        assert_eq!(2, clusters.len());
+        assert_eq!(ClusterId::new(faces[0].id()), clusters[0].cluster_id);
+        assert_eq!(vec![faces[1], faces[2], faces[0]], clusters[0].face_ids);
+        assert_eq!(
+            library.path().join("face_1_thumbnail.png"),
+            clusters[0].thumbnail_path
+        );
+
+        // Faces already assigned elsewhere are left alone.
+        repo.mark_ignore(faces[2]).unwrap();
+        let person_id = repo
+            .add_person_from_cluster(clusters[0].cluster_id, "Alice")
+            .unwrap();
+
+        let person = repo.get_person(person_id).unwrap().unwrap();
+        assert_eq!("Alice", person.name);
+        assert_eq!(
+            library.path().join("face_1_thumbnail.png"),
+            person.thumbnail_path
+        );
+
+        let mut pictures = repo.find_pictures_for_person(person_id).unwrap();
+        pictures.dedup();
+        assert_eq!(1, pictures.len());
+
+        repo.mark_cluster_as_person(clusters[1].cluster_id, person_id)
+            .unwrap();
+        assert!(repo.all_clusters().unwrap().is_empty());
+
+        let faces_of_person: Vec<FaceId> = {
+            let con = repo.con.lock().unwrap();
+            let mut stmt = con
+                .prepare(
+                    "SELECT face_id FROM pictures_faces
+                    WHERE person_id = ?1 AND is_confirmed = TRUE
+                    ORDER BY face_id",
+                )
+                .unwrap();
+            stmt.query_map([person_id.id()], |row| row.get(0).map(FaceId::new))
+                .unwrap()
+                .flatten()
+                .collect()
+        };
+        assert_eq!(
+            vec![faces[0], faces[1], faces[3], faces[4]],
+            faces_of_person
+        );
+
+        // A new clustering pass replaces the old clusters.
+        repo.save_clusters(&[vec![faces[5]]]).unwrap();
+        let clusters = repo.all_clusters().unwrap();
+        assert_eq!(1, clusters.len());
+        assert_eq!(vec![faces[5]], clusters[0].face_ids);
+    }
+
+    #[test]
+    fn stores_embeddings() {
+        let (_library, mut repo, faces) = setup(&[0.7, 0.9]);
+
+        assert_eq!(2, repo.find_need_embedding().unwrap().len());
+
+        repo.add_embedding(faces[0], &[0.6, -0.8]).unwrap();
+
+        let need_embedding = repo.find_need_embedding().unwrap();
+        assert_eq!(1, need_embedding.len());
+        assert_eq!(faces[1], need_embedding[0].face_id);
+        assert_eq!(None, need_embedding[0].embedding);
+
+        let unknown = repo.find_unknown_faces().unwrap();
+        let face = unknown.iter().find(|x| x.face_id == faces[0]).unwrap();
+        assert_eq!(Some(vec![0.6, -0.8]), face.embedding);
+    }
+
+    #[test]
+    fn recognizes_from_confirmed_faces_and_ranks_suggestions() {
+        let (_library, mut repo, faces) = setup(&[0.7, 0.9, 0.8, 0.6, 0.5]);
+
+        repo.add_person(faces[0], "Alice").unwrap();
+        let person_id = repo.all_people().unwrap()[0].person_id;
+        repo.mark_as_person(faces[1], person_id).unwrap();
+
+        // Only confirmed faces with an embedding are used for recognition.
+        repo.add_embedding(faces[0], &[1.0, 0.0]).unwrap();
+        repo.add_embedding(faces[1], &[0.0, 1.0]).unwrap();
+        repo.add_embedding(faces[2], &[0.5, 0.5]).unwrap();
+
+        let people = repo.find_people_for_recognition().unwrap();
+        assert_eq!(1, people.len());
+        assert_eq!(person_id, people[0].person_id);
+        assert_eq!(vec![vec![1.0, 0.0], vec![0.0, 1.0]], people[0].embeddings);
+
+        repo.mark_as_person_unconfirmed(faces[2], person_id, 0.5)
+            .unwrap();
+        repo.mark_as_person_unconfirmed(faces[3], person_id, 0.9)
+            .unwrap();
+        repo.mark_as_person_unconfirmed(faces[4], person_id, 0.7)
+            .unwrap();
+
+        let suggested: Vec<(FaceId, Option<f32>)> = repo
+            .find_suggested_faces(person_id)
+            .unwrap()
+            .into_iter()
+            .map(|x| (x.face.face_id, x.similarity))
+            .collect();
+        assert_eq!(
+            vec![
+                (faces[3], Some(0.9)),
+                (faces[4], Some(0.7)),
+                (faces[2], Some(0.5))
+            ],
+            suggested
+        );
+
+        repo.mark_not_person(faces[3]).unwrap();
+        repo.mark_as_person(faces[4], person_id).unwrap();
+        let suggested = repo.find_suggested_faces(person_id).unwrap();
+        assert_eq!(1, suggested.len());
+        assert_eq!(faces[2], suggested[0].face.face_id);
+    }
+
+    #[test]
+    fn new_confirmed_faces_reset_recognition() {
+        let (_library, mut repo, faces) = setup(&[0.7, 0.9, 0.8, 0.6]);
+
+        repo.add_person(faces[0], "Alice").unwrap();
+        let person_id = repo.all_people().unwrap()[0].person_id;
+        repo.add_embedding(faces[0], &[1.0, 0.0]).unwrap();
+
+        let never = DateTime::from_timestamp(0, 0).unwrap();
+        let is_recognized = |repo: &Repository| {
+            repo.find_people_for_recognition().unwrap()[0].recognized_at != never
+        };
+
+        repo.mark_face_recognition_complete(person_id).unwrap();
+        assert!(is_recognized(&repo));
+        repo.mark_as_person(faces[1], person_id).unwrap();
+        assert!(!is_recognized(&repo));
+
+        repo.mark_face_recognition_complete(person_id).unwrap();
+        repo.mark_as_person_unconfirmed(faces[2], person_id, 0.5)
+            .unwrap();
+        assert!(is_recognized(&repo));
+        repo.confirm_faces(person_id, &[faces[2]]).unwrap();
+        assert!(!is_recognized(&repo));
+
+        repo.mark_face_recognition_complete(person_id).unwrap();
+        repo.move_faces(&[faces[3]], person_id).unwrap();
+        assert!(!is_recognized(&repo));
+    }
+
+    #[test]
+    fn confirms_and_rejects_faces_in_bulk() {
+        let (_library, mut repo, faces) = setup(&[0.7, 0.9, 0.8, 0.6, 0.5]);
+
+        repo.add_person(faces[0], "Alice").unwrap();
+        let person_id = repo.all_people().unwrap()[0].person_id;
+        repo.add_embedding(faces[0], &[1.0, 0.0]).unwrap();
+
+        for face_id in &faces[1..] {
+            repo.mark_as_person_unconfirmed(*face_id, person_id, 0.5)
+                .unwrap();
+        }
+
+        repo.confirm_faces(person_id, &[faces[1], faces[2]])
+            .unwrap();
+        repo.reject_faces(person_id, &[faces[3]]).unwrap();
+        repo.mark_not_person(faces[4]).unwrap();
+
+        assert!(repo.find_suggested_faces(person_id).unwrap().is_empty());
+
+        let mut pictures = repo.find_pictures_for_person(person_id).unwrap();
+        pictures.dedup();
+        assert_eq!(1, pictures.len());
+
+        let people = repo.find_people_for_recognition().unwrap();
+        assert_eq!(vec![faces[3], faces[4]], people[0].rejected_face_ids);
+
+        // Rejected faces are unknown again, and can be recognized as someone else.
+        let mut unknown: Vec<FaceId> = repo
+            .find_unknown_faces()
+            .unwrap()
+            .into_iter()
+            .map(|x| x.face_id)
+            .collect();
+        unknown.sort_by_key(|x| x.id());
+        assert_eq!(vec![faces[3], faces[4]], unknown);
+    }
+
+    #[test]
+    fn merges_people() {
+        let (library, mut repo, faces) = setup(&[0.7, 0.9, 0.8, 0.6, 0.5]);
+
+        repo.add_person(faces[0], "Alice").unwrap();
+        repo.add_person(faces[1], "Alice Again").unwrap();
+        let people = repo.all_people().unwrap();
+        let alice = people.iter().find(|x| x.name == "Alice").unwrap().person_id;
+        let again = people
+            .iter()
+            .find(|x| x.name == "Alice Again")
+            .unwrap()
+            .person_id;
+
+        repo.mark_as_person_unconfirmed(faces[2], again, 0.5)
+            .unwrap();
+        repo.mark_as_person_unconfirmed(faces[3], again, 0.5)
+            .unwrap();
+        repo.reject_faces(alice, &[faces[3]]).unwrap();
+        repo.reject_faces(again, &[faces[4]]).unwrap();
+        repo.reject_faces(alice, &[faces[1]]).unwrap();
+        repo.add_embedding(faces[0], &[1.0, 0.0]).unwrap();
+
+        assert!(repo.merge_people(alice, alice).is_err());
+        repo.merge_people(again, alice).unwrap();
+
+        let people = repo.all_people().unwrap();
+        assert_eq!(1, people.len());
+        assert_eq!(alice, people[0].person_id);
+        assert_eq!(
+            library.path().join("face_0_thumbnail.png"),
+            people[0].thumbnail_path
+        );
+
+        let faces_of_alice: Vec<FaceId> = repo
+            .find_faces_for_person(alice)
+            .unwrap()
+            .into_iter()
+            .map(|x| x.face_id)
+            .collect();
+        assert_eq!(vec![faces[0], faces[1], faces[2]], faces_of_alice);
+
+        let suggested = repo.find_suggested_faces(alice).unwrap();
+        assert_eq!(1, suggested.len());
+        assert_eq!(faces[2], suggested[0].face.face_id);
+
+        // Confirmed faces of the merged person aren't rejected.
+        let people = repo.find_people_for_recognition().unwrap();
+        assert_eq!(vec![faces[3], faces[4]], people[0].rejected_face_ids);
+        assert_eq!(2, library.count("people_rejected_faces"));
+    }
+
+    #[test]
+    fn merging_people_updates_smart_albums() {
+        let (library, mut repo, faces) = setup(&[0.7, 0.9]);
+
+        repo.add_person(faces[0], "Alice").unwrap();
+        repo.add_person(faces[1], "Alice Again").unwrap();
+        let people = repo.all_people().unwrap();
+        let alice = people.iter().find(|x| x.name == "Alice").unwrap().person_id;
+        let again = people
+            .iter()
+            .find(|x| x.name == "Alice Again")
+            .unwrap()
+            .person_id;
+
+        let mut smart_album_repo = smart_album::Repository::open(library.con.clone()).unwrap();
+        let query = |person_id| {
+            Query::And(vec![
+                Query::Not(Box::new(Query::Person(person_id))),
+                Query::Favourite,
+            ])
+        };
+        smart_album_repo.create("Alice", &query(alice)).unwrap();
+        smart_album_repo
+            .create("Alice Again", &query(again))
+            .unwrap();
+
+        repo.merge_people(again, alice).unwrap();
+
+        let smart_albums = smart_album_repo.all().unwrap();
+        assert_eq!(2, smart_albums.len());
+        assert!(smart_albums.iter().all(|x| x.query == query(alice)));
+    }
+
+    #[test]
+    fn splits_faces_from_person() {
+        let (library, mut repo, faces) = setup(&[0.7, 0.9, 0.8, 0.6]);
+
+        repo.add_person(faces[0], "Alice").unwrap();
+        let alice = repo.all_people().unwrap()[0].person_id;
+        for face_id in &faces[1..] {
+            repo.mark_as_person(*face_id, alice).unwrap();
+        }
+        repo.reject_faces(alice, &[faces[3]]).unwrap();
+        repo.add_embedding(faces[0], &[1.0, 0.0]).unwrap();
+        repo.add_embedding(faces[1], &[0.0, 1.0]).unwrap();
+
+        // Moving the face used as a thumbnail picks a new thumbnail.
+        let bob = repo
+            .add_person_from_faces(&[faces[0], faces[2]], "Bob")
+            .unwrap();
+
+        let alice_person = repo.get_person(alice).unwrap().unwrap();
+        assert_eq!(
+            library.path().join("face_1_thumbnail.png"),
+            alice_person.thumbnail_path
+        );
+
+        let bob_person = repo.get_person(bob).unwrap().unwrap();
+        assert_eq!("Bob", bob_person.name);
+        assert_eq!(
+            library.path().join("face_2_thumbnail.png"),
+            bob_person.thumbnail_path
+        );
+
+        // Moving a rejected face to a person confirms it.
+        repo.move_faces(&[faces[3]], alice).unwrap();
+
+        let face_ids = |person_id| -> Vec<FaceId> {
+            repo.find_faces_for_person(person_id)
+                .unwrap()
+                .into_iter()
+                .map(|x| x.face_id)
+                .collect()
         };
+        assert_eq!(vec![faces[1], faces[3]], face_ids(alice));
+        assert_eq!(vec![faces[0], faces[2]], face_ids(bob));
 
-        std::result::Result::Ok(person)
+        let people = repo.find_people_for_recognition().unwrap();
+        assert_eq!(2, people.len());
+        assert!(people.iter().all(|x| x.rejected_face_ids.is_empty()));
     }
 }
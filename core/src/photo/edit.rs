@@ -0,0 +1,512 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Change the capture date, location, and orientation of pictures.
+//! Changes are persisted to the picture's EXIF metadata or to an XMP sidecar,
+//! and then to the repository.
+
+use super::gps::{GPSCoord, GPSLocation};
+use super::model::Orientation;
+use super::PictureId;
+use super::Repository;
+use crate::scan::Scanner;
+use crate::xmp;
+use anyhow::*;
+use chrono::{DateTime, FixedOffset, TimeDelta};
+use exif::experimental::Writer;
+use exif::{Field, In, Rational, Tag, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+use std::result::Result::Ok;
+use strum::{AsRefStr, EnumString, FromRepr};
+use tracing::{error, info, warn};
+
+/// Where changed metadata is written.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, EnumString, AsRefStr, FromRepr)]
+#[repr(u32)]
+pub enum WriteTarget {
+    /// Write changes to an XMP sidecar next to the picture. Pictures are never modified.
+    #[default]
+    Sidecar,
+
+    /// Write changes into the picture's EXIF metadata.
+    /// Only JPEG files can be written, so other formats fall back to a sidecar.
+    File,
+}
+
+/// Metadata changes for a picture. Absent values are left unchanged.
+#[derive(Debug, Default, Clone)]
+pub struct MetadataEdit {
+    pub created_at: Option<DateTime<FixedOffset>>,
+
+    pub location: Option<GPSLocation>,
+
+    pub orientation: Option<Orientation>,
+}
+
+impl MetadataEdit {
+    pub fn is_empty(&self) -> bool {
+        self.created_at.is_none() && self.location.is_none() && self.orientation.is_none()
+    }
+}
+
+/// Write metadata changes for a picture.
+/// Returns where the changes were actually written.
+pub fn write(path: &Path, edit: &MetadataEdit, target: WriteTarget) -> Result<WriteTarget> {
+    if target == WriteTarget::File && is_jpeg(path) {
+        write_jpeg_exif(path, edit)?;
+        return Ok(WriteTarget::File);
+    }
+
+    xmp::writer::write_sidecar(path, edit)?;
+    Ok(WriteTarget::Sidecar)
+}
+
+/// Parse a time shift such as "+1h", "-30m", or "+1d2h30m15s".
+pub fn parse_time_shift(shift: &str) -> Result<TimeDelta> {
+    let shift = shift.trim();
+    let (is_negative, mut rest) = match shift.chars().next() {
+        Some('-') => (true, &shift[1..]),
+        Some('+') => (false, &shift[1..]),
+        _ => (false, shift),
+    };
+
+    if rest.is_empty() {
+        bail!("Empty time shift");
+    }
+
+    let mut delta = TimeDelta::zero();
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        if digits == 0 {
+            bail!("Expected a number in time shift: {}", shift);
+        }
+        let amount: i64 = rest[..digits].parse()?;
+        let unit = rest[digits..]
+            .chars()
+            .next()
+            .ok_or_else(|| anyhow!("Missing unit in time shift: {}", shift))?;
+
+        let part = match unit {
+            'd' => TimeDelta::try_days(amount),
+            'h' => TimeDelta::try_hours(amount),
+            'm' => TimeDelta::try_minutes(amount),
+            's' => TimeDelta::try_seconds(amount),
+            _ => bail!("Unknown unit '{}' in time shift: {}", unit, shift),
+        };
+        delta = part
+            .and_then(|part| delta.checked_add(&part))
+            .ok_or_else(|| anyhow!("Time shift too large: {}", shift))?;
+        rest = &rest[digits + unit.len_utf8()..];
+    }
+
+    Ok(if is_negative { -delta } else { delta })
+}
+
+/// Applies metadata changes to pictures and keeps the repository in step.
+#[derive(Debug, Clone)]
+pub struct Editor {
+    repo: Repository,
+    target: WriteTarget,
+}
+
+impl Editor {
+    pub fn new(repo: Repository, target: WriteTarget) -> Self {
+        Self { repo, target }
+    }
+
+    /// Apply the same change to several pictures.
+    /// Returns count of pictures changed.
+    pub fn edit(&mut self, picture_ids: &[PictureId], edit: &MetadataEdit) -> Result<usize> {
+        if edit.is_empty() {
+            return Ok(0);
+        }
+
+        let edits = picture_ids
+            .iter()
+            .map(|picture_id| (*picture_id, edit.clone()))
+            .collect();
+
+        self.apply(edits)
+    }
+
+    /// Apply a different change to each picture.
+    /// Returns count of pictures changed.
+    pub fn edit_each(&mut self, edits: Vec<(PictureId, MetadataEdit)>) -> Result<usize> {
+        self.apply(edits)
+    }
+
+    /// Shift the capture time of every picture directly in a folder, such as
+    /// to correct a camera clock set to the wrong time zone.
+    /// Returns count of pictures changed.
+    pub fn shift_time(&mut self, folder: &Path, delta: TimeDelta) -> Result<usize> {
+        // Folder and picture paths may differ by symlinks or relative components,
+        // so compare them canonically. Each parent folder is only resolved once.
+        let folder = folder
+            .canonicalize()
+            .with_context(|| format!("Failed resolving folder {:?}", folder))?;
+
+        let mut parents: HashMap<PathBuf, Option<PathBuf>> = HashMap::new();
+        let pics = self.repo.all()?.into_iter().filter(|pic| {
+            let Some(parent) = pic.path.parent() else {
+                return false;
+            };
+            let parent = parents
+                .entry(parent.to_path_buf())
+                .or_insert_with(|| parent.canonicalize().ok());
+            parent.as_ref() == Some(&folder)
+        });
+
+        let mut edits = Vec::new();
+        for pic in pics {
+            let Some(created_at) = self.repo.get_created_at(pic.picture_id)? else {
+                continue;
+            };
+
+            let edit = MetadataEdit {
+                created_at: Some(created_at + delta),
+                ..Default::default()
+            };
+            edits.push((pic.picture_id, edit));
+        }
+
+        info!(
+            "Shifting time of {} pictures in {:?} by {}",
+            edits.len(),
+            folder,
+            delta
+        );
+
+        self.apply(edits)
+    }
+
+    fn apply(&mut self, edits: Vec<(PictureId, MetadataEdit)>) -> Result<usize> {
+        let mut written = Vec::with_capacity(edits.len());
+        let mut rewritten = Vec::new();
+
+        for (picture_id, edit) in edits {
+            let Some(path) = self.repo.get_picture_path(picture_id)? else {
+                warn!("No path for picture {}, so not editing", picture_id);
+                continue;
+            };
+
+            match write(&path, &edit, self.target) {
+                Ok(target) => {
+                    if target != self.target {
+                        info!("Wrote metadata for {:?} to {:?} instead", path, target);
+                    }
+
+                    // Writing the picture changes its modification time and size.
+                    if target == WriteTarget::File {
+                        match Scanner::scan_one(&path) {
+                            Ok(file) => rewritten.push((picture_id, file)),
+                            Err(e) => error!("Failed reading rewritten file {:?}: {:?}", path, e),
+                        }
+                    }
+
+                    written.push((picture_id, edit));
+                }
+                Err(e) => {
+                    error!("Failed writing metadata for {:?}: {:?}", path, e);
+                }
+            }
+        }
+
+        let count = written.len();
+        self.repo.update_metadata(written, rewritten)?;
+        Ok(count)
+    }
+}
+
+fn is_jpeg(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+        .is_some_and(|ext| ext == "jpg" || ext == "jpeg")
+}
+
+/// Rewrite the EXIF APP1 segment of a JPEG with changed fields.
+/// Other primary image fields are kept, but the embedded EXIF thumbnail is dropped.
+fn write_jpeg_exif(path: &Path, edit: &MetadataEdit) -> Result<()> {
+    let jpeg = fs::read(path)?;
+    if !jpeg.starts_with(&[0xFF, 0xD8]) {
+        bail!("Not a JPEG file: {:?}", path);
+    }
+
+    let existing = exif::Reader::new()
+        .read_from_container(&mut Cursor::new(&jpeg))
+        .ok();
+
+    let little_endian = existing.as_ref().is_some_and(|e| e.little_endian());
+
+    let changed = changed_fields(edit);
+
+    // Pointers, offsets, and byte counts are synthesized by the writer.
+    let synthesized = [
+        Tag::ExifIFDPointer,
+        Tag::GPSInfoIFDPointer,
+        Tag::InteropIFDPointer,
+        Tag::JPEGInterchangeFormat,
+        Tag::JPEGInterchangeFormatLength,
+        Tag::StripOffsets,
+        Tag::StripByteCounts,
+        Tag::TileOffsets,
+        Tag::TileByteCounts,
+    ];
+
+    let kept: Vec<Field> = existing
+        .iter()
+        .flat_map(|e| e.fields())
+        .filter(|f| f.ifd_num == In::PRIMARY)
+        .filter(|f| !synthesized.contains(&f.tag))
+        .filter(|f| !matches!(f.value, Value::Unknown(..)))
+        .filter(|f| !changed.iter().any(|c| c.tag == f.tag))
+        .cloned()
+        .collect();
+
+    let mut writer = Writer::new();
+    for field in kept.iter().chain(changed.iter()) {
+        writer.push_field(field);
+    }
+
+    let mut tiff = Cursor::new(Vec::new());
+    writer.write(&mut tiff, little_endian)?;
+
+    let mut app1 = b"Exif\0\0".to_vec();
+    app1.extend_from_slice(tiff.get_ref());
+
+    let segment_len = app1.len() + 2;
+    if segment_len > u16::MAX as usize {
+        bail!("EXIF data too large for JPEG: {} bytes", segment_len);
+    }
+
+    let (insert_at, remove_len) = find_exif_segment(&jpeg)?;
+
+    let mut out = Vec::with_capacity(jpeg.len() + app1.len());
+    out.extend_from_slice(&jpeg[..insert_at]);
+    out.extend_from_slice(&[0xFF, 0xE1]);
+    out.extend_from_slice(&(segment_len as u16).to_be_bytes());
+    out.extend_from_slice(&app1);
+    out.extend_from_slice(&jpeg[insert_at + remove_len..]);
+
+    // Write to a temporary file and rename so a failure can't leave a half written picture.
+    let dir = path
+        .parent()
+        .ok_or_else(|| anyhow!("No parent directory: {:?}", path))?;
+    let mut tmp = tempfile::NamedTempFile::new_in(dir)?;
+    tmp.write_all(&out)?;
+    fs::set_permissions(tmp.path(), fs::metadata(path)?.permissions())?;
+    tmp.persist(path)?;
+
+    Ok(())
+}
+
+/// Find where the EXIF APP1 segment is, or should be, in a JPEG.
+/// Returns offset and length of existing segment, with a length of zero if absent.
+fn find_exif_segment(jpeg: &[u8]) -> Result<(usize, usize)> {
+    let mut offset = 2; // skip start of image marker
+    let mut insert_at = None;
+
+    while offset + 4 <= jpeg.len() {
+        if jpeg[offset] != 0xFF {
+            bail!("Malformed JPEG segment at offset {}", offset);
+        }
+
+        let marker = jpeg[offset + 1];
+
+        // Start of scan or end of image, so no more metadata segments.
+        if marker == 0xDA || marker == 0xD9 {
+            break;
+        }
+
+        let len = u16::from_be_bytes([jpeg[offset + 2], jpeg[offset + 3]]) as usize;
+        let data = jpeg.get(offset + 4..offset + 2 + len).unwrap_or_default();
+
+        if marker == 0xE1 && data.starts_with(b"Exif\0\0") {
+            return Ok((offset, len + 2));
+        }
+
+        // EXIF must follow a JFIF APP0 segment, if there is one.
+        if marker != 0xE0 && insert_at.is_none() {
+            insert_at = Some(offset);
+        }
+
+        offset += len + 2;
+    }
+
+    Ok((insert_at.unwrap_or(2), 0))
+}
+
+fn changed_fields(edit: &MetadataEdit) -> Vec<Field> {
+    fn field(tag: Tag, value: Value) -> Field {
+        Field {
+            tag,
+            ifd_num: In::PRIMARY,
+            value,
+        }
+    }
+
+    fn ascii(s: String) -> Value {
+        Value::Ascii(vec![s.into_bytes()])
+    }
+
+    fn dms(coord: &GPSCoord) -> Value {
+        let decimal = coord.to_f64().abs();
+        let degrees = decimal.trunc();
+        let minutes = ((decimal - degrees) * 60.0).trunc();
+        let seconds = ((decimal - degrees) * 60.0 - minutes) * 60.0;
+        Value::Rational(vec![
+            Rational::from((degrees as u32, 1)),
+            Rational::from((minutes as u32, 1)),
+            Rational::from(((seconds * 10_000.0).round() as u32, 10_000)),
+        ])
+    }
+
+    let mut fields = Vec::new();
+
+    if let Some(created_at) = edit.created_at {
+        let date_time = created_at.format("%Y:%m:%d %H:%M:%S").to_string();
+        let offset = created_at.format("%:z").to_string();
+        fields.push(field(Tag::DateTimeOriginal, ascii(date_time)));
+        fields.push(field(Tag::OffsetTimeOriginal, ascii(offset)));
+    }
+
+    if let Some(location) = edit.location {
+        let latitude_ref = if location.latitude.to_f64() < 0.0 {
+            "S"
+        } else {
+            "N"
+        };
+        let longitude_ref = if location.longitude.to_f64() < 0.0 {
+            "W"
+        } else {
+            "E"
+        };
+        fields.push(field(Tag::GPSLatitudeRef, ascii(latitude_ref.into())));
+        fields.push(field(Tag::GPSLatitude, dms(&location.latitude)));
+        fields.push(field(Tag::GPSLongitudeRef, ascii(longitude_ref.into())));
+        fields.push(field(Tag::GPSLongitude, dms(&location.longitude)));
+    }
+
+    if let Some(orientation) = edit.orientation {
+        fields.push(field(
+            Tag::Orientation,
+            Value::Short(vec![orientation as u16]),
+        ));
+    }
+
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::people;
+    use crate::photo::metadata;
+    use crate::test_support::TestLibrary;
+    use chrono::TimeZone;
+
+    #[test]
+    fn parses_time_shifts() {
+        assert_eq!(TimeDelta::hours(1), parse_time_shift("+1h").unwrap());
+        assert_eq!(TimeDelta::minutes(-30), parse_time_shift("-30m").unwrap());
+        assert_eq!(
+            TimeDelta::days(1) + TimeDelta::hours(2) + TimeDelta::seconds(5),
+            parse_time_shift("1d2h5s").unwrap()
+        );
+        assert!(parse_time_shift("").is_err());
+        assert!(parse_time_shift("+1").is_err());
+        assert!(parse_time_shift("+1y").is_err());
+    }
+
+    #[test]
+    fn writes_jpeg_exif() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Dandelion.jpg");
+        let test_file = Path::new(env!("CARGO_MANIFEST_DIR")).join("resources/test/Dandelion.jpg");
+        fs::copy(test_file, &path).unwrap();
+
+        let created_at = FixedOffset::east_opt(3600)
+            .unwrap()
+            .with_ymd_and_hms(2021, 6, 5, 14, 30, 0)
+            .unwrap();
+
+        let edit = MetadataEdit {
+            created_at: Some(created_at),
+            location: GPSLocation::from_f64(-33.8568, 151.2153),
+            orientation: Some(Orientation::West),
+        };
+
+        let target = write(&path, &edit, WriteTarget::File).unwrap();
+        assert_eq!(WriteTarget::File, target);
+
+        let metadata = metadata::from_path(&path).unwrap();
+        assert_eq!(Some(created_at), metadata.created_at);
+        assert_eq!(Some(6), metadata.orientation.map(|o| o as u32));
+
+        let location = metadata.location.unwrap();
+        assert!((location.latitude.to_f64() + 33.8568).abs() < 0.0001);
+        assert!((location.longitude.to_f64() - 151.2153).abs() < 0.0001);
+
+        // Fields that weren't changed are preserved.
+        assert!(metadata.content_id.is_some());
+    }
+
+    #[test]
+    fn shifts_time_of_pictures_in_folder() {
+        let library = TestLibrary::new();
+        let ts = DateTime::from_timestamp(0, 0);
+        let ids = library.add_pictures(&[("trip/a.jpg", ts), ("b.jpg", ts)]);
+        let repo = library.photo_repo();
+        let mut editor = Editor::new(repo.clone(), WriteTarget::Sidecar);
+
+        // The folder needn't be spelled the same way as the picture paths.
+        let folder = library.path().join("trip").join("..").join("trip");
+        assert_eq!(1, editor.shift_time(&folder, TimeDelta::hours(1)).unwrap());
+
+        let created_at = |id| repo.get_created_at(id).unwrap().unwrap().timestamp();
+        assert_eq!(3600, created_at(ids[0]));
+        assert_eq!(0, created_at(ids[1]));
+    }
+
+    #[test]
+    fn rescan_after_editing_file_keeps_faces() {
+        let library = TestLibrary::new();
+        let path = library.path().join("Dandelion.jpg");
+        let test_file = Path::new(env!("CARGO_MANIFEST_DIR")).join("resources/test/Dandelion.jpg");
+        fs::copy(test_file, &path).unwrap();
+
+        let mut repo = library.photo_repo();
+        repo.add_all(&library.root, &vec![Scanner::scan_one(&path).unwrap()])
+            .unwrap();
+        let picture_id = repo.all().unwrap()[0].picture_id;
+
+        let face_id = library.add_face(picture_id, "thumbnail.png", "bounds.png", 0.9);
+        let mut people_repo =
+            people::Repository::open(library.path(), library.con.clone()).unwrap();
+        people_repo.add_person(face_id, "Alice").unwrap();
+        let person_id = people_repo.all_people().unwrap()[0].person_id;
+
+        let edit = MetadataEdit {
+            orientation: Some(Orientation::West),
+            ..Default::default()
+        };
+        let mut editor = Editor::new(repo.clone(), WriteTarget::File);
+        assert_eq!(1, editor.edit(&[picture_id], &edit).unwrap());
+
+        // The edit isn't mistaken for a changed picture, so the confirmed face is kept.
+        let summary = repo
+            .add_all(&library.root, &vec![Scanner::scan_one(&path).unwrap()])
+            .unwrap();
+        assert_eq!(1, summary.unchanged);
+        assert_eq!(
+            1,
+            people_repo.find_faces_for_person(person_id).unwrap().len()
+        );
+    }
+}
@@ -136,6 +136,32 @@ impl GPSLocation {
         })
     }
 
+    /// Build from decimal degrees, such as when a user sets a location.
+    pub fn from_f64(latitude: f64, longitude: f64) -> Option<Self> {
+        if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+            return None;
+        }
+
+        let latitude = GPSCoord {
+            sing: latitude >= 0.0,
+            deg: latitude.abs(),
+            min: None,
+            sec: None,
+        };
+
+        let longitude = GPSCoord {
+            sing: longitude >= 0.0,
+            deg: longitude.abs(),
+            min: None,
+            sec: None,
+        };
+
+        Some(Self {
+            latitude,
+            longitude,
+        })
+    }
+
     pub fn to_cell_index(&self, resolution: Resolution) -> Result<CellIndex> {
         let ll = LatLng::new(self.latitude.to_f64(), self.longitude.to_f64())?;
         Ok(ll.to_cell(resolution))
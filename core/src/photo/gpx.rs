@@ -0,0 +1,237 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Geotag pictures from GPX tracks, such as those recorded by a phone while
+//! taking pictures with a camera that has no GPS.
+//! Each picture gets the location of the track point nearest to its capture time.
+
+use super::gps::GPSLocation;
+use super::PictureId;
+use anyhow::*;
+use chrono::{DateTime, FixedOffset, NaiveDateTime, TimeDelta, Utc};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::reader::Reader;
+use std::fs;
+use std::path::Path;
+use std::result::Result::Ok;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrackPoint {
+    pub time: DateTime<Utc>,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// Timed points from one or more GPX tracks, in ascending time order.
+#[derive(Debug, Default, Clone)]
+pub struct Track {
+    points: Vec<TrackPoint>,
+}
+
+impl Track {
+    /// Read a GPX file.
+    pub fn open(path: &Path) -> Result<Track> {
+        let xml = fs::read_to_string(path).with_context(|| format!("Opening {:?}", path))?;
+        Self::parse(&xml).with_context(|| format!("Parsing {:?}", path))
+    }
+
+    /// Parse GPX. Track points without a time or a valid location are skipped.
+    pub fn parse(xml: &str) -> Result<Track> {
+        let mut reader = Reader::from_str(xml);
+        reader.config_mut().trim_text(true);
+
+        let mut points = Vec::new();
+
+        // Location of the track point being parsed, and its time once found.
+        let mut point: Option<(Option<(f64, f64)>, Option<DateTime<Utc>>)> = None;
+        let mut is_time = false;
+
+        loop {
+            match reader.read_event()? {
+                Event::Start(e) => match e.local_name().as_ref() {
+                    b"trkpt" => point = Some((location(&e)?, None)),
+                    b"time" => is_time = point.is_some(),
+                    _ => {}
+                },
+                Event::Text(text) if is_time => {
+                    if let Some((_, ref mut time)) = point {
+                        *time = parse_time(&text.unescape()?);
+                    }
+                }
+                Event::End(e) => match e.local_name().as_ref() {
+                    b"trkpt" => {
+                        if let Some((Some((latitude, longitude)), Some(time))) = point.take() {
+                            points.push(TrackPoint {
+                                time,
+                                latitude,
+                                longitude,
+                            });
+                        }
+                    }
+                    b"time" => is_time = false,
+                    _ => {}
+                },
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+
+        ensure!(!points.is_empty(), "No timed track points in GPX");
+
+        points.sort_by_key(|p| p.time);
+        Ok(Track { points })
+    }
+
+    /// Add the points of another track, such as one recorded on another day.
+    pub fn extend(&mut self, other: Track) {
+        self.points.extend(other.points);
+        self.points.sort_by_key(|p| p.time);
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// The track point nearest in time, if it is no further away than the tolerance.
+    pub fn nearest(&self, time: DateTime<Utc>, tolerance: TimeDelta) -> Option<&TrackPoint> {
+        let index = self.points.partition_point(|p| p.time < time);
+
+        let before = index.checked_sub(1).and_then(|i| self.points.get(i));
+        let after = self.points.get(index);
+
+        [before, after]
+            .into_iter()
+            .flatten()
+            .map(|p| ((p.time - time).abs(), p))
+            .filter(|(gap, _)| *gap <= tolerance)
+            .min_by_key(|(gap, _)| *gap)
+            .map(|(_, p)| p)
+    }
+
+    /// Locate pictures by their capture times.
+    /// The clock offset is added to each capture time to get the true time, such as
+    /// "-2h" for a camera set to UTC+2 that doesn't record its time zone.
+    /// Pictures taken too far from any track point are skipped.
+    pub fn locate(
+        &self,
+        pictures: &[(PictureId, DateTime<FixedOffset>)],
+        clock_offset: TimeDelta,
+        tolerance: TimeDelta,
+    ) -> Vec<(PictureId, GPSLocation)> {
+        pictures
+            .iter()
+            .filter_map(|(picture_id, created_at)| {
+                let time = created_at.to_utc() + clock_offset;
+                let point = self.nearest(time, tolerance)?;
+                let location = GPSLocation::from_f64(point.latitude, point.longitude)?;
+                Some((*picture_id, location))
+            })
+            .collect()
+    }
+}
+
+fn location(element: &BytesStart) -> Result<Option<(f64, f64)>> {
+    let mut latitude = None;
+    let mut longitude = None;
+
+    for attribute in element.attributes() {
+        let attribute = attribute?;
+        let value = attribute.unescape_value()?;
+        match attribute.key.local_name().as_ref() {
+            b"lat" => latitude = value.trim().parse::<f64>().ok(),
+            b"lon" => longitude = value.trim().parse::<f64>().ok(),
+            _ => {}
+        }
+    }
+
+    let location = latitude
+        .zip(longitude)
+        .filter(|(lat, lon)| (-90.0..=90.0).contains(lat) && (-180.0..=180.0).contains(lon));
+
+    Ok(location)
+}
+
+/// GPX times should be UTC with a time zone, but some apps leave the time zone out.
+fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    DateTime::parse_from_rfc3339(value)
+        .map(|t| t.to_utc())
+        .or_else(|_| {
+            NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f").map(|t| t.and_utc())
+        })
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GPX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="test" xmlns="http://www.topografix.com/GPX/1/1">
+  <trk>
+    <name>Porto</name>
+    <trkseg>
+      <trkpt lat="41.1400" lon="-8.6110"><ele>80</ele><time>2024-05-01T10:00:00Z</time></trkpt>
+      <trkpt lat="41.1410" lon="-8.6120"><time>2024-05-01T10:10:00Z</time></trkpt>
+      <trkpt lat="41.1420" lon="-8.6130"></trkpt>
+      <trkpt lat="41.1430" lon="-8.6140"><time>2024-05-01T12:20:00+02:00</time></trkpt>
+    </trkseg>
+  </trk>
+</gpx>"#;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().to_utc()
+    }
+
+    #[test]
+    fn parses_timed_track_points() {
+        let track = Track::parse(GPX).unwrap();
+
+        // The point without a time is skipped.
+        assert_eq!(3, track.len());
+        assert_eq!(
+            TrackPoint {
+                time: utc("2024-05-01T10:00:00Z"),
+                latitude: 41.14,
+                longitude: -8.611,
+            },
+            track.points[0]
+        );
+
+        // Times are converted to UTC.
+        assert_eq!(utc("2024-05-01T10:20:00Z"), track.points[2].time);
+
+        assert!(Track::parse("<gpx></gpx>").is_err());
+    }
+
+    #[test]
+    fn locates_pictures_by_nearest_point() {
+        let track = Track::parse(GPX).unwrap();
+        let minutes = TimeDelta::minutes;
+
+        let nearest = track.nearest(utc("2024-05-01T10:06:00Z"), minutes(5));
+        assert_eq!(Some(utc("2024-05-01T10:10:00Z")), nearest.map(|p| p.time));
+
+        // Too far from any point.
+        assert_eq!(None, track.nearest(utc("2024-05-01T10:30:00Z"), minutes(5)));
+        assert_eq!(None, track.nearest(utc("2024-05-01T09:50:00Z"), minutes(5)));
+
+        // Camera set to UTC+1, without recording its time zone.
+        let created_at = DateTime::parse_from_rfc3339("2024-05-01T11:11:00+00:00").unwrap();
+        let located = track.locate(&[(PictureId::new(1), created_at)], -minutes(60), minutes(5));
+
+        assert_eq!(1, located.len());
+        assert_eq!(PictureId::new(1), located[0].0);
+        assert_eq!(41.141, located[0].1.latitude.to_f64());
+        assert_eq!(-8.612, located[0].1.longitude.to_f64());
+
+        // Without the offset, the picture is an hour away from the track.
+        let located = track.locate(&[(PictureId::new(1), created_at)], minutes(0), minutes(5));
+        assert!(located.is_empty());
+    }
+}
@@ -4,7 +4,9 @@
 
 use super::gps::GPSLocation;
 use super::model::Orientation;
+use super::raw;
 use super::Metadata;
+use crate::xmp;
 use anyhow::*;
 use chrono::prelude::*;
 use chrono::{DateTime, FixedOffset};
@@ -25,20 +27,43 @@ use std::result::Result::Ok;
 /// 1. Orientation.
 /// 2. Motion photos.
 /// 3. GPS coordinates.
-pub const VERSION: u32 = 3;
+/// 4. Capture times are local to their time zone offset.
+/// 5. XMP sidecars and embedded XMP.
+pub const VERSION: u32 = 5;
 
-/// Extract EXIF metadata from file
+/// Extract EXIF and XMP metadata from file.
+/// XMP values, such as a corrected capture time, take precedence over EXIF values.
 pub fn from_path(path: &Path) -> Result<Metadata> {
-    let file = fs::File::open(path)?;
-    let file = &mut BufReader::new(file);
-    let exif_data = {
-        match exif::Reader::new().read_from_container(file) {
-            Ok(exif) => exif,
-            Err(_) => {
-                // Assume this error is when there is no EXIF data.
-                return Ok(Metadata::default());
-            }
+    let mut metadata = from_exif_path(path)?;
+
+    if let Some(xmp) = xmp::reader::from_path(path)? {
+        metadata.created_at = xmp.created_at.or(metadata.created_at);
+        metadata.location = xmp.location.or(metadata.location);
+        // See from_exif_path for why HEIC orientation is ignored.
+        if !is_heic(path) {
+            metadata.orientation = xmp.orientation.or(metadata.orientation);
         }
+        metadata.xmp = Some(xmp);
+    }
+
+    Ok(metadata)
+}
+
+/// Extract EXIF metadata from file
+fn from_exif_path(path: &Path) -> Result<Metadata> {
+    let exif_data = if raw::is_raw(path) {
+        raw::read_exif(path)
+    } else {
+        let file = fs::File::open(path)?;
+        let file = &mut BufReader::new(file);
+        exif::Reader::new()
+            .read_from_container(file)
+            .map_err(|e| anyhow!(e))
+    };
+
+    let Ok(exif_data) = exif_data else {
+        // Assume this error is when there is no EXIF data.
+        return Ok(Metadata::default());
     };
 
     let mut metadata = from_exif(exif_data)?;
@@ -55,18 +80,20 @@ pub fn from_path(path: &Path) -> Result<Metadata> {
     // Note that this means from_file(...) and from_raw(...) will
     // return inconsistent metadata... again :-(
 
-    let ext = path
-        .extension()
-        .and_then(|s| s.to_str())
-        .map(|s| s.to_lowercase());
-
-    if ext.is_some_and(|x| x == "heic") {
+    if is_heic(path) {
         metadata.orientation = None;
     }
 
     Ok(metadata)
 }
 
+fn is_heic(path: &Path) -> bool {
+    path.extension()
+        .and_then(|s| s.to_str())
+        .map(|s| s.to_lowercase())
+        .is_some_and(|x| x == "heic")
+}
+
 /// Extract EXIF metadata from raw buffer
 pub fn from_raw(data: Vec<u8>) -> Result<Metadata> {
     let exif_data = {
@@ -120,8 +147,9 @@ fn from_exif(exif_data: Exif) -> Result<Metadata> {
             date_time.second.into(),
         )?;
 
+        // EXIF times are local to the offset.
         let naive_date_time = date.and_time(time);
-        Some(offset.from_utc_datetime(&naive_date_time))
+        offset.from_local_datetime(&naive_date_time).single()
     }
 
     let created_at = parse_date_time(
@@ -157,6 +185,7 @@ fn from_exif(exif_data: Exif) -> Result<Metadata> {
         orientation,
         content_id,
         location,
+        xmp: None,
     };
 
     Ok(metadata)
@@ -2,18 +2,24 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+pub mod edit;
 pub mod gps;
+pub mod gpx;
 pub mod metadata;
 pub mod model;
 pub mod motion_photo;
+pub mod perceptual_hash;
+pub mod raw;
 pub mod repo;
 pub mod scanner;
 pub mod thumbnail;
 
 pub use model::PictureId;
 
+pub use edit::{Editor, MetadataEdit, WriteTarget};
 pub use model::Metadata;
 pub use motion_photo::MotionPhotoExtractor;
+pub use perceptual_hash::PerceptualHash;
 pub use repo::Repository;
 pub use scanner::Scanner;
 pub use thumbnail::Thumbnailer;
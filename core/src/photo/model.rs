@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use super::gps::GPSLocation;
+use crate::xmp::Xmp;
 use chrono::{DateTime, FixedOffset, TimeDelta, Utc};
 use std::fmt::Display;
 use std::path::PathBuf;
@@ -50,18 +51,7 @@ pub struct Picture {
 
 // scanner
 
-/// A picture on the local file system that has been scanned.
-#[derive(Debug, Clone)]
-pub struct ScannedFile {
-    /// Full path to picture file.
-    pub path: PathBuf,
-
-    pub fs_created_at: Option<DateTime<Utc>>,
-
-    pub fs_modified_at: Option<DateTime<Utc>>,
-
-    pub fs_file_size_bytes: u64,
-}
+pub use crate::scan::ScannedFile;
 
 /// Extra (non-filesystem) metadata for videos
 
@@ -159,6 +149,9 @@ pub struct Metadata {
 
     // GPS location
     pub location: Option<GPSLocation>,
+
+    /// XMP from a sidecar or embedded in the picture.
+    pub xmp: Option<Xmp>,
 }
 
 impl Metadata {
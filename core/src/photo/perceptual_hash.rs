@@ -0,0 +1,50 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use anyhow::*;
+use image::imageops::FilterType;
+use std::path::Path;
+
+/// A difference hash (dHash) of an image. Similar images have hashes
+/// that differ in only a few bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PerceptualHash(u64);
+
+impl PerceptualHash {
+    pub fn new(hash: u64) -> Self {
+        Self(hash)
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.0
+    }
+
+    /// Number of bits that differ between two hashes.
+    pub fn distance(&self, other: &PerceptualHash) -> u32 {
+        (self.0 ^ other.0).count_ones()
+    }
+}
+
+/// Computes a difference hash from a thumbnail.
+///
+/// The thumbnail is shrunk to a 9x8 grayscale image and each bit records whether a pixel
+/// is brighter than its neighbour to the right. Using the thumbnail, rather than the
+/// original, is much faster and the thumbnail is already correctly oriented.
+pub fn dhash(thumbnail_path: &Path) -> Result<PerceptualHash> {
+    let img = image::open(thumbnail_path)?
+        .grayscale()
+        .resize_exact(9, 8, FilterType::Triangle)
+        .into_luma8();
+
+    let mut hash: u64 = 0;
+    for y in 0..8 {
+        for x in 0..8 {
+            let left = img.get_pixel(x, y)[0];
+            let right = img.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | u64::from(left > right);
+        }
+    }
+
+    Ok(PerceptualHash(hash))
+}
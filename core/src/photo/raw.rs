@@ -0,0 +1,185 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Camera RAW support.
+//!
+//! Fotema doesn't demosaic RAW files. Instead it uses the JPEG preview that
+//! cameras embed in each RAW file for thumbnails and viewing.
+
+use anyhow::*;
+use exif::Exif;
+use std::fs;
+use std::io::Cursor;
+use std::io::Write;
+use std::path::Path;
+use std::result::Result::Ok;
+use tempfile::NamedTempFile;
+
+/// File extensions of supported camera RAW formats.
+pub const SUFFIXES: [&str; 6] = ["arw", "cr2", "dng", "nef", "orf", "raf"];
+
+/// Is the file a camera RAW file?
+pub fn is_raw(path: &Path) -> bool {
+    path.extension()
+        .and_then(|s| s.to_str())
+        .map(|s| s.to_lowercase())
+        .is_some_and(|ext| SUFFIXES.contains(&ext.as_str()))
+}
+
+/// Extract the largest embedded JPEG preview from a RAW file.
+pub fn extract_preview(path: &Path) -> Result<Vec<u8>> {
+    let data = fs::read(path)?;
+    find_preview(&data)
+        .map(|(start, len)| data[start..start + len].to_vec())
+        .ok_or_else(|| anyhow!("No JPEG preview in {:?}", path))
+}
+
+/// Extract the largest embedded JPEG preview from a RAW file to a temporary file,
+/// for loaders that need a file path. The file is deleted when dropped.
+pub fn extract_preview_file(path: &Path) -> Result<NamedTempFile> {
+    let preview = extract_preview(path)?;
+    let mut preview_file = tempfile::Builder::new().suffix(".jpg").tempfile()?;
+    preview_file.write_all(&preview)?;
+    preview_file.flush()?;
+    Ok(preview_file)
+}
+
+/// Read EXIF data from a RAW file.
+pub fn read_exif(path: &Path) -> Result<Exif> {
+    let mut data = fs::read(path)?;
+
+    // Fujifilm RAF isn't TIFF based, but the embedded JPEG preview carries the EXIF data.
+    if data.starts_with(b"FUJIFILMCCD-RAW") {
+        let (start, len) = find_preview(&data).ok_or_else(|| anyhow!("No JPEG preview"))?;
+        let mut preview = Cursor::new(&data[start..start + len]);
+        return Ok(exif::Reader::new().read_from_container(&mut preview)?);
+    }
+
+    // Olympus ORF is TIFF with a non-standard magic number.
+    if data.starts_with(b"IIRO") || data.starts_with(b"IIRS") {
+        data[2] = 0x2A;
+        data[3] = 0x00;
+    } else if data.starts_with(b"MMOR") {
+        data[2] = 0x00;
+        data[3] = 0x2A;
+    }
+
+    Ok(exif::Reader::new().read_raw(data)?)
+}
+
+/// Finds start offset and length of largest baseline or progressive JPEG embedded in data.
+/// Lossless JPEGs are skipped because they hold the undemosaiced sensor data.
+fn find_preview(data: &[u8]) -> Option<(usize, usize)> {
+    let mut best: Option<(usize, usize)> = None;
+    let mut i = 0;
+    while i + 3 <= data.len() {
+        if data[i] == 0xFF && data[i + 1] == 0xD8 && data[i + 2] == 0xFF {
+            if let Some(len) = jpeg_length(&data[i..]) {
+                if len > best.map(|(_, best_len)| best_len).unwrap_or(0) {
+                    best = Some((i, len));
+                }
+                // Skip over this JPEG so an embedded EXIF thumbnail isn't considered.
+                i += len;
+                continue;
+            }
+        }
+        i += 1;
+    }
+    best
+}
+
+/// Length of a JPEG starting at data[0], including the end-of-image marker.
+/// None if the data isn't a baseline or progressive JPEG.
+fn jpeg_length(data: &[u8]) -> Option<usize> {
+    let u16_at = |i: usize| -> Option<usize> {
+        let bytes = data.get(i..i + 2)?;
+        Some(u16::from_be_bytes([bytes[0], bytes[1]]) as usize)
+    };
+
+    // Walk marker segments up to start-of-scan.
+    let mut i = 2;
+    let mut has_frame = false;
+    loop {
+        if *data.get(i)? != 0xFF {
+            return None;
+        }
+        let marker = *data.get(i + 1)?;
+        match marker {
+            0xFF => {
+                // fill byte
+                i += 1;
+                continue;
+            }
+            0xC0..=0xC2 => has_frame = true,
+            0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF => return None,
+            _ => {}
+        }
+        let len = u16_at(i + 2)?;
+        if len < 2 {
+            return None;
+        }
+        i += 2 + len;
+        if marker == 0xDA {
+            break;
+        }
+    }
+
+    if !has_frame {
+        return None;
+    }
+
+    // Scan entropy coded data for end-of-image. Within entropy coded data
+    // 0xFF is always followed by 0x00 (stuffing), a restart marker, or another marker.
+    while i + 1 < data.len() {
+        if data[i] == 0xFF && data[i + 1] == 0xD9 {
+            return Some(i + 2);
+        }
+        i += 1;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal JPEG marker structure: SOI, SOF0, SOS, one byte of scan data, EOI.
+    fn tiny_jpeg(sof: u8) -> Vec<u8> {
+        let mut jpeg = vec![0xFF, 0xD8];
+        jpeg.extend_from_slice(&[0xFF, sof, 0x00, 0x04, 0x08, 0x00]);
+        jpeg.extend_from_slice(&[0xFF, 0xDA, 0x00, 0x02]);
+        jpeg.extend_from_slice(&[0x12, 0xFF, 0x00, 0x34]);
+        jpeg.extend_from_slice(&[0xFF, 0xD9]);
+        jpeg
+    }
+
+    #[test]
+    fn finds_largest_baseline_preview() {
+        let small = tiny_jpeg(0xC0);
+        let lossless = tiny_jpeg(0xC3);
+
+        // Pad the entropy coded data to make a larger JPEG.
+        let mut large = tiny_jpeg(0xC0);
+        let tail = large.split_off(12);
+        large.extend_from_slice(&[0x56; 32]);
+        large.extend_from_slice(&tail);
+
+        let mut data = b"II*\x00junk".to_vec();
+        data.extend_from_slice(&small);
+        data.extend_from_slice(&[0u8; 7]);
+        let large_start = data.len();
+        data.extend_from_slice(&large);
+        data.extend_from_slice(&lossless);
+
+        assert_eq!(Some((large_start, large.len())), find_preview(&data));
+    }
+
+    #[test]
+    fn detects_raw_suffix() {
+        assert!(is_raw(Path::new("IMG_0001.CR2")));
+        assert!(is_raw(Path::new("DSC_0001.nef")));
+        assert!(!is_raw(Path::new("IMG_0001.JPG")));
+    }
+}
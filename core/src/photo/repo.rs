@@ -4,14 +4,21 @@
 
 use crate::photo::model::{Picture, PictureId, ScannedFile};
 
+use super::edit::MetadataEdit;
 use super::metadata;
 use super::model::MotionPhotoVideo;
 use super::motion_photo;
+use super::raw;
 use super::Metadata;
+use super::PerceptualHash;
+use crate::library::{LibraryRoot, LibraryRoots, RootId};
 use crate::path_encoding;
-use anyhow::{bail, Result};
+use crate::scan::ScanSummary;
+use anyhow::{anyhow, Result};
+use chrono::prelude::*;
 use rusqlite;
 use rusqlite::params;
+use rusqlite::OptionalExtension;
 use rusqlite::Row;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
@@ -20,8 +27,8 @@ use std::sync::{Arc, Mutex};
 /// Repository is backed by a Sqlite database.
 #[derive(Debug, Clone)]
 pub struct Repository {
-    /// Base path to picture library on file system
-    library_base_path: PathBuf,
+    /// Library roots holding pictures on file system
+    roots: LibraryRoots,
 
     /// Base path cache directory for photo thumbnails and motion photo videos
     cache_dir_base_path: PathBuf,
@@ -36,21 +43,17 @@ pub struct Repository {
 impl Repository {
     /// Builds a Repository and creates operational tables.
     pub fn open(
-        library_base_path: &Path,
+        roots: &LibraryRoots,
         cache_dir_base_path: &Path,
         data_dir_base_path: &Path,
         con: Arc<Mutex<rusqlite::Connection>>,
     ) -> Result<Repository> {
-        if !library_base_path.is_dir() {
-            bail!("{:?} is not a directory", library_base_path);
-        }
-
-        let library_base_path = PathBuf::from(library_base_path);
+        let roots = roots.clone();
         let cache_dir_base_path = PathBuf::from(cache_dir_base_path);
         let data_dir_base_path = PathBuf::from(data_dir_base_path);
 
         let repo = Repository {
-            library_base_path,
+            roots,
             cache_dir_base_path,
             data_dir_base_path,
             con,
@@ -59,6 +62,11 @@ impl Repository {
         Ok(repo)
     }
 
+    /// Library roots holding pictures.
+    pub fn roots(&self) -> &LibraryRoots {
+        &self.roots
+    }
+
     pub fn add_metadatas(&mut self, pics: Vec<(PictureId, Metadata)>) -> Result<()> {
         let mut con = self.con.lock().unwrap();
         let tx = con.transaction()?;
@@ -76,6 +84,7 @@ impl Repository {
                 WHERE picture_id = ?1",
             )?;
 
+            // Locations set by the user are kept.
             let mut update_geo = tx.prepare_cached(
                 "INSERT INTO pictures_geo (
                     picture_id,
@@ -86,10 +95,84 @@ impl Repository {
                 ) ON CONFLICT (picture_id) DO UPDATE SET
                     latitude = ?2,
                     longitude = ?3
+                WHERE is_user_set IS FALSE
                 ",
             )?;
 
+            let mut delete_xmp =
+                tx.prepare_cached("DELETE FROM pictures_xmp WHERE picture_id = ?1")?;
+
+            let mut delete_keywords =
+                tx.prepare_cached("DELETE FROM pictures_keywords WHERE picture_id = ?1")?;
+
+            let mut delete_regions =
+                tx.prepare_cached("DELETE FROM pictures_xmp_regions WHERE picture_id = ?1")?;
+
+            let mut insert_xmp = tx.prepare_cached(
+                "INSERT INTO pictures_xmp (
+                    picture_id,
+                    rating,
+                    title,
+                    description
+                ) VALUES (
+                    ?1, ?2, ?3, ?4
+                )",
+            )?;
+
+            let mut insert_keyword = tx.prepare_cached(
+                "INSERT OR IGNORE INTO pictures_keywords (
+                    picture_id,
+                    keyword
+                ) VALUES (
+                    ?1, ?2
+                )",
+            )?;
+
+            let mut insert_region = tx.prepare_cached(
+                "INSERT INTO pictures_xmp_regions (
+                    picture_id,
+                    name,
+                    region_type,
+                    x,
+                    y,
+                    width,
+                    height
+                ) VALUES (
+                    ?1, ?2, ?3, ?4, ?5, ?6, ?7
+                )",
+            )?;
+
             for (picture_id, metadata) in pics {
+                // XMP may have been removed since the picture was last scanned.
+                delete_xmp.execute([picture_id.id()])?;
+                delete_keywords.execute([picture_id.id()])?;
+                delete_regions.execute([picture_id.id()])?;
+
+                if let Some(ref xmp) = metadata.xmp {
+                    insert_xmp.execute(params![
+                        picture_id.id(),
+                        xmp.rating,
+                        xmp.title,
+                        xmp.description,
+                    ])?;
+
+                    for keyword in &xmp.keywords {
+                        insert_keyword.execute(params![picture_id.id(), keyword])?;
+                    }
+
+                    for region in &xmp.regions {
+                        insert_region.execute(params![
+                            picture_id.id(),
+                            region.name,
+                            region.kind,
+                            region.x,
+                            region.y,
+                            region.width,
+                            region.height,
+                        ])?;
+                    }
+                }
+
                 update_pictures.execute(params![
                     picture_id.id(),
                     metadata::VERSION,
@@ -117,6 +200,145 @@ impl Repository {
         Ok(())
     }
 
+    /// Update metadata changed by the user. Absent values are left unchanged.
+    /// Locations are marked as set by the user, so rescanning metadata won't overwrite them.
+    /// Changing the orientation forces thumbnails and perceptual hashes to be regenerated.
+    /// Pictures whose files were rewritten take the fingerprint of the rewritten file,
+    /// so a rescan doesn't mistake the edit for a changed picture and drop its faces.
+    /// Their content hashes are regenerated.
+    pub fn update_metadata(
+        &mut self,
+        edits: Vec<(PictureId, MetadataEdit)>,
+        rewritten: Vec<(PictureId, ScannedFile)>,
+    ) -> Result<()> {
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+
+        {
+            let mut update_pictures = tx.prepare_cached(
+                "UPDATE pictures
+                SET
+                    exif_created_ts = COALESCE(?2, exif_created_ts),
+                    orientation = COALESCE(?3, orientation),
+                    thumbnail_path = CASE WHEN ?3 IS NULL THEN thumbnail_path ELSE NULL END
+                WHERE picture_id = ?1",
+            )?;
+
+            let mut perceptual_hash_stmt =
+                tx.prepare_cached("DELETE FROM pictures_perceptual_hashes WHERE picture_id = ?1")?;
+
+            let mut update_geo = tx.prepare_cached(
+                "INSERT INTO pictures_geo (
+                    picture_id,
+                    latitude,
+                    longitude,
+                    is_user_set
+                ) VALUES (
+                    ?1, ?2, ?3, TRUE
+                ) ON CONFLICT (picture_id) DO UPDATE SET
+                    latitude = ?2,
+                    longitude = ?3,
+                    is_user_set = TRUE
+                ",
+            )?;
+
+            for (picture_id, edit) in edits {
+                update_pictures.execute(params![
+                    picture_id.id(),
+                    edit.created_at,
+                    edit.orientation.map(|x| x as u8),
+                ])?;
+
+                if edit.orientation.is_some() {
+                    perceptual_hash_stmt.execute([picture_id.id()])?;
+                }
+
+                if let Some(location) = edit.location {
+                    let latitude = location.latitude.to_f64_safe();
+                    let longitude = location.longitude.to_f64_safe();
+                    if latitude.is_some() && longitude.is_some() {
+                        update_geo.execute(params![picture_id.id(), latitude, longitude,])?;
+                    }
+                }
+            }
+
+            let mut fingerprint_stmt = tx.prepare_cached(
+                "UPDATE pictures
+                SET
+                    fs_modified_ts = ?2,
+                    fs_file_size_bytes = ?3
+                WHERE picture_id = ?1",
+            )?;
+
+            let mut hash_stmt =
+                tx.prepare_cached("DELETE FROM pictures_hashes WHERE picture_id = ?1")?;
+
+            for (picture_id, file) in rewritten {
+                fingerprint_stmt.execute(params![
+                    picture_id.id(),
+                    file.fs_modified_at,
+                    file.fs_file_size_bytes,
+                ])?;
+                hash_stmt.execute([picture_id.id()])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Capture time of a picture, falling back to file system times if
+    /// the picture has no EXIF metadata.
+    pub fn get_created_at(&self, picture_id: PictureId) -> Result<Option<DateTime<FixedOffset>>> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare_cached(
+            "SELECT
+                    COALESCE(
+                        pictures.exif_created_ts,
+                        pictures.exif_modified_ts,
+                        pictures.fs_created_ts,
+                        pictures.fs_modified_ts
+                    ) AS created_ts
+                FROM pictures
+                WHERE pictures.picture_id = ?1",
+        )?;
+
+        let result = stmt
+            .query_row([picture_id.id()], |row| row.get("created_ts"))
+            .optional()?
+            .flatten();
+
+        Ok(result)
+    }
+
+    /// Capture times of pictures that have an EXIF capture time, but no location.
+    pub fn find_need_geotag(&self) -> Result<Vec<(PictureId, DateTime<FixedOffset>)>> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare(
+            "SELECT
+                    pictures.picture_id,
+                    pictures.exif_created_ts
+                FROM pictures
+                LEFT OUTER JOIN pictures_geo USING (picture_id)
+                WHERE pictures_geo.picture_id IS NULL
+                AND pictures.exif_created_ts IS NOT NULL
+                AND COALESCE(pictures.is_broken, FALSE) IS FALSE
+                AND pictures.deleted_ts IS NULL
+                ORDER BY pictures.exif_created_ts ASC",
+        )?;
+
+        let result = stmt
+            .query_map([], |row| {
+                let picture_id = row.get("picture_id").map(PictureId::new)?;
+                let created_at = row.get("exif_created_ts")?;
+                Ok((picture_id, created_at))
+            })?
+            .flatten()
+            .collect();
+
+        Ok(result)
+    }
+
     pub fn add_thumbnail(&mut self, picture_id: &PictureId, thumbnail_path: &Path) -> Result<()> {
         let mut con = self.con.lock().unwrap();
         let tx = con.transaction()?;
@@ -143,6 +365,28 @@ impl Repository {
         Ok(())
     }
 
+    pub fn add_perceptual_hash(
+        &mut self,
+        picture_id: PictureId,
+        perceptual_hash: &PerceptualHash,
+    ) -> Result<()> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare_cached(
+            "INSERT INTO pictures_perceptual_hashes (
+                picture_id,
+                dhash
+            ) VALUES (
+                ?1, ?2
+            ) ON CONFLICT (picture_id) DO UPDATE SET
+                dhash = ?2",
+        )?;
+
+        // Sqlite integers are signed, so store the bits of the hash as an i64.
+        stmt.execute(params![picture_id.id(), perceptual_hash.hash() as i64])?;
+
+        Ok(())
+    }
+
     pub fn mark_broken(&mut self, picture_id: &PictureId) -> Result<()> {
         let mut con = self.con.lock().unwrap();
         let tx = con.transaction()?;
@@ -162,56 +406,241 @@ impl Repository {
         Ok(())
     }
 
-    /// Add all Pictures received from a vector.
-    pub fn add_all(&mut self, pics: &Vec<ScannedFile>) -> Result<()> {
+    /// Add all Pictures received from a vector, which must be the result of scanning the
+    /// whole of a library root. Pictures that are unchanged since the previous scan are left
+    /// alone. Changed pictures have their metadata, thumbnails, motion photo videos,
+    /// and faces re-queued for processing.
+    pub fn add_all(&mut self, root: &LibraryRoot, pics: &Vec<ScannedFile>) -> Result<ScanSummary> {
+        self.add(pics, Some(root))
+    }
+
+    /// Add or update some pictures, such as those reported by a file system watcher.
+    /// Removed pictures aren't counted because only part of the library has been scanned.
+    pub fn add_some(&mut self, pics: &[ScannedFile]) -> Result<ScanSummary> {
+        self.add(pics, None)
+    }
+
+    /// Add pictures. If `scanned_root` is set, then pics holds every picture in that root.
+    fn add(
+        &mut self,
+        pics: &[ScannedFile],
+        scanned_root: Option<&LibraryRoot>,
+    ) -> Result<ScanSummary> {
         let mut con = self.con.lock().unwrap();
         let tx = con.transaction()?;
 
+        let mut summary = ScanSummary::default();
+
+        // Face files of changed pictures, deleted once the transaction commits.
+        let mut face_files: Vec<PathBuf> = vec![];
+
         // Create a scope to make borrowing of tx not be an error.
         {
+            let previous_count: usize = match scanned_root {
+                Some(root) => tx.query_row(
+                    "SELECT COUNT(*) FROM pictures WHERE root_id = ?1 AND deleted_ts IS NULL",
+                    [root.root_id.id()],
+                    |row| row.get(0),
+                )?,
+                None => 0,
+            };
+
+            // Soft deleted items that reappear weren't counted above, so only count
+            // items that were present when calculating how many are now missing.
+            let mut seen_count: usize = 0;
+
+            let mut pic_fingerprint_stmt = tx.prepare_cached(
+                "SELECT
+                    picture_id,
+                    fs_modified_ts,
+                    fs_file_size_bytes,
+                    deleted_ts
+                FROM pictures
+                WHERE root_id = ?1
+                AND picture_path_b64 = ?2",
+            )?;
+
             let mut pic_insert_stmt = tx.prepare_cached(
                 "INSERT INTO pictures (
                     fs_created_ts,
                     fs_modified_ts,
+                    fs_file_size_bytes,
                     picture_path_b64,
                     picture_path_lossy,
                     link_path_b64,
-                    link_path_lossy
+                    link_path_lossy,
+                    is_raw,
+                    root_id
                 ) VALUES (
-                    ?1, ?2, ?3, ?4, ?5, ?6
-                ) ON CONFLICT (picture_path_b64) DO UPDATE SET
-                    fs_created_ts = ?1,
-                    fs_modified_ts = ?2
-                ",
+                    ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9
+                )",
+            )?;
+
+            let mut pic_restore_stmt = tx.prepare_cached(
+                "UPDATE pictures
+                SET
+                    deleted_ts = NULL
+                WHERE picture_id = ?1",
+            )?;
+
+            let mut pic_size_stmt = tx.prepare_cached(
+                "UPDATE pictures
+                SET
+                    fs_file_size_bytes = ?2
+                WHERE picture_id = ?1",
+            )?;
+
+            let mut pic_changed_stmt = tx.prepare_cached(
+                "UPDATE pictures
+                SET
+                    fs_created_ts = ?2,
+                    fs_modified_ts = ?3,
+                    fs_file_size_bytes = ?4,
+                    metadata_version = 0,
+                    thumbnail_path = NULL,
+                    is_broken = FALSE
+                WHERE picture_id = ?1",
+            )?;
+
+            let mut motion_photo_changed_stmt = tx.prepare_cached(
+                "UPDATE motion_photos
+                SET
+                    extract_version = 0
+                WHERE picture_id = ?1",
+            )?;
+
+            let mut face_scan_changed_stmt =
+                tx.prepare_cached("DELETE FROM pictures_face_scans WHERE picture_id = ?1")?;
+
+            // A face thumbnail can also be the thumbnail of a person, so must be kept.
+            let mut face_files_stmt = tx.prepare_cached(
+                "SELECT
+                    bounds_path,
+                    CASE
+                        WHEN thumbnail_path IN (SELECT thumbnail_path FROM people) THEN NULL
+                        ELSE thumbnail_path
+                    END AS thumbnail_path
+                FROM pictures_faces
+                WHERE picture_id = ?1",
             )?;
 
+            // Faces must go before the rescan because bounds paths are unique and reused.
+            let mut faces_changed_stmt =
+                tx.prepare_cached("DELETE FROM pictures_faces WHERE picture_id = ?1")?;
+
+            let mut hash_changed_stmt =
+                tx.prepare_cached("DELETE FROM pictures_hashes WHERE picture_id = ?1")?;
+
+            let mut perceptual_hash_changed_stmt =
+                tx.prepare_cached("DELETE FROM pictures_perceptual_hashes WHERE picture_id = ?1")?;
+
             for pic in pics {
                 // convert to relative path before saving to database
-                let picture_path = pic.path.strip_prefix(&self.library_base_path)?;
+                let (root, picture_path) = match scanned_root {
+                    Some(root) => (root, pic.path.strip_prefix(&root.path)?),
+                    None => self
+                        .roots
+                        .relativize(&pic.path)
+                        .ok_or_else(|| anyhow!("{:?} is not in a library root", pic.path))?,
+                };
                 let picture_path_b64 = path_encoding::to_base64(picture_path);
 
-                // Path without suffix so sibling pictures and videos can be related
-                let link_path = picture_path
-                    .file_stem()
-                    .and_then(|x| x.to_str())
-                    .expect("Must exist");
-
-                let link_path = picture_path.with_file_name(link_path);
-                let link_path_b64 = path_encoding::to_base64(&link_path);
+                let fingerprint = pic_fingerprint_stmt
+                    .query_row(params![root.root_id.id(), &picture_path_b64], |row| {
+                        let picture_id: i64 = row.get(0)?;
+                        let fs_modified_at: Option<DateTime<Utc>> = row.get(1)?;
+                        let fs_file_size_bytes: Option<u64> = row.get(2)?;
+                        let deleted_at: Option<DateTime<Utc>> = row.get(3)?;
+                        std::result::Result::Ok((
+                            picture_id,
+                            fs_modified_at,
+                            fs_file_size_bytes,
+                            deleted_at,
+                        ))
+                    })
+                    .optional()?;
+
+                match fingerprint {
+                    Some((picture_id, fs_modified_at, fs_file_size_bytes, deleted_at)) => {
+                        if deleted_at.is_some() {
+                            // A missing file has come back before being purged.
+                            pic_restore_stmt.execute([picture_id])?;
+                        } else {
+                            seen_count += 1;
+                        }
+
+                        if pic.is_changed(fs_modified_at, fs_file_size_bytes) {
+                            pic_changed_stmt.execute(params![
+                                picture_id,
+                                pic.fs_created_at,
+                                pic.fs_modified_at,
+                                pic.fs_file_size_bytes,
+                            ])?;
+                            motion_photo_changed_stmt.execute([picture_id])?;
+                            face_scan_changed_stmt.execute([picture_id])?;
+
+                            let files = face_files_stmt.query_map([picture_id], |row| {
+                                let bounds_path: String = row.get(0)?;
+                                let thumbnail_path: Option<String> = row.get(1)?;
+                                std::result::Result::Ok((bounds_path, thumbnail_path))
+                            })?;
+                            for (bounds_path, thumbnail_path) in files.flatten() {
+                                face_files.push(self.data_dir_base_path.join(bounds_path));
+                                if let Some(thumbnail_path) = thumbnail_path {
+                                    face_files.push(self.data_dir_base_path.join(thumbnail_path));
+                                }
+                            }
+                            faces_changed_stmt.execute([picture_id])?;
+
+                            hash_changed_stmt.execute([picture_id])?;
+                            perceptual_hash_changed_stmt.execute([picture_id])?;
+                            summary.changed += 1;
+                        } else {
+                            if fs_file_size_bytes.is_none() {
+                                pic_size_stmt
+                                    .execute(params![picture_id, pic.fs_file_size_bytes])?;
+                            }
+                            summary.unchanged += 1;
+                        }
+                    }
+                    None => {
+                        // Path without suffix so sibling pictures and videos can be related
+                        let link_path = picture_path
+                            .file_stem()
+                            .and_then(|x| x.to_str())
+                            .expect("Must exist");
+
+                        let link_path = picture_path.with_file_name(link_path);
+                        let link_path_b64 = path_encoding::to_base64(&link_path);
+
+                        pic_insert_stmt.execute(params![
+                            pic.fs_created_at,
+                            pic.fs_modified_at,
+                            pic.fs_file_size_bytes,
+                            picture_path_b64,
+                            picture_path.to_string_lossy(),
+                            link_path_b64,
+                            link_path.to_string_lossy(),
+                            raw::is_raw(picture_path),
+                            root.root_id.id(),
+                        ])?;
+                        summary.added += 1;
+                    }
+                }
+            }
 
-                pic_insert_stmt.execute(params![
-                    pic.fs_created_at,
-                    pic.fs_modified_at,
-                    picture_path_b64,
-                    picture_path.to_string_lossy(),
-                    link_path_b64,
-                    link_path.to_string_lossy(),
-                ])?;
+            if scanned_root.is_some() {
+                summary.removed = previous_count.saturating_sub(seen_count);
             }
         }
 
         tx.commit()?;
-        Ok(())
+
+        for path in face_files {
+            let _ = std::fs::remove_file(path);
+        }
+
+        Ok(summary)
     }
 
     /// Gets all pictures in the repository, in ascending order of modification timestamp.
@@ -220,6 +649,7 @@ impl Repository {
         let mut stmt = con.prepare(
             "SELECT
                     pictures.picture_id,
+                    pictures.root_id,
                     pictures.picture_path_b64,
                     pictures.thumbnail_path,
                     COALESCE(
@@ -232,6 +662,7 @@ impl Repository {
                     pictures.is_selfie
                 FROM pictures
                 WHERE COALESCE(is_broken, FALSE) IS FALSE
+                AND deleted_ts IS NULL
                 ORDER BY ordering_ts ASC",
         )?;
 
@@ -251,6 +682,7 @@ impl Repository {
         let mut stmt = con.prepare(
             "SELECT
                     pictures.picture_id,
+                    pictures.root_id,
                     pictures.picture_path_b64,
                     pictures.thumbnail_path,
                     COALESCE(
@@ -264,6 +696,7 @@ impl Repository {
                 FROM pictures
                 WHERE metadata_version < ?1
                 AND COALESCE(is_broken, FALSE) IS FALSE
+                AND deleted_ts IS NULL
                 ORDER BY ordering_ts ASC",
         )?;
 
@@ -281,6 +714,7 @@ impl Repository {
         let mut stmt = con.prepare(
             "SELECT
                     pictures.picture_id,
+                    pictures.root_id,
                     pictures.picture_path_b64,
                     pictures.thumbnail_path,
                     COALESCE(
@@ -294,7 +728,8 @@ impl Repository {
                 FROM pictures
                 FULL OUTER JOIN motion_photos USING (picture_id)
                 WHERE COALESCE(motion_photos.extract_version, 0) < ?1
-                AND COALESCE(is_broken, FALSE) IS FALSE",
+                AND COALESCE(is_broken, FALSE) IS FALSE
+                AND deleted_ts IS NULL",
         )?;
 
         let result = stmt
@@ -401,7 +836,7 @@ impl Repository {
         let picture_path: String = row.get("picture_path_b64")?;
         let picture_path =
             path_encoding::from_base64(&picture_path).map_err(|_| rusqlite::Error::InvalidQuery)?;
-        let picture_path = self.library_base_path.join(picture_path);
+        let picture_path = self.resolve(row, &picture_path)?;
 
         let thumbnail_path = row
             .get("thumbnail_path")
@@ -420,6 +855,15 @@ impl Repository {
         })
     }
 
+    /// Full path for a path relative to the library root of a row.
+    /// Fails if the root is no longer configured.
+    fn resolve(&self, row: &Row<'_>, relative_path: &Path) -> rusqlite::Result<PathBuf> {
+        let root_id = row.get("root_id").map(RootId::new)?;
+        self.roots
+            .resolve(root_id, relative_path)
+            .ok_or_else(|| rusqlite::Error::InvalidPath(relative_path.into()))
+    }
+
     fn to_cleanup_path(&self, row: &Row<'_>) -> rusqlite::Result<PathBuf> {
         let root_name: String = row.get("root_name")?;
 
@@ -431,6 +875,47 @@ impl Repository {
             })
     }
 
+    /// Soft delete a picture whose file is missing. The picture is hidden, but
+    /// not removed, so it can be restored if the file reappears.
+    pub fn mark_deleted(&mut self, picture_id: PictureId) -> Result<()> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare(
+            "UPDATE pictures
+            SET
+                deleted_ts = ?2
+            WHERE picture_id = ?1
+            AND deleted_ts IS NULL",
+        )?;
+
+        stmt.execute(params![picture_id.id(), Utc::now()])?;
+
+        Ok(())
+    }
+
+    /// Gets pictures that were soft deleted before a time and can be purged.
+    pub fn find_deleted_before(&self, before: DateTime<Utc>) -> Result<Vec<Picture>> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare(
+            "SELECT
+                    pictures.picture_id,
+                    pictures.root_id,
+                    pictures.picture_path_b64,
+                    pictures.thumbnail_path,
+                    pictures.deleted_ts AS ordering_ts,
+                    pictures.is_selfie
+                FROM pictures
+                WHERE deleted_ts < ?1",
+        )?;
+
+        let result = stmt
+            .query_map([before], |row| self.to_picture(row))?
+            .flatten()
+            .collect();
+
+        Ok(result)
+    }
+
+    /// Hard delete a picture. Everything recorded about it is deleted by cascade.
     pub fn remove(&mut self, picture_id: PictureId) -> Result<()> {
         let con = self.con.lock().unwrap();
         let mut stmt = con.prepare("DELETE FROM pictures WHERE picture_id = ?1")?;
@@ -448,6 +933,7 @@ impl Repository {
         let mut stmt = con.prepare(
             "SELECT
                     pictures.picture_id,
+                    pictures.root_id,
                     pictures.picture_path_b64,
                     COALESCE(
                         pictures.exif_created_ts,
@@ -460,6 +946,7 @@ impl Repository {
                 LEFT OUTER JOIN pictures_face_scans USING (picture_id)
                 WHERE pictures_face_scans.picture_id IS NULL
                 AND COALESCE(pictures.is_broken, FALSE) IS FALSE
+                AND pictures.deleted_ts IS NULL
                 ORDER BY ordering_ts DESC",
         )?;
 
@@ -471,11 +958,80 @@ impl Repository {
         Ok(result)
     }
 
+    /// Gets all pictures that haven't had their content hashed.
+    pub fn find_need_content_hash(&self) -> Result<Vec<Picture>> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare(
+            "SELECT
+                    pictures.picture_id,
+                    pictures.root_id,
+                    pictures.picture_path_b64,
+                    pictures.thumbnail_path,
+                    COALESCE(
+                        pictures.exif_created_ts,
+                        pictures.exif_modified_ts,
+                        pictures.fs_created_ts,
+                        pictures.fs_modified_ts,
+                        CURRENT_TIMESTAMP
+                      ) AS ordering_ts,
+                    pictures.is_selfie
+                FROM pictures
+                LEFT OUTER JOIN pictures_hashes USING (picture_id)
+                WHERE pictures_hashes.picture_id IS NULL
+                AND COALESCE(pictures.is_broken, FALSE) IS FALSE
+                AND pictures.deleted_ts IS NULL
+                ORDER BY ordering_ts ASC",
+        )?;
+
+        let result = stmt
+            .query_map([], |row| self.to_picture(row))?
+            .flatten()
+            .collect();
+
+        Ok(result)
+    }
+
+    /// Gets all pictures that have a thumbnail, but haven't had their thumbnail
+    /// perceptually hashed.
+    pub fn find_need_perceptual_hash(&self) -> Result<Vec<Picture>> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare(
+            "SELECT
+                    pictures.picture_id,
+                    pictures.root_id,
+                    pictures.picture_path_b64,
+                    pictures.thumbnail_path,
+                    COALESCE(
+                        pictures.exif_created_ts,
+                        pictures.exif_modified_ts,
+                        pictures.fs_created_ts,
+                        pictures.fs_modified_ts,
+                        CURRENT_TIMESTAMP
+                      ) AS ordering_ts,
+                    pictures.is_selfie
+                FROM pictures
+                LEFT OUTER JOIN pictures_perceptual_hashes USING (picture_id)
+                WHERE pictures_perceptual_hashes.picture_id IS NULL
+                AND pictures.thumbnail_path IS NOT NULL
+                AND COALESCE(pictures.is_broken, FALSE) IS FALSE
+                AND pictures.deleted_ts IS NULL
+                ORDER BY ordering_ts ASC",
+        )?;
+
+        let result = stmt
+            .query_map([], |row| self.to_picture(row))?
+            .flatten()
+            .collect();
+
+        Ok(result)
+    }
+
     pub fn get_picture_path(&self, picture_id: PictureId) -> Result<Option<PathBuf>> {
         let con = self.con.lock().unwrap();
         let mut stmt = con.prepare(
             "SELECT
                     pictures.picture_id,
+                    pictures.root_id,
                     pictures.picture_path_b64
                 FROM pictures
                 WHERE pictures.picture_id = ?1",
@@ -496,8 +1052,336 @@ impl Repository {
         let picture_path: String = row.get("picture_path_b64")?;
         let picture_path =
             path_encoding::from_base64(&picture_path).map_err(|_| rusqlite::Error::InvalidQuery)?;
-        let picture_path = self.library_base_path.join(picture_path);
+        let picture_path = self.resolve(row, &picture_path)?;
 
         std::result::Result::Ok((picture_id, picture_path))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{scanned, TestLibrary};
+
+    #[test]
+    fn add_all_summarises_rescan() {
+        let library = TestLibrary::new();
+        let mut repo = library.photo_repo();
+
+        let ts = Utc::now();
+        let a = library.path().join("a.jpg");
+        let b = library.path().join("b.jpg");
+        let c = library.path().join("c.jpg");
+
+        let summary = repo
+            .add_all(
+                &library.root,
+                &vec![scanned(a.clone(), ts, 10), scanned(b.clone(), ts, 20)],
+            )
+            .unwrap();
+        assert_eq!(2, summary.added);
+
+        // b changed size, a removed, c added.
+        let summary = repo
+            .add_all(
+                &library.root,
+                &vec![scanned(b.clone(), ts, 21), scanned(c.clone(), ts, 30)],
+            )
+            .unwrap();
+        assert_eq!(
+            ScanSummary {
+                added: 1,
+                changed: 1,
+                unchanged: 0,
+                removed: 1,
+            },
+            summary
+        );
+
+        // Removed pictures stay in the database until cleaned up, so a reappears unchanged.
+        let summary = repo
+            .add_all(
+                &library.root,
+                &vec![
+                    scanned(a.clone(), ts, 10),
+                    scanned(b.clone(), ts, 21),
+                    scanned(c.clone(), ts, 30),
+                ],
+            )
+            .unwrap();
+        assert_eq!(3, summary.unchanged);
+        assert_eq!(0, summary.added + summary.changed + summary.removed);
+
+        // Pictures already soft deleted aren't removed again.
+        let picture_id = repo
+            .all()
+            .unwrap()
+            .into_iter()
+            .find(|pic| pic.path == a)
+            .unwrap()
+            .picture_id;
+        repo.mark_deleted(picture_id).unwrap();
+        let summary = repo
+            .add_all(&library.root, &vec![scanned(b, ts, 21), scanned(c, ts, 30)])
+            .unwrap();
+        assert_eq!(2, summary.unchanged);
+        assert_eq!(0, summary.removed);
+    }
+
+    #[test]
+    fn soft_deleted_picture_restored_by_rescan() {
+        let library = TestLibrary::new();
+        let mut repo = library.photo_repo();
+
+        let a = scanned(library.path().join("a.jpg"), Utc::now(), 10);
+        repo.add_all(&library.root, &vec![a.clone()]).unwrap();
+        let picture_id = repo.all().unwrap()[0].picture_id;
+
+        repo.mark_deleted(picture_id).unwrap();
+        assert!(repo.all().unwrap().is_empty());
+
+        // Only purge once the grace period has passed.
+        let past = Utc::now() - chrono::TimeDelta::days(1);
+        let future = Utc::now() + chrono::TimeDelta::days(1);
+        assert!(repo.find_deleted_before(past).unwrap().is_empty());
+        assert_eq!(1, repo.find_deleted_before(future).unwrap().len());
+
+        repo.add_all(&library.root, &vec![a]).unwrap();
+        assert_eq!(picture_id, repo.all().unwrap()[0].picture_id);
+        assert!(repo.find_deleted_before(future).unwrap().is_empty());
+    }
+
+    #[test]
+    fn find_need_geotag_skips_located_pictures() {
+        let library = TestLibrary::new();
+        let mut repo = library.photo_repo();
+        let ids = library.add_pictures(&[("a.jpg", None), ("b.jpg", None), ("c.jpg", None)]);
+
+        let created_at = DateTime::parse_from_rfc3339("2024-05-01T10:00:00+01:00").unwrap();
+        let location = crate::photo::gps::GPSLocation::from_f64(41.14, -8.61);
+
+        // a has no capture time, b has no location, and c has both.
+        repo.update_metadata(
+            vec![
+                (
+                    ids[1],
+                    MetadataEdit {
+                        created_at: Some(created_at),
+                        ..Default::default()
+                    },
+                ),
+                (
+                    ids[2],
+                    MetadataEdit {
+                        created_at: Some(created_at),
+                        location,
+                        ..Default::default()
+                    },
+                ),
+            ],
+            vec![],
+        )
+        .unwrap();
+
+        assert_eq!(vec![(ids[1], created_at)], repo.find_need_geotag().unwrap());
+    }
+
+    #[test]
+    fn rescan_keeps_user_set_location() {
+        let library = TestLibrary::new();
+        let mut repo = library.photo_repo();
+        let ids = library.add_pictures(&[("a.jpg", None), ("b.jpg", None)]);
+
+        let exif = |latitude| Metadata {
+            location: crate::photo::gps::GPSLocation::from_f64(latitude, -8.61),
+            ..Default::default()
+        };
+
+        let latitude = |picture_id: PictureId| -> f64 {
+            library
+                .con
+                .lock()
+                .unwrap()
+                .query_row(
+                    "SELECT latitude FROM pictures_geo WHERE picture_id = ?1",
+                    [picture_id.id()],
+                    |row| row.get(0),
+                )
+                .unwrap()
+        };
+
+        repo.add_metadatas(vec![(ids[0], exif(41.0)), (ids[1], exif(41.0))])
+            .unwrap();
+
+        repo.update_metadata(
+            vec![(
+                ids[0],
+                MetadataEdit {
+                    location: crate::photo::gps::GPSLocation::from_f64(38.7, -9.1),
+                    ..Default::default()
+                },
+            )],
+            vec![],
+        )
+        .unwrap();
+
+        repo.add_metadatas(vec![(ids[0], exif(42.0)), (ids[1], exif(42.0))])
+            .unwrap();
+
+        assert_eq!(38.7, latitude(ids[0]));
+        assert_eq!(42.0, latitude(ids[1]));
+    }
+
+    #[test]
+    fn changed_picture_loses_faces_before_rescan() {
+        let library = TestLibrary::new();
+        let mut repo = library.photo_repo();
+
+        let ts = Utc::now();
+        let a = library.path().join("a.jpg");
+        repo.add_all(&library.root, &vec![scanned(a.clone(), ts, 10)])
+            .unwrap();
+        let picture_id = repo.all().unwrap()[0].picture_id;
+
+        // Same file names as face detection would produce for every scan of the picture.
+        let detect_faces = || {
+            for index in 0..2 {
+                let thumbnail_path = format!("{}_test_thumbnail.png", index);
+                let bounds_path = format!("{}_test_original.png", index);
+                library.add_face(picture_id, &thumbnail_path, &bounds_path, 0.9);
+                std::fs::write(library.path().join(thumbnail_path), b"").unwrap();
+                std::fs::write(library.path().join(bounds_path), b"").unwrap();
+            }
+            let con = library.con.lock().unwrap();
+            con.execute(
+                "INSERT INTO pictures_face_scans (picture_id, is_broken, face_count, scan_ts)
+                VALUES (?1, FALSE, 2, CURRENT_TIMESTAMP)",
+                [picture_id.id()],
+            )
+            .unwrap();
+            con.execute(
+                "INSERT INTO people (name, thumbnail_path) VALUES ('Alice', '0_test_thumbnail.png')",
+                [],
+            )
+            .unwrap();
+            con.execute(
+                "INSERT INTO people_rejected_faces (person_id, face_id)
+                SELECT people.person_id, MAX(face_id) FROM people, pictures_faces",
+                [],
+            )
+            .unwrap();
+        };
+
+        detect_faces();
+        assert!(repo.find_need_face_scan().unwrap().is_empty());
+
+        // Unchanged pictures keep their faces.
+        repo.add_all(&library.root, &vec![scanned(a.clone(), ts, 10)])
+            .unwrap();
+        assert!(repo.find_need_face_scan().unwrap().is_empty());
+
+        let summary = repo
+            .add_all(&library.root, &vec![scanned(a, ts, 11)])
+            .unwrap();
+        assert_eq!(1, summary.changed);
+        assert_eq!(1, repo.find_need_face_scan().unwrap().len());
+
+        assert_eq!(0, library.count("pictures_faces"));
+        assert_eq!(0, library.count("people_rejected_faces"));
+
+        // Face files are removed, except a thumbnail still used by a person.
+        assert!(library.path().join("0_test_thumbnail.png").exists());
+        assert!(!library.path().join("1_test_thumbnail.png").exists());
+        assert!(!library.path().join("0_test_original.png").exists());
+        assert!(!library.path().join("1_test_original.png").exists());
+
+        // Detecting faces again doesn't clash with the faces of the previous scan.
+        library
+            .con
+            .lock()
+            .unwrap()
+            .execute("DELETE FROM people", [])
+            .unwrap();
+        detect_faces();
+        assert_eq!(2, library.count("pictures_faces"));
+    }
+
+    #[test]
+    fn remove_cascades_to_dependent_rows() {
+        let library = TestLibrary::new();
+        let mut repo = library.photo_repo();
+        let ids = library.add_pictures(&[("a.jpg", None), ("b.jpg", None)]);
+
+        for (index, id) in ids.iter().enumerate() {
+            library.add_face(
+                *id,
+                &format!("face_{}_thumbnail.png", index),
+                &format!("face_{}_bounds.png", index),
+                0.9,
+            );
+        }
+
+        {
+            let con = library.con.lock().unwrap();
+            con.execute(
+                "INSERT INTO user_albums (album_id, name, created_ts)
+                VALUES (1, 'Holiday', CURRENT_TIMESTAMP)",
+                [],
+            )
+            .unwrap();
+            for (index, id) in ids.iter().enumerate() {
+                con.execute(
+                    "INSERT INTO pictures_hashes (picture_id, content_hash) VALUES (?1, 'hash')",
+                    [id.id()],
+                )
+                .unwrap();
+                con.execute(
+                    "INSERT INTO user_album_items (album_id, position, picture_id)
+                    VALUES (1, ?2, ?1)",
+                    params![id.id(), index],
+                )
+                .unwrap();
+            }
+            con.execute(
+                "INSERT INTO people (name, thumbnail_path) VALUES ('Alice', 'face_1_thumbnail.png')",
+                [],
+            )
+            .unwrap();
+            con.execute(
+                "INSERT INTO people_rejected_faces (person_id, face_id)
+                SELECT people.person_id, face_id FROM people, pictures_faces",
+                [],
+            )
+            .unwrap();
+            con.execute(
+                "UPDATE user_albums
+                SET cover_item_id = (SELECT item_id FROM user_album_items WHERE picture_id = ?1)",
+                [ids[0].id()],
+            )
+            .unwrap();
+        }
+
+        repo.remove(ids[0]).unwrap();
+
+        // Rows for the other picture are left alone.
+        for table in [
+            "pictures",
+            "pictures_hashes",
+            "pictures_faces",
+            "people_rejected_faces",
+            "user_album_items",
+        ] {
+            assert_eq!(1, library.count(table), "{}", table);
+        }
+
+        let cover_item_id: Option<i64> = library
+            .con
+            .lock()
+            .unwrap()
+            .query_row("SELECT cover_item_id FROM user_albums", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(None, cover_item_id);
+    }
+}
@@ -2,7 +2,9 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use crate::photo::model::PictureId;
+use crate::photo::metadata;
+use crate::photo::model::{Orientation, PictureId};
+use crate::photo::raw;
 use anyhow::*;
 
 use image::codecs::png::PngEncoder;
@@ -57,13 +59,35 @@ impl Thumbnailer {
         }
 
         debug!("Generating thumbnail: {:?}", picture_path);
-        Self::sandboxed_thumbnail_async(picture_path, &thumbnail_path).await?;
+        if raw::is_raw(picture_path) {
+            Self::raw_thumbnail_async(picture_path, &thumbnail_path).await?;
+        } else {
+            Self::sandboxed_thumbnail_async(picture_path, &thumbnail_path).await?;
+        }
         Ok(thumbnail_path)
     }
 
     /// Generate a thumbnail from a file that has already been processed in a Glycin sandbox.
-    fn trusted_thumbnail(path: &Path, thumbnail_path: &Path) -> Result<()> {
-        let src_image = ImageReader::open(path)?.decode()?.into_rgb8();
+    /// An orientation is only needed if Glycin hasn't already applied one.
+    fn trusted_thumbnail(
+        path: &Path,
+        thumbnail_path: &Path,
+        orientation: Option<Orientation>,
+    ) -> Result<()> {
+        let src_image = ImageReader::open(path)?.decode()?;
+
+        let src_image = match orientation {
+            Some(Orientation::NorthMirrored) => src_image.fliph(),
+            Some(Orientation::South) => src_image.rotate180(),
+            Some(Orientation::SouthMirrored) => src_image.flipv(),
+            Some(Orientation::WestMirrored) => src_image.rotate90().fliph(),
+            Some(Orientation::West) => src_image.rotate90(),
+            Some(Orientation::EastMirrored) => src_image.rotate270().fliph(),
+            Some(Orientation::East) => src_image.rotate270(),
+            Some(Orientation::North) | None => src_image,
+        };
+
+        let src_image = src_image.into_rgb8();
 
         // WARNING src_image, dst_image, and the PngEncoder must all
         // use the _same_ pixel type or the PngEncoder will throw errors
@@ -131,6 +155,33 @@ impl Thumbnailer {
 
         frame.texture().save_to_png(png_file.path())?;
 
-        Self::trusted_thumbnail(png_file.path(), thumbnail_path)
+        Self::trusted_thumbnail(png_file.path(), thumbnail_path, None)
+    }
+
+    /// Camera RAW files are thumbnailed from their embedded JPEG preview.
+    /// The preview doesn't reliably carry the RAW file's orientation, so Glycin
+    /// mustn't apply any transformations and the RAW orientation is applied instead.
+    async fn raw_thumbnail_async(source_path: &Path, thumbnail_path: &Path) -> Result<()> {
+        let jpeg_file = raw::extract_preview_file(source_path)?;
+
+        let orientation = metadata::from_path(source_path)
+            .ok()
+            .and_then(|m| m.orientation);
+
+        let file = gio::File::for_path(jpeg_file.path());
+
+        let mut loader = glycin::Loader::new(file);
+        loader.sandbox_selector(glycin::SandboxSelector::FlatpakSpawn);
+        loader.apply_transformations(false);
+
+        let image = loader.load().await?;
+
+        let frame = image.next_frame().await?;
+
+        let png_file = tempfile::Builder::new().suffix(".png").tempfile()?;
+
+        frame.texture().save_to_png(png_file.path())?;
+
+        Self::trusted_thumbnail(png_file.path(), thumbnail_path, orientation)
     }
 }
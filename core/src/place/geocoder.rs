@@ -0,0 +1,217 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Offline reverse geocoding with the GeoNames cities dataset.
+//!
+//! Expects the following files from <https://download.geonames.org/export/dump/>
+//! to be in the data directory:
+//! * `cities15000.txt` — cities with a population greater than 15,000.
+//! * `admin1CodesASCII.txt` — names of first-level administrative divisions.
+//! * `countryInfo.txt` — names of countries.
+
+use super::model::Place;
+use anyhow::*;
+use h3o::{CellIndex, LatLng, Resolution};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::result::Result::Ok;
+
+pub const CITIES_FILE: &str = "cities15000.txt";
+pub const ADMIN1_CODES_FILE: &str = "admin1CodesASCII.txt";
+pub const COUNTRY_INFO_FILE: &str = "countryInfo.txt";
+
+/// Resolution of cells used to index cities. Cells are roughly 140km across.
+const INDEX_RESOLUTION: Resolution = Resolution::Three;
+
+/// Rings of cells around a location to search for cities. Enough to cover MAX_DISTANCE_KM.
+const SEARCH_RADIUS: u32 = 2;
+
+/// Pictures further than this from any city have no place.
+const MAX_DISTANCE_KM: f64 = 100.0;
+
+#[derive(Debug)]
+struct City {
+    name: String,
+    location: LatLng,
+    country: String,
+    region: Option<String>,
+}
+
+/// Finds the nearest city to a location.
+#[derive(Debug)]
+pub struct Geocoder {
+    /// Cities keyed by the cell containing them.
+    cities: HashMap<CellIndex, Vec<City>>,
+}
+
+impl Geocoder {
+    /// Loads the GeoNames dataset from a directory.
+    pub fn open(data_dir: &Path) -> Result<Geocoder> {
+        let open = |name: &str| {
+            let path = data_dir.join(name);
+            File::open(&path)
+                .map(BufReader::new)
+                .with_context(|| format!("Opening {:?}", path))
+        };
+
+        Self::from_readers(
+            open(CITIES_FILE)?,
+            open(ADMIN1_CODES_FILE)?,
+            open(COUNTRY_INFO_FILE)?,
+        )
+    }
+
+    /// Loads the GeoNames dataset from tab-separated cities, admin1 codes, and country info.
+    pub fn from_readers(
+        cities: impl BufRead,
+        admin1_codes: impl BufRead,
+        country_info: impl BufRead,
+    ) -> Result<Geocoder> {
+        // ISO country code to country name
+        let countries: HashMap<String, String> = country_info
+            .lines()
+            .map_while(std::result::Result::ok)
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split('\t').collect();
+                Some((fields.first()?.to_string(), fields.get(4)?.to_string()))
+            })
+            .collect();
+
+        // Country and admin1 code, such as "PT.14", to region name
+        let regions: HashMap<String, String> = admin1_codes
+            .lines()
+            .map_while(std::result::Result::ok)
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split('\t').collect();
+                Some((fields.first()?.to_string(), fields.get(1)?.to_string()))
+            })
+            .collect();
+
+        let mut index: HashMap<CellIndex, Vec<City>> = HashMap::new();
+
+        for line in cities.lines() {
+            let line = line?;
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() < 11 {
+                continue;
+            }
+
+            let (Ok(lat), Ok(lng)) = (fields[4].parse::<f64>(), fields[5].parse::<f64>()) else {
+                continue;
+            };
+
+            let Ok(location) = LatLng::new(lat, lng) else {
+                continue;
+            };
+
+            let country_code = fields[8];
+            let Some(country) = countries.get(country_code) else {
+                continue;
+            };
+
+            let region = regions
+                .get(&format!("{}.{}", country_code, fields[10]))
+                .cloned();
+
+            let city = City {
+                name: fields[1].to_string(),
+                location,
+                country: country.clone(),
+                region,
+            };
+
+            index
+                .entry(location.to_cell(INDEX_RESOLUTION))
+                .or_default()
+                .push(city);
+        }
+
+        ensure!(!index.is_empty(), "No cities in GeoNames dataset");
+
+        Ok(Geocoder { cities: index })
+    }
+
+    /// Finds the place nearest to a location. None if no city is nearby.
+    pub fn reverse(&self, location: LatLng) -> Option<Place> {
+        location
+            .to_cell(INDEX_RESOLUTION)
+            .grid_disk::<Vec<_>>(SEARCH_RADIUS)
+            .into_iter()
+            .filter_map(|cell| self.cities.get(&cell))
+            .flatten()
+            .map(|city| (city.location.distance_km(location), city))
+            .filter(|(distance, _)| *distance <= MAX_DISTANCE_KM)
+            .min_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(_, city)| Place {
+                country: city.country.clone(),
+                region: city.region.clone(),
+                city: city.name.clone(),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CITIES: &str = "\
+2267057\tLisbon\tLisbon\t\t38.71667\t-9.13333\tP\tPPLC\tPT\t\t14\t\t\t\t517802\t\t45\tEurope/Lisbon\t2023-01-12
+2735943\tPorto\tPorto\t\t41.14961\t-8.61099\tP\tPPLA\tPT\t\t17\t\t\t\t249633\t\t93\tEurope/Lisbon\t2023-01-12
+2268339\tFaro\tFaro\t\t37.01869\t-7.92716\tP\tPPLA\tPT\t\t09\t\t\t\t41355\t\t15\tEurope/Lisbon\t2023-01-12
+";
+
+    const ADMIN1_CODES: &str = "\
+PT.14\tLisbon\tLisbon\t2267056
+PT.17\tPorto\tPorto\t2735941
+";
+
+    const COUNTRY_INFO: &str = "\
+#ISO\tISO3\tISO-Numeric\tfips\tCountry\tCapital
+PT\tPRT\t620\tPO\tPortugal\tLisbon
+";
+
+    fn geocoder() -> Geocoder {
+        Geocoder::from_readers(
+            CITIES.as_bytes(),
+            ADMIN1_CODES.as_bytes(),
+            COUNTRY_INFO.as_bytes(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn finds_nearest_city() {
+        let geocoder = geocoder();
+
+        // Sintra is closer to Lisbon than to Porto
+        let place = geocoder.reverse(LatLng::new(38.8029, -9.3817).unwrap());
+        assert_eq!(
+            Some(Place {
+                country: "Portugal".into(),
+                region: Some("Lisbon".into()),
+                city: "Lisbon".into(),
+            }),
+            place
+        );
+
+        let place = geocoder.reverse(LatLng::new(41.1, -8.6).unwrap()).unwrap();
+        assert_eq!("Porto, Portugal", place.to_string());
+
+        // Region is optional
+        let place = geocoder.reverse(LatLng::new(37.0, -7.9).unwrap()).unwrap();
+        assert_eq!("Faro", place.city);
+        assert_eq!(None, place.region);
+    }
+
+    #[test]
+    fn no_place_far_from_cities() {
+        let geocoder = geocoder();
+
+        // Middle of the Atlantic
+        assert_eq!(None, geocoder.reverse(LatLng::new(40.0, -30.0).unwrap()));
+    }
+}
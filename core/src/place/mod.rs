@@ -0,0 +1,11 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+pub mod geocoder;
+pub mod model;
+pub mod repo;
+
+pub use geocoder::Geocoder;
+pub use model::Place;
+pub use repo::Repository;
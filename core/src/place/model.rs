@@ -0,0 +1,24 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::fmt::Display;
+
+/// Named place near where a picture was taken, found by reverse geocoding.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Place {
+    /// Country name, such as "Portugal".
+    pub country: String,
+
+    /// First-level administrative division, such as a state or province.
+    pub region: Option<String>,
+
+    /// Nearest city or town.
+    pub city: String,
+}
+
+impl Display for Place {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}, {}", self.city, self.country)
+    }
+}
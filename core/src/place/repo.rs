@@ -0,0 +1,153 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::model::Place;
+use crate::photo::PictureId;
+use anyhow::*;
+use h3o::LatLng;
+use rusqlite;
+use rusqlite::params;
+use std::result::Result::Ok;
+use std::sync::{Arc, Mutex};
+
+/// Repository of place names for pictures with a GPS location.
+#[derive(Debug, Clone)]
+pub struct Repository {
+    /// Connection to backing Sqlite database.
+    con: Arc<Mutex<rusqlite::Connection>>,
+}
+
+impl Repository {
+    pub fn open(con: Arc<Mutex<rusqlite::Connection>>) -> Result<Repository> {
+        Ok(Repository { con })
+    }
+
+    /// Pictures with a GPS location that haven't been reverse geocoded.
+    pub fn find_need_geocode(&self) -> Result<Vec<(PictureId, LatLng)>> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare(
+            "SELECT
+                pictures_geo.picture_id,
+                pictures_geo.latitude,
+                pictures_geo.longitude
+            FROM pictures_geo
+            LEFT OUTER JOIN pictures_places USING (picture_id)
+            WHERE pictures_places.picture_id IS NULL",
+        )?;
+
+        let result = stmt.query_map([], |row| {
+            let picture_id = row.get("picture_id").map(PictureId::new)?;
+            let latitude: f64 = row.get("latitude")?;
+            let longitude: f64 = row.get("longitude")?;
+            Ok((picture_id, latitude, longitude))
+        })?;
+
+        let pics = result
+            .flatten()
+            .filter_map(|(picture_id, lat, lng)| {
+                LatLng::new(lat, lng).ok().map(|loc| (picture_id, loc))
+            })
+            .collect();
+
+        Ok(pics)
+    }
+
+    /// Stores places found for pictures. A picture without a place is recorded
+    /// so it won't be geocoded again.
+    pub fn add_places(&mut self, places: Vec<(PictureId, Option<Place>)>) -> Result<()> {
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO pictures_places (
+                    picture_id,
+                    country,
+                    region,
+                    city
+                ) VALUES (
+                    ?1, ?2, ?3, ?4
+                ) ON CONFLICT (picture_id) DO UPDATE SET
+                    country = ?2,
+                    region = ?3,
+                    city = ?4",
+            )?;
+
+            for (picture_id, place) in places {
+                stmt.execute(params![
+                    picture_id.id(),
+                    place.as_ref().map(|p| &p.country),
+                    place.as_ref().and_then(|p| p.region.as_ref()),
+                    place.as_ref().map(|p| &p.city),
+                ])?;
+            }
+        }
+        tx.commit()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestLibrary;
+    use crate::visual;
+    use chrono::prelude::*;
+
+    #[test]
+    fn geocodes_once_until_location_changes() {
+        let library = TestLibrary::new();
+        library.add_pictures(&[("a.jpg", Some(Utc::now())), ("b.jpg", Some(Utc::now()))]);
+        let con = library.con.clone();
+
+        let set_location = |lat: f64, lng: f64| {
+            con.lock()
+                .unwrap()
+                .execute(
+                    "INSERT INTO pictures_geo (picture_id, latitude, longitude)
+                    SELECT picture_id, ?1, ?2 FROM pictures
+                    ON CONFLICT (picture_id) DO UPDATE SET
+                        latitude = ?1,
+                        longitude = ?2",
+                    params![lat, lng],
+                )
+                .unwrap();
+        };
+
+        set_location(38.7, -9.1);
+
+        let mut repo = Repository::open(con.clone()).unwrap();
+        let need = repo.find_need_geocode().unwrap();
+        assert_eq!(2, need.len());
+
+        let lisbon = Place {
+            country: "Portugal".into(),
+            region: Some("Lisbon".into()),
+            city: "Lisbon".into(),
+        };
+
+        repo.add_places(vec![(need[0].0, Some(lisbon.clone())), (need[1].0, None)])
+            .unwrap();
+        assert!(repo.find_need_geocode().unwrap().is_empty());
+
+        let visual_repo =
+            visual::Repository::open(&library.roots, library.path(), con.clone()).unwrap();
+        let places: Vec<Option<Place>> = visual_repo
+            .all()
+            .unwrap()
+            .into_iter()
+            .map(|v| v.place)
+            .collect();
+        assert!(places.contains(&Some(lisbon)));
+        assert!(places.contains(&None));
+
+        // Same location isn't geocoded again
+        set_location(38.7, -9.1);
+        assert!(repo.find_need_geocode().unwrap().is_empty());
+
+        // New location is geocoded again
+        set_location(41.1, -8.6);
+        assert_eq!(2, repo.find_need_geocode().unwrap().len());
+    }
+}
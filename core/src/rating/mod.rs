@@ -0,0 +1,8 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+pub mod repo;
+
+pub use repo::Repository;
+pub use repo::MAX_RATING;
@@ -0,0 +1,145 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::visual::Visual;
+use anyhow::*;
+use rusqlite;
+use rusqlite::params;
+use std::result::Result::Ok;
+use std::sync::{Arc, Mutex};
+
+/// Highest star rating.
+pub const MAX_RATING: u8 = 5;
+
+/// Repository of star ratings and favourites.
+/// Ratings are stored for both the picture and the video of a visual item so
+/// that they survive a live photo being paired, or unpaired, with its video.
+#[derive(Debug, Clone)]
+pub struct Repository {
+    /// Connection to backing Sqlite database.
+    con: Arc<Mutex<rusqlite::Connection>>,
+}
+
+impl Repository {
+    pub fn open(con: Arc<Mutex<rusqlite::Connection>>) -> Result<Repository> {
+        Ok(Repository { con })
+    }
+
+    /// Sets star rating of a visual item. 0 for unrated.
+    pub fn set_rating(&mut self, visual: &Visual, rating: u8) -> Result<()> {
+        ensure!(
+            rating <= MAX_RATING,
+            "Rating {} is greater than {}",
+            rating,
+            MAX_RATING
+        );
+
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+        {
+            let mut update_picture = tx.prepare_cached(
+                "INSERT INTO pictures_ratings (
+                    picture_id,
+                    rating
+                ) VALUES (
+                    ?1, ?2
+                ) ON CONFLICT (picture_id) DO UPDATE SET
+                    rating = ?2",
+            )?;
+
+            let mut update_video = tx.prepare_cached(
+                "INSERT INTO videos_ratings (
+                    video_id,
+                    rating
+                ) VALUES (
+                    ?1, ?2
+                ) ON CONFLICT (video_id) DO UPDATE SET
+                    rating = ?2",
+            )?;
+
+            if let Some(picture_id) = visual.picture_id {
+                update_picture.execute(params![picture_id.id(), rating])?;
+            }
+
+            if let Some(video_id) = visual.video_id {
+                update_video.execute(params![video_id.id(), rating])?;
+            }
+        }
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    /// Marks, or unmarks, a visual item as a favourite.
+    pub fn set_favourite(&mut self, visual: &Visual, is_favourite: bool) -> Result<()> {
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+        {
+            let mut update_picture = tx.prepare_cached(
+                "INSERT INTO pictures_ratings (
+                    picture_id,
+                    is_favourite
+                ) VALUES (
+                    ?1, ?2
+                ) ON CONFLICT (picture_id) DO UPDATE SET
+                    is_favourite = ?2",
+            )?;
+
+            let mut update_video = tx.prepare_cached(
+                "INSERT INTO videos_ratings (
+                    video_id,
+                    is_favourite
+                ) VALUES (
+                    ?1, ?2
+                ) ON CONFLICT (video_id) DO UPDATE SET
+                    is_favourite = ?2",
+            )?;
+
+            if let Some(picture_id) = visual.picture_id {
+                update_picture.execute(params![picture_id.id(), is_favourite])?;
+            }
+
+            if let Some(video_id) = visual.video_id {
+                update_video.execute(params![video_id.id(), is_favourite])?;
+            }
+        }
+        tx.commit()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestLibrary;
+    use crate::visual;
+    use chrono::prelude::*;
+
+    #[test]
+    fn sets_rating_and_favourite() {
+        let library = TestLibrary::new();
+        library.add_pictures(&[("a.jpg", Some(Utc::now()))]);
+
+        let visual_repo =
+            visual::Repository::open(&library.roots, library.path(), library.con.clone()).unwrap();
+        let visual = visual_repo.all().unwrap().remove(0);
+        assert_eq!(None, visual.rating);
+        assert!(!visual.is_favourite);
+
+        let mut repo = Repository::open(library.con.clone()).unwrap();
+        repo.set_rating(&visual, 4).unwrap();
+        repo.set_favourite(&visual, true).unwrap();
+        assert!(repo.set_rating(&visual, 6).is_err());
+
+        let visual = visual_repo.all().unwrap().remove(0);
+        assert_eq!(Some(4), visual.rating);
+        assert!(visual.is_favourite);
+
+        repo.set_favourite(&visual, false).unwrap();
+        let visual = visual_repo.all().unwrap().remove(0);
+        assert_eq!(Some(4), visual.rating);
+        assert!(!visual.is_favourite);
+    }
+}
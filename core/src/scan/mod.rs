@@ -0,0 +1,18 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+pub mod model;
+pub mod registry;
+pub mod scanner;
+pub mod watcher;
+
+pub use model::MediaKind;
+pub use model::ScanSummary;
+pub use model::ScannedFile;
+pub use registry::Detector;
+pub use registry::ExtensionDetector;
+pub use registry::MagicDetector;
+pub use registry::Registry;
+pub use scanner::Scanner;
+pub use watcher::{WatchBatch, Watcher};
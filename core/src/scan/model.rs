@@ -0,0 +1,80 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use chrono::prelude::*;
+use std::fmt::Display;
+use std::path::PathBuf;
+
+/// Kind of media a scanned file has been classified as.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MediaKind {
+    Photo,
+    Video,
+
+    /// Media kind registered by a downstream crate, such as "gif" or "svg".
+    Other(String),
+}
+
+impl Display for MediaKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MediaKind::Photo => write!(f, "photo"),
+            MediaKind::Video => write!(f, "video"),
+            MediaKind::Other(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// A photo or video on the local file system that has been scanned.
+#[derive(Debug, Clone)]
+pub struct ScannedFile {
+    /// Full path to file.
+    pub path: PathBuf,
+
+    pub fs_created_at: Option<DateTime<Utc>>,
+
+    pub fs_modified_at: Option<DateTime<Utc>>,
+
+    pub fs_file_size_bytes: u64,
+}
+
+impl ScannedFile {
+    /// Has the file changed since it was last scanned with the given fingerprint?
+    /// A missing file size is from a scan before file sizes were recorded, so
+    /// only the modification time is compared.
+    pub fn is_changed(
+        &self,
+        fs_modified_at: Option<DateTime<Utc>>,
+        fs_file_size_bytes: Option<u64>,
+    ) -> bool {
+        self.fs_modified_at != fs_modified_at
+            || fs_file_size_bytes.is_some_and(|size| size != self.fs_file_size_bytes)
+    }
+}
+
+/// Counts of files added to a repository by a library scan.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScanSummary {
+    /// New files.
+    pub added: usize,
+
+    /// Files whose size or modification time differ from the previous scan.
+    pub changed: usize,
+
+    /// Files that are the same as in the previous scan.
+    pub unchanged: usize,
+
+    /// Previously scanned files that are no longer present.
+    pub removed: usize,
+}
+
+impl Display for ScanSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} added, {} changed, {} unchanged, {} removed",
+            self.added, self.changed, self.unchanged, self.removed
+        )
+    }
+}
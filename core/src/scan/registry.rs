@@ -0,0 +1,253 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::model::MediaKind;
+use crate::photo::raw;
+use std::fmt::Debug;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Number of leading bytes of a file read for magic-byte detection.
+const HEADER_LENGTH: usize = 16;
+
+/// Classifies a file as a kind of media.
+pub trait Detector: Debug + Send + Sync {
+    /// Kind of media for a file, or None if this detector doesn't recognise it.
+    /// `header` holds the first few bytes of the file and will be empty
+    /// if the detector doesn't need them.
+    fn detect(&self, path: &Path, header: &[u8]) -> Option<MediaKind>;
+
+    /// Does this detector need the file header?
+    fn needs_header(&self) -> bool {
+        false
+    }
+}
+
+/// Detects media by case-insensitive file name extension.
+#[derive(Debug, Clone)]
+pub struct ExtensionDetector {
+    kind: MediaKind,
+
+    /// Lower-case extensions without a leading dot.
+    extensions: Vec<String>,
+}
+
+impl ExtensionDetector {
+    pub fn new(kind: MediaKind, extensions: &[&str]) -> Self {
+        let extensions = extensions.iter().map(|x| x.to_lowercase()).collect();
+        Self { kind, extensions }
+    }
+}
+
+impl Detector for ExtensionDetector {
+    fn detect(&self, path: &Path, _header: &[u8]) -> Option<MediaKind> {
+        let ext = path
+            .extension()
+            .and_then(|s| s.to_str())
+            .map(|s| s.to_lowercase())?;
+
+        if self.extensions.contains(&ext) {
+            Some(self.kind.clone())
+        } else {
+            None
+        }
+    }
+}
+
+/// Detects media by a signature at a fixed offset from the start of a file.
+#[derive(Debug, Clone)]
+pub struct MagicDetector {
+    kind: MediaKind,
+
+    /// Offset and bytes. A file matches if any of the signatures match.
+    signatures: Vec<(usize, Vec<u8>)>,
+}
+
+impl MagicDetector {
+    pub fn new(kind: MediaKind, signatures: &[(usize, &[u8])]) -> Self {
+        let signatures = signatures
+            .iter()
+            .map(|(offset, magic)| (*offset, magic.to_vec()))
+            .collect();
+        Self { kind, signatures }
+    }
+}
+
+impl Detector for MagicDetector {
+    fn detect(&self, _path: &Path, header: &[u8]) -> Option<MediaKind> {
+        let is_match = self.signatures.iter().any(|(offset, magic)| {
+            header
+                .get(*offset..*offset + magic.len())
+                .is_some_and(|bytes| bytes == magic.as_slice())
+        });
+
+        if is_match {
+            Some(self.kind.clone())
+        } else {
+            None
+        }
+    }
+
+    fn needs_header(&self) -> bool {
+        true
+    }
+}
+
+/// Ordered collection of detectors used to classify scanned files.
+///
+/// Extension detectors are tried first. Files that have no extension are then
+/// classified by reading their header and trying the magic-byte detectors.
+/// Files with an unrecognised extension are skipped without being read because
+/// sidecar and backup files (such as Canon .THM or exiftool's .jpg_original)
+/// often carry the same signature as the media they accompany.
+#[derive(Debug, Clone)]
+pub struct Registry {
+    detectors: Vec<Arc<dyn Detector>>,
+}
+
+impl Default for Registry {
+    /// Registry recognising the photo and video formats Fotema supports.
+    fn default() -> Self {
+        let mut registry = Registry::empty();
+
+        registry.register(ExtensionDetector::new(
+            MediaKind::Photo,
+            // heic is not supported by image-rs
+            &["avif", "heic", "jpeg", "jpg", "jxl", "png", "tiff", "webp"],
+        ));
+
+        // Camera RAW files are indexed as photos and thumbnailed from their embedded preview.
+        registry.register(ExtensionDetector::new(MediaKind::Photo, &raw::SUFFIXES));
+
+        registry.register(ExtensionDetector::new(MediaKind::Video, &["mov", "mp4"]));
+
+        // TIFF signatures are deliberately absent because most camera RAW formats
+        // are TIFF containers.
+        registry.register(MagicDetector::new(
+            MediaKind::Photo,
+            &[
+                (0, &[0xFF, 0xD8, 0xFF]),       // JPEG
+                (0, &[0x89, b'P', b'N', b'G']), // PNG
+                (8, b"WEBP"),                   // WebP
+                (0, &[0xFF, 0x0A]),             // JPEG XL codestream
+                (4, b"JXL "),                   // JPEG XL container
+                (4, b"ftypavif"),               // AVIF
+                (4, b"ftypheic"),               // HEIC
+                (4, b"ftypheix"),               // HEIC
+                (4, b"ftypmif1"),               // HEIF
+            ],
+        ));
+
+        registry.register(MagicDetector::new(
+            MediaKind::Video,
+            &[
+                (4, b"ftypqt  "), // QuickTime
+                (4, b"ftypisom"), // MP4
+                (4, b"ftypmp41"), // MP4
+                (4, b"ftypmp42"), // MP4
+            ],
+        ));
+
+        registry
+    }
+}
+
+impl Registry {
+    /// Registry without any detectors.
+    pub fn empty() -> Self {
+        Self {
+            detectors: Vec::new(),
+        }
+    }
+
+    /// Add a detector.
+    /// Detection runs in two phases. All extension detectors are consulted first, in
+    /// registration order. Only if the file has no extension are the header detectors
+    /// then consulted, also in registration order.
+    pub fn register<D: Detector + 'static>(&mut self, detector: D) {
+        self.detectors.push(Arc::new(detector));
+    }
+
+    /// Kind of media for path, or None if the file isn't recognised.
+    pub fn detect(&self, path: &Path) -> Option<MediaKind> {
+        let by_extension = self
+            .detectors
+            .iter()
+            .filter(|d| !d.needs_header())
+            .find_map(|d| d.detect(path, &[]));
+
+        if by_extension.is_some() || path.extension().is_some() {
+            return by_extension;
+        }
+
+        let header = Self::read_header(path)?;
+
+        self.detectors
+            .iter()
+            .filter(|d| d.needs_header())
+            .find_map(|d| d.detect(path, &header))
+    }
+
+    fn read_header(path: &Path) -> Option<Vec<u8>> {
+        let file = fs::File::open(path).ok()?;
+        let mut header = Vec::with_capacity(HEADER_LENGTH);
+        file.take(HEADER_LENGTH as u64)
+            .read_to_end(&mut header)
+            .ok()?;
+        Some(header)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn detect_by_extension() {
+        let registry = Registry::default();
+        assert_eq!(
+            Some(MediaKind::Photo),
+            registry.detect(Path::new("a/b/IMG_0001.JPG"))
+        );
+        assert_eq!(
+            Some(MediaKind::Video),
+            registry.detect(Path::new("a/b/IMG_0001.mov"))
+        );
+        assert_eq!(
+            Some(MediaKind::Photo),
+            registry.detect(Path::new("a/b/IMG_0001.CR2"))
+        );
+        assert_eq!(None, registry.detect(Path::new("a/b/IMG_0001.THM")));
+    }
+
+    #[test]
+    fn detect_by_magic_when_no_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("no_extension");
+        let mut file = fs::File::create(&path).unwrap();
+        file.write_all(&[
+            0, 0, 0, 0x18, b'f', b't', b'y', b'p', b'q', b't', b' ', b' ',
+        ])
+        .unwrap();
+
+        let registry = Registry::default();
+        assert_eq!(Some(MediaKind::Video), registry.detect(&path));
+    }
+
+    #[test]
+    fn detect_registered_kind() {
+        let mut registry = Registry::default();
+        registry.register(ExtensionDetector::new(
+            MediaKind::Other("gif".into()),
+            &["gif"],
+        ));
+        assert_eq!(
+            Some(MediaKind::Other("gif".into())),
+            registry.detect(Path::new("anim.GIF"))
+        );
+    }
+}
@@ -0,0 +1,110 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::model::{MediaKind, ScannedFile};
+use super::registry::Registry;
+use anyhow::*;
+use chrono::prelude::*;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use tracing::error;
+use walkdir::WalkDir;
+
+/// Scans a file system for photos, videos, and any other registered media kinds.
+#[derive(Debug, Clone)]
+pub struct Scanner {
+    /// File system path to scan.
+    scan_base: PathBuf,
+
+    /// Detectors for classifying files.
+    registry: Registry,
+}
+
+impl Scanner {
+    /// Scanner recognising the default photo and video formats.
+    pub fn build(scan_base: &Path) -> Result<Self> {
+        Self::build_with_registry(scan_base, Registry::default())
+    }
+
+    pub fn build_with_registry(scan_base: &Path, registry: Registry) -> Result<Self> {
+        fs::create_dir_all(scan_base)?;
+        let scan_base = PathBuf::from(scan_base);
+        Ok(Self {
+            scan_base,
+            registry,
+        })
+    }
+
+    /// Scans all media in the base directory for function `func` to visit.
+    pub fn scan_all_visit<F>(&self, mut func: F)
+    where
+        F: FnMut(MediaKind, ScannedFile),
+    {
+        WalkDir::new(&self.scan_base)
+            .into_iter()
+            .inspect(|x| {
+                let _ = x
+                    .as_ref()
+                    .inspect_err(|e| error!("Failed walking: {:?}", e));
+            })
+            .flatten() // skip files we failed to read
+            .filter(|x| x.path().is_file()) // only process files
+            .filter_map(|x| self.registry.detect(x.path()).map(|kind| (kind, x))) // only process supported media
+            .map(|(kind, x)| Self::scan_one(x.path()).map(|file| (kind, file)))
+            .inspect(|x| {
+                let _ = x
+                    .as_ref()
+                    .inspect_err(|e| error!("Failed scanning: {:?}", e));
+            })
+            .flatten() // ignore any errors when reading files
+            .for_each(|(kind, file)| func(kind, file)); // visit
+    }
+
+    /// Scans all media in the base directory, grouped by media kind.
+    pub fn scan_all(&self) -> Result<HashMap<MediaKind, Vec<ScannedFile>>> {
+        let mut files: HashMap<MediaKind, Vec<ScannedFile>> = HashMap::new();
+        self.scan_all_visit(|kind, file| files.entry(kind).or_default().push(file));
+        Ok(files)
+    }
+
+    /// Scans all media of one kind in the base directory.
+    pub fn scan_all_of(&self, media_kind: &MediaKind) -> Result<Vec<ScannedFile>> {
+        let mut files = Vec::new();
+        self.scan_all_visit(|kind, file| {
+            if kind == *media_kind {
+                files.push(file);
+            }
+        });
+        Ok(files)
+    }
+
+    /// Classify a file.
+    pub fn detect(&self, path: &Path) -> Option<MediaKind> {
+        self.registry.detect(path)
+    }
+
+    /// Scans a single file, which can be in any directory.
+    pub fn scan_one(path: &Path) -> Result<ScannedFile> {
+        let file = fs::File::open(path)?;
+
+        let metadata = file.metadata()?;
+
+        let fs_created_at = metadata.created().map(Into::<DateTime<Utc>>::into).ok();
+
+        let fs_modified_at = metadata.modified().map(Into::<DateTime<Utc>>::into).ok();
+
+        let fs_file_size_bytes = metadata.len();
+
+        let scanned = ScannedFile {
+            path: PathBuf::from(path),
+            fs_created_at,
+            fs_modified_at,
+            fs_file_size_bytes,
+        };
+
+        Ok(scanned)
+    }
+}
@@ -0,0 +1,333 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::model::MediaKind;
+use super::registry::Registry;
+use anyhow::*;
+use gio::glib;
+use gio::prelude::*;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::result::Result::Ok;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info};
+use walkdir::WalkDir;
+
+/// How long the file system must be quiet before changes are reported.
+const QUIET_PERIOD: Duration = Duration::from_secs(3);
+
+/// Longest time changes are held back during a continuous burst of events,
+/// such as a phone sync copying hundreds of files.
+const MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// File system changes collected over a debounce period.
+#[derive(Debug, Clone, Default)]
+pub struct WatchBatch {
+    /// Media files that have been created, modified, or moved into the library.
+    pub changed: Vec<(MediaKind, PathBuf)>,
+
+    /// Files or directories that have been deleted or moved out of the library.
+    /// Anything at or below these paths that no longer exists should be cleaned up.
+    pub removed: Vec<PathBuf>,
+}
+
+impl WatchBatch {
+    /// Changed files of one media kind.
+    pub fn changed_of(&self, media_kind: &MediaKind) -> Vec<PathBuf> {
+        self.changed
+            .iter()
+            .filter(|(kind, _)| kind == media_kind)
+            .map(|(_, path)| path.clone())
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Events received but not yet reported.
+#[derive(Debug, Default)]
+struct Pending {
+    changed: HashMap<PathBuf, MediaKind>,
+    removed: HashSet<PathBuf>,
+    first_event_at: Option<Instant>,
+    last_event_at: Option<Instant>,
+}
+
+impl Pending {
+    fn touch(&mut self) {
+        let now = Instant::now();
+        self.first_event_at.get_or_insert(now);
+        self.last_event_at = Some(now);
+    }
+
+    /// Take the pending changes if the debounce period has elapsed.
+    fn take_if_due(&mut self) -> Option<WatchBatch> {
+        let first = self.first_event_at?;
+        let last = self.last_event_at?;
+        if last.elapsed() < QUIET_PERIOD && first.elapsed() < MAX_DELAY {
+            return None;
+        }
+
+        let batch = WatchBatch {
+            changed: self
+                .changed
+                .drain()
+                .map(|(path, kind)| (kind, path))
+                .collect(),
+            removed: self.removed.drain().collect(),
+        };
+        self.first_event_at = None;
+        self.last_event_at = None;
+        Some(batch)
+    }
+}
+
+/// State owned by the watcher thread.
+struct WatchState {
+    registry: Registry,
+
+    /// One monitor per directory because GIO directory monitors aren't recursive.
+    monitors: RefCell<HashMap<PathBuf, gio::FileMonitor>>,
+
+    pending: Arc<Mutex<Pending>>,
+}
+
+impl WatchState {
+    /// Watch a directory and all directories below it.
+    fn watch_tree(self: &Rc<Self>, dir: &Path) {
+        WalkDir::new(dir)
+            .into_iter()
+            .flatten()
+            .filter(|x| x.file_type().is_dir())
+            .for_each(|x| self.watch_dir(x.path()));
+    }
+
+    fn watch_dir(self: &Rc<Self>, dir: &Path) {
+        if self.monitors.borrow().contains_key(dir) {
+            return;
+        }
+
+        let monitor = gio::File::for_path(dir)
+            .monitor_directory(gio::FileMonitorFlags::WATCH_MOVES, gio::Cancellable::NONE);
+
+        let monitor = match monitor {
+            Ok(monitor) => monitor,
+            Err(e) => {
+                error!("Failed watching {:?}: {}", dir, e);
+                return;
+            }
+        };
+
+        // Weak reference so the monitors don't keep the state alive.
+        let state = Rc::downgrade(self);
+        monitor.connect_changed(move |_, file, other_file, event| {
+            if let Some(state) = state.upgrade() {
+                state.on_event(file, other_file, event);
+            }
+        });
+
+        self.monitors
+            .borrow_mut()
+            .insert(PathBuf::from(dir), monitor);
+    }
+
+    fn on_event(
+        self: &Rc<Self>,
+        file: &gio::File,
+        other_file: Option<&gio::File>,
+        event: gio::FileMonitorEvent,
+    ) {
+        let Some(path) = file.path() else {
+            return;
+        };
+
+        debug!("{:?}: {:?}", event, path);
+
+        match event {
+            gio::FileMonitorEvent::Created
+            | gio::FileMonitorEvent::Changed
+            | gio::FileMonitorEvent::ChangesDoneHint
+            | gio::FileMonitorEvent::MovedIn => self.on_changed(&path),
+            gio::FileMonitorEvent::Deleted | gio::FileMonitorEvent::MovedOut => {
+                self.on_removed(&path)
+            }
+            gio::FileMonitorEvent::Renamed => {
+                self.on_removed(&path);
+                if let Some(new_path) = other_file.and_then(|f| f.path()) {
+                    self.on_changed(&new_path);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn on_changed(self: &Rc<Self>, path: &Path) {
+        if path.is_dir() {
+            // A new directory might already hold files, such as when moved into the library.
+            self.watch_tree(path);
+            WalkDir::new(path)
+                .into_iter()
+                .flatten()
+                .filter(|x| x.file_type().is_file())
+                .for_each(|x| self.add_changed(x.path()));
+        } else {
+            self.add_changed(path);
+        }
+    }
+
+    fn add_changed(&self, path: &Path) {
+        let Some(kind) = self.registry.detect(path) else {
+            return;
+        };
+        let mut pending = self.pending.lock().unwrap();
+        pending.changed.insert(PathBuf::from(path), kind);
+        pending.touch();
+    }
+
+    fn on_removed(&self, path: &Path) {
+        // Stop watching a removed directory and everything below it.
+        self.monitors
+            .borrow_mut()
+            .retain(|dir, _| !dir.starts_with(path));
+
+        let mut pending = self.pending.lock().unwrap();
+        pending
+            .changed
+            .retain(|changed, _| !changed.starts_with(path));
+        pending.removed.insert(PathBuf::from(path));
+        pending.touch();
+    }
+}
+
+/// Watches a library directory tree for changes to media files.
+/// Changes are reported in batches once the file system has been quiet for a few seconds.
+///
+/// Watching runs on a dedicated thread with its own GLib main loop, which stops
+/// when the watcher is dropped.
+#[derive(Debug)]
+pub struct Watcher {
+    main_loop: glib::MainLoop,
+
+    /// Stop flag, in case the main loop is quit before it has started running.
+    stop: Arc<AtomicBool>,
+}
+
+impl Watcher {
+    /// Start watching `scan_base`. Function `on_change` is called on the watcher thread
+    /// with each batch of changes.
+    pub fn start<F>(scan_base: &Path, registry: Registry, on_change: F) -> Result<Watcher>
+    where
+        F: Fn(WatchBatch) + Send + 'static,
+    {
+        if !scan_base.is_dir() {
+            bail!("{:?} is not a directory", scan_base);
+        }
+
+        let scan_base = PathBuf::from(scan_base);
+        let context = glib::MainContext::new();
+        let main_loop = glib::MainLoop::new(Some(&context), false);
+
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let thread_main_loop = main_loop.clone();
+        let thread_stop = stop.clone();
+        thread::Builder::new()
+            .name("library-watcher".into())
+            .spawn(move || {
+                let result = context.with_thread_default(|| {
+                    let pending = Arc::new(Mutex::new(Pending::default()));
+
+                    let state = Rc::new(WatchState {
+                        registry,
+                        monitors: RefCell::new(HashMap::new()),
+                        pending: pending.clone(),
+                    });
+
+                    let start = Instant::now();
+                    state.watch_tree(&scan_base);
+                    info!(
+                        "Watching {} directories in {} seconds",
+                        state.monitors.borrow().len(),
+                        start.elapsed().as_secs()
+                    );
+
+                    let flush_main_loop = thread_main_loop.clone();
+                    let flush = glib::timeout_source_new(
+                        Duration::from_secs(1),
+                        Some("library-watcher-flush"),
+                        glib::Priority::DEFAULT_IDLE,
+                        move || {
+                            if thread_stop.load(Ordering::Relaxed) {
+                                flush_main_loop.quit();
+                                return glib::ControlFlow::Break;
+                            }
+
+                            let batch = pending.lock().unwrap().take_if_due();
+                            if let Some(batch) = batch {
+                                info!(
+                                    "Library changed: {} changed, {} removed",
+                                    batch.changed.len(),
+                                    batch.removed.len()
+                                );
+                                on_change(batch);
+                            }
+                            glib::ControlFlow::Continue
+                        },
+                    );
+                    flush.attach(Some(&context));
+
+                    thread_main_loop.run();
+
+                    if !flush.is_destroyed() {
+                        flush.destroy();
+                    }
+                });
+
+                if let Err(e) = result {
+                    error!("Failed running library watcher: {}", e);
+                }
+            })?;
+
+        Ok(Watcher { main_loop, stop })
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.main_loop.quit();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pending_waits_for_quiet_period() {
+        let mut pending = Pending::default();
+        assert!(pending.take_if_due().is_none());
+
+        pending
+            .changed
+            .insert(PathBuf::from("a.jpg"), MediaKind::Photo);
+        pending.touch();
+        assert!(pending.take_if_due().is_none());
+
+        pending.last_event_at = Some(Instant::now() - QUIET_PERIOD);
+        let batch = pending.take_if_due().expect("Must be due");
+        assert_eq!(
+            vec![(MediaKind::Photo, PathBuf::from("a.jpg"))],
+            batch.changed
+        );
+        assert!(pending.take_if_due().is_none());
+    }
+}
@@ -0,0 +1,28 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::smart_album::Query;
+use crate::visual::{Visual, VisualId};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Photos and videos matching a search.
+/// Cheap to clone, so can be used to filter albums.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResults {
+    /// Criteria from structured search tokens.
+    pub criteria: Query,
+
+    /// Items found in the full-text search index, or None if the search had no text.
+    pub visual_ids: Option<Arc<HashSet<VisualId>>>,
+}
+
+impl SearchResults {
+    pub fn matches(&self, v: &Visual) -> bool {
+        self.visual_ids
+            .as_ref()
+            .is_none_or(|ids| ids.contains(&v.visual_id))
+            && self.criteria.matches(v)
+    }
+}
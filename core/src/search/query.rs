@@ -0,0 +1,242 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Search text typed by the user.
+//!
+//! Words are found in the full-text search index. Structured tokens, such as
+//! `year:2022`, `person:Bob`, or `type:video`, narrow the search.
+//! Values with spaces can be quoted, such as `person:"Bob Smith"`.
+
+use crate::smart_album::{MediaType, Query};
+use chrono::NaiveDate;
+use strum::AsRefStr;
+
+/// Column of the full-text search index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AsRefStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum Column {
+    FileName,
+    Folder,
+    People,
+    Tags,
+    Places,
+}
+
+/// Text to find in the full-text search index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Term {
+    /// Column to search, or None to search all columns.
+    pub column: Option<Column>,
+
+    /// Word or phrase, matched as a prefix.
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchQuery {
+    /// Text to find in the full-text search index. All terms must match.
+    pub terms: Vec<Term>,
+
+    /// Criteria from structured tokens that aren't full-text searches, such as `year:2022`.
+    pub criteria: Vec<Query>,
+}
+
+impl SearchQuery {
+    /// Parse search text. Any text is a valid search, so tokens that aren't
+    /// understood are searched for as text.
+    pub fn parse(text: &str) -> SearchQuery {
+        let mut query = SearchQuery::default();
+
+        for (key, value) in tokenize(text) {
+            let Some(key) = key else {
+                query.terms.push(Term {
+                    column: None,
+                    text: value,
+                });
+                continue;
+            };
+
+            if !query.add_structured(&key.to_lowercase(), &value) {
+                query.terms.push(Term {
+                    column: None,
+                    text: format!("{}:{}", key, value),
+                });
+            }
+        }
+
+        query
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty() && self.criteria.is_empty()
+    }
+
+    /// FTS5 match expression for the terms. None if there are no terms.
+    pub fn match_expression(&self) -> Option<String> {
+        if self.terms.is_empty() {
+            return None;
+        }
+
+        let terms: Vec<String> = self
+            .terms
+            .iter()
+            .map(|term| {
+                let phrase = format!("\"{}\"*", term.text.replace('"', "\"\""));
+                match term.column {
+                    Some(column) => format!("{} : {}", column.as_ref(), phrase),
+                    None => phrase,
+                }
+            })
+            .collect();
+
+        Some(terms.join(" "))
+    }
+
+    /// Add a structured token. False if the key or value isn't understood.
+    fn add_structured(&mut self, key: &str, value: &str) -> bool {
+        let column = match key {
+            "person" => Some(Column::People),
+            "tag" => Some(Column::Tags),
+            "folder" => Some(Column::Folder),
+            "file" => Some(Column::FileName),
+            "place" => Some(Column::Places),
+            _ => None,
+        };
+
+        if let Some(column) = column {
+            self.terms.push(Term {
+                column: Some(column),
+                text: value.into(),
+            });
+            return true;
+        }
+
+        let criterion = match key {
+            "year" => value.parse::<i32>().ok().and_then(|year| {
+                let from = NaiveDate::from_ymd_opt(year, 1, 1)?;
+                let to = NaiveDate::from_ymd_opt(year, 12, 31)?;
+                Some(Query::DateRange(from, to))
+            }),
+            "type" => match value.to_lowercase().as_str() {
+                "photo" | "photos" => Some(Query::Media(MediaType::Photo)),
+                "video" | "videos" => Some(Query::Media(MediaType::Video)),
+                "animated" | "live" | "motion" => Some(Query::Media(MediaType::Motion)),
+                "selfie" | "selfies" => Some(Query::Selfie),
+                _ => None,
+            },
+            "rating" => value
+                .parse::<i32>()
+                .ok()
+                .filter(|stars| (1..=5).contains(stars))
+                .map(Query::MinRating),
+            "is" => match value.to_lowercase().as_str() {
+                "favourite" | "favorite" => Some(Query::Favourite),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        if let Some(criterion) = criterion {
+            self.criteria.push(criterion);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Split search text into optional keys and values, keeping quoted text together.
+fn tokenize(text: &str) -> Vec<(Option<String>, String)> {
+    let mut tokens = Vec::new();
+    let mut key: Option<String> = None;
+    let mut value = String::new();
+    let mut is_quoted = false;
+
+    for c in text.chars() {
+        match c {
+            '"' => is_quoted = !is_quoted,
+            ':' if !is_quoted && key.is_none() && !value.is_empty() => {
+                key = Some(std::mem::take(&mut value));
+            }
+            c if c.is_whitespace() && !is_quoted => {
+                if !value.trim().is_empty() {
+                    tokens.push((key.take(), value.trim().to_string()));
+                }
+                key = None;
+                value.clear();
+            }
+            c => value.push(c),
+        }
+    }
+
+    if !value.trim().is_empty() {
+        tokens.push((key, value.trim().to_string()));
+    }
+
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_words_and_structured_tokens() {
+        let query = SearchQuery::parse("beach person:\"Bob Smith\" year:2022 type:video tag:sea");
+
+        assert_eq!(
+            vec![
+                Term {
+                    column: None,
+                    text: "beach".into()
+                },
+                Term {
+                    column: Some(Column::People),
+                    text: "Bob Smith".into()
+                },
+                Term {
+                    column: Some(Column::Tags),
+                    text: "sea".into()
+                },
+            ],
+            query.terms
+        );
+
+        assert_eq!(
+            vec![
+                Query::DateRange(
+                    NaiveDate::from_ymd_opt(2022, 1, 1).unwrap(),
+                    NaiveDate::from_ymd_opt(2022, 12, 31).unwrap()
+                ),
+                Query::Media(MediaType::Video),
+            ],
+            query.criteria
+        );
+    }
+
+    #[test]
+    fn searches_unknown_tokens_as_text() {
+        let query = SearchQuery::parse("year:soon colour:red");
+        assert!(query.criteria.is_empty());
+        assert_eq!(2, query.terms.len());
+        assert_eq!("year:soon", query.terms[0].text);
+        assert_eq!("colour:red", query.terms[1].text);
+    }
+
+    #[test]
+    fn builds_match_expression() {
+        assert_eq!(None, SearchQuery::parse("type:photo").match_expression());
+        assert!(SearchQuery::parse("  ").is_empty());
+
+        let mut query = SearchQuery::parse("red car person:Bob");
+        query.terms.push(Term {
+            column: None,
+            text: "say \"hi\"".into(),
+        });
+        assert_eq!(
+            Some("\"red\"* \"car\"* people : \"Bob\"* \"say \"\"hi\"\"\"*".to_string()),
+            query.match_expression()
+        );
+    }
+}
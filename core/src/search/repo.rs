@@ -0,0 +1,249 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::model::SearchResults;
+use super::query::SearchQuery;
+use crate::smart_album::Query;
+use crate::visual::{Visual, VisualId};
+use anyhow::*;
+use rusqlite;
+use rusqlite::params;
+use std::collections::{HashMap, HashSet};
+use std::result::Result::Ok;
+use std::sync::{Arc, Mutex};
+
+/// Full-text search index over pictures and videos.
+#[derive(Debug, Clone)]
+pub struct Repository {
+    /// Connection to backing Sqlite database.
+    con: Arc<Mutex<rusqlite::Connection>>,
+}
+
+impl Repository {
+    pub fn open(con: Arc<Mutex<rusqlite::Connection>>) -> Result<Repository> {
+        Ok(Repository { con })
+    }
+
+    /// Replaces the search index with the given visual items.
+    pub fn index<'a>(&mut self, visuals: impl IntoIterator<Item = &'a Visual>) -> Result<()> {
+        let mut con = self.con.lock().unwrap();
+
+        let people = Self::people_names(&con)?;
+
+        let tx = con.transaction()?;
+        {
+            tx.execute("DELETE FROM visual_search", [])?;
+
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO visual_search (
+                    visual_id,
+                    file_name,
+                    folder,
+                    people,
+                    tags,
+                    title,
+                    description,
+                    places
+                ) VALUES (
+                    ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8
+                )",
+            )?;
+
+            for v in visuals {
+                let file_names = [&v.picture_path, &v.video_path]
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|path| path.file_name())
+                    .map(|name| name.to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+
+                let folder = v.parent_path.file_name().map(|name| name.to_string_lossy());
+
+                let mut names: Vec<&str> = v
+                    .person_ids
+                    .iter()
+                    .filter_map(|person_id| people.get(&person_id.id()))
+                    .map(|name| name.as_str())
+                    .collect();
+
+                names.extend(v.regions.iter().filter_map(|region| region.name.as_deref()));
+
+                stmt.execute(params![
+                    v.visual_id.id(),
+                    file_names,
+                    folder,
+                    names.join(" "),
+                    v.keywords.join(" "),
+                    v.title,
+                    v.description,
+                    Self::place_names(v),
+                ])?;
+            }
+        }
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    /// Finds photos and videos matching a search query.
+    pub fn search(&self, query: &SearchQuery) -> Result<SearchResults> {
+        let criteria = Query::And(query.criteria.clone());
+
+        let Some(expression) = query.match_expression() else {
+            return Ok(SearchResults {
+                criteria,
+                visual_ids: None,
+            });
+        };
+
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare_cached(
+            "SELECT visual_id
+            FROM visual_search
+            WHERE visual_search MATCH ?1",
+        )?;
+
+        let visual_ids: HashSet<VisualId> = stmt
+            .query_map([expression], |row| row.get("visual_id").map(VisualId::new))?
+            .flatten()
+            .collect();
+
+        Ok(SearchResults {
+            criteria,
+            visual_ids: Some(Arc::new(visual_ids)),
+        })
+    }
+
+    /// Names of known people, keyed by person ID.
+    fn people_names(con: &rusqlite::Connection) -> Result<HashMap<i64, String>> {
+        let mut stmt = con.prepare("SELECT person_id, name FROM people")?;
+
+        let result = stmt.query_map([], |row| Ok((row.get("person_id")?, row.get("name")?)))?;
+
+        Ok(result.flatten().collect())
+    }
+
+    /// Names of the place where a visual item was captured.
+    fn place_names(v: &Visual) -> String {
+        v.place
+            .iter()
+            .flat_map(|place| {
+                [
+                    Some(&place.city),
+                    place.region.as_ref(),
+                    Some(&place.country),
+                ]
+            })
+            .flatten()
+            .map(|name| name.as_str())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database;
+    use crate::people::PersonId;
+    use crate::photo::PictureId;
+    use crate::place::Place;
+    use crate::smart_album::MediaType;
+    use chrono::DateTime;
+    use std::path::{Path, PathBuf};
+
+    fn photo(id: i64, path: &str, keywords: Vec<String>) -> Visual {
+        let path = PathBuf::from(path);
+        Visual {
+            visual_id: VisualId::new(format!("{}_x", id)),
+            parent_path: path.parent().map(Path::to_path_buf).unwrap(),
+            thumbnail_path: None,
+            video_id: None,
+            video_path: None,
+            video_transcoded_path: None,
+            video_duration: None,
+            video_orientation: None,
+            picture_id: Some(PictureId::new(id)),
+            picture_path: Some(path),
+            picture_orientation: None,
+            raw_picture_id: None,
+            raw_picture_path: None,
+            motion_photo_video_path: None,
+            ordering_ts: DateTime::parse_from_rfc3339("2022-06-01T12:00:00Z")
+                .unwrap()
+                .into(),
+            is_selfie: None,
+            is_live_photo: false,
+            is_transcode_required: None,
+            location: None,
+            place: None,
+            stack_id: None,
+            stack_count: 1,
+            rating: None,
+            is_favourite: false,
+            title: None,
+            description: None,
+            keywords,
+            regions: Vec::new(),
+            person_ids: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn indexes_and_searches() {
+        let con = Arc::new(Mutex::new(database::setup_in_memory().unwrap()));
+        {
+            let con = con.lock().unwrap();
+            con.execute(
+                "INSERT INTO people (person_id, name, thumbnail_path) VALUES (7, 'Bob Smith', 'bob.png')",
+                [],
+            )
+            .unwrap();
+        }
+
+        let mut repo = Repository::open(con).unwrap();
+
+        let mut beach = photo(1, "/photos/Lisbon/beach.jpg", vec!["Sea".into()]);
+        beach.person_ids = vec![PersonId::new(7)];
+        beach.place = Some(Place {
+            country: "Portugal".into(),
+            region: Some("Lisbon".into()),
+            city: "Cascais".into(),
+        });
+        let city = photo(2, "/photos/Porto/IMG_0002.jpg", vec![]);
+
+        repo.index([&beach, &city]).unwrap();
+
+        let results = repo.search(&SearchQuery::parse("lisb")).unwrap();
+        assert!(results.matches(&beach));
+        assert!(!results.matches(&city));
+
+        let results = repo
+            .search(&SearchQuery::parse("person:bob tag:sea"))
+            .unwrap();
+        assert!(results.matches(&beach));
+        assert!(!results.matches(&city));
+
+        let results = repo.search(&SearchQuery::parse("place:portugal")).unwrap();
+        assert!(results.matches(&beach));
+        assert!(!results.matches(&city));
+
+        let results = repo.search(&SearchQuery::parse("img year:2022")).unwrap();
+        assert!(!results.matches(&beach));
+        assert!(results.matches(&city));
+
+        let results = repo.search(&SearchQuery::parse("type:video")).unwrap();
+        assert_eq!(
+            Query::And(vec![Query::Media(MediaType::Video)]),
+            results.criteria
+        );
+        assert!(!results.matches(&city));
+
+        // Re-indexing replaces previous index.
+        repo.index([&city]).unwrap();
+        let results = repo.search(&SearchQuery::parse("beach")).unwrap();
+        assert!(!results.matches(&beach));
+    }
+}
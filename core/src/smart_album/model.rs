@@ -0,0 +1,36 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::query::Query;
+use std::fmt::Display;
+
+/// Database ID of smart album
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SmartAlbumId(i64);
+
+impl SmartAlbumId {
+    pub fn new(id: i64) -> Self {
+        Self(id)
+    }
+
+    pub fn id(&self) -> i64 {
+        self.0
+    }
+}
+
+impl Display for SmartAlbumId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A named query over the library.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmartAlbum {
+    pub smart_album_id: SmartAlbumId,
+
+    pub name: String,
+
+    pub query: Query,
+}
@@ -0,0 +1,406 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Composable queries over visual items.
+//!
+//! Queries are saved as s-expressions, such as
+//! `(and (person 3) (date 2023-01-01 2023-12-31) (not (selfie)))`.
+
+use crate::path_encoding;
+use crate::people::PersonId;
+use crate::visual::Visual;
+use anyhow::*;
+use chrono::NaiveDate;
+use h3o::CellIndex;
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::str::FromStr;
+use strum::{AsRefStr, EnumString};
+
+/// Kind of media
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AsRefStr, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum MediaType {
+    /// Photos that aren't motion photos.
+    Photo,
+
+    /// Videos that aren't part of a motion photo.
+    Video,
+
+    /// iOS live photos and Android motion photos.
+    Motion,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Query {
+    /// Matches if all sub-queries match. Matches everything if empty.
+    And(Vec<Query>),
+
+    /// Matches if any sub-query matches. Matches nothing if empty.
+    Or(Vec<Query>),
+
+    Not(Box<Query>),
+
+    /// A known person has been recognized in the picture.
+    Person(PersonId),
+
+    /// Captured on or between two dates.
+    DateRange(NaiveDate, NaiveDate),
+
+    /// In a folder, but not in a sub-folder.
+    Folder(PathBuf),
+
+    /// Captured in a geographic area.
+    Place(CellIndex),
+
+    /// Captured near a city in a country.
+    Country(String),
+
+    /// Captured near a city, given as country and city names.
+    City(String, String),
+
+    Media(MediaType),
+
+    Selfie,
+
+    Favourite,
+
+    /// Rated with at least this many stars.
+    MinRating(i32),
+
+    /// Has a keyword, or tag, ignoring case.
+    Tag(String),
+}
+
+impl Query {
+    pub fn matches(&self, v: &Visual) -> bool {
+        match self {
+            Query::And(queries) => queries.iter().all(|q| q.matches(v)),
+            Query::Or(queries) => queries.iter().any(|q| q.matches(v)),
+            Query::Not(query) => !query.matches(v),
+            Query::Person(person_id) => v.person_ids.contains(person_id),
+            Query::DateRange(from, to) => {
+                let date = v.ordering_ts.date_naive();
+                *from <= date && date <= *to
+            }
+            Query::Folder(path) => v.parent_path == *path,
+            Query::Place(cell_index) => v
+                .location
+                .is_some_and(|location| location.to_cell(cell_index.resolution()) == *cell_index),
+            Query::Country(country) => v.place.as_ref().is_some_and(|p| p.country == *country),
+            Query::City(country, city) => v
+                .place
+                .as_ref()
+                .is_some_and(|p| p.country == *country && p.city == *city),
+            Query::Media(MediaType::Photo) => v.is_photo_only(),
+            Query::Media(MediaType::Video) => v.is_video_only() && !v.is_motion_photo(),
+            Query::Media(MediaType::Motion) => v.is_motion_photo(),
+            Query::Selfie => v.is_selfie(),
+            Query::Favourite => v.is_favourite,
+            Query::MinRating(stars) => v.rating.is_some_and(|rating| rating >= *stars),
+            Query::Tag(tag) => {
+                let tag = tag.to_lowercase();
+                v.keywords.iter().any(|k| k.to_lowercase() == tag)
+            }
+        }
+    }
+
+    /// Same query, but matching another person instead of the given one,
+    /// such as when two people are merged.
+    pub fn replace_person(&self, from: PersonId, into: PersonId) -> Query {
+        let replace_all = |queries: &[Query]| -> Vec<Query> {
+            queries
+                .iter()
+                .map(|q| q.replace_person(from, into))
+                .collect()
+        };
+
+        match self {
+            Query::And(queries) => Query::And(replace_all(queries)),
+            Query::Or(queries) => Query::Or(replace_all(queries)),
+            Query::Not(query) => Query::Not(Box::new(query.replace_person(from, into))),
+            Query::Person(person_id) if *person_id == from => Query::Person(into),
+            query => query.clone(),
+        }
+    }
+}
+
+impl Display for Query {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Query::And(queries) | Query::Or(queries) => {
+                let op = if matches!(self, Query::And(_)) {
+                    "and"
+                } else {
+                    "or"
+                };
+                write!(f, "({}", op)?;
+                for q in queries {
+                    write!(f, " {}", q)?;
+                }
+                write!(f, ")")
+            }
+            Query::Not(query) => write!(f, "(not {})", query),
+            Query::Person(person_id) => write!(f, "(person {})", person_id),
+            Query::DateRange(from, to) => write!(f, "(date {} {})", from, to),
+            Query::Folder(path) => {
+                write!(f, "(folder {})", quote(&path_encoding::to_base64(path)))
+            }
+            Query::Place(cell_index) => write!(f, "(place {})", cell_index),
+            Query::Country(country) => write!(f, "(country {})", quote(country)),
+            Query::City(country, city) => write!(f, "(city {} {})", quote(country), quote(city)),
+            Query::Media(media_type) => write!(f, "(media {})", media_type.as_ref()),
+            Query::Selfie => write!(f, "(selfie)"),
+            Query::Favourite => write!(f, "(favourite)"),
+            Query::MinRating(stars) => write!(f, "(rating {})", stars),
+            Query::Tag(tag) => write!(f, "(tag {})", quote(tag)),
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let tokens = tokenize(s)?;
+        let mut tokens = tokens.into_iter().peekable();
+        let query = parse(&mut tokens)?;
+        if tokens.next().is_some() {
+            bail!("Unexpected text after query: {}", s);
+        }
+        Ok(query)
+    }
+}
+
+/// Quote and escape a string value.
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Atom(String),
+    Quoted(String),
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            '"' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\\') => {
+                            let Some(c) = chars.next() else {
+                                bail!("Unterminated escape in query");
+                            };
+                            value.push(c);
+                        }
+                        Some('"') => break,
+                        Some(c) => value.push(c),
+                        None => bail!("Unterminated string in query"),
+                    }
+                }
+                tokens.push(Token::Quoted(value));
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut value = String::from(c);
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace() && !"()\"".contains(*c)) {
+                    value.push(c);
+                }
+                tokens.push(Token::Atom(value));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn parse<I: Iterator<Item = Token>>(tokens: &mut std::iter::Peekable<I>) -> Result<Query> {
+    if tokens.next() != Some(Token::Open) {
+        bail!("Expected '(' in query");
+    }
+
+    let Some(Token::Atom(op)) = tokens.next() else {
+        bail!("Expected query operator");
+    };
+
+    let query = match op.as_str() {
+        "and" | "or" => {
+            let mut queries = Vec::new();
+            while tokens.peek() == Some(&Token::Open) {
+                queries.push(parse(tokens)?);
+            }
+            if op == "and" {
+                Query::And(queries)
+            } else {
+                Query::Or(queries)
+            }
+        }
+        "not" => Query::Not(Box::new(parse(tokens)?)),
+        "person" => Query::Person(PersonId::new(atom(tokens)?.parse()?)),
+        "date" => Query::DateRange(atom(tokens)?.parse()?, atom(tokens)?.parse()?),
+        "folder" => Query::Folder(path_encoding::from_base64(&quoted(tokens)?)?),
+        "place" => Query::Place(atom(tokens)?.parse()?),
+        "country" => Query::Country(quoted(tokens)?),
+        "city" => Query::City(quoted(tokens)?, quoted(tokens)?),
+        "media" => Query::Media(atom(tokens)?.parse()?),
+        "selfie" => Query::Selfie,
+        "favourite" => Query::Favourite,
+        "rating" => Query::MinRating(atom(tokens)?.parse()?),
+        "tag" => Query::Tag(quoted(tokens)?),
+        _ => bail!("Unknown query operator: {}", op),
+    };
+
+    if tokens.next() != Some(Token::Close) {
+        bail!("Expected ')' after {} query", op);
+    }
+
+    Ok(query)
+}
+
+fn atom<I: Iterator<Item = Token>>(tokens: &mut I) -> Result<String> {
+    match tokens.next() {
+        Some(Token::Atom(value)) => Ok(value),
+        _ => bail!("Expected value in query"),
+    }
+}
+
+fn quoted<I: Iterator<Item = Token>>(tokens: &mut I) -> Result<String> {
+    match tokens.next() {
+        Some(Token::Quoted(value)) => Ok(value),
+        _ => bail!("Expected quoted value in query"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::photo::PictureId;
+    use crate::place::Place;
+    use crate::visual::VisualId;
+    use chrono::DateTime;
+
+    fn photo(person_ids: Vec<PersonId>, keywords: Vec<String>, is_selfie: bool) -> Visual {
+        Visual {
+            visual_id: VisualId::new("1_x".into()),
+            parent_path: PathBuf::from("/photos/Lisbon"),
+            thumbnail_path: None,
+            video_id: None,
+            video_path: None,
+            video_transcoded_path: None,
+            video_duration: None,
+            video_orientation: None,
+            picture_id: Some(PictureId::new(1)),
+            picture_path: Some(PathBuf::from("/photos/Lisbon/1.jpg")),
+            picture_orientation: None,
+            raw_picture_id: None,
+            raw_picture_path: None,
+            motion_photo_video_path: None,
+            ordering_ts: DateTime::parse_from_rfc3339("2023-06-01T12:00:00Z")
+                .unwrap()
+                .into(),
+            is_selfie: Some(is_selfie),
+            is_live_photo: false,
+            is_transcode_required: None,
+            location: None,
+            place: None,
+            stack_id: None,
+            stack_count: 1,
+            rating: Some(3),
+            is_favourite: false,
+            title: None,
+            description: None,
+            keywords,
+            regions: Vec::new(),
+            person_ids,
+        }
+    }
+
+    #[test]
+    fn round_trips_as_text() {
+        let query = Query::And(vec![
+            Query::Person(PersonId::new(3)),
+            Query::DateRange(
+                NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(),
+                NaiveDate::from_ymd_opt(2023, 12, 31).unwrap(),
+            ),
+            Query::Or(vec![
+                Query::Media(MediaType::Video),
+                Query::Folder(PathBuf::from("/photos/Lisbon \"old\"")),
+            ]),
+            Query::Not(Box::new(Query::Selfie)),
+            Query::Tag("Café \\ \"bar\"".into()),
+            Query::MinRating(2),
+            Query::Favourite,
+            Query::City("Portugal".into(), "Lisbon".into()),
+            Query::Country("Portugal".into()),
+            Query::And(vec![]),
+        ]);
+
+        let text = query.to_string();
+        assert_eq!(query, text.parse::<Query>().unwrap());
+    }
+
+    #[test]
+    fn rejects_malformed_text() {
+        assert!("(and (selfie)".parse::<Query>().is_err());
+        assert!("(selfie) (selfie)".parse::<Query>().is_err());
+        assert!("(person alice)".parse::<Query>().is_err());
+        assert!("(tag unquoted)".parse::<Query>().is_err());
+        assert!("(frobnicate)".parse::<Query>().is_err());
+    }
+
+    #[test]
+    fn matches_composed_queries() {
+        let alice = PersonId::new(1);
+        let bob = PersonId::new(2);
+        let v = photo(vec![alice], vec!["Lisbon".into()], false);
+
+        let in_2023 = Query::DateRange(
+            NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2023, 12, 31).unwrap(),
+        );
+
+        let query = Query::And(vec![
+            Query::Person(alice),
+            in_2023.clone(),
+            Query::Tag("lisbon".into()),
+            Query::Not(Box::new(Query::Selfie)),
+            Query::MinRating(3),
+        ]);
+        assert!(query.matches(&v));
+
+        assert!(!Query::And(vec![Query::Person(bob), in_2023.clone()]).matches(&v));
+        assert!(Query::Or(vec![Query::Person(bob), in_2023]).matches(&v));
+        assert!(!Query::Or(vec![]).matches(&v));
+        assert!(Query::Folder(PathBuf::from("/photos/Lisbon")).matches(&v));
+        assert!(Query::Media(MediaType::Photo).matches(&v));
+        assert!(!Query::Media(MediaType::Video).matches(&v));
+    }
+
+    #[test]
+    fn matches_places() {
+        let mut v = photo(vec![], vec![], false);
+        assert!(!Query::Country("Portugal".into()).matches(&v));
+
+        v.place = Some(Place {
+            country: "Portugal".into(),
+            region: Some("Lisbon".into()),
+            city: "Sintra".into(),
+        });
+        assert!(Query::Country("Portugal".into()).matches(&v));
+        assert!(Query::City("Portugal".into(), "Sintra".into()).matches(&v));
+        assert!(!Query::City("Portugal".into(), "Lisbon".into()).matches(&v));
+        assert!(!Query::Country("Spain".into()).matches(&v));
+    }
+}
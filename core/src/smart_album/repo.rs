@@ -0,0 +1,136 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::model::{SmartAlbum, SmartAlbumId};
+use super::query::Query;
+use anyhow::*;
+use chrono::*;
+use rusqlite;
+use rusqlite::params;
+use std::result::Result::Ok;
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+/// Repository of smart albums saved by the user.
+#[derive(Debug, Clone)]
+pub struct Repository {
+    /// Connection to backing Sqlite database.
+    con: Arc<Mutex<rusqlite::Connection>>,
+}
+
+impl Repository {
+    pub fn open(con: Arc<Mutex<rusqlite::Connection>>) -> Result<Repository> {
+        Ok(Repository { con })
+    }
+
+    /// Gets all smart albums, sorted by name.
+    pub fn all(&self) -> Result<Vec<SmartAlbum>> {
+        let con = self.con.lock().unwrap();
+
+        let mut stmt = con.prepare(
+            "SELECT
+                    smart_album_id,
+                    name,
+                    query
+                FROM smart_albums
+                ORDER BY name COLLATE NOCASE ASC",
+        )?;
+
+        let result = stmt.query_map([], |row| {
+            let smart_album_id = row.get("smart_album_id").map(SmartAlbumId::new)?;
+            let query: String = row.get("query")?;
+            let query = query.parse::<Query>().map_err(|e| {
+                warn!("Invalid query for smart album {}: {}", smart_album_id, e);
+                rusqlite::Error::InvalidQuery
+            })?;
+
+            Ok(SmartAlbum {
+                smart_album_id,
+                name: row.get("name")?,
+                query,
+            })
+        })?;
+
+        Ok(result.flatten().collect())
+    }
+
+    pub fn create(&mut self, name: &str, query: &Query) -> Result<SmartAlbumId> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare_cached(
+            "INSERT INTO smart_albums (
+                name,
+                query,
+                created_ts
+            ) VALUES (
+                ?1, ?2, ?3
+            )",
+        )?;
+
+        stmt.execute(params![name, query.to_string(), Utc::now()])?;
+
+        Ok(SmartAlbumId::new(con.last_insert_rowid()))
+    }
+
+    pub fn update(
+        &mut self,
+        smart_album_id: SmartAlbumId,
+        name: &str,
+        query: &Query,
+    ) -> Result<()> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare_cached(
+            "UPDATE smart_albums
+            SET
+                name = ?2,
+                query = ?3
+            WHERE smart_album_id = ?1",
+        )?;
+
+        stmt.execute(params![smart_album_id.id(), name, query.to_string()])?;
+
+        Ok(())
+    }
+
+    /// Deletes smart album. Pictures and videos matching the query are not deleted.
+    pub fn delete(&mut self, smart_album_id: SmartAlbumId) -> Result<()> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare_cached("DELETE FROM smart_albums WHERE smart_album_id = ?1")?;
+
+        stmt.execute(params![smart_album_id.id()])?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database;
+    use crate::smart_album::MediaType;
+
+    #[test]
+    fn saves_updates_and_deletes() {
+        let con = Arc::new(Mutex::new(database::setup_in_memory().unwrap()));
+        let mut repo = Repository::open(con).unwrap();
+
+        let videos = Query::Media(MediaType::Video);
+        let smart_album_id = repo.create("Videos", &videos).unwrap();
+
+        let albums = repo.all().unwrap();
+        assert_eq!(1, albums.len());
+        assert_eq!("Videos", albums[0].name);
+        assert_eq!(videos, albums[0].query);
+
+        let favourite_videos = Query::And(vec![videos, Query::Favourite]);
+        repo.update(smart_album_id, "Favourite videos", &favourite_videos)
+            .unwrap();
+
+        let albums = repo.all().unwrap();
+        assert_eq!("Favourite videos", albums[0].name);
+        assert_eq!(favourite_videos, albums[0].query);
+
+        repo.delete(smart_album_id).unwrap();
+        assert!(repo.all().unwrap().is_empty());
+    }
+}
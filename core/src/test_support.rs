@@ -0,0 +1,134 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Fixtures shared by repository tests.
+
+use crate::database;
+use crate::library::{self, LibraryRoot, LibraryRoots};
+use crate::people::FaceId;
+use crate::photo;
+use crate::photo::PictureId;
+use crate::scan::ScannedFile;
+use chrono::prelude::*;
+use rusqlite::params;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Library of a single root in a temporary directory, backed by a migrated in-memory database.
+pub struct TestLibrary {
+    pub dir: tempfile::TempDir,
+    pub con: Arc<Mutex<rusqlite::Connection>>,
+    pub roots: LibraryRoots,
+    pub root: LibraryRoot,
+}
+
+impl TestLibrary {
+    pub fn new() -> TestLibrary {
+        let dir = tempfile::tempdir().unwrap();
+        let con = Arc::new(Mutex::new(database::setup_in_memory().unwrap()));
+        let roots = library::Repository::open(con.clone())
+            .unwrap()
+            .sync(&[library::RootConfig::from_path(dir.path())])
+            .unwrap();
+        let root = roots.iter().next().unwrap().clone();
+        TestLibrary {
+            dir,
+            con,
+            roots,
+            root,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// Photo repository using the library directory for cached and generated files.
+    pub fn photo_repo(&self) -> photo::Repository {
+        photo::Repository::open(&self.roots, self.path(), self.path(), self.con.clone()).unwrap()
+    }
+
+    /// Creates empty files relative to the library directory and adds them as pictures
+    /// created and modified at the given times.
+    /// Returns picture IDs in the same order as the files.
+    pub fn add_pictures(&self, files: &[(&str, Option<DateTime<Utc>>)]) -> Vec<PictureId> {
+        let scanned: Vec<ScannedFile> = files
+            .iter()
+            .map(|(name, ts)| {
+                let path = self.path().join(name);
+                std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+                std::fs::write(&path, b"").unwrap();
+                ScannedFile {
+                    path,
+                    fs_created_at: *ts,
+                    fs_modified_at: *ts,
+                    fs_file_size_bytes: 10,
+                }
+            })
+            .collect();
+
+        let mut photo_repo = self.photo_repo();
+        photo_repo.add_all(&self.root, &scanned).unwrap();
+
+        let pictures = photo_repo.all().unwrap();
+        scanned
+            .iter()
+            .map(|file| {
+                pictures
+                    .iter()
+                    .find(|pic| pic.path == file.path)
+                    .map(|pic| pic.picture_id)
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    /// Adds a face detected in a picture, with thumbnail and bounds paths relative
+    /// to the library directory.
+    pub fn add_face(
+        &self,
+        picture_id: PictureId,
+        thumbnail_path: &str,
+        bounds_path: &str,
+        confidence: f32,
+    ) -> FaceId {
+        let con = self.con.lock().unwrap();
+        con.execute(
+            "INSERT INTO pictures_faces (
+                model_name, picture_id, thumbnail_path, bounds_path,
+                bounds_x, bounds_y, bounds_width, bounds_height,
+                right_eye_x, right_eye_y, left_eye_x, left_eye_y, nose_x, nose_y,
+                right_mouth_corner_x, right_mouth_corner_y,
+                left_mouth_corner_x, left_mouth_corner_y,
+                confidence
+            ) VALUES (
+                'test', ?1, ?2, ?3, 0, 0, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, ?4
+            )",
+            params![picture_id.id(), thumbnail_path, bounds_path, confidence],
+        )
+        .unwrap();
+        FaceId::new(con.last_insert_rowid())
+    }
+
+    /// Number of rows in a table.
+    pub fn count(&self, table: &str) -> i64 {
+        self.con
+            .lock()
+            .unwrap()
+            .query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
+                row.get(0)
+            })
+            .unwrap()
+    }
+}
+
+/// Scanned file of the given size, created and modified at the given time.
+pub fn scanned(path: PathBuf, ts: DateTime<Utc>, fs_file_size_bytes: u64) -> ScannedFile {
+    ScannedFile {
+        path,
+        fs_created_at: Some(ts),
+        fs_modified_at: Some(ts),
+        fs_file_size_bytes,
+    }
+}
@@ -0,0 +1,88 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::photo::PictureId;
+use crate::video::VideoId;
+use crate::visual::Visual;
+use std::fmt::Display;
+
+/// Database ID of user album
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AlbumId(i64);
+
+impl AlbumId {
+    pub fn new(id: i64) -> Self {
+        Self(id)
+    }
+
+    pub fn id(&self) -> i64 {
+        self.0
+    }
+}
+
+impl Display for AlbumId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A picture, video, or both, in a user album or an event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlbumItem {
+    pub picture_id: Option<PictureId>,
+    pub video_id: Option<VideoId>,
+}
+
+impl AlbumItem {
+    pub fn of(visual: &Visual) -> Self {
+        Self {
+            picture_id: visual.picture_id,
+            video_id: visual.video_id,
+        }
+    }
+
+    /// Is this item the picture or video of a visual item?
+    pub fn matches(&self, visual: &Visual) -> bool {
+        (self.picture_id.is_some() && self.picture_id == visual.picture_id)
+            || (self.video_id.is_some() && self.video_id == visual.video_id)
+    }
+}
+
+/// An album of pictures and videos chosen by the user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserAlbum {
+    pub album_id: AlbumId,
+
+    pub name: String,
+
+    /// Items in manual sort order.
+    pub items: Vec<AlbumItem>,
+
+    /// Item chosen as album cover, if any.
+    pub cover_item: Option<AlbumItem>,
+}
+
+impl UserAlbum {
+    /// Item to show as album cover. First item unless another was chosen.
+    pub fn cover(&self) -> Option<&AlbumItem> {
+        self.cover_item.as_ref().or(self.items.first())
+    }
+
+    pub fn contains(&self, visual: &Visual) -> bool {
+        self.items.iter().any(|item| item.matches(visual))
+    }
+
+    /// Position of visual item in manual sort order.
+    pub fn position(&self, visual: &Visual) -> Option<usize> {
+        self.items.iter().position(|item| item.matches(visual))
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
@@ -0,0 +1,320 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::model::{AlbumId, AlbumItem, UserAlbum};
+use crate::photo::PictureId;
+use crate::video::VideoId;
+use anyhow::*;
+use chrono::*;
+use rusqlite;
+use rusqlite::params;
+use rusqlite::Row;
+use std::result::Result::Ok;
+use std::sync::{Arc, Mutex};
+
+/// Repository of albums created by the user.
+#[derive(Debug, Clone)]
+pub struct Repository {
+    /// Connection to backing Sqlite database.
+    con: Arc<Mutex<rusqlite::Connection>>,
+}
+
+impl Repository {
+    pub fn open(con: Arc<Mutex<rusqlite::Connection>>) -> Result<Repository> {
+        Ok(Repository { con })
+    }
+
+    /// Gets all user albums, sorted by name, with items in manual sort order.
+    pub fn all(&self) -> Result<Vec<UserAlbum>> {
+        let con = self.con.lock().unwrap();
+
+        let mut stmt = con.prepare(
+            "SELECT
+                    user_albums.album_id,
+                    user_albums.name,
+                    cover_items.picture_id AS cover_picture_id,
+                    cover_items.video_id AS cover_video_id
+                FROM user_albums
+                LEFT OUTER JOIN user_album_items AS cover_items
+                    ON cover_items.item_id = user_albums.cover_item_id
+                ORDER BY user_albums.name COLLATE NOCASE ASC",
+        )?;
+
+        let result = stmt.query_map([], |row| {
+            let cover_item = Self::to_album_item(row, "cover_picture_id", "cover_video_id")?;
+            Ok(UserAlbum {
+                album_id: row.get("album_id").map(AlbumId::new)?,
+                name: row.get("name")?,
+                items: Vec::new(),
+                cover_item,
+            })
+        })?;
+
+        let mut albums: Vec<UserAlbum> = result.flatten().collect();
+
+        let mut stmt = con.prepare(
+            "SELECT
+                    album_id,
+                    picture_id,
+                    video_id
+                FROM user_album_items
+                ORDER BY album_id, position ASC",
+        )?;
+
+        let result = stmt.query_map([], |row| {
+            let album_id = row.get("album_id").map(AlbumId::new)?;
+            let item = Self::to_album_item(row, "picture_id", "video_id")?;
+            Ok((album_id, item))
+        })?;
+
+        for (album_id, item) in result.flatten() {
+            let album = albums.iter_mut().find(|x| x.album_id == album_id);
+            if let (Some(album), Some(item)) = (album, item) {
+                album.items.push(item);
+            }
+        }
+
+        Ok(albums)
+    }
+
+    /// Creates an empty album.
+    pub fn create(&mut self, name: &str) -> Result<AlbumId> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare_cached(
+            "INSERT INTO user_albums (
+                name,
+                created_ts
+            ) VALUES (
+                ?1, ?2
+            )",
+        )?;
+
+        stmt.execute(params![name, Utc::now()])?;
+
+        Ok(AlbumId::new(con.last_insert_rowid()))
+    }
+
+    pub fn rename(&mut self, album_id: AlbumId, name: &str) -> Result<()> {
+        let con = self.con.lock().unwrap();
+        let mut stmt =
+            con.prepare_cached("UPDATE user_albums SET name = ?2 WHERE album_id = ?1")?;
+
+        stmt.execute(params![album_id.id(), name])?;
+
+        Ok(())
+    }
+
+    /// Deletes album. Pictures and videos in the album are not deleted.
+    pub fn delete(&mut self, album_id: AlbumId) -> Result<()> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare_cached("DELETE FROM user_albums WHERE album_id = ?1")?;
+
+        stmt.execute(params![album_id.id()])?;
+
+        Ok(())
+    }
+
+    /// Adds items to the end of an album. Items already in the album are skipped.
+    pub fn add(&mut self, album_id: AlbumId, items: &[AlbumItem]) -> Result<()> {
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+        {
+            let mut max_position = tx.prepare_cached(
+                "SELECT COALESCE(MAX(position), -1) FROM user_album_items WHERE album_id = ?1",
+            )?;
+
+            let mut position: i64 = max_position.query_row([album_id.id()], |row| row.get(0))?;
+
+            let mut is_present = tx.prepare_cached(
+                "SELECT EXISTS (
+                    SELECT 1
+                    FROM user_album_items
+                    WHERE album_id = ?1
+                    AND (picture_id = ?2 OR video_id = ?3)
+                )",
+            )?;
+
+            let mut insert = tx.prepare_cached(
+                "INSERT INTO user_album_items (
+                    album_id,
+                    position,
+                    picture_id,
+                    video_id
+                ) VALUES (
+                    ?1, ?2, ?3, ?4
+                )",
+            )?;
+
+            for item in items {
+                let picture_id = item.picture_id.map(|x| x.id());
+                let video_id = item.video_id.map(|x| x.id());
+
+                let present: bool = is_present
+                    .query_row(params![album_id.id(), picture_id, video_id], |row| {
+                        row.get(0)
+                    })?;
+
+                if !present {
+                    position += 1;
+                    insert.execute(params![album_id.id(), position, picture_id, video_id])?;
+                }
+            }
+        }
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    /// Removes items from an album.
+    pub fn remove(&mut self, album_id: AlbumId, items: &[AlbumItem]) -> Result<()> {
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+        {
+            let mut delete = tx.prepare_cached(
+                "DELETE FROM user_album_items
+                WHERE album_id = ?1
+                AND (picture_id = ?2 OR video_id = ?3)",
+            )?;
+
+            for item in items {
+                delete.execute(params![
+                    album_id.id(),
+                    item.picture_id.map(|x| x.id()),
+                    item.video_id.map(|x| x.id()),
+                ])?;
+            }
+        }
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    /// Sets manual sort order of album items. Items not in the album are ignored.
+    pub fn set_order(&mut self, album_id: AlbumId, items: &[AlbumItem]) -> Result<()> {
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+        {
+            let mut update = tx.prepare_cached(
+                "UPDATE user_album_items
+                SET position = ?4
+                WHERE album_id = ?1
+                AND (picture_id = ?2 OR video_id = ?3)",
+            )?;
+
+            for (position, item) in items.iter().enumerate() {
+                update.execute(params![
+                    album_id.id(),
+                    item.picture_id.map(|x| x.id()),
+                    item.video_id.map(|x| x.id()),
+                    position,
+                ])?;
+            }
+        }
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    /// Sets item to show as album cover.
+    pub fn set_cover(&mut self, album_id: AlbumId, item: &AlbumItem) -> Result<()> {
+        let con = self.con.lock().unwrap();
+        // Other items can share the picture or video, so prefer an exact match.
+        let mut stmt = con.prepare_cached(
+            "UPDATE user_albums
+            SET cover_item_id = (
+                SELECT item_id
+                FROM user_album_items
+                WHERE album_id = ?1
+                AND (picture_id = ?2 OR video_id = ?3)
+                ORDER BY (picture_id IS ?2 AND video_id IS ?3) DESC, position ASC
+                LIMIT 1
+            )
+            WHERE album_id = ?1",
+        )?;
+
+        stmt.execute(params![
+            album_id.id(),
+            item.picture_id.map(|x| x.id()),
+            item.video_id.map(|x| x.id()),
+        ])?;
+
+        Ok(())
+    }
+
+    fn to_album_item(
+        row: &Row<'_>,
+        picture_column: &str,
+        video_column: &str,
+    ) -> rusqlite::Result<Option<AlbumItem>> {
+        let picture_id: Option<PictureId> = row
+            .get::<_, Option<i64>>(picture_column)?
+            .map(PictureId::new);
+        let video_id: Option<VideoId> = row.get::<_, Option<i64>>(video_column)?.map(VideoId::new);
+
+        if picture_id.is_none() && video_id.is_none() {
+            return Ok(None);
+        }
+
+        Ok(Some(AlbumItem {
+            picture_id,
+            video_id,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestLibrary;
+
+    #[test]
+    fn adds_removes_and_orders_items() {
+        let library = TestLibrary::new();
+        let items: Vec<AlbumItem> = library
+            .add_pictures(&[
+                ("a.jpg", Some(Utc::now())),
+                ("b.jpg", Some(Utc::now())),
+                ("c.jpg", Some(Utc::now())),
+            ])
+            .into_iter()
+            .map(|picture_id| AlbumItem {
+                picture_id: Some(picture_id),
+                video_id: None,
+            })
+            .collect();
+
+        let mut repo = Repository::open(library.con.clone()).unwrap();
+        let album_id = repo.create("Holiday").unwrap();
+
+        // Adding twice doesn't duplicate items.
+        repo.add(album_id, &items[0..2]).unwrap();
+        repo.add(album_id, &items).unwrap();
+
+        let album = repo.all().unwrap().remove(0);
+        assert_eq!("Holiday", album.name);
+        assert_eq!(items, album.items);
+        assert_eq!(Some(&items[0]), album.cover());
+
+        repo.set_order(album_id, &[items[2], items[0], items[1]])
+            .unwrap();
+        repo.set_cover(album_id, &items[1]).unwrap();
+        repo.remove(album_id, &items[0..1]).unwrap();
+        repo.rename(album_id, "Summer").unwrap();
+
+        let album = repo.all().unwrap().remove(0);
+        assert_eq!("Summer", album.name);
+        assert_eq!(vec![items[2], items[1]], album.items);
+        assert_eq!(Some(&items[1]), album.cover());
+
+        // Removing the cover falls back to the first item.
+        repo.remove(album_id, &items[1..2]).unwrap();
+        let album = repo.all().unwrap().remove(0);
+        assert_eq!(Some(&items[2]), album.cover());
+
+        repo.delete(album_id).unwrap();
+        assert!(repo.all().unwrap().is_empty());
+
+        assert_eq!(0, library.count("user_album_items"));
+    }
+}
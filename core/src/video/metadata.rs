@@ -3,6 +3,8 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use super::Metadata;
+use crate::photo::gps::GPSLocation;
+use crate::xmp;
 use anyhow::*;
 use chrono::{DateTime, TimeDelta};
 
@@ -17,8 +19,19 @@ use std::process::Command;
 /// a bug fix or feature addition that changes the metadata produced.
 /// Each photo will be saved with a metadata scan version which will allow for
 /// easy selection of videos when there metadata can be updated.
-
-pub const VERSION: u32 = 2;
+///
+/// History:
+/// 3. XMP sidecars and embedded XMP.
+/// 4. GPS location from QuickTime metadata.
+pub const VERSION: u32 = 4;
+
+/// Container metadata keys for ISO 6709 locations. Apple devices write the first,
+/// and Android devices write the second.
+const LOCATION_KEYS: [&str; 3] = [
+    "com.apple.quicktime.location.ISO6709",
+    "location",
+    "location-eng",
+];
 
 pub fn from_path(path: &Path) -> Result<Metadata> {
     let mut metadata = Metadata::default();
@@ -38,6 +51,11 @@ pub fn from_path(path: &Path) -> Result<Metadata> {
 
     metadata.container_format = Some(String::from(context.format().description()));
 
+    metadata.location = LOCATION_KEYS
+        .iter()
+        .find_map(|key| context_metadata.get(key))
+        .and_then(from_iso6709);
+
     if let Some(stream) = context.streams().best(ffmpeg::media::Type::Video) {
         let duration = stream.duration() as f64 * f64::from(stream.time_base()) * 1000.0;
         metadata.duration = TimeDelta::try_milliseconds(duration as i64);
@@ -93,13 +111,96 @@ pub fn from_path(path: &Path) -> Result<Metadata> {
         metadata.audio_codec = Some(String::from(codec.id().name()));
     }
 
+    // XMP values, such as a corrected capture time, take precedence.
+    if let Some(xmp) = xmp::reader::from_path(path)? {
+        metadata.created_at = xmp.created_at.map(|x| x.to_utc()).or(metadata.created_at);
+        metadata.location = xmp.location.or(metadata.location);
+        metadata.xmp = Some(xmp);
+    }
+
     Ok(metadata)
 }
 
+/// Parse an ISO 6709 location, such as `+38.7223-009.1393+012.000/`.
+/// Latitude and longitude may be in degrees, degrees and minutes, or degrees,
+/// minutes, and seconds. Altitude is ignored.
+fn from_iso6709(text: &str) -> Option<GPSLocation> {
+    let text = text.trim().trim_end_matches('/');
+
+    // Split into signed parts, stopping at any coordinate reference system.
+    let mut parts: Vec<String> = Vec::new();
+    for c in text.chars() {
+        match c {
+            '+' | '-' => parts.push(String::from(c)),
+            'C' => break,
+            c => parts.last_mut()?.push(c),
+        }
+    }
+
+    let latitude = iso6709_coord(parts.first()?, 2)?;
+    let longitude = iso6709_coord(parts.get(1)?, 3)?;
+    GPSLocation::from_f64(latitude, longitude)
+}
+
+/// Parse a signed ISO 6709 coordinate to decimal degrees. The number of
+/// integer digits shows whether minutes and seconds are present.
+fn iso6709_coord(part: &str, degree_digits: usize) -> Option<f64> {
+    let (sign, number) = part.split_at(1);
+    let sign = if sign == "-" { -1.0 } else { 1.0 };
+
+    let integer_digits = number.find('.').unwrap_or(number.len());
+    if integer_digits < 1 || !number[..integer_digits].bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let degrees = if integer_digits <= degree_digits {
+        number.parse::<f64>().ok()?
+    } else if integer_digits == degree_digits + 2 {
+        let degrees = number[..degree_digits].parse::<f64>().ok()?;
+        let minutes = number[degree_digits..].parse::<f64>().ok()?;
+        degrees + minutes / 60.0
+    } else if integer_digits == degree_digits + 4 {
+        let degrees = number[..degree_digits].parse::<f64>().ok()?;
+        let minutes = number[degree_digits..degree_digits + 2]
+            .parse::<f64>()
+            .ok()?;
+        let seconds = number[degree_digits + 2..].parse::<f64>().ok()?;
+        degrees + minutes / 60.0 + seconds / 3600.0
+    } else {
+        return None;
+    };
+
+    Some(sign * degrees)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn assert_location(text: &str, latitude: f64, longitude: f64) {
+        let location = from_iso6709(text).unwrap();
+        assert!((location.latitude.to_f64() - latitude).abs() < 0.0001);
+        assert!((location.longitude.to_f64() - longitude).abs() < 0.0001);
+    }
+
+    #[test]
+    fn parses_iso6709_locations() {
+        // Apple, with altitude
+        assert_location("+38.7223-009.1393+012.000/", 38.7223, -9.1393);
+
+        // Android, without altitude
+        assert_location("-33.8568+151.2153/", -33.8568, 151.2153);
+
+        // Degrees and minutes, and degrees, minutes, and seconds
+        assert_location("+4043.5-07400.5/", 40.725, -74.008333);
+        assert_location("+404330-0740030CRSWGS_84/", 40.725, -74.008333);
+
+        assert!(from_iso6709("").is_none());
+        assert!(from_iso6709("+38.7223/").is_none());
+        assert!(from_iso6709("+98.0+010.0/").is_none());
+        assert!(from_iso6709("nonsense").is_none());
+    }
+
     #[test]
     fn test_ffmpeg_next() {
         ffmpeg::init().unwrap();
@@ -2,6 +2,8 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use crate::photo::gps::GPSLocation;
+use crate::xmp::Xmp;
 use chrono::{DateTime, TimeDelta, Utc};
 use std::fmt::Display;
 use std::path::PathBuf;
@@ -52,18 +54,7 @@ pub struct Video {
     pub video_codec: Option<String>,
 }
 
-/// A video on the local file system that has been scanned.
-#[derive(Debug, Clone)]
-pub struct ScannedFile {
-    /// Full path to picture file.
-    pub path: PathBuf,
-
-    pub fs_created_at: Option<DateTime<Utc>>,
-
-    pub fs_modified_at: Option<DateTime<Utc>>,
-
-    pub fs_file_size_bytes: u64,
-}
+pub use crate::scan::ScannedFile;
 
 #[derive(Debug, Default, Clone)]
 pub struct Metadata {
@@ -86,4 +77,10 @@ pub struct Metadata {
     // Rotation of video in degrees.
     // Should be 90, 180, 270, or the negative of those.
     pub rotation: Option<i32>,
+
+    /// Where video was recorded, from QuickTime location metadata.
+    pub location: Option<GPSLocation>,
+
+    /// XMP from a sidecar or embedded in the video.
+    pub xmp: Option<Xmp>,
 }
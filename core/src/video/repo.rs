@@ -4,12 +4,16 @@
 
 use super::metadata;
 use super::Metadata;
+use crate::library::{LibraryRoot, LibraryRoots, RootId};
 use crate::path_encoding;
+use crate::photo::gps::GPSLocation;
+use crate::scan::ScanSummary;
 use crate::video::model::{ScannedFile, Video, VideoId};
 use anyhow::*;
 use chrono::*;
 use rusqlite;
 use rusqlite::params;
+use rusqlite::OptionalExtension;
 use rusqlite::Row;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
@@ -18,8 +22,8 @@ use std::sync::{Arc, Mutex};
 /// Repository is backed by a Sqlite database.
 #[derive(Debug, Clone)]
 pub struct Repository {
-    /// Base path to picture library on file system
-    library_base_path: PathBuf,
+    /// Library roots holding videos on file system
+    roots: LibraryRoots,
 
     /// Base path for thumbnails and transcoded videos
     cache_dir_base_path: PathBuf,
@@ -34,7 +38,7 @@ pub struct Repository {
 impl Repository {
     /// Builds a Repository and creates operational tables.
     pub fn open(
-        library_base_path: &Path,
+        roots: &LibraryRoots,
         cache_dir_base_path: &Path,
         data_dir_base_path: &Path,
         con: Arc<Mutex<rusqlite::Connection>>,
@@ -42,7 +46,7 @@ impl Repository {
         std::fs::create_dir_all(cache_dir_base_path)?;
 
         let repo = Repository {
-            library_base_path: PathBuf::from(library_base_path),
+            roots: roots.clone(),
             cache_dir_base_path: cache_dir_base_path.into(),
             data_dir_base_path: data_dir_base_path.into(),
             con,
@@ -51,6 +55,11 @@ impl Repository {
         Ok(repo)
     }
 
+    /// Library roots holding videos.
+    pub fn roots(&self) -> &LibraryRoots {
+        &self.roots
+    }
+
     pub fn add_thumbnail(&mut self, video_id: &VideoId, thumbnail_path: &Path) -> Result<()> {
         let mut con = self.con.lock().unwrap();
         let tx = con.transaction()?;
@@ -138,6 +147,46 @@ impl Repository {
                 WHERE video_id = ?1",
             )?;
 
+            let mut delete_xmp = tx.prepare_cached("DELETE FROM videos_xmp WHERE video_id = ?1")?;
+
+            let mut delete_keywords =
+                tx.prepare_cached("DELETE FROM videos_keywords WHERE video_id = ?1")?;
+
+            let mut insert_xmp = tx.prepare_cached(
+                "INSERT INTO videos_xmp (
+                    video_id,
+                    rating,
+                    title,
+                    description
+                ) VALUES (
+                    ?1, ?2, ?3, ?4
+                )",
+            )?;
+
+            let mut insert_keyword = tx.prepare_cached(
+                "INSERT OR IGNORE INTO videos_keywords (
+                    video_id,
+                    keyword
+                ) VALUES (
+                    ?1, ?2
+                )",
+            )?;
+
+            // Locations set by the user are kept.
+            let mut update_geo = tx.prepare_cached(
+                "INSERT INTO videos_geo (
+                    video_id,
+                    latitude,
+                    longitude
+                ) VALUES (
+                    ?1, ?2, ?3
+                ) ON CONFLICT (video_id) DO UPDATE SET
+                    latitude = ?2,
+                    longitude = ?3
+                WHERE is_user_set IS FALSE
+                ",
+            )?;
+
             for (video_id, metadata) in vids {
                 stmt.execute(params![
                     video_id.id(),
@@ -148,6 +197,70 @@ impl Repository {
                     metadata.content_id,
                     metadata.rotation,
                 ])?;
+
+                // XMP may have been removed since the video was last scanned.
+                delete_xmp.execute([video_id.id()])?;
+                delete_keywords.execute([video_id.id()])?;
+
+                if let Some(ref xmp) = metadata.xmp {
+                    insert_xmp.execute(params![
+                        video_id.id(),
+                        xmp.rating,
+                        xmp.title,
+                        xmp.description,
+                    ])?;
+
+                    for keyword in &xmp.keywords {
+                        insert_keyword.execute(params![video_id.id(), keyword])?;
+                    }
+                }
+
+                if let Some(location) = metadata.location {
+                    // SQLite treats a "nan" as a null, which violates the not-null constraint.
+                    let latitude = location.latitude.to_f64_safe();
+                    let longitude = location.longitude.to_f64_safe();
+                    if latitude.is_some() && longitude.is_some() {
+                        update_geo.execute(params![video_id.id(), latitude, longitude])?;
+                    }
+                }
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Set the location of videos, such as when a user picks a point on the map.
+    /// Locations are marked as set by the user, so rescanning metadata won't overwrite them.
+    pub fn set_location(&mut self, video_ids: &[VideoId], location: &GPSLocation) -> Result<()> {
+        let (Some(latitude), Some(longitude)) = (
+            location.latitude.to_f64_safe(),
+            location.longitude.to_f64_safe(),
+        ) else {
+            bail!("Invalid location: {:?}", location);
+        };
+
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+
+        {
+            let mut update_geo = tx.prepare_cached(
+                "INSERT INTO videos_geo (
+                    video_id,
+                    latitude,
+                    longitude,
+                    is_user_set
+                ) VALUES (
+                    ?1, ?2, ?3, TRUE
+                ) ON CONFLICT (video_id) DO UPDATE SET
+                    latitude = ?2,
+                    longitude = ?3,
+                    is_user_set = TRUE
+                ",
+            )?;
+
+            for video_id in video_ids {
+                update_geo.execute(params![video_id.id(), latitude, longitude])?;
             }
         }
 
@@ -155,55 +268,183 @@ impl Repository {
         Ok(())
     }
 
-    pub fn add_all(&mut self, vids: &Vec<ScannedFile>) -> Result<()> {
+    /// Add all Videos received from a vector, which must be the result of scanning the
+    /// whole of a library root. Videos that are unchanged since the previous scan are left
+    /// alone. Changed videos have their metadata, thumbnails, and transcodes re-queued.
+    pub fn add_all(&mut self, root: &LibraryRoot, vids: &Vec<ScannedFile>) -> Result<ScanSummary> {
+        self.add(vids, Some(root))
+    }
+
+    /// Add or update some videos, such as those reported by a file system watcher.
+    /// Removed videos aren't counted because only part of the library has been scanned.
+    pub fn add_some(&mut self, vids: &[ScannedFile]) -> Result<ScanSummary> {
+        self.add(vids, None)
+    }
+
+    /// Add videos. If `scanned_root` is set, then vids holds every video in that root.
+    fn add(
+        &mut self,
+        vids: &[ScannedFile],
+        scanned_root: Option<&LibraryRoot>,
+    ) -> Result<ScanSummary> {
         let mut con = self.con.lock().unwrap();
         let tx = con.transaction()?;
 
+        let mut summary = ScanSummary::default();
+
         // Create a scope to make borrowing of tx not be an error.
         {
+            let previous_count: usize = match scanned_root {
+                Some(root) => tx.query_row(
+                    "SELECT COUNT(*) FROM videos WHERE root_id = ?1 AND deleted_ts IS NULL",
+                    [root.root_id.id()],
+                    |row| row.get(0),
+                )?,
+                None => 0,
+            };
+
+            // Videos present before this scan that are found again.
+            let mut seen_count: usize = 0;
+
+            let mut vid_fingerprint_stmt = tx.prepare_cached(
+                "SELECT
+                        video_id,
+                        fs_modified_ts,
+                        fs_file_size_bytes,
+                        deleted_ts
+                    FROM videos
+                    WHERE root_id = ?1
+                    AND video_path_b64 = ?2",
+            )?;
+
             let mut vid_stmt = tx.prepare_cached(
                 "INSERT INTO videos (
                         fs_created_ts,
                         fs_modified_ts,
+                        fs_file_size_bytes,
                         video_path_b64,
                         video_path_lossy,
                         link_path_b64,
-                        link_path_lossy
+                        link_path_lossy,
+                        root_id
                     ) VALUES (
-                        ?1, ?2, ?3, ?4, ?5, ?6
-                    ) ON CONFLICT (video_path_b64) DO UPDATE SET
-                        fs_created_ts = ?1,
-                        fs_modified_ts = ?2
-                    ",
+                        ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8
+                    )",
+            )?;
+
+            let mut vid_restore_stmt = tx.prepare_cached(
+                "UPDATE videos
+                    SET
+                        deleted_ts = NULL
+                    WHERE video_id = ?1",
+            )?;
+
+            let mut vid_size_stmt = tx.prepare_cached(
+                "UPDATE videos
+                    SET
+                        fs_file_size_bytes = ?2
+                    WHERE video_id = ?1",
+            )?;
+
+            let mut hash_changed_stmt =
+                tx.prepare_cached("DELETE FROM videos_hashes WHERE video_id = ?1")?;
+
+            let mut vid_changed_stmt = tx.prepare_cached(
+                "UPDATE videos
+                    SET
+                        fs_created_ts = ?2,
+                        fs_modified_ts = ?3,
+                        fs_file_size_bytes = ?4,
+                        metadata_version = 0,
+                        thumbnail_path = NULL,
+                        transcoded_path = NULL,
+                        is_broken = FALSE
+                    WHERE video_id = ?1",
             )?;
 
             for vid in vids {
                 // convert to relative path before saving to database
-                let video_path = vid.path.strip_prefix(&self.library_base_path)?;
+                let (root, video_path) = match scanned_root {
+                    Some(root) => (root, vid.path.strip_prefix(&root.path)?),
+                    None => self
+                        .roots
+                        .relativize(&vid.path)
+                        .ok_or_else(|| anyhow!("{:?} is not in a library root", vid.path))?,
+                };
                 let video_path_b64 = path_encoding::to_base64(video_path);
 
-                // Path without suffix so sibling pictures and videos can be related
-                let link_path = video_path
-                    .file_stem()
-                    .and_then(|x| x.to_str())
-                    .expect("Must exist");
-
-                let link_path = video_path.with_file_name(link_path);
-                let link_path_b64 = path_encoding::to_base64(&link_path);
+                let fingerprint = vid_fingerprint_stmt
+                    .query_row(params![root.root_id.id(), &video_path_b64], |row| {
+                        let video_id: i64 = row.get(0)?;
+                        let fs_modified_at: Option<DateTime<Utc>> = row.get(1)?;
+                        let fs_file_size_bytes: Option<u64> = row.get(2)?;
+                        let deleted_at: Option<DateTime<Utc>> = row.get(3)?;
+                        std::result::Result::Ok((
+                            video_id,
+                            fs_modified_at,
+                            fs_file_size_bytes,
+                            deleted_at,
+                        ))
+                    })
+                    .optional()?;
+
+                match fingerprint {
+                    Some((video_id, fs_modified_at, fs_file_size_bytes, deleted_at)) => {
+                        if deleted_at.is_some() {
+                            // A missing file has come back before being purged.
+                            vid_restore_stmt.execute([video_id])?;
+                        } else {
+                            seen_count += 1;
+                        }
+
+                        if vid.is_changed(fs_modified_at, fs_file_size_bytes) {
+                            vid_changed_stmt.execute(params![
+                                video_id,
+                                vid.fs_created_at,
+                                vid.fs_modified_at,
+                                vid.fs_file_size_bytes,
+                            ])?;
+                            hash_changed_stmt.execute([video_id])?;
+                            summary.changed += 1;
+                        } else {
+                            if fs_file_size_bytes.is_none() {
+                                vid_size_stmt.execute(params![video_id, vid.fs_file_size_bytes])?;
+                            }
+                            summary.unchanged += 1;
+                        }
+                    }
+                    None => {
+                        // Path without suffix so sibling pictures and videos can be related
+                        let link_path = video_path
+                            .file_stem()
+                            .and_then(|x| x.to_str())
+                            .expect("Must exist");
+
+                        let link_path = video_path.with_file_name(link_path);
+                        let link_path_b64 = path_encoding::to_base64(&link_path);
+
+                        vid_stmt.execute(params![
+                            vid.fs_created_at,
+                            vid.fs_modified_at,
+                            vid.fs_file_size_bytes,
+                            video_path_b64,
+                            video_path.to_string_lossy(),
+                            link_path_b64,
+                            link_path.to_string_lossy(),
+                            root.root_id.id(),
+                        ])?;
+                        summary.added += 1;
+                    }
+                }
+            }
 
-                vid_stmt.execute(params![
-                    vid.fs_created_at,
-                    vid.fs_modified_at,
-                    video_path_b64,
-                    video_path.to_string_lossy(),
-                    link_path_b64,
-                    link_path.to_string_lossy(),
-                ])?;
+            if scanned_root.is_some() {
+                summary.removed = previous_count.saturating_sub(seen_count);
             }
         }
 
         tx.commit()?;
-        Ok(())
+        Ok(summary)
     }
 
     /// Gets all videos in the repository, in ascending order of modification timestamp.
@@ -212,6 +453,7 @@ impl Repository {
         let mut stmt = con.prepare(
             "SELECT
                     video_id,
+                    root_id,
                     video_path_b64,
                     thumbnail_path,
                     COALESCE(
@@ -225,6 +467,7 @@ impl Repository {
                     transcoded_path
                 FROM videos
                 WHERE COALESCE(is_broken, FALSE) IS FALSE
+                AND deleted_ts IS NULL
                 ORDER BY ordering_ts ASC",
         )?;
 
@@ -239,6 +482,7 @@ impl Repository {
         let mut stmt = con.prepare(
             "SELECT
                     video_id,
+                    root_id,
                     video_path_b64,
                     thumbnail_path,
                     COALESCE(
@@ -253,6 +497,7 @@ impl Repository {
                 FROM videos
                 WHERE metadata_version < ?1
                 AND COALESCE(is_broken, FALSE) IS FALSE
+                AND deleted_ts IS NULL
                 ORDER BY ordering_ts ASC",
         )?;
 
@@ -261,6 +506,37 @@ impl Repository {
         Ok(result)
     }
 
+    /// Gets all videos that haven't had their content hashed.
+    pub fn find_need_content_hash(&self) -> Result<Vec<Video>> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare(
+            "SELECT
+                    videos.video_id,
+                    videos.root_id,
+                    videos.video_path_b64,
+                    videos.thumbnail_path,
+                    COALESCE(
+                        videos.stream_created_ts,
+                        videos.fs_created_ts,
+                        videos.fs_modified_ts,
+                        CURRENT_TIMESTAMP
+                    ) AS ordering_ts,
+                    videos.duration_millis,
+                    videos.video_codec,
+                    videos.transcoded_path
+                FROM videos
+                LEFT OUTER JOIN videos_hashes USING (video_id)
+                WHERE videos_hashes.video_id IS NULL
+                AND COALESCE(videos.is_broken, FALSE) IS FALSE
+                AND videos.deleted_ts IS NULL
+                ORDER BY ordering_ts ASC",
+        )?;
+
+        let result = stmt.query_map([], |row| self.to_video(row))?;
+        let result = result.flatten().collect();
+        Ok(result)
+    }
+
     /// Gets paths of files to delete when a video is no longer present.
     pub fn find_files_to_cleanup(&self, video_id: VideoId) -> Result<Vec<PathBuf>> {
         let con = self.con.lock().unwrap();
@@ -281,7 +557,11 @@ impl Repository {
         let video_path: String = row.get("video_path_b64")?;
         let video_path =
             path_encoding::from_base64(&video_path).map_err(|_| rusqlite::Error::InvalidQuery)?;
-        let video_path = self.library_base_path.join(video_path);
+        let root_id = row.get("root_id").map(RootId::new)?;
+        let video_path = self
+            .roots
+            .resolve(root_id, &video_path)
+            .ok_or_else(|| rusqlite::Error::InvalidPath(video_path))?;
 
         let thumbnail_path = row
             .get("thumbnail_path")
@@ -324,6 +604,46 @@ impl Repository {
             })
     }
 
+    /// Soft delete a video whose file is missing. The video is hidden, but
+    /// not removed, so it can be restored if the file reappears.
+    pub fn mark_deleted(&mut self, video_id: VideoId) -> Result<()> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare(
+            "UPDATE videos
+            SET
+                deleted_ts = ?2
+            WHERE video_id = ?1
+            AND deleted_ts IS NULL",
+        )?;
+
+        stmt.execute(params![video_id.id(), Utc::now()])?;
+
+        Ok(())
+    }
+
+    /// Gets videos that were soft deleted before a time and can be purged.
+    pub fn find_deleted_before(&self, before: DateTime<Utc>) -> Result<Vec<Video>> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare(
+            "SELECT
+                    video_id,
+                    root_id,
+                    video_path_b64,
+                    thumbnail_path,
+                    deleted_ts AS ordering_ts,
+                    duration_millis,
+                    video_codec,
+                    transcoded_path
+                FROM videos
+                WHERE deleted_ts < ?1",
+        )?;
+
+        let result = stmt.query_map([before], |row| self.to_video(row))?;
+        let result = result.flatten().collect();
+        Ok(result)
+    }
+
+    /// Hard delete a video. Everything recorded about it is deleted by cascade.
     pub fn remove(&mut self, video_id: VideoId) -> Result<()> {
         let con = self.con.lock().unwrap();
         let mut stmt = con.prepare("DELETE FROM videos WHERE video_id = ?1")?;
@@ -4,7 +4,9 @@
 
 pub mod model;
 pub mod repo;
+pub mod stack;
 
 pub use model::Visual;
 pub use model::VisualId;
 pub use repo::Repository;
+pub use stack::StackPolicy;
@@ -6,7 +6,9 @@ use std::fmt::Display;
 use std::path::PathBuf;
 
 use crate::photo::model::Orientation;
-use crate::{PictureId, VideoId, YearMonth};
+use crate::place::Place;
+use crate::xmp::Region;
+use crate::{PersonId, PictureId, VideoId, YearMonth};
 
 use chrono::*;
 
@@ -15,7 +17,7 @@ use h3o::LatLng;
 pub use crate::photo::model::Orientation as PictureOrientation;
 
 /// Database ID of a visual item
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct VisualId(String);
 
 impl VisualId {
@@ -66,6 +68,11 @@ pub struct Visual {
 
     pub picture_orientation: Option<Orientation>,
 
+    // Camera RAW file grouped with the picture, if any.
+    pub raw_picture_id: Option<PictureId>,
+
+    pub raw_picture_path: Option<PathBuf>,
+
     pub motion_photo_video_path: Option<PathBuf>,
 
     /// Best candidate for ordering visual items. With a final fallback of the current timestamp.
@@ -82,6 +89,38 @@ pub struct Visual {
 
     // Where photo was taken
     pub location: Option<LatLng>,
+
+    /// Named place nearest to where photo was taken.
+    pub place: Option<Place>,
+
+    /// Visual ID of the first photo in a stack of similar photos taken within seconds
+    /// of each other, such as a burst. None if not in a stack.
+    pub stack_id: Option<VisualId>,
+
+    /// Number of photos in the stack, or 1 if not in a stack.
+    pub stack_count: usize,
+
+    /// Star rating set in Fotema, or else read from XMP.
+    /// -1 for rejected, 0 for unrated, and 1 to 5 stars.
+    pub rating: Option<i32>,
+
+    /// Has the user marked this as a favourite?
+    pub is_favourite: bool,
+
+    /// Title from XMP.
+    pub title: Option<String>,
+
+    /// Description or caption from XMP.
+    pub description: Option<String>,
+
+    /// Keywords, or tags, from XMP.
+    pub keywords: Vec<String>,
+
+    /// Regions, such as named faces, from XMP.
+    pub regions: Vec<Region>,
+
+    /// Known people recognized in the picture.
+    pub person_ids: Vec<PersonId>,
 }
 
 impl Visual {
@@ -101,6 +140,20 @@ impl Visual {
         self.picture_id.is_some() && self.video_id.is_none() && !self.is_live_photo
     }
 
+    /// Is the displayed picture a camera RAW file without a JPEG sibling?
+    pub fn is_raw_only(&self) -> bool {
+        self.picture_path
+            .as_ref()
+            .is_some_and(|path| crate::photo::raw::is_raw(path))
+    }
+
+    /// Is this in a stack, but not the photo representing the stack?
+    pub fn is_hidden_in_stack(&self) -> bool {
+        self.stack_id
+            .as_ref()
+            .is_some_and(|stack_id| *stack_id != self.visual_id)
+    }
+
     pub fn is_video_only(&self) -> bool {
         self.picture_id.is_none() && self.video_id.is_some()
     }
@@ -2,16 +2,22 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use crate::photo::PictureId;
+use crate::people::PersonId;
+use crate::photo::{PerceptualHash, PictureId};
+use crate::place::Place;
 use crate::video::VideoId;
 use crate::visual::model::{PictureOrientation, Visual, VisualId};
+use crate::visual::stack::StackPolicy;
+use crate::xmp::Region;
 
+use crate::library::{LibraryRoots, RootId};
 use crate::path_encoding;
 use anyhow::*;
 use chrono::*;
 use h3o::LatLng;
 use rusqlite;
 use rusqlite::Row;
+use std::collections::HashMap;
 use std::path;
 use std::path::PathBuf;
 use std::result::Result::Ok;
@@ -21,12 +27,15 @@ use std::sync::{Arc, Mutex};
 /// Repository is backed by a Sqlite database.
 #[derive(Debug, Clone)]
 pub struct Repository {
-    /// Base path to picture library on file system
-    library_base_path: path::PathBuf,
+    /// Library roots holding pictures and videos on file system
+    roots: LibraryRoots,
 
     /// Base path for thumbnails and transcoded videos
     cache_dir_base_path: path::PathBuf,
 
+    /// Groups bursts of similar photos
+    stack_policy: StackPolicy,
+
     /// Connection to backing Sqlite database.
     con: Arc<Mutex<rusqlite::Connection>>,
 }
@@ -34,24 +43,26 @@ pub struct Repository {
 impl Repository {
     /// Builds a Repository and creates operational tables.
     pub fn open(
-        library_base_path: &path::Path,
+        roots: &LibraryRoots,
         cache_dir_base_path: &path::Path,
         con: Arc<Mutex<rusqlite::Connection>>,
     ) -> Result<Repository> {
         let repo = Repository {
-            library_base_path: path::PathBuf::from(library_base_path),
+            roots: roots.clone(),
             cache_dir_base_path: path::PathBuf::from(cache_dir_base_path),
+            stack_policy: StackPolicy::default(),
             con,
         };
         Ok(repo)
     }
 
-    /// Gets all visual artefacts.
+    /// Gets all visual artefacts, with similar photos taken close together grouped into stacks.
     pub fn all(&self) -> Result<Vec<Visual>> {
         let con = self.con.lock().unwrap();
         let mut stmt = con.prepare(
             "SELECT
                     visual_id,
+                    root_id,
                     link_path_b64,
 
                     picture_id,
@@ -60,6 +71,9 @@ impl Repository {
                     picture_orientation,
                     is_selfie,
 
+                    raw_picture_id,
+                    raw_picture_path_b64,
+
                     video_id,
                     video_path_b64,
                     video_thumbnail,
@@ -75,14 +89,103 @@ impl Repository {
                     video_rotation,
 
                     latitude,
-                    longitude
+                    longitude,
+
+                    pictures_places.country AS place_country,
+                    pictures_places.region AS place_region,
+                    pictures_places.city AS place_city,
+
+                    pictures_perceptual_hashes.dhash,
+
+                    COALESCE(
+                        (SELECT rating FROM pictures_ratings WHERE pictures_ratings.picture_id = visual.picture_id),
+                        (SELECT rating FROM videos_ratings WHERE videos_ratings.video_id = visual.video_id),
+                        (SELECT rating FROM pictures_xmp WHERE pictures_xmp.picture_id = visual.picture_id),
+                        (SELECT rating FROM videos_xmp WHERE videos_xmp.video_id = visual.video_id)
+                    ) AS rating,
+                    COALESCE(
+                        (SELECT is_favourite FROM pictures_ratings WHERE pictures_ratings.picture_id = visual.picture_id),
+                        (SELECT is_favourite FROM videos_ratings WHERE videos_ratings.video_id = visual.video_id),
+                        FALSE
+                    ) AS is_favourite,
+                    COALESCE(
+                        (SELECT title FROM pictures_xmp WHERE pictures_xmp.picture_id = visual.picture_id),
+                        (SELECT title FROM videos_xmp WHERE videos_xmp.video_id = visual.video_id)
+                    ) AS title,
+                    COALESCE(
+                        (SELECT description FROM pictures_xmp WHERE pictures_xmp.picture_id = visual.picture_id),
+                        (SELECT description FROM videos_xmp WHERE videos_xmp.video_id = visual.video_id)
+                    ) AS description,
+                    COALESCE(
+                        (SELECT group_concat(keyword, char(31)) FROM pictures_keywords WHERE pictures_keywords.picture_id = visual.picture_id),
+                        (SELECT group_concat(keyword, char(31)) FROM videos_keywords WHERE videos_keywords.video_id = visual.video_id)
+                    ) AS keywords,
+                    (
+                        SELECT group_concat(DISTINCT person_id)
+                        FROM pictures_faces
+                        WHERE pictures_faces.picture_id = visual.picture_id
+                        AND pictures_faces.person_id IS NOT NULL
+                        AND NOT pictures_faces.is_ignored
+                    ) AS person_ids
                 FROM visual
+                LEFT OUTER JOIN pictures_perceptual_hashes USING (picture_id)
+                LEFT OUTER JOIN pictures_places USING (picture_id)
                 ORDER BY ordering_ts ASC",
         )?;
 
-        let result = stmt.query_map([], |row| self.to_visual(row))?;
-        let visuals = result.flatten().collect();
-        Ok(visuals)
+        let result = stmt.query_map([], |row| {
+            let visual = self.to_visual(row)?;
+            let dhash: Option<PerceptualHash> = row
+                .get("dhash")
+                .ok()
+                .map(|x: i64| PerceptualHash::new(x as u64));
+            Ok((visual, dhash))
+        })?;
+        let mut visuals: Vec<(Visual, Option<PerceptualHash>)> = result.flatten().collect();
+
+        let mut regions = Self::xmp_regions(&con)?;
+        for (visual, _) in visuals.iter_mut() {
+            if let Some(regions) = visual.picture_id.and_then(|id| regions.remove(&id.id())) {
+                visual.regions = regions;
+            }
+        }
+
+        Ok(self.stack_policy.stack(visuals))
+    }
+
+    /// XMP regions for all pictures, keyed by picture.
+    fn xmp_regions(con: &rusqlite::Connection) -> Result<HashMap<i64, Vec<Region>>> {
+        let mut stmt = con.prepare(
+            "SELECT
+                    picture_id,
+                    name,
+                    region_type,
+                    x,
+                    y,
+                    width,
+                    height
+                FROM pictures_xmp_regions
+                ORDER BY region_id",
+        )?;
+
+        let result = stmt.query_map([], |row| {
+            let picture_id: i64 = row.get("picture_id")?;
+            let region = Region {
+                name: row.get("name")?,
+                kind: row.get("region_type")?,
+                x: row.get("x")?,
+                y: row.get("y")?,
+                width: row.get("width")?,
+                height: row.get("height")?,
+            };
+            Ok((picture_id, region))
+        })?;
+
+        let mut regions: HashMap<i64, Vec<Region>> = HashMap::new();
+        for (picture_id, region) in result.flatten() {
+            regions.entry(picture_id).or_default().push(region);
+        }
+        Ok(regions)
     }
 
     fn to_visual(&self, row: &Row<'_>) -> rusqlite::Result<Visual> {
@@ -91,10 +194,16 @@ impl Repository {
             .map(VisualId::new)
             .expect("Must have visual_id");
 
+        // Visual items in a root that is no longer configured can't be shown.
+        let root_id = row.get("root_id").map(RootId::new)?;
+        let Some(root) = self.roots.get(root_id) else {
+            return Err(rusqlite::Error::InvalidQuery);
+        };
+
         let link_path: String = row.get("link_path_b64")?;
         let link_path =
             path_encoding::from_base64(&link_path).map_err(|_| rusqlite::Error::InvalidQuery)?;
-        let link_path = self.library_base_path.join(link_path);
+        let link_path = root.path.join(link_path);
 
         let picture_id: Option<PictureId> = row.get("picture_id").map(PictureId::new).ok();
 
@@ -103,7 +212,7 @@ impl Repository {
             .ok()
             .and_then(|x: String| path_encoding::from_base64(&x).ok());
 
-        let picture_path = picture_path.map(|x| self.library_base_path.join(x));
+        let picture_path = picture_path.map(|x| root.path.join(x));
 
         let picture_thumbnail: Option<PathBuf> = row
             .get("picture_thumbnail")
@@ -118,6 +227,14 @@ impl Repository {
 
         let is_selfie: Option<bool> = row.get("is_selfie").ok();
 
+        let raw_picture_id: Option<PictureId> = row.get("raw_picture_id").map(PictureId::new).ok();
+
+        let raw_picture_path: Option<PathBuf> = row
+            .get("raw_picture_path_b64")
+            .ok()
+            .and_then(|x: String| path_encoding::from_base64(&x).ok())
+            .map(|x| root.path.join(x));
+
         let video_id: Option<VideoId> = row.get("video_id").map(VideoId::new).ok();
 
         let video_path: Option<PathBuf> = row
@@ -125,7 +242,7 @@ impl Repository {
             .ok()
             .and_then(|x: String| path_encoding::from_base64(&x).ok());
 
-        let video_path = video_path.map(|x| self.library_base_path.join(x));
+        let video_path = video_path.map(|x| root.path.join(x));
 
         let video_thumbnail: Option<PathBuf> = row
             .get("video_thumbnail")
@@ -174,6 +291,43 @@ impl Repository {
             None
         };
 
+        let place_country: Option<String> = row.get("place_country").ok().flatten();
+        let place_city: Option<String> = row.get("place_city").ok().flatten();
+
+        let place = if let (Some(country), Some(city)) = (place_country, place_city) {
+            Some(Place {
+                country,
+                region: row.get("place_region").ok().flatten(),
+                city,
+            })
+        } else {
+            None
+        };
+
+        let rating: Option<i32> = row.get("rating").ok().flatten();
+        let is_favourite: bool = row.get("is_favourite").unwrap_or(false);
+        let title: Option<String> = row.get("title").ok().flatten();
+        let description: Option<String> = row.get("description").ok().flatten();
+
+        let keywords: Vec<String> = row
+            .get("keywords")
+            .ok()
+            .flatten()
+            .map(|x: String| x.split('\u{1f}').map(String::from).collect())
+            .unwrap_or_default();
+
+        let person_ids: Vec<PersonId> = row
+            .get("person_ids")
+            .ok()
+            .flatten()
+            .map(|x: String| {
+                x.split(',')
+                    .flat_map(|id| id.parse().ok())
+                    .map(PersonId::new)
+                    .collect()
+            })
+            .unwrap_or_default();
+
         let v = Visual {
             visual_id,
             parent_path: link_path.parent().map(PathBuf::from).expect("Parent path"),
@@ -181,6 +335,8 @@ impl Repository {
             picture_id,
             picture_path,
             picture_orientation,
+            raw_picture_id,
+            raw_picture_path,
             video_id,
             video_path,
             ordering_ts,
@@ -192,6 +348,16 @@ impl Repository {
             video_duration,
             motion_photo_video_path,
             location,
+            place,
+            stack_id: None,
+            stack_count: 1,
+            rating,
+            is_favourite,
+            title,
+            description,
+            keywords,
+            regions: Vec::new(),
+            person_ids,
         };
         Ok(v)
     }
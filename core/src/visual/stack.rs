@@ -0,0 +1,163 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::model::Visual;
+use crate::photo::PerceptualHash;
+use chrono::TimeDelta;
+
+/// Groups photos taken within seconds of each other that look alike, such as bursts
+/// and lightly edited copies, into stacks.
+#[derive(Debug, Clone)]
+pub struct StackPolicy {
+    /// Longest time between consecutive photos in a stack.
+    pub max_gap: TimeDelta,
+
+    /// Most bits that can differ between perceptual hashes of consecutive photos in a stack.
+    pub max_distance: u32,
+}
+
+impl Default for StackPolicy {
+    fn default() -> Self {
+        Self {
+            max_gap: TimeDelta::seconds(3),
+            max_distance: 10,
+        }
+    }
+}
+
+impl StackPolicy {
+    /// Assigns stacks to visual items, which must be in ascending time order.
+    /// The first photo in a stack represents the stack.
+    pub fn stack(&self, items: Vec<(Visual, Option<PerceptualHash>)>) -> Vec<Visual> {
+        let mut visuals: Vec<Visual> = Vec::with_capacity(items.len());
+
+        // Index of first item of current stack, and the perceptual hash of the last item.
+        let mut current: Option<(usize, PerceptualHash)> = None;
+
+        for (mut visual, hash) in items {
+            let hash = hash.filter(|_| visual.is_photo_only());
+
+            let Some(hash) = hash else {
+                current = None;
+                visuals.push(visual);
+                continue;
+            };
+
+            match current {
+                Some((first, last_hash))
+                    if self.is_similar(&visuals[visuals.len() - 1], &last_hash, &visual, &hash) =>
+                {
+                    let stack_id = visuals[first].visual_id.clone();
+                    visuals[first].stack_id = Some(stack_id.clone());
+                    visual.stack_id = Some(stack_id);
+                    current = Some((first, hash));
+                }
+                _ => {
+                    current = Some((visuals.len(), hash));
+                }
+            }
+
+            visuals.push(visual);
+        }
+
+        // Count members of each stack on every member.
+        let mut start = 0;
+        while start < visuals.len() {
+            let mut end = start + 1;
+            if visuals[start].stack_id.is_some() {
+                while end < visuals.len() && visuals[end].stack_id == visuals[start].stack_id {
+                    end += 1;
+                }
+            }
+            for visual in &mut visuals[start..end] {
+                visual.stack_count = end - start;
+            }
+            start = end;
+        }
+
+        visuals
+    }
+
+    fn is_similar(
+        &self,
+        previous: &Visual,
+        previous_hash: &PerceptualHash,
+        visual: &Visual,
+        hash: &PerceptualHash,
+    ) -> bool {
+        visual.ordering_ts - previous.ordering_ts <= self.max_gap
+            && previous_hash.distance(hash) <= self.max_distance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::photo::PictureId;
+    use crate::visual::VisualId;
+    use chrono::prelude::*;
+    use std::path::PathBuf;
+
+    fn photo(id: i64, secs: i64) -> Visual {
+        Visual {
+            visual_id: VisualId::new(format!("{}_x", id)),
+            parent_path: PathBuf::from("/"),
+            thumbnail_path: None,
+            video_id: None,
+            video_path: None,
+            video_transcoded_path: None,
+            video_duration: None,
+            video_orientation: None,
+            picture_id: Some(PictureId::new(id)),
+            picture_path: Some(PathBuf::from(format!("/{}.jpg", id))),
+            picture_orientation: None,
+            raw_picture_id: None,
+            raw_picture_path: None,
+            motion_photo_video_path: None,
+            ordering_ts: DateTime::from_timestamp(secs, 0).unwrap(),
+            is_selfie: None,
+            is_live_photo: false,
+            is_transcode_required: None,
+            location: None,
+            place: None,
+            stack_id: None,
+            stack_count: 1,
+            rating: None,
+            is_favourite: false,
+            title: None,
+            description: None,
+            keywords: Vec::new(),
+            regions: Vec::new(),
+            person_ids: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn stacks_similar_photos_taken_close_together() {
+        let burst = PerceptualHash::new(0b1111_0000);
+        let edited = PerceptualHash::new(0b1111_0011);
+        let other = PerceptualHash::new(u64::MAX);
+
+        let visuals = StackPolicy::default().stack(vec![
+            (photo(1, 0), Some(burst)),
+            (photo(2, 1), Some(burst)),
+            (photo(3, 2), Some(edited)),
+            // Looks different
+            (photo(4, 3), Some(other)),
+            // Looks the same, but taken later
+            (photo(5, 60), Some(other)),
+            (photo(6, 61), None),
+        ]);
+
+        let stack_id = Some(visuals[0].visual_id.clone());
+        assert_eq!(stack_id, visuals[1].stack_id);
+        assert_eq!(stack_id, visuals[2].stack_id);
+        assert_eq!(3, visuals[0].stack_count);
+
+        for visual in &visuals[3..] {
+            assert_eq!(None, visual.stack_id);
+            assert_eq!(1, visual.stack_count);
+        }
+    }
+}
@@ -0,0 +1,31 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! XMP metadata from sidecar files and XMP packets embedded in pictures and videos.
+//!
+//! Sidecars are usually named after the full media file name, so `IMG_1234.JPG` has a
+//! sidecar of `IMG_1234.JPG.xmp`. This keeps RAW and JPEG pairs apart and is the
+//! convention used by darktable and digiKam. Lightroom replaces the extension instead,
+//! so `IMG_1234.xmp` is also read.
+
+pub mod model;
+pub mod reader;
+pub mod writer;
+
+pub use model::Region;
+pub use model::Xmp;
+
+use std::path::{Path, PathBuf};
+
+/// Path of XMP sidecar written by Fotema for a picture or video.
+pub fn sidecar_path(media_path: &Path) -> PathBuf {
+    let mut file_name = media_path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".xmp");
+    media_path.with_file_name(file_name)
+}
+
+/// Paths an existing XMP sidecar might have, in order of preference.
+pub fn sidecar_paths(media_path: &Path) -> Vec<PathBuf> {
+    vec![sidecar_path(media_path), media_path.with_extension("xmp")]
+}
@@ -0,0 +1,84 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::photo::gps::GPSLocation;
+use crate::photo::model::Orientation;
+use chrono::{DateTime, FixedOffset};
+
+/// Metadata from an XMP sidecar or from XMP embedded in a picture or video.
+/// Typically written by tools such as darktable, digiKam, or Lightroom.
+#[derive(Debug, Default, Clone)]
+pub struct Xmp {
+    /// xmp:Rating. 1 to 5 stars, 0 for unrated, and -1 for rejected.
+    pub rating: Option<i32>,
+
+    /// dc:title
+    pub title: Option<String>,
+
+    /// dc:description
+    pub description: Option<String>,
+
+    /// dc:subject
+    pub keywords: Vec<String>,
+
+    /// MWG regions, such as named faces.
+    pub regions: Vec<Region>,
+
+    /// exif:DateTimeOriginal, such as written by Fotema when correcting capture time.
+    pub created_at: Option<DateTime<FixedOffset>>,
+
+    /// exif:GPSLatitude and exif:GPSLongitude
+    pub location: Option<GPSLocation>,
+
+    /// tiff:Orientation
+    pub orientation: Option<Orientation>,
+}
+
+impl Xmp {
+    /// Combine with lower priority XMP, such as that embedded in a file, filling in
+    /// values that are absent from self.
+    pub fn or(self, other: Xmp) -> Xmp {
+        Xmp {
+            rating: self.rating.or(other.rating),
+            title: self.title.or(other.title),
+            description: self.description.or(other.description),
+            keywords: if self.keywords.is_empty() {
+                other.keywords
+            } else {
+                self.keywords
+            },
+            regions: if self.regions.is_empty() {
+                other.regions
+            } else {
+                self.regions
+            },
+            created_at: self.created_at.or(other.created_at),
+            location: self.location.or(other.location),
+            orientation: self.orientation.or(other.orientation),
+        }
+    }
+}
+
+/// An MWG region of a picture.
+/// Coordinates are normalized to the range 0.0 to 1.0 and x and y are the
+/// centre of the region.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Region {
+    /// Name of region, such as the name of a person.
+    pub name: Option<String>,
+
+    /// Kind of region, such as "Face", "Pet", or "Focus".
+    pub kind: Option<String>,
+
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Region {
+    pub fn is_face(&self) -> bool {
+        self.kind.as_deref() == Some("Face")
+    }
+}
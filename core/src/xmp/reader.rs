@@ -0,0 +1,377 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Read XMP from sidecars and from packets embedded in media files.
+//! Only the simple properties Fotema uses are read, so this is not a general RDF parser.
+
+use super::model::{Region, Xmp};
+use super::sidecar_paths;
+use crate::photo::gps::GPSLocation;
+use crate::photo::model::Orientation;
+use anyhow::*;
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::reader::Reader;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::result::Result::Ok;
+use tracing::warn;
+
+/// Embedded XMP is usually near the start of a file, such as in a JPEG APP1 segment
+/// or a HEIC meta box, so only this many bytes are searched.
+const EMBEDDED_SEARCH_LEN: u64 = 1024 * 1024;
+
+/// Read XMP for a picture or video. Values in a sidecar take precedence over
+/// values embedded in the file. Unreadable XMP is logged and ignored.
+pub fn from_path(media_path: &Path) -> Result<Option<Xmp>> {
+    let sidecar = sidecar_paths(media_path)
+        .into_iter()
+        .find(|path| path.exists())
+        .and_then(|path| {
+            fs::read_to_string(&path)
+                .map_err(|e| anyhow!(e))
+                .and_then(|xml| parse(&xml))
+                .inspect_err(|e| warn!("Failed reading XMP sidecar {:?}: {:?}", path, e))
+                .ok()
+        });
+
+    let embedded = embedded_packet(media_path)?.and_then(|xml| {
+        parse(&xml)
+            .inspect_err(|e| warn!("Failed reading XMP embedded in {:?}: {:?}", media_path, e))
+            .ok()
+    });
+
+    let xmp = match (sidecar, embedded) {
+        (Some(sidecar), Some(embedded)) => Some(sidecar.or(embedded)),
+        (sidecar, embedded) => sidecar.or(embedded),
+    };
+
+    Ok(xmp)
+}
+
+/// Find an XMP packet embedded in a file by searching for the xmpmeta element.
+/// This works for JPEG, HEIC, PNG, and most other containers without having
+/// to understand each format.
+fn embedded_packet(media_path: &Path) -> Result<Option<String>> {
+    let mut buf = Vec::new();
+    fs::File::open(media_path)?
+        .take(EMBEDDED_SEARCH_LEN)
+        .read_to_end(&mut buf)?;
+
+    let start_tag = b"<x:xmpmeta";
+    let end_tag = b"</x:xmpmeta>";
+
+    let Some(start) = buf.windows(start_tag.len()).position(|w| w == start_tag) else {
+        return Ok(None);
+    };
+
+    let Some(end) = buf[start..]
+        .windows(end_tag.len())
+        .position(|w| w == end_tag)
+        .map(|i| start + i + end_tag.len())
+    else {
+        return Ok(None);
+    };
+
+    Ok(Some(String::from_utf8_lossy(&buf[start..end]).into_owned()))
+}
+
+/// Parse an XMP packet.
+pub fn parse(xml: &str) -> Result<Xmp> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut parser = Parser::default();
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => {
+                parser.start(&e)?;
+            }
+            Event::Empty(e) => {
+                parser.start(&e)?;
+                parser.end();
+            }
+            Event::Text(text) => {
+                parser.text(&text.unescape()?);
+            }
+            Event::End(_) => {
+                parser.end();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(parser.finish())
+}
+
+#[derive(Debug, Default)]
+struct Parser {
+    xmp: Xmp,
+
+    /// Qualified names of open elements.
+    stack: Vec<String>,
+
+    /// Region being parsed and depth of its rdf:li element.
+    region: Option<(usize, Region)>,
+
+    /// Units of region being parsed. Only normalized regions are kept.
+    region_unit: Option<String>,
+
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+}
+
+impl Parser {
+    fn start(&mut self, element: &BytesStart) -> Result<()> {
+        let name = String::from_utf8_lossy(element.name().as_ref()).into_owned();
+
+        // Each item in an MWG region list is a region.
+        let is_region = name == "rdf:li"
+            && self.region.is_none()
+            && self.stack.iter().any(|n| n == "mwg-rs:RegionList");
+
+        if is_region {
+            self.region = Some((self.stack.len(), Region::default()));
+            self.region_unit = None;
+        }
+
+        self.stack.push(name);
+
+        // Simple properties are often written as attributes.
+        for attribute in element.attributes() {
+            let attribute = attribute?;
+            let key = String::from_utf8_lossy(attribute.key.as_ref()).into_owned();
+            let value = attribute.unescape_value()?;
+            self.set(&key, &value);
+        }
+
+        Ok(())
+    }
+
+    fn end(&mut self) {
+        self.stack.pop();
+
+        let is_region_end = self
+            .region
+            .as_ref()
+            .is_some_and(|(depth, _)| *depth == self.stack.len());
+
+        if is_region_end {
+            if let Some((_, region)) = self.region.take() {
+                let is_normalized = !self
+                    .region_unit
+                    .as_ref()
+                    .is_some_and(|unit| unit != "normalized");
+                if is_normalized && region.width > 0.0 && region.height > 0.0 {
+                    self.xmp.regions.push(region);
+                }
+            }
+        }
+    }
+
+    /// Text belongs to the nearest enclosing property, skipping RDF containers
+    /// such as rdf:Alt, rdf:Bag, and rdf:li.
+    fn text(&mut self, text: &str) {
+        let property = self
+            .stack
+            .iter()
+            .rev()
+            .find(|name| !name.starts_with("rdf:"))
+            .cloned();
+
+        if let Some(property) = property {
+            self.set(&property, text);
+        }
+    }
+
+    fn set(&mut self, property: &str, value: &str) {
+        if let Some((_, ref mut region)) = self.region {
+            match property {
+                "mwg-rs:Name" => region.name = Some(value.to_string()),
+                "mwg-rs:Type" => region.kind = Some(value.to_string()),
+                "stArea:x" => region.x = value.parse().unwrap_or_default(),
+                "stArea:y" => region.y = value.parse().unwrap_or_default(),
+                "stArea:w" => region.width = value.parse().unwrap_or_default(),
+                "stArea:h" => region.height = value.parse().unwrap_or_default(),
+                "stArea:unit" => self.region_unit = Some(value.to_string()),
+                _ => {}
+            }
+            return;
+        }
+
+        match property {
+            "xmp:Rating" => {
+                self.xmp.rating = value.parse::<f64>().ok().map(|r| r.round() as i32);
+            }
+            "dc:title" if self.xmp.title.is_none() => {
+                self.xmp.title = Some(value.to_string());
+            }
+            "dc:description" if self.xmp.description.is_none() => {
+                self.xmp.description = Some(value.to_string());
+            }
+            "dc:subject" => {
+                if !self.xmp.keywords.iter().any(|k| k == value) {
+                    self.xmp.keywords.push(value.to_string());
+                }
+            }
+            "exif:DateTimeOriginal" => {
+                self.xmp.created_at = parse_date_time(value);
+            }
+            "exif:GPSLatitude" => {
+                self.latitude = parse_coord(value, 'N', 'S');
+            }
+            "exif:GPSLongitude" => {
+                self.longitude = parse_coord(value, 'E', 'W');
+            }
+            "tiff:Orientation" => {
+                self.xmp.orientation = value.parse::<u32>().ok().map(Orientation::from);
+            }
+            _ => {}
+        }
+    }
+
+    fn finish(mut self) -> Xmp {
+        if let (Some(latitude), Some(longitude)) = (self.latitude, self.longitude) {
+            self.xmp.location = GPSLocation::from_f64(latitude, longitude);
+        }
+        self.xmp
+    }
+}
+
+/// XMP dates may omit the time zone, seconds, or even the time.
+/// Dates without a time zone are assumed to be UTC.
+fn parse_date_time(value: &str) -> Option<DateTime<FixedOffset>> {
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(value) {
+        return Some(date_time);
+    }
+
+    let utc = FixedOffset::east_opt(0)?;
+
+    [
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+    .or_else(|| {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .ok()
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+    })
+    .and_then(|naive| naive.and_local_timezone(utc).single())
+}
+
+/// XMP GPS coordinates are "DDD,MM,SSk" or "DDD,MM.mmk" where k is a direction.
+fn parse_coord(value: &str, positive: char, negative: char) -> Option<f64> {
+    let direction = value.chars().next_back()?.to_ascii_uppercase();
+    let sign = if direction == positive {
+        1.0
+    } else if direction == negative {
+        -1.0
+    } else {
+        return None;
+    };
+
+    let parts: Vec<f64> = value[..value.len() - 1]
+        .split(',')
+        .map(|part| part.trim().parse::<f64>())
+        .collect::<Result<_, _>>()
+        .ok()?;
+
+    let decimal = match parts[..] {
+        [degrees, minutes] => degrees + minutes / 60.0,
+        [degrees, minutes, seconds] => degrees + minutes / 60.0 + seconds / 3600.0,
+        _ => return None,
+    };
+
+    Some(sign * decimal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::photo::edit::MetadataEdit;
+    use crate::xmp::writer;
+
+    #[test]
+    fn parses_digikam_sidecar() {
+        let xml = r#"<?xpacket begin="" id="W5M0MpCehiHzreSzNTczkc9d"?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about=""
+    xmlns:xmp="http://ns.adobe.com/xap/1.0/"
+    xmlns:dc="http://purl.org/dc/elements/1.1/"
+    xmlns:mwg-rs="http://www.metadataworkinggroup.com/schemas/regions/"
+    xmlns:stArea="http://ns.adobe.com/xmp/sType/Area#"
+    xmp:Rating="4">
+   <dc:title>
+    <rdf:Alt>
+     <rdf:li xml:lang="x-default">Picnic &amp; games</rdf:li>
+    </rdf:Alt>
+   </dc:title>
+   <dc:subject>
+    <rdf:Bag>
+     <rdf:li>Summer</rdf:li>
+     <rdf:li>Park</rdf:li>
+    </rdf:Bag>
+   </dc:subject>
+   <mwg-rs:Regions rdf:parseType="Resource">
+    <mwg-rs:RegionList>
+     <rdf:Bag>
+      <rdf:li>
+       <rdf:Description mwg-rs:Name="Alice" mwg-rs:Type="Face">
+        <mwg-rs:Area stArea:x="0.25" stArea:y="0.5" stArea:w="0.1" stArea:h="0.2" stArea:unit="normalized"/>
+       </rdf:Description>
+      </rdf:li>
+     </rdf:Bag>
+    </mwg-rs:RegionList>
+   </mwg-rs:Regions>
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>"#;
+
+        let xmp = parse(xml).unwrap();
+
+        assert_eq!(Some(4), xmp.rating);
+        assert_eq!(Some("Picnic & games".to_string()), xmp.title);
+        assert_eq!(None, xmp.description);
+        assert_eq!(vec!["Summer", "Park"], xmp.keywords);
+
+        assert_eq!(1, xmp.regions.len());
+        let region = &xmp.regions[0];
+        assert_eq!(Some("Alice".to_string()), region.name);
+        assert!(region.is_face());
+        assert_eq!(
+            (0.25, 0.5, 0.1, 0.2),
+            (region.x, region.y, region.width, region.height)
+        );
+    }
+
+    #[test]
+    fn reads_sidecar_written_by_fotema() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("IMG_0001.JPG");
+        fs::write(&path, b"").unwrap();
+
+        let created_at = DateTime::parse_from_rfc3339("2021-06-05T14:30:00+01:00").unwrap();
+        let edit = MetadataEdit {
+            created_at: Some(created_at),
+            location: GPSLocation::from_f64(-33.8568, 151.2153),
+            orientation: Some(Orientation::West),
+        };
+        writer::write_sidecar(&path, &edit).unwrap();
+
+        let xmp = from_path(&path).unwrap().unwrap();
+        assert_eq!(Some(created_at), xmp.created_at);
+        assert_eq!(Some(6), xmp.orientation.map(|o| o as u32));
+
+        let location = xmp.location.unwrap();
+        assert!((location.latitude.to_f64() + 33.8568).abs() < 0.0001);
+        assert!((location.longitude.to_f64() - 151.2153).abs() < 0.0001);
+    }
+}
@@ -0,0 +1,138 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Write picture metadata changes to XMP sidecars, so the original picture isn't modified.
+
+use super::sidecar_path;
+use crate::photo::edit::MetadataEdit;
+use crate::photo::gps::GPSCoord;
+use anyhow::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const NS_EXIF: (&str, &str) = ("exif", "http://ns.adobe.com/exif/1.0/");
+const NS_TIFF: (&str, &str) = ("tiff", "http://ns.adobe.com/tiff/1.0/");
+const NS_PHOTOSHOP: (&str, &str) = ("photoshop", "http://ns.adobe.com/photoshop/1.0/");
+
+/// Minimal XMP packet for a new sidecar.
+const EMPTY_SIDECAR: &str = "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>
+<x:xmpmeta xmlns:x=\"adobe:ns:meta/\" x:xmptk=\"Fotema\">
+ <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">
+  <rdf:Description rdf:about=\"\"/>
+ </rdf:RDF>
+</x:xmpmeta>
+<?xpacket end=\"w\"?>
+";
+
+/// Write metadata changes to the sidecar of a picture, creating the sidecar if
+/// it doesn't already exist. Properties in an existing sidecar that aren't being
+/// changed are preserved.
+pub fn write_sidecar(picture_path: &Path, edit: &MetadataEdit) -> Result<PathBuf> {
+    let path = sidecar_path(picture_path);
+
+    let mut xmp = if path.exists() {
+        fs::read_to_string(&path)?
+    } else {
+        EMPTY_SIDECAR.to_string()
+    };
+
+    if let Some(created_at) = edit.created_at {
+        let value = created_at.format("%Y-%m-%dT%H:%M:%S%:z").to_string();
+        xmp = set_property(&xmp, NS_EXIF, "DateTimeOriginal", &value)?;
+        xmp = set_property(&xmp, NS_PHOTOSHOP, "DateCreated", &value)?;
+    }
+
+    if let Some(location) = edit.location {
+        let latitude = to_xmp_coord(&location.latitude, 'N', 'S');
+        let longitude = to_xmp_coord(&location.longitude, 'E', 'W');
+        xmp = set_property(&xmp, NS_EXIF, "GPSLatitude", &latitude)?;
+        xmp = set_property(&xmp, NS_EXIF, "GPSLongitude", &longitude)?;
+    }
+
+    if let Some(orientation) = edit.orientation {
+        let value = (orientation as u32).to_string();
+        xmp = set_property(&xmp, NS_TIFF, "Orientation", &value)?;
+    }
+
+    fs::write(&path, xmp)?;
+    Ok(path)
+}
+
+/// XMP GPS coordinates are degrees and decimal minutes followed by a direction,
+/// such as "51,30.12345678N".
+fn to_xmp_coord(coord: &GPSCoord, positive: char, negative: char) -> String {
+    let decimal = coord.to_f64();
+    let direction = if decimal < 0.0 { negative } else { positive };
+    let decimal = decimal.abs();
+    let degrees = decimal.trunc();
+    let minutes = (decimal - degrees) * 60.0;
+    format!("{},{:.8}{}", degrees, minutes, direction)
+}
+
+/// Set a simple property on the first rdf:Description.
+/// Replaces the value if the property is present in either attribute or element form,
+/// otherwise adds it as an attribute and declares the namespace if needed.
+fn set_property(xmp: &str, (prefix, uri): (&str, &str), name: &str, value: &str) -> Result<String> {
+    let qname = format!("{}:{}", prefix, name);
+
+    // Attribute form: exif:DateTimeOriginal="..."
+    let attribute = format!("{}=\"", qname);
+    let attribute_start = xmp.match_indices(&attribute).find(|(index, _)| {
+        xmp[..*index]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_whitespace())
+    });
+
+    if let Some((index, _)) = attribute_start {
+        let value_start = index + attribute.len();
+        let value_end = xmp[value_start..]
+            .find('"')
+            .map(|i| value_start + i)
+            .ok_or_else(|| anyhow!("Unterminated XMP attribute {}", qname))?;
+        return Ok(format!(
+            "{}{}{}",
+            &xmp[..value_start],
+            value,
+            &xmp[value_end..]
+        ));
+    }
+
+    // Element form: <exif:DateTimeOriginal>...</exif:DateTimeOriginal>
+    let open = format!("<{}>", qname);
+    let close = format!("</{}>", qname);
+    if let (Some(start), Some(end)) = (xmp.find(&open), xmp.find(&close)) {
+        let value_start = start + open.len();
+        if value_start <= end {
+            return Ok(format!("{}{}{}", &xmp[..value_start], value, &xmp[end..]));
+        }
+    }
+
+    // Absent, so add attribute to end of first rdf:Description start tag.
+    let description = xmp
+        .find("<rdf:Description")
+        .ok_or_else(|| anyhow!("XMP has no rdf:Description"))?;
+    let tag_end = xmp[description..]
+        .find('>')
+        .map(|i| description + i)
+        .ok_or_else(|| anyhow!("Unterminated rdf:Description"))?;
+    let insert_at = if xmp[..tag_end].ends_with('/') {
+        tag_end - 1
+    } else {
+        tag_end
+    };
+
+    let mut addition = String::new();
+    if !xmp.contains(&format!("xmlns:{}=", prefix)) {
+        addition.push_str(&format!("\n    xmlns:{}=\"{}\"", prefix, uri));
+    }
+    addition.push_str(&format!("\n    {}=\"{}\"", qname, value));
+
+    Ok(format!(
+        "{}{}{}",
+        &xmp[..insert_at],
+        addition,
+        &xmp[insert_at..]
+    ))
+}
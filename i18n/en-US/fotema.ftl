@@ -24,6 +24,117 @@ videos-album = Videos
 # Title for album of selfies.
 selfies-album = Selfies
 
+# Title for album of photos and videos marked as a favourite.
+favourites-album = Favourites
+
+# Drop down to choose between showing favourites or highly rated items in favourites album.
+# Attributes:
+#  .tooltip - tooltip text for drop down.
+#  .favourites - show photos and videos marked as a favourite.
+#  .min-rating - show photos and videos rated with at least $stars stars.
+# Translator note: do not values in square brackets, such as '[other]'.
+favourites-album-filter =
+  .tooltip = Show favourites or by rating
+  .favourites = Favourites
+  .min-rating = { $stars ->
+     [one] {$stars} star or more
+    *[other] {$stars} stars or more
+    }
+
+# Title for album of albums created by the user.
+user-albums-album = Albums
+
+# Count of items in a user album, shown below the album cover.
+# Variables:
+#   $count - number of photos and videos in album.
+# Translator note: do not values in square brackets, such as '[other]'.
+user-albums-album-count = { $count ->
+   [one] {$count} item
+  *[other] {$count} items
+  }
+
+# Status page shown for albums view when the user hasn't created any albums.
+user-albums-album-status-none =
+  .title = No albums
+  .description = Create an album, or select photos and videos to add to an album.
+
+# Button in albums view header bar to create a new album.
+user-albums-album-new-button =
+  .tooltip = New album
+
+# Title for view of smart albums, which are saved searches.
+smart-albums-album = Smart Albums
+
+# Count of items matching a smart album, shown below the smart album name.
+# Variables:
+#   $count - number of photos and videos matching smart album.
+# Translator note: do not values in square brackets, such as '[other]'.
+smart-albums-album-count = { $count ->
+   [one] {$count} item
+  *[other] {$count} items
+  }
+
+# Status page shown for smart albums view when the user hasn't saved any smart albums.
+smart-albums-album-status-none =
+  .title = No smart albums
+  .description = Create a smart album to automatically collect photos and videos matching a search.
+
+# Title for view of events, such as trips and parties, found by grouping photos
+# and videos taken close together in time and place.
+events-album = Events
+
+# Count of items in an event, shown below the event cover.
+# Variables:
+#   $count - number of photos and videos in event.
+# Translator note: do not values in square brackets, such as '[other]'.
+events-album-count = { $count ->
+   [one] {$count} item
+  *[other] {$count} items
+  }
+
+# Status page shown for events view when no events have been found.
+events-album-status-none =
+  .title = No events
+  .description = Photos and videos taken close together in time and place will be grouped into events.
+
+# Search page, which shows photos and videos matching search text.
+# Attributes:
+#  .tooltip - tooltip for button in main window header bar that shows search page.
+#  .placeholder - placeholder for empty search entry.
+search-album =
+  .tooltip = Search
+  .placeholder = Search photos and videos
+
+# Status page shown for search page when no search text has been entered.
+# Translator note: do not translate the search keys, such as 'year:' or 'type:'.
+search-album-status-help =
+  .title = Search your library
+  .description = Search file names, folders, people, tags, titles, and descriptions. Narrow a search with year:2022, person:Bob, tag:beach, folder:Holidays, rating:3, is:favourite, or type:photo, type:video, type:animated, or type:selfie.
+
+# Status page shown for search page when nothing matches the search text.
+search-album-status-none =
+  .title = No results
+  .description = No photos or videos match your search.
+
+# Button to start selecting photos and videos.
+album-select-button =
+  .tooltip = Select photos and videos
+
+# Action bar shown below an album when selecting photos and videos.
+# Attributes:
+#  .count - number of selected photos and videos.
+# Translator note: do not values in square brackets, such as '[other]'.
+album-selection =
+  .cancel-button = Cancel
+  .count = { $count ->
+     [one] {$count} selected
+    *[other] {$count} selected
+    }
+  .add-button = Add to Album
+  .remove-button = Remove from Album
+  .split-button = Split into New Event
+  .location-button = Set Location
+
 # Title for album of iOS live photos and Android motion photos.
 animated-album = Animated
 
@@ -33,8 +144,69 @@ folders-album = Folders
 # Title for album showing contents of one folder.
 folder-album = Folder
 
+# Button in folder album header bar to shift capture time of photos.
+folder-album-shift-time-button =
+  .tooltip = Shift time of photos in folder.
+
+# Button in folder album header bar to save folder, or place, as a smart album.
+folder-album-save-as-smart-album-button =
+  .tooltip = Save as smart album
+
+# Title for album of photos and videos that have copies in more than one folder.
+duplicates-album = Duplicates
+
+# Status page shown for duplicates album when no duplicates are found.
+duplicates-album-status-none =
+  .title = No duplicates found
+  .description = Photos and videos copied into more than one folder will be shown here.
+
+# Summary of duplicates shown below the duplicates album.
+# Variables:
+#   $copy_count - number of extra copies that can be moved to the trash.
+# Translator note: do not values in square brackets, such as '[other]'.
+duplicates-album-summary = { $copy_count ->
+   [one] One extra copy
+  *[other] {$copy_count} extra copies
+  }
+
+# Button to move all but one copy of each duplicate to the trash.
+duplicates-album-keep-one-button = Keep One Copy
+
 # Title for places page which shows photos overlayed onto a map.
+# Attributes:
+#   .map - Title of view switcher button to show photos on a map.
+#   .list - Title of view switcher button to show a list of named places.
 places-page = Places
+  .map = Map
+  .list = List
+
+# Number of photos and videos at a country or city in the places list.
+# Variables:
+#   $count - number of photos and videos at place.
+places-page-place-count = { $count ->
+   [one] {$count} item
+  *[other] {$count} items
+}
+
+# Button to view all photos and videos in a country from the places list.
+places-page-view-country =
+  .tooltip = View Country
+
+# Status page shown for places list when no places have been named.
+places-page-status-none =
+  .title = No Places
+  .description = Places are named from the locations of photos.
+
+# Banner shown above the map while picking the location of photos and videos.
+# Attributes:
+#   .title - Tells user to click the map. $count is number of photos and videos.
+#   .cancel-button - Button to stop picking a location.
+places-page-pick-location =
+  .title = { $count ->
+     [one] Click the map to set the location of {$count} item
+    *[other] Click the map to set the location of {$count} items
+    }
+  .cancel-button = Cancel
 
 # Title for people page which shows an album of faces.
 people-page = People
@@ -52,6 +224,21 @@ people-page-status-no-people =
   .description = { -app-name } will look for faces in new photos when launched.
   Name the people in your photos so { -app-name } can make an album for each person.
 
+# Heading for groups of unknown faces that look like the same person.
+people-page-unnamed-groups = Unnamed Groups
+
+# Number of faces in a group of unknown faces.
+people-page-unnamed-group-count = { $count ->
+   [one] {$count} face
+  *[other] {$count} faces
+  }
+
+# Button for a group of unknown faces.
+# Attributes:
+#  .tooltip - tooltip text for button.
+people-page-unnamed-group-name =
+  .tooltip = Name this person
+
 ## Thumbnail decorations
 
 # Label on month album thumbnails.
@@ -90,6 +277,32 @@ about-translator-credits =
 # Tooltip for (i) button to show photo/video information sidebar
 viewer-info-tooltip = Show properties
 
+# Tooltip for button to mark, or unmark, photo or video as a favourite.
+viewer-favourite-tooltip = Toggle favourite
+
+# Menu to set star rating of photo or video.
+# Attributes:
+#  .tooltip - tooltip text for menu button.
+#  .unrated - menu item to clear rating.
+#  .stars - menu item to rate with $stars stars.
+# Translator note: do not values in square brackets, such as '[other]'.
+viewer-rating-menu =
+  .tooltip = Rating
+  .unrated = Unrated
+  .stars = { $stars ->
+     [one] {$stars} star
+    *[other] {$stars} stars
+    }
+
+# Menu in viewer to add the photo or video to an album, or to change the album being viewed.
+viewer-album-menu =
+  .tooltip = Albums menu
+  .add = Add to album
+  .remove = Remove from this album
+  .set-cover = Use as album cover
+  .move-earlier = Move earlier
+  .move-later = Move later
+
 viewer-faces-menu =
   .tooltip = Faces menu
   .restore-ignored = Restore all ignored faces
@@ -163,6 +376,27 @@ infobar-folder = Folder
 # File name of photo or video
 infobar-file-name = File Name
 
+# Title of photo or video, as set in another application such as digiKam.
+infobar-title = Title
+
+# Description or caption of photo or video.
+infobar-description = Description
+
+# Comma separated keywords, or tags, of photo or video.
+infobar-keywords = Keywords
+
+# Star rating of photo or video.
+# Attributes:
+#  .rejected - shown when the photo or video has been marked as rejected.
+infobar-rating = Rating
+  .rejected = Rejected
+
+# Comma separated names of people whose faces were tagged in another application.
+infobar-people = People
+
+# Name of place near where photo was taken, such as "Lisbon, Portugal".
+infobar-place = Place
+
 # File creation timestamp from file system metadata.
 infobar-file-created = File Created
 
@@ -249,14 +483,30 @@ prefs-machine-learning-section = Machine Learning
 prefs-machine-learning-face-detection = Face Detection
   .subtitle = Enable face detection when { -app-name } launches. This is a time consuming process.
 
+# Preferences for changing photo metadata, such as capture time.
+prefs-metadata-section = Metadata
+  .description = Configure how changes to photo metadata are saved.
+
+# Where changed metadata is written.
+prefs-metadata-write-target = Save Changes To
+  .subtitle = Sidecar files leave your photos untouched. Only JPEG photos can be changed directly.
+  .sidecar = Sidecar File
+  .file = Photo File
+
 prefs-library-section =
   .title = Library
-  .description = Configure library directory.
-  Warning: changing the pictures directory can cause { -app-name } to reprocess all your pictures.
+  .description = Configure library directories, such as a local folder, a network share, or an external drive.
+  Items in a directory that isn't mounted are hidden until it is mounted again.
+
+prefs-library-section-add-root =
+  .tooltip = Add library directory.
+
+prefs-library-section-remove-root =
+  .tooltip = Remove library directory.
 
-prefs-library-section-pictures-dir =
-  .title = Pictures Directory
-  .tooltip = Choose pictures directory.
+# Toast shown when a chosen directory is inside, or contains, a library directory.
+# $name is the name of the library directory.
+prefs-library-section-overlapping-root = Can't add a directory that overlaps the { $name } library directory.
 
 ## Progress bar for background tasks
 
@@ -272,6 +522,15 @@ progress-thumbnails-photos = Generating photo thumbnails.
 # Generating thumbnails from videos
 progress-thumbnails-videos = Generating video thumbnails.
 
+# Computing content hashes of photos to find duplicates
+progress-hash-photos = Finding duplicate photos.
+
+# Computing content hashes of videos to find duplicates
+progress-hash-videos = Finding duplicate videos.
+
+# Computing perceptual hashes of photos to group bursts
+progress-perceptual-hash-photos = Finding similar photos.
+
 # Transcoding videos to a compatible format
 progress-convert-videos = Converting videos.
 
@@ -291,11 +550,8 @@ progress-idle = Idle.
 
 # Similar to the progress bar, but allows for longer messages.
 
-# Scanning file system for new photos
-banner-scan-photos = Scanning file system for photos.
-
-# Scanning file system for new videos
-banner-scan-videos = Scanning file system for videos.
+# Scanning file system for new photos and videos
+banner-scan-library = Scanning file system for photos and videos.
 
 # Processing new photos to extract metadata from EXIF tags.
 banner-metadata-photos = Processing photo metadata.
@@ -309,12 +565,30 @@ banner-thumbnails-photos = Generating photo thumbnails. This will take a while.
 # Generating thumbnails for all videos.
 banner-thumbnails-videos = Generating video thumbnails. This will take a while.
 
+# Computing content hashes of all photos to find duplicates.
+banner-hash-photos = Finding duplicate photos. This will take a while.
+
+# Computing content hashes of all videos to find duplicates.
+banner-hash-videos = Finding duplicate videos. This will take a while.
+
+# Computing perceptual hashes of all photos to group bursts of similar photos.
+banner-perceptual-hash-photos = Finding similar photos. This will take a while.
+
+# Saving user changes to photo metadata.
+banner-edit-metadata-photos = Saving photo changes.
+
 # Updating the database to remove details of absent photos.
 banner-clean-photos = Photo database maintenance.
 
 # Updating the database to remove details of absent videos.
 banner-clean-videos = Video database maintenance.
 
+# Finding names of places near where photos were taken.
+banner-geocode-photos = Naming places.
+
+# Grouping photos and videos into events, such as trips
+banner-detect-events = Finding events.
+
 # Extracting video component from Android motion photos
 banner-extract-motion-photos = Processing motion photos.
 
@@ -324,6 +598,9 @@ banner-detect-faces-photos = Detecting faces in photos. This will take a while.
 # Recognize faces as people
 banner-recognize-faces-photos = Recognizing people in photos. This will take a while.
 
+# Grouping unknown faces that look like the same person
+banner-cluster-faces-photos = Grouping unknown faces.
+
 # Transcoding videos to a compatible format
 banner-convert-videos = Converting videos.
 
@@ -353,6 +630,67 @@ person-menu-rename = Rename person
 # Menu item to delete a person
 person-menu-delete = Delete person
 
+# Menu item to review all faces that face recognition thinks are a person
+person-menu-review = Review suggestions
+
+# Menu item to merge another person into a person, such as when
+# the same person was added twice
+person-menu-merge = Merge with another person
+
+# Menu item to move some faces of a person to another person
+person-menu-split = Move faces to another person
+
+# Heading above faces that face recognition thinks are the person,
+# but that the user hasn't confirmed.
+person-suggestions = Suggestions
+
+# How similar a suggested face is to the faces of a person.
+# Variables:
+#   $percent - similarity as a whole number percentage.
+person-suggestion-similarity = { $percent }%
+
+# Menu item to confirm a suggested face is the person.
+person-suggestion-confirm = Confirm
+
+# Button to review all suggested faces of a person.
+person-suggestions-review-button = Review All
+
+# Dialog to confirm or reject many suggested faces of a person at once.
+# Variables:
+#   $name - name of person
+#   $count - number of selected faces
+person-review-dialog =
+  .title = Is This { $name }?
+  .selected = { $count ->
+        [one] 1 face selected
+       *[other] { $count } faces selected
+    }
+  .select-all-button = Select All
+  .confirm-button = Confirm
+  .empty-title = All Reviewed
+  .empty-description = There are no more suggested faces to review.
+
+# Dialog to merge another person into a person.
+# Variables:
+#   $name - name of person who will keep their name and thumbnail
+person-merge-dialog =
+  .heading = Merge People?
+  .body = All faces of the chosen person will be moved to { $name }, and the chosen person will be deleted.
+  .cancel-button = Cancel
+  .merge-button = Merge
+
+# Dialog to choose faces of a person to move to another person.
+# Variables:
+#   $name - name of person
+#   $count - number of selected faces
+person-split-dialog =
+  .title = Faces of { $name }
+  .selected = { $count ->
+        [one] 1 face selected
+       *[other] { $count } faces selected
+    }
+  .move-button = Move to Person…
+
 # Person delete dialog
 person-delete-dialog =
   .heading = Delete person?
@@ -360,6 +698,35 @@ person-delete-dialog =
   .cancel-button = Cancel
   .delete-button = Delete
 
+# Dialog shown when a clean task finds many pictures and videos are missing.
+# Variables:
+#   $missing_count - number of missing pictures or videos.
+#   $library_count - number of pictures or videos in library.
+clean-confirm-dialog =
+  .heading = Remove missing items?
+  .body = {$missing_count} of {$library_count} items in your library are missing. If your library is on a drive or network share, then check it is connected before removing. Removed items are kept for 30 days in case they come back.
+  .cancel-button = Cancel
+  .remove-button = Remove
+
+# Dialog shown before moving extra copies of duplicates to the trash.
+# Variables:
+#   $copy_count - number of extra copies to move to the trash.
+duplicates-keep-one-dialog =
+  .heading = Keep one copy?
+  .body = {$copy_count} extra copies will be moved to the trash. The oldest copy of each photo and video will be kept.
+  .cancel-button = Cancel
+  .trash-button = Move to Trash
+
+# Dialog to shift capture time of all photos in a folder, such as when
+# the camera clock was set to the wrong time zone.
+# Variables:
+#   $folder_name - name of folder holding the photos.
+shift-time-dialog =
+  .heading = Shift Time?
+  .body = Hours to add to the capture time of every photo in {$folder_name}. Use a negative number to go back in time.
+  .cancel-button = Cancel
+  .shift-button = Shift
+
 # Person delete dialog
 person-rename-dialog =
   .heading = Rename person?
@@ -367,6 +734,167 @@ person-rename-dialog =
   .cancel-button = Cancel
   .rename-button = Rename
 
+## User album menu
+
+# Menu item to rename a user album
+user-album-menu-rename = Rename album
+
+# Menu item to delete a user album
+user-album-menu-delete = Delete album
+
+# User album rename dialog
+user-album-rename-dialog =
+  .heading = Rename album?
+  .placeholder = New name
+  .cancel-button = Cancel
+  .rename-button = Rename
+
+# User album delete dialog
+user-album-delete-dialog =
+  .heading = Delete album?
+  .body = No pictures or videos will be deleted.
+  .cancel-button = Cancel
+  .delete-button = Delete
+
+# Dialog to create a new, empty, album.
+user-album-new-dialog =
+  .heading = New album
+  .placeholder = Album name
+  .cancel-button = Cancel
+  .create-button = Create
+
+# Dialog to choose which album to add photos and videos to.
+# Attributes:
+#  .heading - $count is the number of photos and videos to add.
+#  .new-album - drop down option to add to a new album.
+#  .placeholder - placeholder for name of new album.
+# Translator note: do not values in square brackets, such as '[other]'.
+add-to-user-album-dialog =
+  .heading = { $count ->
+     [one] Add {$count} item to album?
+    *[other] Add {$count} items to album?
+    }
+  .new-album = New album
+  .placeholder = Album name
+  .cancel-button = Cancel
+  .add-button = Add
+
+## Event menu
+
+# Menu item to rename an event
+event-menu-rename = Rename event
+
+# Menu item to merge an event with the event before it
+event-menu-merge-previous = Merge with previous event
+
+# Menu item to merge an event with the event after it
+event-menu-merge-next = Merge with next event
+
+# Event rename dialog
+event-rename-dialog =
+  .heading = Rename event?
+  .placeholder = New name
+  .cancel-button = Cancel
+  .rename-button = Rename
+
+# Day and month of a date in an event's date range, such as "3 May".
+# Variables:
+#   $day - day of month.
+#   $month - month number, from 1 to 12.
+# Translator note: do not values in square brackets, such as '[other]'.
+event-day-month = { $month ->
+   [1] {$day} Jan
+   [2] {$day} Feb
+   [3] {$day} Mar
+   [4] {$day} Apr
+   [5] {$day} May
+   [6] {$day} Jun
+   [7] {$day} Jul
+   [8] {$day} Aug
+   [9] {$day} Sep
+   [10] {$day} Oct
+   [11] {$day} Nov
+  *[12] {$day} Dec
+  }
+
+# Date of an event on a single day, such as "3 May 2024".
+# Variables:
+#   $date - day and month, from event-day-month.
+#   $year - year.
+event-date = {$date} {$year}
+
+# Dates of an event within a month, such as "3–5 May 2024".
+# Variables:
+#   $start - first day of month.
+#   $end - last day and month, from event-day-month.
+#   $year - year.
+event-date-range-month = {$start}–{$end} {$year}
+
+# Dates of an event within a year, such as "30 Apr – 2 May 2024".
+# Variables:
+#   $start - first day and month, from event-day-month.
+#   $end - last day and month, from event-day-month.
+#   $year - year.
+event-date-range-year = {$start} – {$end} {$year}
+
+# Dates of an event spanning more than one year, such as "30 Dec 2023 – 2 Jan 2024".
+# Variables:
+#   $start - first date, from event-date.
+#   $end - last date, from event-date.
+event-date-range = {$start} – {$end}
+
+## Smart album menu
+
+# Menu item to edit name and search of a smart album
+smart-album-menu-edit = Edit smart album
+
+# Menu item to delete a smart album
+smart-album-menu-delete = Delete smart album
+
+# Smart album delete dialog
+smart-album-delete-dialog =
+  .heading = Delete smart album?
+  .body = No pictures or videos will be deleted.
+  .cancel-button = Cancel
+  .delete-button = Delete
+
+# Dialog to create or edit a smart album.
+# Attributes:
+#  .match - whether photos and videos must match all criteria, or any criteria.
+#  .from-date and .to-date - dates written as YYYY-MM-DD.
+#  .other-criteria - criteria, such as a folder, that can't be changed in this dialog.
+#  .other-criteria-count - $count is number of other criteria.
+# Translator note: do not values in square brackets, such as '[other]'.
+smart-album-editor =
+  .title = Smart Album
+  .cancel-button = Cancel
+  .save-button = Save
+  .name = Name
+  .match = Match
+  .match-all = All criteria
+  .match-any = Any criteria
+  .criteria = Criteria
+  .any = Any
+  .person = Person
+  .anyone = Anyone
+  .from-date = From date (YYYY-MM-DD)
+  .to-date = To date (YYYY-MM-DD)
+  .media = Media
+  .media-photos = Photos
+  .media-videos = Videos
+  .media-animated = Animated
+  .selfies = Selfies
+  .selfies-only = Only selfies
+  .selfies-exclude = No selfies
+  .rating = Rating
+  .favourite = Favourites only
+  .tag = Tag
+  .other-criteria = Other criteria
+  .other-criteria-count = { $count ->
+     [one] {$count} criterion, such as a folder or place, will be kept.
+    *[other] {$count} criteria, such as a folder or place, will be kept.
+    }
+
 # First view to present to a user.
 onboard-select-pictures =
   .title = Welcome to { -app-name }.
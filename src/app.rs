@@ -11,7 +11,7 @@ use relm4::{
     gtk::{
         gio, glib,
         prelude::{
-            ApplicationExt, ButtonExt, GtkWindowExt, OrientableExt,
+            ApplicationExt, BoxExt, ButtonExt, EditableExt, EntryExt, GtkWindowExt, OrientableExt,
             SettingsExt, WidgetExt,
         },
     },
@@ -27,13 +27,24 @@ use crate::adaptive;
 use crate::fl;
 
 use fotema_core::database;
+use fotema_core::duplicate;
 use fotema_core::VisualId;
 use fotema_core::PictureId;
 use fotema_core::people;
 use fotema_core::path_encoding;
+use fotema_core::library;
+use fotema_core::rating;
+use fotema_core::user_album::{self, AlbumId, AlbumItem, UserAlbum};
+use fotema_core::event::{self, Event};
+use fotema_core::smart_album::{self, Query, SmartAlbum};
+use fotema_core::search;
+use fotema_core::photo::WriteTarget;
+use fotema_core::photo::gps::GPSLocation;
 
 use h3o::CellIndex;
 
+use chrono::TimeDelta;
+
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use std::str::FromStr;
@@ -52,15 +63,24 @@ use self::components::{
         album::{Album, AlbumInput, AlbumOutput},
         album_filter::AlbumFilter,
         album_sort::AlbumSort,
+        duplicates_album::{DuplicatesAlbum, DuplicatesAlbumInput, DuplicatesAlbumOutput},
+        event::{EventPage, EventInput, EventOutput},
+        events_album::{EventsAlbum, EventsAlbumInput, EventsAlbumOutput},
         folders_album::{FoldersAlbum, FoldersAlbumInput, FoldersAlbumOutput},
         people_album::{PeopleAlbum, PeopleAlbumInput, PeopleAlbumOutput},
         person_album::{PersonAlbum, PersonAlbumInput, PersonAlbumOutput},
         places_album::{PlacesAlbum, PlacesAlbumInput, PlacesAlbumOutput},
+        search_album::{SearchAlbum, SearchAlbumInput, SearchAlbumOutput},
+        smart_album::{SmartAlbumPage, SmartAlbumInput, SmartAlbumOutput},
+        smart_albums_album::{SmartAlbumsAlbum, SmartAlbumsAlbumInput, SmartAlbumsAlbumOutput},
+        user_album::{UserAlbumPage, UserAlbumInput, UserAlbumOutput},
+        user_albums_album::{UserAlbumsAlbum, UserAlbumsAlbumInput, UserAlbumsAlbumOutput},
     },
     library::{Library, LibraryInput, LibraryOutput},
     viewer::view_nav::{ViewNav, ViewNavInput, ViewNavOutput},
     onboard::{Onboard, OnboardOutput},
     preferences::{PreferencesDialog, PreferencesInput},
+    smart_album_editor::{SmartAlbumEditor, SmartAlbumEditorInput, SmartAlbumEditorOutput},
 };
 
 mod background;
@@ -89,6 +109,15 @@ pub enum ViewName {
     Person,
     Places,
     Selfies,
+    Favourites,
+    Duplicates,
+    UserAlbums,
+    UserAlbum,
+    SmartAlbums,
+    SmartAlbum,
+    Events,
+    Event,
+    Search,
 }
 
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq, EnumString, AsRefStr, FromRepr)]
@@ -120,8 +149,11 @@ pub struct Settings {
     /// the picture library root directory?
     pub is_onboarding_complete: bool,
 
-    /// Base path of pictures directory.
-    pub pictures_base_dir: PathBuf,
+    /// Named directories holding pictures and videos.
+    pub library_roots: Vec<library::RootConfig>,
+
+    /// Write changed metadata to picture files or to XMP sidecars.
+    pub metadata_write_target: WriteTarget,
 }
 
 /// Active settings
@@ -159,6 +191,12 @@ pub(super) struct App {
     videos_page: Controller<Album>,
     motion_page: Controller<Album>,
 
+    /// Album of favourites, or of photos and videos with a minimum rating.
+    favourites_page: Controller<Album>,
+
+    /// Chooses between showing favourites or a minimum rating in favourites album.
+    favourites_filter: gtk::DropDown,
+
     /// Album with photos overlayed onto a map
     people_page: Controller<PeopleAlbum>,
 
@@ -168,12 +206,58 @@ pub(super) struct App {
     /// Album with photos overlayed onto a map
     places_page: Controller<PlacesAlbum>,
 
+    /// Album of photos and videos copied into more than one folder
+    duplicates_page: Controller<DuplicatesAlbum>,
+
+    /// Grid of albums created by the user.
+    user_albums_page: Controller<UserAlbumsAlbum>,
+
+    /// User album currently being viewed.
+    user_album_page: Controller<UserAlbumPage>,
+
+    user_album_repo: user_album::Repository,
+
+    /// List of smart albums saved by the user.
+    smart_albums_page: Controller<SmartAlbumsAlbum>,
+
+    /// Smart album currently being viewed.
+    smart_album_page: Controller<SmartAlbumPage>,
+
+    /// Grid of events, such as trips, found in the library.
+    events_page: Controller<EventsAlbum>,
+
+    /// Event currently being viewed.
+    event_page: Controller<EventPage>,
+
+    /// Dialog to create or edit a smart album.
+    smart_album_editor: Controller<SmartAlbumEditor>,
+
+    /// Search entry and album of search results.
+    search_page: Controller<SearchAlbum>,
+
+    /// Button to create a new user album or smart album.
+    /// Only visible in user albums and smart albums views.
+    new_album_button: gtk::Button,
+
+    /// Button to start selecting photos and videos to add to a user album.
+    select_button: gtk::Button,
+
     // Grid of folders of photos
     folders_album: Controller<FoldersAlbum>,
 
     // Folder album currently being viewed
     folder_album: Controller<Album>,
 
+    /// Directory of folder album, if showing a folder.
+    folder_path: Option<PathBuf>,
+
+    /// Query matching the folder or geographic area shown in the folder album.
+    /// Used to save the folder album as a smart album.
+    folder_album_query: Option<(Query, String)>,
+
+    /// Button to shift capture time of photos in folder album.
+    shift_time_button: gtk::Button,
+
     // Main navigation. Parent of library stack.
     main_navigation: adw::OverlaySplitView,
 
@@ -195,6 +279,9 @@ pub(super) struct App {
     banner: adw::Banner,
 
     settings_state: SettingsState,
+
+    /// Is a dialog asking to confirm removing missing items being shown?
+    is_clean_confirmation_pending: bool,
 }
 
 #[derive(Debug)]
@@ -220,14 +307,96 @@ pub(super) enum AppMsg {
 
     ViewFolder(PathBuf),
 
+    /// Ask user how much to shift capture time of photos in current folder.
+    ShiftTimeDialog,
+
+    /// Favourites filter drop down selection has changed.
+    /// 0 for favourites, otherwise minimum number of stars.
+    FavouritesFilter(u32),
+
+    /// Shift capture time of photos in a folder.
+    ShiftTime(PathBuf, TimeDelta),
+
     ViewGeographicArea(CellIndex),
 
+    /// View items at a named country or city. Query to match items, and place name.
+    ViewPlace(Query, String),
+
+    /// Show the map so the user can pick the location of photos and videos.
+    PickLocation(Vec<AlbumItem>),
+
+    /// Set the location of photos and videos.
+    SetLocation(Vec<AlbumItem>, GPSLocation),
+
     ViewPerson(people::Person),
 
     PersonDeleted,
 
     PersonRenamed,
 
+    /// Faces moved between people, or people merged.
+    PeopleChanged,
+
+    /// Extra copies of duplicates have been moved to the trash.
+    DuplicatesResolved(Vec<PathBuf>),
+
+    /// Start selecting photos and videos in the visible album.
+    SelectionMode,
+
+    /// Start selecting photos and videos in the folder album.
+    FolderSelectionMode,
+
+    /// Ask user which user album to add photos and videos to.
+    AddToUserAlbumDialog(Vec<AlbumItem>),
+
+    /// Add photos and videos to an existing album, or to a new album with the given name.
+    AddToUserAlbum(Option<AlbumId>, String, Vec<AlbumItem>),
+
+    /// Ask user for name of a new, empty, user album.
+    NewUserAlbumDialog,
+
+    /// Create a new, empty, user album.
+    NewUserAlbum(String),
+
+    ViewUserAlbum(UserAlbum),
+
+    /// Remove photos and videos from a user album.
+    RemoveFromUserAlbum(AlbumId, Vec<AlbumItem>),
+
+    /// Show a photo or video as the cover of a user album.
+    SetUserAlbumCover(AlbumId, AlbumItem),
+
+    /// Change manual sort order of a user album.
+    ReorderUserAlbum(AlbumId, Vec<AlbumItem>),
+
+    UserAlbumDeleted,
+
+    /// A user album has been renamed, or has had items added or removed.
+    UserAlbumChanged,
+
+    ViewEvent(Event),
+
+    /// An event has been renamed, merged, or split.
+    EventChanged,
+
+    /// Create a user album or smart album, depending on visible view.
+    NewAlbum,
+
+    /// Save folder or geographic area being viewed as a smart album.
+    SaveAsSmartAlbum,
+
+    EditSmartAlbum(SmartAlbum),
+
+    ViewSmartAlbum(SmartAlbum),
+
+    /// A smart album has been created or edited.
+    SmartAlbumSaved(SmartAlbum),
+
+    SmartAlbumDeleted,
+
+    /// Show search page.
+    ViewSearch,
+
     // A background task has started.
     TaskStarted(TaskName),
 
@@ -245,6 +414,13 @@ pub(super) enum AppMsg {
     // Stopping background tasks is in progress
     StoppingBackgroundTasks,
 
+    /// Ask user to confirm removing missing items.
+    /// Count of missing items and count of items in library.
+    CleanConfirmationRequired(usize, usize),
+
+    /// User has answered whether to remove missing items.
+    CleanConfirmed(bool),
+
     // Adapt to layout change
     Adapt(adaptive::Layout),
 
@@ -324,6 +500,7 @@ impl SimpleComponent for App {
 
                 // Page for showing main navigation. Such as "Library", "Selfies", etc.
                 adw::NavigationPage {
+                    set_tag: Some("main"),
 
                     #[local_ref]
                     main_navigation -> adw::OverlaySplitView {
@@ -363,8 +540,38 @@ impl SimpleComponent for App {
                                         connect_clicked => AppMsg::ToggleSidebar,
                                     },
 
+                                    pack_start = &gtk::Button {
+                                        set_icon_name: "system-search-symbolic",
+                                        set_tooltip_text: Some(&fl!("search-album", "tooltip")),
+                                        connect_clicked => AppMsg::ViewSearch,
+                                    },
+
                                     #[local_ref]
                                     pack_end = &spinner -> gtk::Spinner,
+
+                                    #[local_ref]
+                                    pack_end = &select_button -> gtk::Button {
+                                        set_icon_name: "object-select-symbolic",
+                                        set_tooltip_text: Some(&fl!("album-select-button", "tooltip")),
+                                        connect_clicked => AppMsg::SelectionMode,
+                                    },
+
+                                    #[local_ref]
+                                    pack_end = &new_album_button -> gtk::Button {
+                                        set_visible: false,
+                                        set_icon_name: "list-add-symbolic",
+                                        set_tooltip_text: Some(&fl!("user-albums-album-new-button", "tooltip")),
+                                        connect_clicked => AppMsg::NewAlbum,
+                                    },
+
+                                    #[local_ref]
+                                    pack_end = &favourites_filter -> gtk::DropDown {
+                                        set_visible: false,
+                                        set_tooltip_text: Some(&fl!("favourites-album-filter", "tooltip")),
+                                        connect_selected_notify[sender] => move |dropdown| {
+                                            sender.input(AppMsg::FavouritesFilter(dropdown.selected()));
+                                        },
+                                    },
                                 },
 
                                 // NOTE I would like this to be an adw::ViewStack
@@ -447,6 +654,56 @@ impl SimpleComponent for App {
                                             set_icon_name: "sentiment-very-satisfied-symbolic",
                                         },
 
+                                        add_child = &gtk::Box {
+                                            set_orientation: gtk::Orientation::Vertical,
+                                            container_add: model.favourites_page.widget(),
+                                        } -> {
+                                            set_title: &fl!("favourites-album"),
+                                            set_name: ViewName::Favourites.into(),
+                                            // NOTE gtk::StackSidebar doesn't show icon :-/
+                                            set_icon_name: "starred-symbolic",
+                                        },
+
+                                        add_child = &gtk::Box {
+                                            set_orientation: gtk::Orientation::Vertical,
+                                            container_add: model.user_albums_page.widget(),
+                                        } -> {
+                                            set_title: &fl!("user-albums-album"),
+                                            set_name: ViewName::UserAlbums.into(),
+                                            // NOTE gtk::StackSidebar doesn't show icon :-/
+                                            set_icon_name: "folder-pictures-symbolic",
+                                        },
+
+                                        add_child = &gtk::Box {
+                                            set_orientation: gtk::Orientation::Vertical,
+                                            container_add: model.smart_albums_page.widget(),
+                                        } -> {
+                                            set_title: &fl!("smart-albums-album"),
+                                            set_name: ViewName::SmartAlbums.into(),
+                                            // NOTE gtk::StackSidebar doesn't show icon :-/
+                                            set_icon_name: "system-search-symbolic",
+                                        },
+
+                                        add_child = &gtk::Box {
+                                            set_orientation: gtk::Orientation::Vertical,
+                                            container_add: model.events_page.widget(),
+                                        } -> {
+                                            set_title: &fl!("events-album"),
+                                            set_name: ViewName::Events.into(),
+                                            // NOTE gtk::StackSidebar doesn't show icon :-/
+                                            set_icon_name: "x-office-calendar-symbolic",
+                                        },
+
+                                        add_child = &gtk::Box {
+                                            set_orientation: gtk::Orientation::Vertical,
+                                            container_add: model.duplicates_page.widget(),
+                                        } -> {
+                                            set_title: &fl!("duplicates-album"),
+                                            set_name: ViewName::Duplicates.into(),
+                                            // NOTE gtk::StackSidebar doesn't show icon :-/
+                                            set_icon_name: "edit-copy-symbolic",
+                                        },
+
                                         add_child = &adw::NavigationView {
                                             set_pop_on_escape: true,
 
@@ -476,7 +733,26 @@ impl SimpleComponent for App {
                             set_title_widget = &gtk::Label {
                                 set_label: &fl!("folder-album"),
                                 add_css_class: "title",
-                            }
+                            },
+
+                            #[local_ref]
+                            pack_end = &shift_time_button -> gtk::Button {
+                                set_icon_name: "preferences-system-time-symbolic",
+                                set_tooltip_text: Some(&fl!("folder-album-shift-time-button", "tooltip")),
+                                connect_clicked => AppMsg::ShiftTimeDialog,
+                            },
+
+                            pack_end = &gtk::Button {
+                                set_icon_name: "system-search-symbolic",
+                                set_tooltip_text: Some(&fl!("folder-album-save-as-smart-album-button", "tooltip")),
+                                connect_clicked => AppMsg::SaveAsSmartAlbum,
+                            },
+
+                            pack_end = &gtk::Button {
+                                set_icon_name: "object-select-symbolic",
+                                set_tooltip_text: Some(&fl!("album-select-button", "tooltip")),
+                                connect_clicked => AppMsg::FolderSelectionMode,
+                            },
                         },
 
                         #[wrap(Some)]
@@ -489,6 +765,26 @@ impl SimpleComponent for App {
                     model.person_album.widget(),
                 },
 
+                adw::NavigationPage {
+                    set_tag: Some("user_album"),
+                    model.user_album_page.widget(),
+                },
+
+                adw::NavigationPage {
+                    set_tag: Some("smart_album"),
+                    model.smart_album_page.widget(),
+                },
+
+                adw::NavigationPage {
+                    set_tag: Some("event"),
+                    model.event_page.widget(),
+                },
+
+                adw::NavigationPage {
+                    set_tag: Some("search"),
+                    model.search_page.widget(),
+                },
+
                 // Page for showing a single photo.
                 adw::NavigationPage {
                     set_tag: Some("picture"),
@@ -537,6 +833,14 @@ impl SimpleComponent for App {
             con.clone(),
         ).unwrap();
 
+        let rating_repo = rating::Repository::open(con.clone()).unwrap();
+
+        let user_album_repo = user_album::Repository::open(con.clone()).unwrap();
+
+        let smart_album_repo = smart_album::Repository::open(con.clone()).unwrap();
+
+        let event_repo = event::Repository::open(con.clone()).unwrap();
+
         let state = SharedState::new(relm4::SharedState::new());
         let active_view = ActiveView::new(relm4::SharedState::new());
         let adaptive_layout = Arc::new(adaptive::LayoutState::new());
@@ -565,6 +869,8 @@ impl SimpleComponent for App {
                 BootstrapOutput::TaskStarted(msg) => AppMsg::TaskStarted(msg),
                 BootstrapOutput::Completed => AppMsg::BootstrapCompleted,
                 BootstrapOutput::Stopping => AppMsg::StoppingBackgroundTasks,
+                BootstrapOutput::CleanConfirmationRequired(_, missing_count, library_count) =>
+                    AppMsg::CleanConfirmationRequired(missing_count, library_count),
             });
 
         let onboard = Onboard::builder()
@@ -578,16 +884,22 @@ impl SimpleComponent for App {
         let library = Library::builder()
             .launch((state.clone(), active_view.clone(), adaptive_layout.clone()))
             .forward(sender.input_sender(), |msg| match msg {
-                LibraryOutput::View(id) => AppMsg::View(id, AlbumFilter::All),
+                LibraryOutput::View(id, filter) => AppMsg::View(id, filter),
+                LibraryOutput::AddToUserAlbum(items) => AppMsg::AddToUserAlbumDialog(items),
+                LibraryOutput::SetLocation(items) => AppMsg::PickLocation(items),
             });
 
         settings_state.subscribe(library.sender(), |settings| LibraryInput::Sort(settings.album_sort));
 
         let view_nav = ViewNav::builder()
-            .launch((state.clone(), bootstrap_progress_monitor, adaptive_layout.clone(), people_repo.clone()))
+            .launch((state.clone(), bootstrap_progress_monitor, adaptive_layout.clone(), people_repo.clone(), rating_repo))
             .forward(sender.input_sender(), |msg| match msg {
                 ViewNavOutput::TranscodeAll => AppMsg::TranscodeAll,
                 ViewNavOutput::ScanForFaces(picture_id) => AppMsg::ScanPictureForFaces(picture_id),
+                ViewNavOutput::AddToUserAlbum(items) => AppMsg::AddToUserAlbumDialog(items),
+                ViewNavOutput::RemoveFromUserAlbum(album_id, items) => AppMsg::RemoveFromUserAlbum(album_id, items),
+                ViewNavOutput::SetUserAlbumCover(album_id, item) => AppMsg::SetUserAlbumCover(album_id, item),
+                ViewNavOutput::ReorderUserAlbum(album_id, items) => AppMsg::ReorderUserAlbum(album_id, items),
             });
 
         let selfies_page = Album::builder()
@@ -595,6 +907,10 @@ impl SimpleComponent for App {
             .forward(sender.input_sender(), |msg| match msg {
                 AlbumOutput::Selected(id, filter) => AppMsg::View(id, filter),
                 AlbumOutput::ScrollOffset(_) => AppMsg::Ignore,
+                AlbumOutput::AddToUserAlbum(items) => AppMsg::AddToUserAlbumDialog(items),
+                AlbumOutput::SetLocation(items) => AppMsg::PickLocation(items),
+                AlbumOutput::RemoveFromUserAlbum(_, _) => AppMsg::Ignore,
+                AlbumOutput::SplitEvent(_, _) => AppMsg::Ignore,
             });
 
         state.subscribe(selfies_page.sender(), |_| AlbumInput::Refresh);
@@ -608,6 +924,10 @@ impl SimpleComponent for App {
             .forward(sender.input_sender(), |msg| match msg {
                 AlbumOutput::Selected(id, filter) => AppMsg::View(id, filter),
                 AlbumOutput::ScrollOffset(_) => AppMsg::Ignore,
+                AlbumOutput::AddToUserAlbum(items) => AppMsg::AddToUserAlbumDialog(items),
+                AlbumOutput::SetLocation(items) => AppMsg::PickLocation(items),
+                AlbumOutput::RemoveFromUserAlbum(_, _) => AppMsg::Ignore,
+                AlbumOutput::SplitEvent(_, _) => AppMsg::Ignore,
             });
 
         state.subscribe(motion_page.sender(), |_| AlbumInput::Refresh);
@@ -619,12 +939,31 @@ impl SimpleComponent for App {
             .forward(sender.input_sender(), |msg| match msg {
                 AlbumOutput::Selected(id, filter) => AppMsg::View(id, filter),
                 AlbumOutput::ScrollOffset(_) => AppMsg::Ignore,
+                AlbumOutput::AddToUserAlbum(items) => AppMsg::AddToUserAlbumDialog(items),
+                AlbumOutput::SetLocation(items) => AppMsg::PickLocation(items),
+                AlbumOutput::RemoveFromUserAlbum(_, _) => AppMsg::Ignore,
+                AlbumOutput::SplitEvent(_, _) => AppMsg::Ignore,
             });
 
         state.subscribe(videos_page.sender(), |_| AlbumInput::Refresh);
         adaptive_layout.subscribe(videos_page.sender(), |layout| AlbumInput::Adapt(*layout));
         settings_state.subscribe(videos_page.sender(), |settings| AlbumInput::Sort(settings.album_sort));
 
+        let favourites_page = Album::builder()
+            .launch((state.clone(), active_view.clone(), ViewName::Favourites, AlbumFilter::Favourites))
+            .forward(sender.input_sender(), |msg| match msg {
+                AlbumOutput::Selected(id, filter) => AppMsg::View(id, filter),
+                AlbumOutput::ScrollOffset(_) => AppMsg::Ignore,
+                AlbumOutput::AddToUserAlbum(items) => AppMsg::AddToUserAlbumDialog(items),
+                AlbumOutput::SetLocation(items) => AppMsg::PickLocation(items),
+                AlbumOutput::RemoveFromUserAlbum(_, _) => AppMsg::Ignore,
+                AlbumOutput::SplitEvent(_, _) => AppMsg::Ignore,
+            });
+
+        state.subscribe(favourites_page.sender(), |_| AlbumInput::Refresh);
+        adaptive_layout.subscribe(favourites_page.sender(), |layout| AlbumInput::Adapt(*layout));
+        settings_state.subscribe(favourites_page.sender(), |settings| AlbumInput::Sort(settings.album_sort));
+
         let people_page = PeopleAlbum::builder()
             .launch((people_repo.clone(), active_view.clone(), settings_state.clone()))
             .forward(
@@ -643,17 +982,118 @@ impl SimpleComponent for App {
                 PersonAlbumOutput::Selected(id, filter) => AppMsg::View(id, filter),
                 PersonAlbumOutput::Deleted => AppMsg::PersonDeleted,
                 PersonAlbumOutput::Renamed => AppMsg::PersonRenamed,
+                PersonAlbumOutput::PeopleChanged => AppMsg::PeopleChanged,
+                PersonAlbumOutput::AddToUserAlbum(items) => AppMsg::AddToUserAlbumDialog(items),
+                PersonAlbumOutput::SetLocation(items) => AppMsg::PickLocation(items),
             });
 
         state.subscribe(person_album.sender(), |_| PersonAlbumInput::Refresh);
         adaptive_layout.subscribe(person_album.sender(), |layout| PersonAlbumInput::Adapt(*layout));
         settings_state.subscribe(person_album.sender(), |settings| PersonAlbumInput::Sort(settings.album_sort));
 
+        let user_albums_page = UserAlbumsAlbum::builder()
+            .launch((state.clone(), user_album_repo.clone(), active_view.clone()))
+            .forward(sender.input_sender(), |msg| match msg {
+                UserAlbumsAlbumOutput::AlbumSelected(album) => AppMsg::ViewUserAlbum(album),
+            });
+
+        state.subscribe(user_albums_page.sender(), |_| UserAlbumsAlbumInput::Refresh);
+        adaptive_layout.subscribe(user_albums_page.sender(), |layout| UserAlbumsAlbumInput::Adapt(*layout));
+
+        let user_album_page = UserAlbumPage::builder()
+            .launch((state.clone(), user_album_repo.clone(), active_view.clone()))
+            .forward(sender.input_sender(), |msg| match msg {
+                UserAlbumOutput::Selected(id, filter) => AppMsg::View(id, filter),
+                UserAlbumOutput::Deleted => AppMsg::UserAlbumDeleted,
+                UserAlbumOutput::Changed => AppMsg::UserAlbumChanged,
+                UserAlbumOutput::AddToUserAlbum(items) => AppMsg::AddToUserAlbumDialog(items),
+                UserAlbumOutput::SetLocation(items) => AppMsg::PickLocation(items),
+            });
+
+        state.subscribe(user_album_page.sender(), |_| UserAlbumInput::Refresh);
+        adaptive_layout.subscribe(user_album_page.sender(), |layout| UserAlbumInput::Adapt(*layout));
+
+        let smart_albums_page = SmartAlbumsAlbum::builder()
+            .launch((state.clone(), smart_album_repo.clone(), active_view.clone()))
+            .forward(sender.input_sender(), |msg| match msg {
+                SmartAlbumsAlbumOutput::AlbumSelected(album) => AppMsg::ViewSmartAlbum(album),
+            });
+
+        state.subscribe(smart_albums_page.sender(), |_| SmartAlbumsAlbumInput::Refresh);
+
+        let smart_album_page = SmartAlbumPage::builder()
+            .launch((state.clone(), smart_album_repo.clone(), active_view.clone()))
+            .forward(sender.input_sender(), |msg| match msg {
+                SmartAlbumOutput::Selected(id, filter) => AppMsg::View(id, filter),
+                SmartAlbumOutput::Edit(album) => AppMsg::EditSmartAlbum(album),
+                SmartAlbumOutput::Deleted => AppMsg::SmartAlbumDeleted,
+                SmartAlbumOutput::AddToUserAlbum(items) => AppMsg::AddToUserAlbumDialog(items),
+                SmartAlbumOutput::SetLocation(items) => AppMsg::PickLocation(items),
+            });
+
+        state.subscribe(smart_album_page.sender(), |_| SmartAlbumInput::Refresh);
+        adaptive_layout.subscribe(smart_album_page.sender(), |layout| SmartAlbumInput::Adapt(*layout));
+        settings_state.subscribe(smart_album_page.sender(), |settings| SmartAlbumInput::Sort(settings.album_sort));
+
+        let events_page = EventsAlbum::builder()
+            .launch((state.clone(), event_repo.clone(), active_view.clone()))
+            .forward(sender.input_sender(), |msg| match msg {
+                EventsAlbumOutput::EventSelected(event) => AppMsg::ViewEvent(event),
+            });
+
+        state.subscribe(events_page.sender(), |_| EventsAlbumInput::Refresh);
+        adaptive_layout.subscribe(events_page.sender(), |layout| EventsAlbumInput::Adapt(*layout));
+
+        let event_page = EventPage::builder()
+            .launch((state.clone(), event_repo, active_view.clone()))
+            .forward(sender.input_sender(), |msg| match msg {
+                EventOutput::Selected(id, filter) => AppMsg::View(id, filter),
+                EventOutput::Changed => AppMsg::EventChanged,
+                EventOutput::AddToUserAlbum(items) => AppMsg::AddToUserAlbumDialog(items),
+                EventOutput::SetLocation(items) => AppMsg::PickLocation(items),
+            });
+
+        state.subscribe(event_page.sender(), |_| EventInput::Refresh);
+        adaptive_layout.subscribe(event_page.sender(), |layout| EventInput::Adapt(*layout));
+
+        let smart_album_editor = SmartAlbumEditor::builder()
+            .launch((smart_album_repo, people_repo.clone(), root.clone()))
+            .forward(sender.input_sender(), |msg| match msg {
+                SmartAlbumEditorOutput::Saved(album) => AppMsg::SmartAlbumSaved(album),
+            });
+
+        let search_page = SearchAlbum::builder()
+            .launch((state.clone(), search::Repository::open(con.clone()).unwrap(), active_view.clone()))
+            .forward(sender.input_sender(), |msg| match msg {
+                SearchAlbumOutput::Selected(id, filter) => AppMsg::View(id, filter),
+                SearchAlbumOutput::AddToUserAlbum(items) => AppMsg::AddToUserAlbumDialog(items),
+                SearchAlbumOutput::SetLocation(items) => AppMsg::PickLocation(items),
+            });
+
+        state.subscribe(search_page.sender(), |_| SearchAlbumInput::Refresh);
+        adaptive_layout.subscribe(search_page.sender(), |layout| SearchAlbumInput::Adapt(*layout));
+        settings_state.subscribe(search_page.sender(), |settings| SearchAlbumInput::Sort(settings.album_sort));
+
+        let duplicate_repo = duplicate::Repository::open(con.clone()).unwrap();
+
+        let duplicates_page = DuplicatesAlbum::builder()
+            .launch((state.clone(), duplicate_repo, active_view.clone()))
+            .forward(sender.input_sender(), |msg| match msg {
+                DuplicatesAlbumOutput::Selected(id, filter) => AppMsg::View(id, filter),
+                DuplicatesAlbumOutput::Resolved(paths) => AppMsg::DuplicatesResolved(paths),
+            });
+
+        state.subscribe(duplicates_page.sender(), |_| DuplicatesAlbumInput::Refresh);
+        adaptive_layout.subscribe(duplicates_page.sender(), |layout| DuplicatesAlbumInput::Adapt(*layout));
+        settings_state.subscribe(duplicates_page.sender(), |settings| DuplicatesAlbumInput::Sort(settings.album_sort));
+
         let places_page = PlacesAlbum::builder()
             .launch((state.clone(), active_view.clone()))
             .forward(sender.input_sender(), |msg| match msg {
                 PlacesAlbumOutput::View(visual_id) => AppMsg::View(visual_id.clone(), AlbumFilter::One(visual_id)),
                 PlacesAlbumOutput::GeographicArea(cell_index) => AppMsg::ViewGeographicArea(cell_index),
+                PlacesAlbumOutput::Place(query, name) => AppMsg::ViewPlace(query, name),
+                PlacesAlbumOutput::SetLocation(items, location) => AppMsg::SetLocation(items, location),
             });
 
         state.subscribe(places_page.sender(), |_| PlacesAlbumInput::Refresh);
@@ -676,6 +1116,10 @@ impl SimpleComponent for App {
             .forward(sender.input_sender(), |msg| match msg {
                 AlbumOutput::Selected(id, filter) => AppMsg::View(id, filter),
                 AlbumOutput::ScrollOffset(_) => AppMsg::Ignore,
+                AlbumOutput::AddToUserAlbum(items) => AppMsg::AddToUserAlbumDialog(items),
+                AlbumOutput::SetLocation(items) => AppMsg::PickLocation(items),
+                AlbumOutput::RemoveFromUserAlbum(_, _) => AppMsg::Ignore,
+                AlbumOutput::SplitEvent(_, _) => AppMsg::Ignore,
             });
 
         state.subscribe(folder_album.sender(), |_| AlbumInput::Refresh);
@@ -685,7 +1129,7 @@ impl SimpleComponent for App {
         let about_dialog = AboutDialog::builder().launch(root.clone()).detach();
 
         let preferences_dialog = PreferencesDialog::builder()
-            .launch((settings_state.clone(), root.clone()))
+            .launch((settings_state.clone(), root.clone(), library::Repository::open(con.clone()).unwrap()))
             .detach();
 
         let picture_navigation_view = adw::NavigationView::builder().build();
@@ -696,6 +1140,21 @@ impl SimpleComponent for App {
 
         let header_bar = adw::HeaderBar::new();
 
+        let shift_time_button = gtk::Button::new();
+
+        let select_button = gtk::Button::new();
+
+        let new_album_button = gtk::Button::new();
+
+        let favourites_filter = {
+            let mut options = vec![fl!("favourites-album-filter", "favourites")];
+            for stars in 1..=rating::MAX_RATING {
+                options.push(fl!("favourites-album-filter", "min-rating", stars = stars));
+            }
+            let options: Vec<&str> = options.iter().map(|x| x.as_str()).collect();
+            gtk::DropDown::from_strings(&options)
+        };
+
         let spinner = gtk::Spinner::builder().visible(false).build();
 
         let banner = adw::Banner::builder()
@@ -717,14 +1176,31 @@ impl SimpleComponent for App {
 
             view_nav,
             motion_page,
+            favourites_page,
+            favourites_filter: favourites_filter.clone(),
             videos_page,
             people_page,
             person_album,
             places_page,
             selfies_page,
             show_selfies,
+            duplicates_page,
+            user_albums_page,
+            user_album_page,
+            user_album_repo,
+            smart_albums_page,
+            smart_album_page,
+            events_page,
+            event_page,
+            smart_album_editor,
+            search_page,
+            new_album_button: new_album_button.clone(),
+            select_button: select_button.clone(),
             folders_album,
             folder_album,
+            folder_path: None,
+            folder_album_query: None,
+            shift_time_button: shift_time_button.clone(),
 
             main_navigation: main_navigation.clone(),
             main_stack: main_stack.clone(),
@@ -738,6 +1214,8 @@ impl SimpleComponent for App {
             banner: banner.clone(),
 
             settings_state: settings_state.clone(),
+
+            is_clean_confirmation_pending: false,
         };
 
         let widgets = view_output!();
@@ -772,11 +1250,11 @@ impl SimpleComponent for App {
         model.spinner.start();
 
         let settings = settings_state.read();
-        let is_onboarding_complete = settings.is_onboarding_complete && settings.pictures_base_dir.exists();
+        let is_onboarding_complete = settings.is_onboarding_complete && !settings.library_roots.is_empty();
         if is_onboarding_complete {
             model.picture_navigation_view.set_visible(true);
             model.onboard_view.set_visible(false);
-            model.bootstrap.emit(BootstrapInput::Configure(settings.pictures_base_dir.clone()));
+            model.bootstrap.emit(BootstrapInput::Configure(settings.library_roots.clone()));
         } else {
             model.picture_navigation_view.set_visible(false);
             model.onboard_view.set_visible(true);
@@ -821,6 +1299,12 @@ impl SimpleComponent for App {
                         .policy(adw::ViewSwitcherPolicy::Wide)
                         .build();
                     self.header_bar.set_title_widget(Some(&vs));
+                } else if child_name == ViewName::Places {
+                    let vs = adw::ViewSwitcher::builder()
+                        .stack(self.places_page.widget())
+                        .policy(adw::ViewSwitcherPolicy::Wide)
+                        .build();
+                    self.header_bar.set_title_widget(Some(&vs));
                 } else if let Some(child) = child {
                     let page = self.main_stack.page(&child);
                     let title = page.title().map(|x| x.to_string());
@@ -833,6 +1317,11 @@ impl SimpleComponent for App {
                     self.header_bar.set_title_widget(title.as_ref());
                 }
 
+                self.favourites_filter.set_visible(child_name == ViewName::Favourites);
+                self.new_album_button.set_visible(matches!(child_name, ViewName::UserAlbums | ViewName::SmartAlbums));
+                self.select_button.set_visible(matches!(child_name,
+                    ViewName::Library | ViewName::Videos | ViewName::Animated | ViewName::Selfies | ViewName::Favourites));
+
                 // figure out which view to activate
                 match child_name {
                     ViewName::Library | ViewName::All | ViewName::Month | ViewName::Year => {
@@ -843,12 +1332,21 @@ impl SimpleComponent for App {
                     },
                     ViewName::Videos => self.videos_page.emit(AlbumInput::Activate),
                     ViewName::Selfies => self.selfies_page.emit(AlbumInput::Activate),
+                    ViewName::Favourites => self.favourites_page.emit(AlbumInput::Activate),
                     ViewName::Animated => self.motion_page.emit(AlbumInput::Activate),
                     ViewName::Folders => self.folders_album.emit(FoldersAlbumInput::Activate),
                     ViewName::Folder => self.folder_album.emit(AlbumInput::Activate),
                     ViewName::People => self.people_page.emit(PeopleAlbumInput::Activate),
                     ViewName::Person => self.person_album.emit(PersonAlbumInput::Activate),
                     ViewName::Places => self.places_page.emit(PlacesAlbumInput::Activate),
+                    ViewName::Duplicates => self.duplicates_page.emit(DuplicatesAlbumInput::Activate),
+                    ViewName::UserAlbums => self.user_albums_page.emit(UserAlbumsAlbumInput::Activate),
+                    ViewName::UserAlbum => self.user_album_page.emit(UserAlbumInput::Activate),
+                    ViewName::SmartAlbums => self.smart_albums_page.emit(SmartAlbumsAlbumInput::Activate),
+                    ViewName::SmartAlbum => self.smart_album_page.emit(SmartAlbumInput::Activate),
+                    ViewName::Events => self.events_page.emit(EventsAlbumInput::Activate),
+                    ViewName::Event => self.event_page.emit(EventInput::Activate),
+                    ViewName::Search => self.search_page.emit(SearchAlbumInput::Activate),
                     ViewName::Nothing => warn!("Nothing activated... which should not happen"),
                 }
             },
@@ -863,16 +1361,51 @@ impl SimpleComponent for App {
                 self.view_nav.emit(ViewNavInput::Hidden);
             },
             AppMsg::ViewFolder(path) => {
+                let folder_name = path.file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                self.folder_album_query = Some((Query::Folder(path.clone()), folder_name));
+                self.folder_path = Some(path.clone());
+                self.shift_time_button.set_visible(true);
                 self.folder_album.emit(AlbumInput::Activate);
                 self.folder_album.emit(AlbumInput::Filter(AlbumFilter::Folder(path)));
                 self.picture_navigation_view.push_by_tag("album");
             },
+            AppMsg::FavouritesFilter(selected) => {
+                let filter = if selected == 0 {
+                    AlbumFilter::Favourites
+                } else {
+                    AlbumFilter::MinRating(selected as i32)
+                };
+                self.favourites_page.emit(AlbumInput::Filter(filter));
+            },
             AppMsg::ViewGeographicArea(cell_index) => {
+                self.folder_album_query = Some((Query::Place(cell_index), fl!("places-page")));
+                self.folder_path = None;
+                self.shift_time_button.set_visible(false);
                 self.folder_album.emit(AlbumInput::Activate);
                 self.folder_album.emit(AlbumInput::Filter(AlbumFilter::GeographicArea(cell_index)));
                 self.picture_navigation_view.push_by_tag("album");
 
             },
+            AppMsg::ViewPlace(query, name) => {
+                self.folder_album_query = Some((query.clone(), name));
+                self.folder_path = None;
+                self.shift_time_button.set_visible(false);
+                self.folder_album.emit(AlbumInput::Activate);
+                self.folder_album.emit(AlbumInput::Filter(AlbumFilter::Smart(query)));
+                self.picture_navigation_view.push_by_tag("album");
+            },
+            AppMsg::PickLocation(items) => {
+                // Return from any album page to the map.
+                self.picture_navigation_view.pop_to_tag("main");
+                self.main_stack.set_visible_child_name(ViewName::Places.into());
+                self.places_page.emit(PlacesAlbumInput::PickLocation(items));
+            },
+            AppMsg::SetLocation(items, location) => {
+                info!("Setting location of {} items", items.len());
+                self.bootstrap.emit(BootstrapInput::SetLocation(items, location));
+            },
             AppMsg::ViewPerson(person) => {
                 //info!("picture_ids = {:?}", picture_ids);
                 info!("Viewing person: {}", person.person_id);
@@ -884,9 +1417,303 @@ impl SimpleComponent for App {
                 self.picture_navigation_view.pop();
                 self.people_page.emit(PeopleAlbumInput::Refresh);
             },
-            AppMsg::PersonRenamed => {
+            AppMsg::PersonRenamed | AppMsg::PeopleChanged => {
                 self.people_page.emit(PeopleAlbumInput::Refresh);
             },
+            AppMsg::ShiftTimeDialog => {
+                let Some(ref folder) = self.folder_path else {
+                    return;
+                };
+
+                let folder_name = folder.file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+
+                let hours = gtk::SpinButton::with_range(-48.0, 48.0, 1.0);
+                hours.set_value(0.0);
+                hours.set_halign(gtk::Align::Center);
+
+                let dialog = adw::AlertDialog::builder()
+                    .heading(fl!("shift-time-dialog", "heading"))
+                    .body(fl!("shift-time-dialog", "body", folder_name = folder_name))
+                    .extra_child(&hours)
+                    .build();
+
+                dialog.add_response("cancel", &fl!("shift-time-dialog", "cancel-button"));
+                dialog.set_default_response(Some("cancel"));
+                dialog.set_close_response("cancel");
+
+                dialog.add_response("shift", &fl!("shift-time-dialog", "shift-button"));
+                dialog.set_response_appearance("shift", adw::ResponseAppearance::Suggested);
+
+                let sender = sender.clone();
+                let folder = folder.clone();
+                dialog.connect_response(None, move |_, response| {
+                    let hours = hours.value_as_int();
+                    if response == "shift" && hours != 0 {
+                        sender.input(AppMsg::ShiftTime(folder.clone(), TimeDelta::hours(hours.into())));
+                    }
+                });
+
+                dialog.present(Some(&self.main_navigation));
+            },
+            AppMsg::ShiftTime(folder, delta) => {
+                info!("Shifting time of photos in {:?} by {}", folder, delta);
+                self.bootstrap.emit(BootstrapInput::ShiftTime(folder, delta));
+            },
+            AppMsg::DuplicatesResolved(paths) => {
+                self.bootstrap.emit(BootstrapInput::DuplicatesRemoved(paths));
+            },
+            AppMsg::SelectionMode => {
+                let child_name = self.main_stack.visible_child_name()
+                    .and_then(|x| ViewName::from_str(x.as_str()).ok())
+                    .unwrap_or(ViewName::Nothing);
+
+                match child_name {
+                    ViewName::Library => self.library.emit(LibraryInput::SelectionMode),
+                    ViewName::Videos => self.videos_page.emit(AlbumInput::SelectionMode(true)),
+                    ViewName::Selfies => self.selfies_page.emit(AlbumInput::SelectionMode(true)),
+                    ViewName::Favourites => self.favourites_page.emit(AlbumInput::SelectionMode(true)),
+                    ViewName::Animated => self.motion_page.emit(AlbumInput::SelectionMode(true)),
+                    _ => warn!("Selection mode not supported for {:?}", child_name),
+                }
+            },
+            AppMsg::FolderSelectionMode => {
+                self.folder_album.emit(AlbumInput::SelectionMode(true));
+            },
+            AppMsg::AddToUserAlbumDialog(items) => {
+                if items.is_empty() {
+                    return;
+                }
+
+                let albums = self.user_album_repo.all().unwrap_or_else(|e| {
+                    error!("Failed loading user albums: {}", e);
+                    vec![]
+                });
+
+                // First option is always to create a new album.
+                let mut options = vec![fl!("add-to-user-album-dialog", "new-album")];
+                options.extend(albums.iter().map(|album| album.name.clone()));
+                let options: Vec<&str> = options.iter().map(|x| x.as_str()).collect();
+                let album_choice = gtk::DropDown::from_strings(&options);
+
+                let album_name = gtk::Entry::builder()
+                    .placeholder_text(fl!("add-to-user-album-dialog", "placeholder"))
+                    .build();
+
+                album_choice.connect_selected_notify({
+                    let album_name = album_name.clone();
+                    move |dropdown| album_name.set_visible(dropdown.selected() == 0)
+                });
+
+                // Default to first existing album, if there is one.
+                if !albums.is_empty() {
+                    album_choice.set_selected(1);
+                }
+
+                let extra_child = gtk::Box::builder()
+                    .orientation(gtk::Orientation::Vertical)
+                    .spacing(12)
+                    .build();
+                extra_child.append(&album_choice);
+                extra_child.append(&album_name);
+
+                let dialog = adw::AlertDialog::builder()
+                    .heading(fl!("add-to-user-album-dialog", "heading", count = items.len()))
+                    .extra_child(&extra_child)
+                    .build();
+
+                dialog.add_response("cancel", &fl!("add-to-user-album-dialog", "cancel-button"));
+                dialog.set_default_response(Some("cancel"));
+                dialog.set_close_response("cancel");
+
+                dialog.add_response("add", &fl!("add-to-user-album-dialog", "add-button"));
+                dialog.set_response_appearance("add", adw::ResponseAppearance::Suggested);
+
+                let sender = sender.clone();
+                dialog.connect_response(None, move |_, response| {
+                    if response != "add" {
+                        return;
+                    }
+                    let selected = album_choice.selected() as usize;
+                    let album_id = selected.checked_sub(1)
+                        .and_then(|index| albums.get(index))
+                        .map(|album| album.album_id);
+                    sender.input(AppMsg::AddToUserAlbum(album_id, album_name.text().into(), items.clone()));
+                });
+
+                dialog.present(Some(&self.main_navigation));
+            },
+            AppMsg::AddToUserAlbum(album_id, name, items) => {
+                let album_id = match album_id {
+                    Some(album_id) => album_id,
+                    None => {
+                        let name = name.trim();
+                        if name.is_empty() {
+                            warn!("Not creating user album without a name");
+                            return;
+                        }
+                        match self.user_album_repo.create(name) {
+                            std::result::Result::Ok(album_id) => album_id,
+                            Err(e) => {
+                                error!("Failed creating user album: {}", e);
+                                return;
+                            }
+                        }
+                    }
+                };
+
+                info!("Adding {} items to user album {}", items.len(), album_id);
+                if let Err(e) = self.user_album_repo.add(album_id, &items) {
+                    error!("Failed adding items to user album: {}", e);
+                }
+                sender.input(AppMsg::UserAlbumChanged);
+            },
+            AppMsg::NewUserAlbumDialog => {
+                let album_name = gtk::Entry::builder()
+                    .placeholder_text(fl!("user-album-new-dialog", "placeholder"))
+                    .build();
+
+                let dialog = adw::AlertDialog::builder()
+                    .heading(fl!("user-album-new-dialog", "heading"))
+                    .extra_child(&album_name)
+                    .build();
+
+                dialog.add_response("cancel", &fl!("user-album-new-dialog", "cancel-button"));
+                dialog.set_default_response(Some("cancel"));
+                dialog.set_close_response("cancel");
+
+                dialog.add_response("create", &fl!("user-album-new-dialog", "create-button"));
+                dialog.set_response_appearance("create", adw::ResponseAppearance::Suggested);
+
+                {
+                    let album_name = album_name.clone();
+                    let sender = sender.clone();
+                    dialog.connect_response(None, move |_, response| {
+                        if response == "create" {
+                            sender.input(AppMsg::NewUserAlbum(album_name.text().into()));
+                        }
+                    });
+                }
+
+                {
+                    let album_name = album_name.clone();
+                    let sender = sender.clone();
+                    let dialog = dialog.clone();
+                    album_name.clone().connect_activate(move |_| {
+                        dialog.close();
+                        sender.input(AppMsg::NewUserAlbum(album_name.text().into()));
+                    });
+                }
+
+                dialog.present(Some(&self.main_navigation));
+                album_name.grab_focus();
+            },
+            AppMsg::NewUserAlbum(name) => {
+                let name = name.trim();
+                if name.is_empty() {
+                    warn!("Not creating user album without a name");
+                    return;
+                }
+                if let Err(e) = self.user_album_repo.create(name) {
+                    error!("Failed creating user album: {}", e);
+                }
+                sender.input(AppMsg::UserAlbumChanged);
+            },
+            AppMsg::ViewUserAlbum(album) => {
+                info!("Viewing user album: {}", album.album_id);
+                self.user_album_page.emit(UserAlbumInput::Activate);
+                self.user_album_page.emit(UserAlbumInput::View(album));
+                self.picture_navigation_view.push_by_tag("user_album");
+            },
+            AppMsg::RemoveFromUserAlbum(album_id, items) => {
+                info!("Removing {} items from user album {}", items.len(), album_id);
+                if let Err(e) = self.user_album_repo.remove(album_id, &items) {
+                    error!("Failed removing items from user album: {}", e);
+                    return;
+                }
+                // Item is no longer in album being viewed, so return to album.
+                self.picture_navigation_view.pop();
+                sender.input(AppMsg::UserAlbumChanged);
+            },
+            AppMsg::SetUserAlbumCover(album_id, item) => {
+                if let Err(e) = self.user_album_repo.set_cover(album_id, &item) {
+                    error!("Failed setting user album cover: {}", e);
+                    return;
+                }
+                sender.input(AppMsg::UserAlbumChanged);
+            },
+            AppMsg::ReorderUserAlbum(album_id, items) => {
+                if let Err(e) = self.user_album_repo.set_order(album_id, &items) {
+                    error!("Failed reordering user album: {}", e);
+                    return;
+                }
+                sender.input(AppMsg::UserAlbumChanged);
+            },
+            AppMsg::UserAlbumDeleted => {
+                self.picture_navigation_view.pop();
+                self.user_albums_page.emit(UserAlbumsAlbumInput::Activate);
+            },
+            AppMsg::UserAlbumChanged => {
+                self.user_albums_page.emit(UserAlbumsAlbumInput::Refresh);
+                self.user_album_page.emit(UserAlbumInput::Refresh);
+            },
+            AppMsg::ViewEvent(event) => {
+                info!("Viewing event: {}", event.event_id);
+                self.event_page.emit(EventInput::Activate);
+                self.event_page.emit(EventInput::View(event));
+                self.picture_navigation_view.push_by_tag("event");
+            },
+            AppMsg::EventChanged => {
+                self.events_page.emit(EventsAlbumInput::Refresh);
+                self.event_page.emit(EventInput::Refresh);
+            },
+            AppMsg::NewAlbum => {
+                let child_name = self.main_stack.visible_child_name()
+                    .and_then(|x| ViewName::from_str(x.as_str()).ok())
+                    .unwrap_or(ViewName::Nothing);
+
+                if child_name == ViewName::SmartAlbums {
+                    self.smart_album_editor.emit(SmartAlbumEditorInput::New(None, String::new()));
+                } else {
+                    sender.input(AppMsg::NewUserAlbumDialog);
+                }
+            },
+            AppMsg::SaveAsSmartAlbum => {
+                if let Some((ref query, ref name)) = self.folder_album_query {
+                    self.smart_album_editor.emit(SmartAlbumEditorInput::New(Some(query.clone()), name.clone()));
+                }
+            },
+            AppMsg::EditSmartAlbum(album) => {
+                self.smart_album_editor.emit(SmartAlbumEditorInput::Edit(album));
+            },
+            AppMsg::ViewSmartAlbum(album) => {
+                info!("Viewing smart album: {}", album.smart_album_id);
+                self.smart_album_page.emit(SmartAlbumInput::Activate);
+                self.smart_album_page.emit(SmartAlbumInput::View(album));
+                self.picture_navigation_view.push_by_tag("smart_album");
+            },
+            AppMsg::SmartAlbumSaved(album) => {
+                self.smart_albums_page.emit(SmartAlbumsAlbumInput::Refresh);
+                self.smart_album_page.emit(SmartAlbumInput::Refresh);
+
+                // Show a newly created smart album, but stay on the smart album page after an edit.
+                let is_smart_album_visible = self.picture_navigation_view.visible_page()
+                    .and_then(|page| page.tag())
+                    .is_some_and(|tag| tag == "smart_album");
+
+                if !is_smart_album_visible {
+                    sender.input(AppMsg::ViewSmartAlbum(album));
+                }
+            },
+            AppMsg::SmartAlbumDeleted => {
+                self.picture_navigation_view.pop();
+                self.smart_albums_page.emit(SmartAlbumsAlbumInput::Activate);
+            },
+            AppMsg::ViewSearch => {
+                self.search_page.emit(SearchAlbumInput::Activate);
+                self.picture_navigation_view.push_by_tag("search");
+            },
             AppMsg::TaskStarted(task_name) => {
                 self.spinner.start();
                 self.spinner.set_visible(!self.main_navigation.shows_sidebar());
@@ -897,11 +1724,8 @@ impl SimpleComponent for App {
                     TaskName::LoadLibrary => {
                         // do nothing
                     },
-                    TaskName::Scan(MediaType::Photo) => {
-                        self.banner.set_title(&fl!("banner-scan-photos"));
-                    },
-                    TaskName::Scan(MediaType::Video) => {
-                        self.banner.set_title(&fl!("banner-scan-videos"));
+                    TaskName::Scan => {
+                        self.banner.set_title(&fl!("banner-scan-library"));
                     },
                     TaskName::Enrich(MediaType::Photo) => {
                         self.banner.set_title(&fl!("banner-metadata-photos"));
@@ -909,6 +1733,12 @@ impl SimpleComponent for App {
                     TaskName::Enrich(MediaType::Video) => {
                         self.banner.set_title(&fl!("banner-metadata-videos"));
                     },
+                    TaskName::Geocode => {
+                        self.banner.set_title(&fl!("banner-geocode-photos"));
+                    },
+                    TaskName::DetectEvents => {
+                        self.banner.set_title(&fl!("banner-detect-events"));
+                    },
                     TaskName::MotionPhoto => {
                         self.banner.set_title(&fl!("banner-extract-motion-photos"));
                     },
@@ -918,12 +1748,27 @@ impl SimpleComponent for App {
                     TaskName::Thumbnail(MediaType::Video) => {
                         self.banner.set_title(&fl!("banner-thumbnails-videos"));
                     },
+                    TaskName::Hash(MediaType::Photo) => {
+                        self.banner.set_title(&fl!("banner-hash-photos"));
+                    },
+                    TaskName::Hash(MediaType::Video) => {
+                        self.banner.set_title(&fl!("banner-hash-videos"));
+                    },
+                    TaskName::PerceptualHash => {
+                        self.banner.set_title(&fl!("banner-perceptual-hash-photos"));
+                    },
+                    TaskName::EditMetadata => {
+                        self.banner.set_title(&fl!("banner-edit-metadata-photos"));
+                    },
                     TaskName::DetectFaces => {
                         self.banner.set_title(&fl!("banner-detect-faces-photos"));
                     },
                     TaskName::RecognizeFaces => {
                         self.banner.set_title(&fl!("banner-recognize-faces-photos"));
                     },
+                    TaskName::ClusterFaces => {
+                        self.banner.set_title(&fl!("banner-cluster-faces-photos"));
+                    },
                     TaskName::Clean(MediaType::Photo) => {
                         self.banner.set_title(&fl!("banner-clean-photos"));
                     },
@@ -963,6 +1808,42 @@ impl SimpleComponent for App {
                 self.banner.set_button_label(None);
                 self.banner.set_title(&fl!("banner-stopping"));
             },
+            AppMsg::CleanConfirmationRequired(missing_count, library_count) => {
+                // Photo and video clean tasks can both ask, but one answer covers both.
+                if self.is_clean_confirmation_pending {
+                    return;
+                }
+                self.is_clean_confirmation_pending = true;
+
+                let dialog = adw::AlertDialog::builder()
+                    .heading(fl!("clean-confirm-dialog", "heading"))
+                    .body(fl!("clean-confirm-dialog", "body",
+                        missing_count = missing_count, library_count = library_count))
+                    .build();
+
+                dialog.add_response("cancel", &fl!("clean-confirm-dialog", "cancel-button"));
+                dialog.set_default_response(Some("cancel"));
+                dialog.set_close_response("cancel");
+
+                dialog.add_response("remove", &fl!("clean-confirm-dialog", "remove-button"));
+                dialog.set_response_appearance("remove", adw::ResponseAppearance::Destructive);
+
+                let sender = sender.clone();
+                dialog.connect_response(None, move |_, response| {
+                    sender.input(AppMsg::CleanConfirmed(response == "remove"));
+                });
+
+                dialog.present(Some(&self.main_navigation));
+            },
+            AppMsg::CleanConfirmed(is_confirmed) => {
+                self.is_clean_confirmation_pending = false;
+                if is_confirmed {
+                    info!("User confirmed removing missing items");
+                    self.bootstrap.emit(BootstrapInput::ConfirmClean);
+                } else {
+                    info!("User declined removing missing items");
+                }
+            },
             AppMsg::Adapt(adaptive::Layout::Narrow) => {
                 self.main_navigation.set_collapsed(true);
                 self.main_navigation.set_show_sidebar(false);
@@ -981,10 +1862,10 @@ impl SimpleComponent for App {
             AppMsg::OnboardDone(pic_base_dir) => {
                 let mut settings = self.settings_state.read().clone();
                 settings.is_onboarding_complete = true;
-                settings.pictures_base_dir = pic_base_dir.clone();
-                *self.settings_state.write() = settings;
+                settings.library_roots = vec![library::RootConfig::from_path(&pic_base_dir)];
+                *self.settings_state.write() = settings.clone();
 
-                self.bootstrap.emit(BootstrapInput::Configure(pic_base_dir));
+                self.bootstrap.emit(BootstrapInput::Configure(settings.library_roots));
                 self.picture_navigation_view.set_visible(true);
                 self.onboard_view.set_visible(false);
             },
@@ -1000,6 +1881,23 @@ impl App {
     pub fn load_settings() -> Result<Settings> {
         info!("Loading settings");
         let gio_settings = gio::Settings::new(APP_ID);
+
+        // Library roots are (name, base64 path) pairs. Base64 encoded because paths aren't strings.
+        let library_roots: Vec<(String, String)> = gio_settings.get("library-roots");
+        let mut library_roots = library_roots.into_iter()
+            .map(|(name, path)| path_encoding::from_base64(&path).map(|path| library::RootConfig { name, path }))
+            .collect::<Result<Vec<_>>>()?;
+
+        // Versions of Fotema before library roots had a single pictures directory.
+        // Items scanned from that directory belong to the default root.
+        if library_roots.is_empty() && gio_settings.boolean("onboarding-complete") {
+            let pictures_base_dir = path_encoding::from_base64(&gio_settings.string("pictures-base-dir-b64").into())?;
+            library_roots.push(library::RootConfig {
+                name: library::DEFAULT_ROOT_NAME.into(),
+                path: pictures_base_dir,
+            });
+        }
+
         Ok(Settings {
             show_selfies: gio_settings.boolean("show-selfies"),
             face_detection_mode: FaceDetectionMode::from_str(&gio_settings.string("face-detection-mode"))
@@ -1007,7 +1905,9 @@ impl App {
             album_sort: AlbumSort::from_str(&gio_settings.string("album-sort"))
                 .unwrap_or(AlbumSort::Ascending),
             is_onboarding_complete: gio_settings.boolean("onboarding-complete"),
-            pictures_base_dir: path_encoding::from_base64(&gio_settings.string("pictures-base-dir-b64").into())?,
+            library_roots,
+            metadata_write_target: WriteTarget::from_str(&gio_settings.string("metadata-write-target"))
+                .unwrap_or_default(),
         })
     }
 
@@ -1018,7 +1918,11 @@ impl App {
         gio_settings.set_string("face-detection-mode", settings.face_detection_mode.as_ref())?;
         gio_settings.set_string("album-sort", settings.album_sort.as_ref())?;
         gio_settings.set_boolean("onboarding-complete", settings.is_onboarding_complete)?;
-        gio_settings.set_string("pictures-base-dir-b64", &path_encoding::to_base64(settings.pictures_base_dir.as_ref()))?;
+        let library_roots: Vec<(String, String)> = settings.library_roots.iter()
+            .map(|root| (root.name.clone(), path_encoding::to_base64(&root.path)))
+            .collect();
+        gio_settings.set("library-roots", library_roots)?;
+        gio_settings.set_string("metadata-write-target", settings.metadata_write_target.as_ref())?;
         Ok(())
     }
 }
@@ -11,13 +11,22 @@ use relm4::{
 };
 
 use crate::app::Settings;
-use crate::config::APP_ID;
+use crate::config::{APP_ID, PKGDATADIR};
 use fotema_core::database;
+use fotema_core::duplicate;
+use fotema_core::event;
+use fotema_core::library;
 use fotema_core::photo;
 use fotema_core::video;
 use fotema_core::visual;
 use fotema_core::people;
+use fotema_core::scan;
+use fotema_core::place;
+use fotema_core::search;
 use fotema_core::PictureId;
+use fotema_core::VideoId;
+use fotema_core::photo::gps::GPSLocation;
+use fotema_core::user_album::AlbumItem;
 
 use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -27,24 +36,33 @@ use std::time::Instant;
 use std::collections::VecDeque;
 use std::path::PathBuf;
 
+use chrono::TimeDelta;
+
 use tracing::{error, info, warn};
 
 use anyhow;
 
 use super::{
     load_library::{LoadLibrary, LoadLibraryInput, LoadLibraryOutput},
+    library_scan::{LibraryScan, LibraryScanInput, LibraryScanOutput},
+    detect_events::{DetectEvents, DetectEventsInput, DetectEventsOutput},
 
     photo_clean::{PhotoClean, PhotoCleanInput, PhotoCleanOutput},
     photo_detect_faces::{PhotoDetectFaces, PhotoDetectFacesInput, PhotoDetectFacesOutput},
+    photo_edit::{PhotoEdit, PhotoEditInput, PhotoEditOutput},
     photo_enrich::{PhotoEnrich, PhotoEnrichInput, PhotoEnrichOutput},
     photo_recognize_faces::{PhotoRecognizeFaces, PhotoRecognizeFacesInput, PhotoRecognizeFacesOutput},
-    photo_scan::{PhotoScan, PhotoScanInput, PhotoScanOutput},
+    photo_cluster_faces::{PhotoClusterFaces, PhotoClusterFacesInput, PhotoClusterFacesOutput},
     photo_thumbnail::{PhotoThumbnail, PhotoThumbnailInput, PhotoThumbnailOutput},
     photo_extract_motion::{PhotoExtractMotion, PhotoExtractMotionInput, PhotoExtractMotionOutput},
+    photo_geocode::{PhotoGeocode, PhotoGeocodeInput, PhotoGeocodeOutput},
+    photo_hash::{PhotoHash, PhotoHashInput, PhotoHashOutput},
+    photo_perceptual_hash::{PhotoPerceptualHash, PhotoPerceptualHashInput, PhotoPerceptualHashOutput},
 
     video_clean::{VideoClean, VideoCleanInput, VideoCleanOutput},
+    video_edit::{VideoEdit, VideoEditInput, VideoEditOutput},
     video_enrich::{VideoEnrich, VideoEnrichInput, VideoEnrichOutput},
-    video_scan::{VideoScan, VideoScanInput, VideoScanOutput},
+    video_hash::{VideoHash, VideoHashInput, VideoHashOutput},
     video_thumbnail::{VideoThumbnail, VideoThumbnailInput, VideoThumbnailOutput},
     video_transcode::{VideoTranscode, VideoTranscodeInput, VideoTranscodeOutput},
 };
@@ -56,7 +74,7 @@ use crate::app::FaceDetectionMode;
 use crate::app::components::progress_monitor::ProgressMonitor;
 
 /// FIXME copied from progress_monitor. Consolidate?
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum MediaType {
     Photo,
     Video,
@@ -67,21 +85,27 @@ pub enum MediaType {
 #[derive(Debug)]
 pub enum TaskName {
     LoadLibrary,
-    Scan(MediaType),
+    Scan,
     Enrich(MediaType),
+    Geocode,
+    DetectEvents,
     MotionPhoto,
     Thumbnail(MediaType),
+    Hash(MediaType),
+    PerceptualHash,
+    EditMetadata,
     Clean(MediaType),
     DetectFaces,
     RecognizeFaces,
+    ClusterFaces,
     Transcode,
 }
 
 #[derive(Debug)]
 pub enum BootstrapInput {
 
-    /// Configure the pictures library root
-    Configure(PathBuf),
+    /// Configure the pictures library roots
+    Configure(Vec<library::RootConfig>),
 
     /// Settings updated
     SettingsUpdated(Settings),
@@ -102,6 +126,28 @@ pub enum BootstrapInput {
     // Queue task for transcoding videos
     TranscodeAll,
 
+    /// Files in the library have changed since the last scan.
+    LibraryChanged(scan::WatchBatch),
+
+    /// Too many items are missing for a clean task to remove without confirmation.
+    /// Count of missing items and count of items in library.
+    CleanConfirmationRequired(MediaType, usize, usize),
+
+    /// User has confirmed that missing items should be removed.
+    ConfirmClean,
+
+    /// User has moved extra copies of duplicates to the trash.
+    DuplicatesRemoved(Vec<PathBuf>),
+
+    /// User has changed metadata of pictures.
+    EditMetadata(Vec<PictureId>, photo::MetadataEdit),
+
+    /// User wants to shift capture time of all pictures in a folder.
+    ShiftTime(PathBuf, TimeDelta),
+
+    /// User has picked the location of pictures and videos on the map.
+    SetLocation(Vec<AlbumItem>, GPSLocation),
+
     /// A background task has started.
     TaskStarted(TaskName),
 
@@ -119,7 +165,11 @@ pub enum BootstrapOutput {
     Completed,
 
     // Tasks are in the process of stopping
-    Stopping
+    Stopping,
+
+    /// Ask user to confirm removing missing items.
+    /// Count of missing items and count of items in library.
+    CleanConfirmationRequired(MediaType, usize, usize),
 }
 
 type Task = dyn Fn() + Send + Sync;
@@ -143,25 +193,40 @@ pub struct Controllers {
 
     load_library: Arc<WorkerController<LoadLibrary>>,
 
-    photo_scan: Arc<WorkerController<PhotoScan>>,
-    video_scan: Arc<WorkerController<VideoScan>>,
+    library_scan: Arc<WorkerController<LibraryScan>>,
 
     photo_enrich: Arc<WorkerController<PhotoEnrich>>,
     video_enrich: Arc<WorkerController<VideoEnrich>>,
 
+    photo_geocode: Arc<WorkerController<PhotoGeocode>>,
+
+    detect_events: Arc<WorkerController<DetectEvents>>,
+
     photo_clean: Arc<WorkerController<PhotoClean>>,
     video_clean: Arc<WorkerController<VideoClean>>,
 
     photo_thumbnail: Arc<WorkerController<PhotoThumbnail>>,
     video_thumbnail: Arc<WorkerController<VideoThumbnail>>,
 
+    photo_hash: Arc<WorkerController<PhotoHash>>,
+    video_hash: Arc<WorkerController<VideoHash>>,
+
+    photo_perceptual_hash: Arc<WorkerController<PhotoPerceptualHash>>,
+
+    photo_edit: Arc<WorkerController<PhotoEdit>>,
+    video_edit: Arc<WorkerController<VideoEdit>>,
+
     photo_extract_motion: Arc<WorkerController<PhotoExtractMotion>>,
 
     photo_detect_faces: Arc<WorkerController<PhotoDetectFaces>>,
     photo_recognize_faces: Arc<WorkerController<PhotoRecognizeFaces>>,
+    photo_cluster_faces: Arc<WorkerController<PhotoClusterFaces>>,
 
     video_transcode: Arc<WorkerController<VideoTranscode>>,
 
+    /// Watches each available library root for changes. Stops watching when dropped.
+    _watchers: Vec<scan::Watcher>,
+
     /// Pending ordered tasks to process
     /// Wow... figuring out a type signature that would compile was a nightmare.
     pending_tasks: Arc<Mutex<VecDeque<Box<Task>>>>,
@@ -200,6 +265,7 @@ impl Controllers {
                 info!("Queueing task to scan all pictures for faces");
                 self.add_task_photo_detect_faces();
                 self.add_task_photo_recognize_faces();
+                self.add_task_photo_cluster_faces();
                 self.run_if_idle();
             },
             BootstrapInput::TranscodeAll => {
@@ -207,6 +273,96 @@ impl Controllers {
                 self.add_task_video_transcode();
                 self.run_if_idle();
             },
+            BootstrapInput::LibraryChanged(batch) => {
+                info!("Queueing tasks to process {} changed and {} removed library paths",
+                    batch.changed.len(), batch.removed.len());
+
+                if !batch.changed.is_empty() {
+                    self.add_task_library_scan_paths(batch.changed);
+                }
+                if !batch.removed.is_empty() {
+                    self.add_task_photo_clean_paths(batch.removed.clone());
+                    self.add_task_video_clean_paths(batch.removed);
+                }
+
+                // Only the new and changed items need processing and these tasks
+                // will skip everything else.
+                self.add_task_photo_enrich();
+                self.add_task_video_enrich();
+                self.add_task_photo_geocode();
+                self.add_task_detect_events();
+                self.add_task_photo_thumbnail();
+                self.add_task_video_thumbnail();
+                self.add_task_photo_perceptual_hash();
+                self.add_task_photo_hash();
+                self.add_task_video_hash();
+                self.add_task_photo_extract_motion();
+                self.add_task_photo_detect_faces();
+                self.add_task_photo_recognize_faces();
+                self.add_task_photo_cluster_faces();
+
+                self.library_stale.store(true, Ordering::Relaxed);
+                self.add_task_load_library(sender.input_sender().clone());
+                self.run_if_idle();
+            },
+            BootstrapInput::CleanConfirmationRequired(media_type, missing_count, library_count) => {
+                warn!("{:?} clean needs confirmation to remove {} of {} items",
+                    media_type, missing_count, library_count);
+                let _ = sender.output(BootstrapOutput::CleanConfirmationRequired(media_type, missing_count, library_count));
+            },
+            BootstrapInput::ConfirmClean => {
+                info!("Queueing confirmed clean tasks");
+                self.add_task_photo_clean_confirmed();
+                self.add_task_video_clean_confirmed();
+                self.library_stale.store(true, Ordering::Relaxed);
+                self.add_task_load_library(sender.input_sender().clone());
+                self.run_if_idle();
+            },
+            BootstrapInput::DuplicatesRemoved(paths) => {
+                info!("Queueing clean tasks for {} removed duplicates", paths.len());
+                self.add_task_photo_clean_paths_confirmed(paths.clone());
+                self.add_task_video_clean_paths_confirmed(paths);
+                self.library_stale.store(true, Ordering::Relaxed);
+                self.add_task_load_library(sender.input_sender().clone());
+                self.run_if_idle();
+            },
+            BootstrapInput::EditMetadata(picture_ids, edit) => {
+                info!("Queueing task to edit metadata of {} pictures", picture_ids.len());
+                self.add_task_photo_edit(picture_ids, edit);
+                self.add_task_photo_geocode();
+                self.add_task_detect_events();
+                self.library_stale.store(true, Ordering::Relaxed);
+                self.add_task_load_library(sender.input_sender().clone());
+                self.run_if_idle();
+            },
+            BootstrapInput::SetLocation(items, location) => {
+                let picture_ids: Vec<PictureId> = items.iter().filter_map(|x| x.picture_id).collect();
+                let video_ids: Vec<VideoId> = items.iter().filter_map(|x| x.video_id).collect();
+                info!("Queueing tasks to set location of {} pictures and {} videos", picture_ids.len(), video_ids.len());
+
+                if !picture_ids.is_empty() {
+                    let edit = photo::MetadataEdit {
+                        location: Some(location),
+                        ..Default::default()
+                    };
+                    self.add_task_photo_edit(picture_ids, edit);
+                }
+                if !video_ids.is_empty() {
+                    self.add_task_video_set_location(video_ids, location);
+                }
+                self.add_task_photo_geocode();
+                self.add_task_detect_events();
+                self.library_stale.store(true, Ordering::Relaxed);
+                self.add_task_load_library(sender.input_sender().clone());
+                self.run_if_idle();
+            },
+            BootstrapInput::ShiftTime(folder, delta) => {
+                info!("Queueing task to shift time of pictures in {:?} by {}", folder, delta);
+                self.add_task_photo_shift_time(folder, delta);
+                self.library_stale.store(true, Ordering::Relaxed);
+                self.add_task_load_library(sender.input_sender().clone());
+                self.run_if_idle();
+            },
             BootstrapInput::TaskStarted(task_name) => {
                 info!("Task started: {:?}", task_name);
                 let _  = sender.output(BootstrapOutput::TaskStarted(task_name));
@@ -251,14 +407,14 @@ impl Controllers {
         };
     }
 
-    fn add_task_photo_scan(&mut self)  {
-        let sender = self.photo_scan.sender().clone();
-        self.enqueue(Box::new(move || sender.emit(PhotoScanInput::Start)));
+    fn add_task_library_scan(&mut self)  {
+        let sender = self.library_scan.sender().clone();
+        self.enqueue(Box::new(move || sender.emit(LibraryScanInput::Start)));
     }
 
-    fn add_task_video_scan(&mut self) {
-        let sender = self.video_scan.sender().clone();
-        self.enqueue(Box::new(move || sender.emit(VideoScanInput::Start)));
+    fn add_task_library_scan_paths(&mut self, paths: Vec<(scan::MediaKind, PathBuf)>) {
+        let sender = self.library_scan.sender().clone();
+        self.enqueue(Box::new(move || sender.emit(LibraryScanInput::ScanPaths(paths.clone()))));
     }
 
     fn add_task_photo_enrich(&mut self) {
@@ -271,6 +427,16 @@ impl Controllers {
         self.enqueue(Box::new(move || sender.emit(VideoEnrichInput::Start)));
     }
 
+    fn add_task_photo_geocode(&mut self) {
+        let sender = self.photo_geocode.sender().clone();
+        self.enqueue(Box::new(move || sender.emit(PhotoGeocodeInput::Start)));
+    }
+
+    fn add_task_detect_events(&mut self) {
+        let sender = self.detect_events.sender().clone();
+        self.enqueue(Box::new(move || sender.emit(DetectEventsInput::Start)));
+    }
+
     fn add_task_photo_thumbnail(&mut self) {
         let sender = self.photo_thumbnail.sender().clone();
         self.enqueue(Box::new(move || sender.emit(PhotoThumbnailInput::Start)));
@@ -281,6 +447,38 @@ impl Controllers {
         self.enqueue(Box::new(move || sender.emit(VideoThumbnailInput::Start)));
     }
 
+    fn add_task_photo_hash(&mut self) {
+        let sender = self.photo_hash.sender().clone();
+        self.enqueue(Box::new(move || sender.emit(PhotoHashInput::Start)));
+    }
+
+    fn add_task_video_hash(&mut self) {
+        let sender = self.video_hash.sender().clone();
+        self.enqueue(Box::new(move || sender.emit(VideoHashInput::Start)));
+    }
+
+    fn add_task_photo_perceptual_hash(&mut self) {
+        let sender = self.photo_perceptual_hash.sender().clone();
+        self.enqueue(Box::new(move || sender.emit(PhotoPerceptualHashInput::Start)));
+    }
+
+    fn add_task_photo_edit(&mut self, picture_ids: Vec<PictureId>, edit: photo::MetadataEdit) {
+        let sender = self.photo_edit.sender().clone();
+        let target = self.settings_state.read().metadata_write_target;
+        self.enqueue(Box::new(move || sender.emit(PhotoEditInput::Edit(target, picture_ids.clone(), edit.clone()))));
+    }
+
+    fn add_task_video_set_location(&mut self, video_ids: Vec<VideoId>, location: GPSLocation) {
+        let sender = self.video_edit.sender().clone();
+        self.enqueue(Box::new(move || sender.emit(VideoEditInput::SetLocation(video_ids.clone(), location))));
+    }
+
+    fn add_task_photo_shift_time(&mut self, folder: PathBuf, delta: TimeDelta) {
+        let sender = self.photo_edit.sender().clone();
+        let target = self.settings_state.read().metadata_write_target;
+        self.enqueue(Box::new(move || sender.emit(PhotoEditInput::ShiftTime(target, folder.clone(), delta))));
+    }
+
     fn add_task_photo_clean(&mut self) {
         let sender = self.photo_clean.sender().clone();
         self.enqueue(Box::new(move || sender.emit(PhotoCleanInput::Start)));
@@ -291,6 +489,36 @@ impl Controllers {
         self.enqueue(Box::new(move || sender.emit(VideoCleanInput::Start)));
     }
 
+    fn add_task_photo_clean_confirmed(&mut self) {
+        let sender = self.photo_clean.sender().clone();
+        self.enqueue(Box::new(move || sender.emit(PhotoCleanInput::StartConfirmed)));
+    }
+
+    fn add_task_video_clean_confirmed(&mut self) {
+        let sender = self.video_clean.sender().clone();
+        self.enqueue(Box::new(move || sender.emit(VideoCleanInput::StartConfirmed)));
+    }
+
+    fn add_task_photo_clean_paths(&mut self, paths: Vec<PathBuf>) {
+        let sender = self.photo_clean.sender().clone();
+        self.enqueue(Box::new(move || sender.emit(PhotoCleanInput::CleanPaths(paths.clone()))));
+    }
+
+    fn add_task_video_clean_paths(&mut self, paths: Vec<PathBuf>) {
+        let sender = self.video_clean.sender().clone();
+        self.enqueue(Box::new(move || sender.emit(VideoCleanInput::CleanPaths(paths.clone()))));
+    }
+
+    fn add_task_photo_clean_paths_confirmed(&mut self, paths: Vec<PathBuf>) {
+        let sender = self.photo_clean.sender().clone();
+        self.enqueue(Box::new(move || sender.emit(PhotoCleanInput::CleanPathsConfirmed(paths.clone()))));
+    }
+
+    fn add_task_video_clean_paths_confirmed(&mut self, paths: Vec<PathBuf>) {
+        let sender = self.video_clean.sender().clone();
+        self.enqueue(Box::new(move || sender.emit(VideoCleanInput::CleanPathsConfirmed(paths.clone()))));
+    }
+
     fn add_task_photo_extract_motion(&mut self) {
         let sender = self.photo_extract_motion.sender().clone();
         self.enqueue(Box::new(move || sender.emit(PhotoExtractMotionInput::Start)));
@@ -329,6 +557,17 @@ impl Controllers {
         };
     }
 
+    fn add_task_photo_cluster_faces(&mut self) {
+        let sender = self.photo_cluster_faces.sender().clone();
+        let mode = self.settings_state.read().face_detection_mode;
+        match mode {
+            FaceDetectionMode::Off => {},
+            FaceDetectionMode::On => {
+                self.enqueue(Box::new(move || sender.emit(PhotoClusterFacesInput::Start)));
+            },
+        };
+    }
+
     fn add_task_video_transcode(&mut self) {
         let sender = self.video_transcode.sender().clone();
         self.enqueue(Box::new(move || sender.emit(VideoTranscodeInput::Start)));
@@ -381,22 +620,22 @@ pub struct Bootstrap {
     /// Background task runners. Only present after library path is set.
     controllers: Option<Controllers>,
 
-    /// Current pictures base directory used by background tasks.
-    pictures_base_dir: Option<PathBuf>
+    /// Current library roots used by background tasks.
+    library_roots: Option<Vec<library::RootConfig>>,
 }
 
 impl Bootstrap {
-    fn build_controllers(&mut self, pic_base_dir: PathBuf, sender: &ComponentSender<Self>) -> anyhow::Result<Controllers> {
+    fn build_controllers(&mut self, root_configs: Vec<library::RootConfig>, sender: &ComponentSender<Self>) -> anyhow::Result<Controllers> {
         let data_dir = glib::user_data_dir().join(APP_ID);
         let _ = std::fs::create_dir_all(&data_dir);
 
         let cache_dir = glib::user_cache_dir().join(APP_ID);
         let _ = std::fs::create_dir_all(&cache_dir);
 
-        let photo_scanner = photo::Scanner::build(&pic_base_dir)?;
+        let roots = library::Repository::open(self.con.clone())?.sync(&root_configs)?;
 
         let photo_repo = photo::Repository::open(
-            &pic_base_dir,
+            &roots,
             &cache_dir,
             &data_dir,
             self.con.clone(),
@@ -404,17 +643,15 @@ impl Bootstrap {
 
         let photo_thumbnailer = photo::Thumbnailer::build(&cache_dir)?;
 
-        let video_scanner = video::Scanner::build(&pic_base_dir)?;
-
         let video_repo =
-            video::Repository::open(&pic_base_dir, &cache_dir, &data_dir, self.con.clone())?;
+            video::Repository::open(&roots, &cache_dir, &data_dir, self.con.clone())?;
 
         let video_thumbnailer = video::Thumbnailer::build(&cache_dir)?;
 
         let motion_photo_extractor = photo::MotionPhotoExtractor::build(&cache_dir)?;
 
         let visual_repo = visual::Repository::open(
-            &pic_base_dir,
+            &roots,
             &cache_dir,
             self.con.clone(),
         )?;
@@ -424,26 +661,41 @@ impl Bootstrap {
             self.con.clone(),
         )?;
 
+        let duplicate_repo = duplicate::Repository::open(self.con.clone())?;
+
+        let search_repo = search::Repository::open(self.con.clone())?;
+
+        let place_repo = place::Repository::open(self.con.clone())?;
+
+        let event_repo = event::Repository::open(self.con.clone())?;
+
         let stop = Arc::new(AtomicBool::new(false));
 
+        // Unavailable roots aren't watched. They will be watched when next configured.
+        let watchers = roots.available()
+            .filter_map(|root| {
+                let watcher_sender = sender.input_sender().clone();
+                let watcher = scan::Watcher::start(&root.path, scan::Registry::default(), move |batch| {
+                    watcher_sender.emit(BootstrapInput::LibraryChanged(batch));
+                });
+
+                watcher
+                    .inspect_err(|e| error!("Failed to watch library root {} for changes: {:?}", root.name, e))
+                    .ok()
+            })
+            .collect();
+
         let load_library = LoadLibrary::builder()
-            .detach_worker((visual_repo.clone(), self.shared_state.clone()))
+            .detach_worker((visual_repo.clone(), search_repo, self.shared_state.clone()))
             .forward(sender.input_sender(), |msg| match msg {
                 LoadLibraryOutput::Done => BootstrapInput::TaskCompleted(TaskName::LoadLibrary, None),
             });
 
-        let photo_scan = PhotoScan::builder()
-            .detach_worker((photo_scanner.clone(), photo_repo.clone()))
-            .forward(sender.input_sender(), |msg| match msg {
-                PhotoScanOutput::Started => BootstrapInput::TaskStarted(TaskName::Scan(MediaType::Photo)),
-                PhotoScanOutput::Completed => BootstrapInput::TaskCompleted(TaskName::Scan(MediaType::Photo), None),
-            });
-
-        let video_scan = VideoScan::builder()
-            .detach_worker((video_scanner.clone(), video_repo.clone()))
+        let library_scan = LibraryScan::builder()
+            .detach_worker((photo_repo.clone(), video_repo.clone()))
             .forward(sender.input_sender(), |msg| match msg {
-                VideoScanOutput::Started => BootstrapInput::TaskStarted(TaskName::Scan(MediaType::Video)),
-                VideoScanOutput::Completed => BootstrapInput::TaskCompleted(TaskName::Scan(MediaType::Video), None),
+                LibraryScanOutput::Started => BootstrapInput::TaskStarted(TaskName::Scan),
+                LibraryScanOutput::Completed => BootstrapInput::TaskCompleted(TaskName::Scan, None),
             });
 
         let photo_enrich = PhotoEnrich::builder()
@@ -460,6 +712,20 @@ impl Bootstrap {
                 VideoEnrichOutput::Completed(count) => BootstrapInput::TaskCompleted(TaskName::Enrich(MediaType::Video), Some(count)),
             });
 
+        let photo_geocode = PhotoGeocode::builder()
+            .detach_worker((stop.clone(), PathBuf::from(PKGDATADIR).join("geonames"), place_repo))
+            .forward(sender.input_sender(), |msg| match msg {
+                PhotoGeocodeOutput::Started => BootstrapInput::TaskStarted(TaskName::Geocode),
+                PhotoGeocodeOutput::Completed(count) => BootstrapInput::TaskCompleted(TaskName::Geocode, Some(count)),
+            });
+
+        let detect_events = DetectEvents::builder()
+            .detach_worker((stop.clone(), visual_repo.clone(), event_repo))
+            .forward(sender.input_sender(), |msg| match msg {
+                DetectEventsOutput::Started => BootstrapInput::TaskStarted(TaskName::DetectEvents),
+                DetectEventsOutput::Completed(count) => BootstrapInput::TaskCompleted(TaskName::DetectEvents, Some(count)),
+            });
+
         let photo_extract_motion = PhotoExtractMotion::builder()
             .detach_worker((stop.clone(), motion_photo_extractor, photo_repo.clone(), self.progress_monitor.clone()))
             .forward(sender.input_sender(), |msg| match msg {
@@ -481,6 +747,41 @@ impl Bootstrap {
                 VideoThumbnailOutput::Completed(count) => BootstrapInput::TaskCompleted(TaskName::Thumbnail(MediaType::Video), Some(count)),
             });
 
+        let photo_hash = PhotoHash::builder()
+            .detach_worker((stop.clone(), photo_repo.clone(), duplicate_repo.clone(), self.progress_monitor.clone()))
+            .forward(sender.input_sender(), |msg| match msg {
+                PhotoHashOutput::Started => BootstrapInput::TaskStarted(TaskName::Hash(MediaType::Photo)),
+                PhotoHashOutput::Completed(count) => BootstrapInput::TaskCompleted(TaskName::Hash(MediaType::Photo), Some(count)),
+            });
+
+        let video_hash = VideoHash::builder()
+            .detach_worker((stop.clone(), video_repo.clone(), duplicate_repo.clone(), self.progress_monitor.clone()))
+            .forward(sender.input_sender(), |msg| match msg {
+                VideoHashOutput::Started => BootstrapInput::TaskStarted(TaskName::Hash(MediaType::Video)),
+                VideoHashOutput::Completed(count) => BootstrapInput::TaskCompleted(TaskName::Hash(MediaType::Video), Some(count)),
+            });
+
+        let photo_perceptual_hash = PhotoPerceptualHash::builder()
+            .detach_worker((stop.clone(), photo_repo.clone(), self.progress_monitor.clone()))
+            .forward(sender.input_sender(), |msg| match msg {
+                PhotoPerceptualHashOutput::Started => BootstrapInput::TaskStarted(TaskName::PerceptualHash),
+                PhotoPerceptualHashOutput::Completed(count) => BootstrapInput::TaskCompleted(TaskName::PerceptualHash, Some(count)),
+            });
+
+        let photo_edit = PhotoEdit::builder()
+            .detach_worker(photo_repo.clone())
+            .forward(sender.input_sender(), |msg| match msg {
+                PhotoEditOutput::Started => BootstrapInput::TaskStarted(TaskName::EditMetadata),
+                PhotoEditOutput::Completed(count) => BootstrapInput::TaskCompleted(TaskName::EditMetadata, Some(count)),
+            });
+
+        let video_edit = VideoEdit::builder()
+            .detach_worker(video_repo.clone())
+            .forward(sender.input_sender(), |msg| match msg {
+                VideoEditOutput::Started => BootstrapInput::TaskStarted(TaskName::EditMetadata),
+                VideoEditOutput::Completed(count) => BootstrapInput::TaskCompleted(TaskName::EditMetadata, Some(count)),
+            });
+
         let transcoder = video::Transcoder::new(&cache_dir);
 
         let video_transcode = VideoTranscode::builder()
@@ -495,6 +796,8 @@ impl Bootstrap {
             .forward(sender.input_sender(), |msg| match msg {
                 PhotoCleanOutput::Started => BootstrapInput::TaskStarted(TaskName::Clean(MediaType::Photo)),
                 PhotoCleanOutput::Completed(count) => BootstrapInput::TaskCompleted(TaskName::Clean(MediaType::Photo), Some(count)),
+                PhotoCleanOutput::ConfirmationRequired(missing_count, library_count) =>
+                    BootstrapInput::CleanConfirmationRequired(MediaType::Photo, missing_count, library_count),
             });
 
         let video_clean = VideoClean::builder()
@@ -502,40 +805,57 @@ impl Bootstrap {
             .forward(sender.input_sender(), |msg| match msg {
                 VideoCleanOutput::Started => BootstrapInput::TaskStarted(TaskName::Clean(MediaType::Video)),
                 VideoCleanOutput::Completed(count) => BootstrapInput::TaskCompleted(TaskName::Clean(MediaType::Video), Some(count)),
+                VideoCleanOutput::ConfirmationRequired(missing_count, library_count) =>
+                    BootstrapInput::CleanConfirmationRequired(MediaType::Video, missing_count, library_count),
             });
 
         let photo_detect_faces = PhotoDetectFaces::builder()
-            .detach_worker((stop.clone(), data_dir, photo_repo.clone(), people_repo.clone(), self.progress_monitor.clone()))
+            .detach_worker((stop.clone(), data_dir, cache_dir.clone(), photo_repo.clone(), people_repo.clone(), self.progress_monitor.clone()))
             .forward(sender.input_sender(), |msg| match msg {
                 PhotoDetectFacesOutput::Started => BootstrapInput::TaskStarted(TaskName::DetectFaces),
                 PhotoDetectFacesOutput::Completed => BootstrapInput::TaskCompleted(TaskName::DetectFaces, None),
             });
 
         let photo_recognize_faces = PhotoRecognizeFaces::builder()
-            .detach_worker((stop.clone(), cache_dir.clone(), people_repo.clone(), self.progress_monitor.clone()))
+            .detach_worker((stop.clone(), people_repo.clone(), self.progress_monitor.clone()))
             .forward(sender.input_sender(), |msg| match msg {
                 PhotoRecognizeFacesOutput::Started => BootstrapInput::TaskStarted(TaskName::RecognizeFaces),
                 PhotoRecognizeFacesOutput::Completed => BootstrapInput::TaskCompleted(TaskName::RecognizeFaces, None),
             });
 
+        let photo_cluster_faces = PhotoClusterFaces::builder()
+            .detach_worker((stop.clone(), people_repo.clone()))
+            .forward(sender.input_sender(), |msg| match msg {
+                PhotoClusterFacesOutput::Started => BootstrapInput::TaskStarted(TaskName::ClusterFaces),
+                PhotoClusterFacesOutput::Completed => BootstrapInput::TaskCompleted(TaskName::ClusterFaces, None),
+            });
+
         let mut controllers = Controllers {
             stop,
             started_at: None,
             shared_state: self.shared_state.clone(),
             settings_state: self.settings_state.clone(),
             load_library: Arc::new(load_library),
-            photo_scan: Arc::new(photo_scan),
-            video_scan: Arc::new(video_scan),
+            library_scan: Arc::new(library_scan),
             photo_enrich: Arc::new(photo_enrich),
             video_enrich:Arc::new(video_enrich),
+            photo_geocode: Arc::new(photo_geocode),
+            detect_events: Arc::new(detect_events),
             photo_extract_motion: Arc::new(photo_extract_motion),
             photo_clean: Arc::new(photo_clean),
             video_clean: Arc::new(video_clean),
             photo_thumbnail: Arc::new(photo_thumbnail),
             video_thumbnail: Arc::new(video_thumbnail),
+            photo_hash: Arc::new(photo_hash),
+            video_hash: Arc::new(video_hash),
+            photo_perceptual_hash: Arc::new(photo_perceptual_hash),
+            photo_edit: Arc::new(photo_edit),
+            video_edit: Arc::new(video_edit),
             photo_detect_faces: Arc::new(photo_detect_faces),
             photo_recognize_faces: Arc::new(photo_recognize_faces),
+            photo_cluster_faces: Arc::new(photo_cluster_faces),
             video_transcode: Arc::new(video_transcode),
+            _watchers: watchers,
             pending_tasks: Arc::new(Mutex::new(VecDeque::new())),
             is_running: false,
             library_stale: Arc::new(AtomicBool::new(true)),
@@ -545,10 +865,11 @@ impl Bootstrap {
 
         // Initial library load to reduce time from starting app and seeing a photo grid
         controllers.add_task_load_library(sender.input_sender().clone());
-        controllers.add_task_photo_scan();
-        controllers.add_task_video_scan();
+        controllers.add_task_library_scan();
         controllers.add_task_photo_enrich();
         controllers.add_task_video_enrich();
+        controllers.add_task_photo_geocode();
+        controllers.add_task_detect_events();
 
         // If loaded library is currently empty, then refresh now that the photo and video scans
         // are complete. Note: should do this after enriching because otherwise Fotema won't
@@ -557,11 +878,15 @@ impl Bootstrap {
 
         controllers.add_task_photo_thumbnail();
         controllers.add_task_video_thumbnail();
+        controllers.add_task_photo_perceptual_hash();
         controllers.add_task_photo_clean();
         controllers.add_task_video_clean();
+        controllers.add_task_photo_hash();
+        controllers.add_task_video_hash();
         controllers.add_task_photo_extract_motion();
         controllers.add_task_photo_detect_faces();
         controllers.add_task_photo_recognize_faces();
+        controllers.add_task_photo_cluster_faces();
 
         // This is the last background task to complete. Refresh library if there
         // has been a visible change to the library state.
@@ -585,7 +910,7 @@ impl Worker for Bootstrap {
             progress_monitor,
             con,
             controllers: None,
-            pictures_base_dir: None,
+            library_roots: None,
         }
     }
 
@@ -593,11 +918,11 @@ impl Worker for Bootstrap {
         // This match block coordinates the background tasks launched immediately after
         // the app starts up.
         match msg {
-            BootstrapInput::Configure(pictures_base_dir) => {
-                info!("Configuring with pictures base directory: {:?}", pictures_base_dir);
-                match self.build_controllers(pictures_base_dir.clone(), &sender) {
+            BootstrapInput::Configure(library_roots) => {
+                info!("Configuring with library roots: {:?}", library_roots);
+                match self.build_controllers(library_roots.clone(), &sender) {
                     Ok(controllers) => {
-                        self.pictures_base_dir = Some(pictures_base_dir);
+                        self.library_roots = Some(library_roots);
                         self.controllers = Some(controllers);
                         sender.input(BootstrapInput::Start);
                     },
@@ -608,25 +933,25 @@ impl Worker for Bootstrap {
             },
             BootstrapInput::SettingsUpdated(settings) => {
                 info!("Settings updated.");
-                // Only stop, reconfigure, and restart tasks if pictures dir changes.
-                if self.pictures_base_dir.as_ref().is_some_and(|dir| *dir != settings.pictures_base_dir) {
+                // Only stop, reconfigure, and restart tasks if library roots change.
+                if self.library_roots.as_ref().is_some_and(|roots| *roots != settings.library_roots) {
                     // If running, then shutdown running and queued tasks, and then reconfigure.
                     // Otherwise simply reconfigure with new path.
                     if self.controllers.as_ref().is_some_and(|controllers| controllers.is_running) {
-                        self.pictures_base_dir = None;
+                        self.library_roots = None;
                         sender.input(BootstrapInput::Stop);
                     } else {
                         self.controllers = None;
-                        sender.input(BootstrapInput::Configure(settings.pictures_base_dir));
+                        sender.input(BootstrapInput::Configure(settings.library_roots));
                     }
                 }
             },
-            BootstrapInput::Stopped if self.pictures_base_dir.is_none() => {
-                // If stopped and no pictures base dir, then background tasks were
-                // shutdown in response to the user changing the pictures base directory.
+            BootstrapInput::Stopped if self.library_roots.is_none() => {
+                // If stopped and no library roots, then background tasks were
+                // shutdown in response to the user changing the library roots.
                 // Now that tasks are shutdown, it is safe to reconfigure with
-                // the new directory.
-                sender.input(BootstrapInput::Configure(self.settings_state.read().pictures_base_dir.clone()));
+                // the new roots.
+                sender.input(BootstrapInput::Configure(self.settings_state.read().library_roots.clone()));
             },
             BootstrapInput::TaskCompleted(TaskName::LoadLibrary, _) if self.controllers.is_some()  => {
                 info!("Forwarding {:?} to controllers and marking library as fresh.", msg);
@@ -0,0 +1,104 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use relm4::prelude::*;
+use relm4::Worker;
+use anyhow::*;
+use fotema_core::event::{self, EventPolicy};
+use fotema_core::visual;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use tracing::{error, info};
+
+#[derive(Debug)]
+pub enum DetectEventsInput {
+    Start,
+}
+
+#[derive(Debug)]
+pub enum DetectEventsOutput {
+    // Event detection started.
+    Started,
+
+    // Event detection completed
+    Completed(usize),
+}
+
+pub struct DetectEvents {
+    // Stop flag
+    stop: Arc<AtomicBool>,
+
+    visual_repo: visual::Repository,
+
+    repo: event::Repository,
+}
+
+impl DetectEvents {
+
+    fn detect(
+        stop: Arc<AtomicBool>,
+        visual_repo: visual::Repository,
+        mut repo: event::Repository,
+        sender: &ComponentSender<DetectEvents>) -> Result<()>
+    {
+        if stop.load(Ordering::Relaxed) {
+            let _ = sender.output(DetectEventsOutput::Completed(0));
+            return Ok(());
+        }
+
+        let start = std::time::Instant::now();
+
+        let _ = sender.output(DetectEventsOutput::Started);
+
+        let visuals = visual_repo.all()?;
+        let events = repo.all()?;
+
+        let new_events = EventPolicy::default().detect(&visuals, &events);
+        let count = new_events.len();
+
+        repo.add_all(&new_events)?;
+
+        info!("Found {} new events in {} seconds.", count, start.elapsed().as_secs());
+
+        if let Err(e) = sender.output(DetectEventsOutput::Completed(count)) {
+            error!("Failed sending DetectEventsOutput::Completed: {:?}", e);
+        }
+
+        Ok(())
+    }
+}
+
+impl Worker for DetectEvents {
+    type Init = (Arc<AtomicBool>, visual::Repository, event::Repository);
+    type Input = DetectEventsInput;
+    type Output = DetectEventsOutput;
+
+    fn init((stop, visual_repo, repo): Self::Init, _sender: ComponentSender<Self>) -> Self  {
+        DetectEvents {
+            stop,
+            visual_repo,
+            repo,
+        }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            DetectEventsInput::Start => {
+                info!("Detecting events...");
+                let stop = self.stop.clone();
+                let visual_repo = self.visual_repo.clone();
+                let repo = self.repo.clone();
+
+                rayon::spawn(move || {
+                    if let Err(e) = DetectEvents::detect(stop, visual_repo, repo, &sender) {
+                        error!("Failed to detect events: {}", e);
+                        let _ = sender.output(DetectEventsOutput::Completed(0));
+                    }
+                });
+            }
+        };
+    }
+}
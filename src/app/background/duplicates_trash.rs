@@ -0,0 +1,91 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use relm4::prelude::*;
+use relm4::Worker;
+use relm4::gtk::gio;
+use relm4::gtk::prelude::FileExt;
+use std::path::{Path, PathBuf};
+
+use fotema_core::duplicate;
+
+use tracing::{error, info, warn};
+
+#[derive(Debug)]
+pub enum DuplicatesTrashInput {
+    /// Move extra copies to the trash. Each pair is the path of the file to keep
+    /// and the path of an extra copy of it.
+    Trash(Vec<(PathBuf, PathBuf)>),
+}
+
+#[derive(Debug)]
+pub enum DuplicatesTrashOutput {
+    /// Extra copies that have been moved to the trash.
+    Resolved(Vec<PathBuf>),
+}
+
+pub struct DuplicatesTrash;
+
+impl DuplicatesTrash {
+    /// Move an extra copy to the trash if it really is a copy of the file to keep.
+    fn trash(keep: &Path, path: &Path) -> bool {
+        // The same file reached through different paths, such as through a symlink,
+        // isn't a copy and trashing it would lose the only one.
+        let is_same_file = match (keep.canonicalize(), path.canonicalize()) {
+            (Ok(keep), Ok(path)) => keep == path,
+            _ => false,
+        };
+
+        if is_same_file {
+            warn!("Not moving {:?} to trash because it is the same file as {:?}", path, keep);
+            return false;
+        }
+
+        // Large files are matched on a sample of their content, so
+        // make sure the copy really is the same before trashing it.
+        match duplicate::hash::is_same_content(keep, path) {
+            Ok(true) => {},
+            Ok(false) => {
+                warn!("Not moving {:?} to trash because it differs from {:?}", path, keep);
+                return false;
+            },
+            Err(e) => {
+                error!("Failed to compare {:?} with {:?}: {}", path, keep, e);
+                return false;
+            },
+        }
+
+        let result = gio::File::for_path(path).trash(gio::Cancellable::NONE);
+        if let Err(ref e) = result {
+            error!("Failed to move {:?} to trash: {}", path, e);
+        }
+        result.is_ok()
+    }
+}
+
+impl Worker for DuplicatesTrash {
+    type Init = ();
+    type Input = DuplicatesTrashInput;
+    type Output = DuplicatesTrashOutput;
+
+    fn init(_: Self::Init, _sender: ComponentSender<Self>) -> Self {
+        Self
+    }
+
+    fn update(&mut self, msg: DuplicatesTrashInput, sender: ComponentSender<Self>) {
+        match msg {
+            DuplicatesTrashInput::Trash(copies) => {
+                info!("Moving {} extra copies to the trash", copies.len());
+
+                let trashed: Vec<PathBuf> = copies.into_iter()
+                    .filter(|(keep, path)| Self::trash(keep, path))
+                    .map(|(_, path)| path)
+                    .collect();
+
+                info!("Moved {} files to the trash", trashed.len());
+                let _ = sender.output(DuplicatesTrashOutput::Resolved(trashed));
+            },
+        }
+    }
+}
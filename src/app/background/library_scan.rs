@@ -0,0 +1,137 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use relm4::prelude::*;
+use relm4::Worker;
+use fotema_core::photo;
+use fotema_core::video;
+use fotema_core::scan::{MediaKind, Scanner};
+
+use std::path::PathBuf;
+
+use tracing::{error, info};
+
+#[derive(Debug)]
+pub enum LibraryScanInput {
+    Start,
+
+    /// Scan only these files, such as those reported by the library watcher.
+    ScanPaths(Vec<(MediaKind, PathBuf)>),
+}
+
+#[derive(Debug)]
+pub enum LibraryScanOutput {
+    Started,
+    Completed,
+}
+
+/// Scans library roots for photos and videos.
+/// Each root is walked once and the files found are split between the photo
+/// and video repositories.
+pub struct LibraryScan {
+    photo_repo: photo::Repository,
+    video_repo: video::Repository,
+}
+
+impl Worker for LibraryScan {
+    type Init = (photo::Repository, video::Repository);
+    type Input = LibraryScanInput;
+    type Output = LibraryScanOutput;
+
+    fn init((photo_repo, video_repo): Self::Init, _sender: ComponentSender<Self>) -> Self {
+        Self { photo_repo, video_repo }
+    }
+
+    fn update(&mut self, msg: LibraryScanInput, sender: ComponentSender<Self>) {
+        match msg {
+            LibraryScanInput::Start => {
+                let result = self.scan_and_add(sender);
+                if let Err(e) = result {
+                    error!("Failed scan with: {}", e);
+                }
+            }
+            LibraryScanInput::ScanPaths(paths) => {
+                let result = self.scan_and_add_paths(paths, sender);
+                if let Err(e) = result {
+                    error!("Failed scan with: {}", e);
+                }
+            }
+        };
+    }
+}
+
+impl LibraryScan {
+    fn scan_and_add(&mut self, sender: ComponentSender<Self>) -> std::result::Result<(), String> {
+
+        sender.output(LibraryScanOutput::Started)
+            .map_err(|e| format!("{:?}", e))?;
+
+        info!("Scanning file system for photos and videos...");
+
+        // Scan roots one at a time so that an unmounted root is skipped rather than
+        // having all its photos and videos counted as removed.
+        for root in self.photo_repo.roots().clone().iter() {
+            if !root.is_available() {
+                info!("Skipping unavailable library root {}: {:?}", root.name, root.path);
+                continue;
+            }
+
+            let scanner = Scanner::build(&root.path).map_err(|e| e.to_string())?;
+            let mut files = scanner.scan_all().map_err(|e| e.to_string())?;
+
+            let pics = files.remove(&MediaKind::Photo).unwrap_or_default();
+            info!("Found {} photos in {} to add to database", pics.len(), root.name);
+            let summary = self.photo_repo.add_all(root, &pics).map_err(|e| e.to_string())?;
+            info!("Scanned photos in {}: {}", root.name, summary);
+
+            let vids = files.remove(&MediaKind::Video).unwrap_or_default();
+            info!("Found {} videos in {} to add to database", vids.len(), root.name);
+            let summary = self.video_repo.add_all(root, &vids).map_err(|e| e.to_string())?;
+            info!("Scanned videos in {}: {}", root.name, summary);
+        }
+
+        sender.output(LibraryScanOutput::Completed)
+            .map_err(|e| format!("{:?}", e))
+
+    }
+
+    fn scan_and_add_paths(&mut self, paths: Vec<(MediaKind, PathBuf)>, sender: ComponentSender<Self>) -> std::result::Result<(), String> {
+
+        sender.output(LibraryScanOutput::Started)
+            .map_err(|e| format!("{:?}", e))?;
+
+        let mut pics = vec![];
+        let mut vids = vec![];
+
+        for (kind, path) in paths {
+            // A file might have been deleted again before being scanned
+            let file = match Scanner::scan_one(&path) {
+                Ok(file) => file,
+                Err(e) => {
+                    error!("Failed scanning: {:?}", e);
+                    continue;
+                }
+            };
+
+            match kind {
+                MediaKind::Photo => pics.push(file),
+                MediaKind::Video => vids.push(file),
+                MediaKind::Other(_) => {},
+            }
+        }
+
+        if !pics.is_empty() {
+            let summary = self.photo_repo.add_some(&pics).map_err(|e| e.to_string())?;
+            info!("Scanned changed photos: {}", summary);
+        }
+
+        if !vids.is_empty() {
+            let summary = self.video_repo.add_some(&vids).map_err(|e| e.to_string())?;
+            info!("Scanned changed videos: {}", summary);
+        }
+
+        sender.output(LibraryScanOutput::Completed)
+            .map_err(|e| format!("{:?}", e))
+    }
+}
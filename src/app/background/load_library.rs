@@ -6,6 +6,7 @@ use relm4::prelude::*;
 use relm4::Worker;
 use crate::app::SharedState;
 use fotema_core::visual::Repository;
+use fotema_core::search;
 use fotema_core::Visual;
 use std::sync::Arc;
 use anyhow::*;
@@ -23,16 +24,17 @@ pub enum LoadLibraryOutput {
 
 pub struct LoadLibrary {
     repo: Repository,
+    search_repo: search::Repository,
     state: SharedState,
 }
 
 impl Worker for LoadLibrary {
-    type Init = (Repository, SharedState);
+    type Init = (Repository, search::Repository, SharedState);
     type Input = LoadLibraryInput;
     type Output = LoadLibraryOutput;
 
-    fn init((repo, state): Self::Init, _sender: ComponentSender<Self>) -> Self {
-        Self { repo, state }
+    fn init((repo, search_repo, state): Self::Init, _sender: ComponentSender<Self>) -> Self {
+        Self { repo, search_repo, state }
     }
 
     fn update(&mut self, msg: LoadLibraryInput, sender: ComponentSender<Self>) {
@@ -51,7 +53,7 @@ impl Worker for LoadLibrary {
 }
 
 impl LoadLibrary {
-    fn load(&self) -> Result<()> {
+    fn load(&mut self) -> Result<()> {
         let mut all = self
             .repo
             .all()?
@@ -61,6 +63,12 @@ impl LoadLibrary {
 
         info!("Loaded {} visual items", all.len());
 
+        // Update search index before the library so that searches re-run
+        // after the library is refreshed will see the new index.
+        if let Err(e) = self.search_repo.index(all.iter().map(|v| v.as_ref())) {
+            error!("Failed updating search index: {}", e);
+        }
+
         let mut index = self.state.write();
         index.clear();
         index.append(&mut all);
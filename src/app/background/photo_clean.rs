@@ -7,14 +7,28 @@ use relm4::Worker;
 use rayon::prelude::*;
 use anyhow::Result;
 
+use fotema_core::library::CleanPolicy;
+
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 #[derive(Debug)]
 pub enum PhotoCleanInput {
     Start,
+
+    /// Clean only photos at or below these paths, such as those reported by the library watcher.
+    CleanPaths(Vec<PathBuf>),
+
+    /// Clean photos at these paths that the user has removed, such as extra copies of
+    /// duplicates moved to the trash. Doesn't ask for confirmation.
+    CleanPathsConfirmed(Vec<PathBuf>),
+
+    /// Clean all photos, even if a large share of the library is missing.
+    /// Sent after the user has confirmed.
+    StartConfirmed,
 }
 
 #[derive(Debug)]
@@ -25,6 +39,9 @@ pub enum PhotoCleanOutput {
     // Thumbnail generation has completed
     Completed(usize),
 
+    /// Too many photos are missing to clean without confirmation.
+    /// Count of missing photos and count of photos in library.
+    ConfirmationRequired(usize, usize),
 }
 
 pub struct PhotoClean {
@@ -33,20 +50,54 @@ pub struct PhotoClean {
 
     // Danger! Don't hold the repo mutex for too long as it blocks viewing images.
     repo: fotema_core::photo::Repository,
+
+    policy: CleanPolicy,
 }
 
 impl PhotoClean {
 
-    fn cleanup(&mut self, sender: &ComponentSender<Self>) -> Result<()> {
+    fn cleanup(&mut self, removed: Option<Vec<PathBuf>>, is_confirmed: bool, sender: &ComponentSender<Self>) -> Result<()> {
 
         let start = std::time::Instant::now();
 
         // Scrub pics from database if they no longer exist on the file system.
         let pics: Vec<fotema_core::photo::model::Picture> = self.repo.all()?;
 
-        info!("Found {} photos as candidates for cleaning", pics.len());
+        // Files in an unmounted library root are missing, but not deleted.
+        let is_available = self.repo.roots().available_filter();
+        let pics: Vec<_> = pics.into_iter().filter(|x| is_available(&x.path)).collect();
+
+        let library_count = pics.len();
+
+        let candidates: Vec<&fotema_core::photo::model::Picture> = match removed {
+            Some(removed) => pics.iter()
+                .filter(|x| removed.iter().any(|r| x.path.starts_with(r)))
+                .collect(),
+            None => pics.iter().collect(),
+        };
+
+        info!("Found {} photos as candidates for cleaning", candidates.len());
+
+        let missing: Vec<_> = candidates.into_par_iter().filter(|p| !p.path.exists()).collect();
+
+        if !is_confirmed && self.policy.is_confirmation_required_per_root(
+            self.repo.roots(),
+            pics.iter().map(|x| x.path.as_path()),
+            missing.iter().map(|x| x.path.as_path()),
+        ) {
+            warn!("{} of {} photos are missing. Not cleaning without confirmation.", missing.len(), library_count);
+            let _ = sender.output(PhotoCleanOutput::ConfirmationRequired(missing.len(), library_count));
+            let _ = sender.output(PhotoCleanOutput::Completed(0));
+            return Ok(());
+        }
+
+        // Photos soft deleted by an earlier clean and not restored by a scan since.
+        let expired: Vec<_> = self.repo.find_deleted_before(self.policy.purge_before())?
+            .into_iter()
+            .filter(|x| is_available(&x.path) && !x.path.exists())
+            .collect();
 
-        let count = pics.par_iter().filter(|p| !p.path.exists()).count();
+        let count = missing.len() + expired.len();
 
         // Short-circuit before sending progress messages to stop
         // banner from appearing and disappearing.
@@ -59,30 +110,40 @@ impl PhotoClean {
             error!("Failed sending cleanup started: {:?}", e);
         }
 
-        pics.par_iter()
+        // Hide missing photos, but keep their metadata and faces in case the files come back.
+        missing.iter()
+            .take_while(|_| !self.stop.load(Ordering::Relaxed))
+            .for_each(|pic| {
+                if let Err(e) = self.repo.mark_deleted(pic.picture_id) {
+                    error!("Failed marking {} as deleted: {:?}", pic.picture_id, e);
+                } else {
+                    info!("Marked {} as deleted", pic.picture_id);
+                }
+            });
+
+        expired.par_iter()
             .take_any_while(|_| !self.stop.load(Ordering::Relaxed))
             .for_each(|pic| {
-                if !pic.path.exists() {
-                    let mut repo = self.repo.clone();
-                    if let Ok(paths) = repo.find_files_to_cleanup(pic.picture_id) {
-                        for path in paths {
-                            debug!("Deleting {:?}", path);
-                            if let Err(e) = std::fs::remove_file(&path) {
-                                error!("Failed deleting {:?} with {}", path, e);
-                            }
+                let mut repo = self.repo.clone();
+                if let Ok(paths) = repo.find_files_to_cleanup(pic.picture_id) {
+                    for path in paths {
+                        debug!("Deleting {:?}", path);
+                        if let Err(e) = std::fs::remove_file(&path) {
+                            error!("Failed deleting {:?} with {}", path, e);
                         }
                     }
+                }
 
-                    let result = repo.remove(pic.picture_id);
-                    if let Err(e) = result {
-                        error!("Failed remove {}: {:?}", pic.picture_id, e);
-                    } else {
-                        info!("Removed {}", pic.picture_id);
-                    }
+                let result = repo.remove(pic.picture_id);
+                if let Err(e) = result {
+                    error!("Failed remove {}: {:?}", pic.picture_id, e);
+                } else {
+                    info!("Removed {}", pic.picture_id);
                 }
             });
 
-        info!("Cleaned {} photos in {} seconds.", count, start.elapsed().as_secs());
+        info!("Marked {} photos as deleted and purged {} photos in {} seconds.",
+            missing.len(), expired.len(), start.elapsed().as_secs());
 
         if let Err(e) = sender.output(PhotoCleanOutput::Completed(count)) {
             error!("Failed sending PhotoCleanOutput::Completed: {:?}", e);
@@ -98,7 +159,7 @@ impl Worker for PhotoClean {
     type Output = PhotoCleanOutput;
 
     fn init((stop, repo): Self::Init, _sender: ComponentSender<Self>) -> Self {
-        Self { stop, repo }
+        Self { stop, repo, policy: CleanPolicy::default() }
     }
 
     fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
@@ -106,7 +167,28 @@ impl Worker for PhotoClean {
             PhotoCleanInput::Start => {
                 info!("Cleaning photos...");
 
-                if let Err(e) = self.cleanup(&sender) {
+                if let Err(e) = self.cleanup(None, false, &sender) {
+                    error!("Failed to clean photos: {}", e);
+                }
+            }
+            PhotoCleanInput::CleanPaths(paths) => {
+                info!("Cleaning photos below {} paths...", paths.len());
+
+                if let Err(e) = self.cleanup(Some(paths), false, &sender) {
+                    error!("Failed to clean photos: {}", e);
+                }
+            }
+            PhotoCleanInput::CleanPathsConfirmed(paths) => {
+                info!("Cleaning {} removed photos...", paths.len());
+
+                if let Err(e) = self.cleanup(Some(paths), true, &sender) {
+                    error!("Failed to clean photos: {}", e);
+                }
+            }
+            PhotoCleanInput::StartConfirmed => {
+                info!("Cleaning photos after confirmation...");
+
+                if let Err(e) = self.cleanup(None, true, &sender) {
                     error!("Failed to clean photos: {}", e);
                 }
             }
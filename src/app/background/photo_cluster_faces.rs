@@ -0,0 +1,102 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use relm4::prelude::*;
+use relm4::Worker;
+use anyhow::*;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::result::Result::Ok;
+use tracing::{error, info};
+
+use fotema_core::people::{self, ClusterPolicy, FaceId};
+
+
+#[derive(Debug)]
+pub enum PhotoClusterFacesInput {
+    Start,
+}
+
+#[derive(Debug)]
+pub enum PhotoClusterFacesOutput {
+    // Face clustering has started.
+    Started,
+
+    // Face clustering has completed
+    Completed,
+
+}
+
+#[derive(Clone)]
+pub struct PhotoClusterFaces {
+    // Stop flag
+    stop: Arc<AtomicBool>,
+
+    // Danger! Don't hold the repo mutex for too long as it blocks viewing images.
+    repo: people::Repository,
+}
+
+impl PhotoClusterFaces {
+
+    fn cluster(&self, sender: &ComponentSender<Self>) -> Result<()>
+     {
+        if self.stop.load(Ordering::Relaxed) {
+            let _ = sender.output(PhotoClusterFacesOutput::Completed);
+            return Ok(());
+        }
+
+        let start = std::time::Instant::now();
+
+        let _ = sender.output(PhotoClusterFacesOutput::Started);
+
+        // Faces without an embedding can't be compared, so are left out.
+        let embeddings: Vec<(FaceId, Vec<f32>)> = self.repo
+            .find_unknown_faces()?
+            .into_iter()
+            .filter_map(|face| Some((face.face_id, face.embedding?)))
+            .collect();
+
+        info!("Found {} unknown faces to cluster", embeddings.len());
+
+        let clusters = ClusterPolicy::default().cluster(&embeddings);
+
+        let mut repo = self.repo.clone();
+        repo.save_clusters(&clusters)?;
+
+        info!("Found {} face clusters in {} seconds.", clusters.len(), start.elapsed().as_secs());
+
+        let _ = sender.output(PhotoClusterFacesOutput::Completed);
+
+        Ok(())
+    }
+}
+
+impl Worker for PhotoClusterFaces {
+    type Init = (Arc<AtomicBool>, people::Repository);
+    type Input = PhotoClusterFacesInput;
+    type Output = PhotoClusterFacesOutput;
+
+    fn init((stop, repo): Self::Init, _sender: ComponentSender<Self>) -> Self  {
+        PhotoClusterFaces {
+            stop,
+            repo,
+        }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            PhotoClusterFacesInput::Start => {
+                info!("Clustering unknown faces...");
+                let this = self.clone();
+
+                rayon::spawn(move || {
+                    if let Err(e) = this.cluster(&sender) {
+                        error!("Failed to cluster faces: {}", e);
+                        let _ = sender.output(PhotoClusterFacesOutput::Completed);
+                    }
+                });
+            }
+        };
+    }
+}
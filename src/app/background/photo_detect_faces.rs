@@ -16,6 +16,7 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use tracing::{error, info};
 use futures::executor::block_on;
 
+use fotema_core::machine_learning::face_embedder::FaceEmbedder;
 use fotema_core::machine_learning::face_extractor::FaceExtractor;
 use fotema_core::people;
 use fotema_core::photo;
@@ -52,6 +53,9 @@ pub struct PhotoDetectFaces {
     /// Base directory for storing photo faces
     faces_base_dir: PathBuf,
 
+    /// Base directory for face recognition model
+    cache_dir: PathBuf,
+
     photo_repo: photo::Repository,
     people_repo: people::Repository,
 
@@ -90,6 +94,10 @@ impl PhotoDetectFaces {
         // Short-circuit before sending progress messages to stop
         // banner from appearing and disappearing.
         if count == 0 {
+            // Faces detected before embeddings were stored still need them.
+            if let Err(e) = self.embed() {
+                error!("Failed computing face embeddings: {:?}", e);
+            }
             let _ = sender.output(PhotoDetectFacesOutput::Completed);
             return Ok(());
         }
@@ -130,21 +138,64 @@ impl PhotoDetectFaces {
 
         self.progress_monitor.emit(ProgressMonitorInput::Complete);
 
+        if let Err(e) = self.embed() {
+            error!("Failed computing face embeddings: {:?}", e);
+        }
+
         let _ = sender.output(PhotoDetectFacesOutput::Completed);
 
         Ok(())
     }
+
+    /// Computes and saves embeddings of newly detected faces, so that face recognition
+    /// and clustering don't need to run the face recognition model.
+    fn embed(&self) -> Result<()> {
+        let start = std::time::Instant::now();
+
+        let faces = self.people_repo.find_need_embedding()?;
+        if faces.is_empty() {
+            return Ok(());
+        }
+
+        let count = faces.len();
+        info!("Found {} faces that need an embedding", count);
+
+        self.progress_monitor.emit(ProgressMonitorInput::Start(TaskName::DetectFaces, count));
+
+        let embedder = FaceEmbedder::build(&self.cache_dir)?;
+
+        faces
+            .par_iter()
+            .take_any_while(|_| !self.stop.load(Ordering::Relaxed))
+            .for_each(|face| {
+                let result = embedder.embed(face)
+                    .and_then(|face_embedding| self.people_repo.clone().add_embedding(face.face_id, &face_embedding));
+
+                if let Err(e) = result {
+                    error!("Failed computing embedding of face {}: {:?}", face.face_id, e);
+                }
+
+                self.progress_monitor.emit(ProgressMonitorInput::Advance);
+            });
+
+        info!("Computed embeddings of {} faces in {} seconds.", count, start.elapsed().as_secs());
+
+        self.progress_monitor.emit(ProgressMonitorInput::Complete);
+
+        Ok(())
+    }
 }
 
 impl Worker for PhotoDetectFaces {
-    type Init = (Arc<AtomicBool>, PathBuf, photo::Repository, people::Repository, Arc<Reducer<ProgressMonitor>>);
+    type Init = (Arc<AtomicBool>, PathBuf, PathBuf, photo::Repository, people::Repository, Arc<Reducer<ProgressMonitor>>);
     type Input = PhotoDetectFacesInput;
     type Output = PhotoDetectFacesOutput;
 
-    fn init((stop, faces_base_dir, photo_repo, people_repo, progress_monitor): Self::Init, _sender: ComponentSender<Self>) -> Self  {
+    fn init((stop, faces_base_dir, cache_dir, photo_repo, people_repo, progress_monitor): Self::Init, _sender: ComponentSender<Self>) -> Self  {
         PhotoDetectFaces {
             stop,
             faces_base_dir,
+            cache_dir,
             photo_repo,
             people_repo,
             progress_monitor,
@@ -0,0 +1,66 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use relm4::prelude::*;
+use relm4::Worker;
+use chrono::TimeDelta;
+use std::path::PathBuf;
+
+use fotema_core::photo::{Editor, MetadataEdit, WriteTarget};
+use fotema_core::PictureId;
+
+use tracing::{error, info};
+
+#[derive(Debug)]
+pub enum PhotoEditInput {
+    /// Apply the same metadata change to pictures.
+    Edit(WriteTarget, Vec<PictureId>, MetadataEdit),
+
+    /// Shift capture time of all pictures in a folder.
+    ShiftTime(WriteTarget, PathBuf, TimeDelta),
+}
+
+#[derive(Debug)]
+pub enum PhotoEditOutput {
+    // Metadata editing has started.
+    Started,
+
+    // Metadata editing has completed.
+    Completed(usize),
+}
+
+pub struct PhotoEdit {
+    repo: fotema_core::photo::Repository,
+}
+
+impl Worker for PhotoEdit {
+    type Init = fotema_core::photo::Repository;
+    type Input = PhotoEditInput;
+    type Output = PhotoEditOutput;
+
+    fn init(repo: Self::Init, _sender: ComponentSender<Self>) -> Self {
+        Self { repo }
+    }
+
+    fn update(&mut self, msg: PhotoEditInput, sender: ComponentSender<Self>) {
+        let _ = sender.output(PhotoEditOutput::Started);
+
+        let result = match msg {
+            PhotoEditInput::Edit(target, picture_ids, edit) => {
+                info!("Editing metadata of {} photos", picture_ids.len());
+                Editor::new(self.repo.clone(), target).edit(&picture_ids, &edit)
+            }
+            PhotoEditInput::ShiftTime(target, folder, delta) => {
+                info!("Shifting time of photos in {:?} by {}", folder, delta);
+                Editor::new(self.repo.clone(), target).shift_time(&folder, delta)
+            }
+        };
+
+        let count = result
+            .inspect_err(|e| error!("Failed editing metadata: {:?}", e))
+            .unwrap_or(0);
+
+        let _ = sender.output(PhotoEditOutput::Completed(count));
+    }
+}
@@ -0,0 +1,123 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use relm4::prelude::*;
+use relm4::Worker;
+use rayon::prelude::*;
+use anyhow::*;
+use fotema_core::place::{self, Geocoder};
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::result::Result::Ok;
+
+use tracing::{error, info, warn};
+
+#[derive(Debug)]
+pub enum PhotoGeocodeInput {
+    Start,
+}
+
+#[derive(Debug)]
+pub enum PhotoGeocodeOutput {
+    // Reverse geocoding started.
+    Started,
+
+    // Reverse geocoding completed
+    Completed(usize),
+}
+
+pub struct PhotoGeocode {
+    // Stop flag
+    stop: Arc<AtomicBool>,
+
+    // Directory containing GeoNames dataset.
+    geonames_dir: PathBuf,
+
+    repo: place::Repository,
+}
+
+impl PhotoGeocode {
+
+    fn geocode(
+        stop: Arc<AtomicBool>,
+        geonames_dir: PathBuf,
+        mut repo: place::Repository,
+        sender: &ComponentSender<PhotoGeocode>) -> Result<()>
+    {
+        let start = std::time::Instant::now();
+
+        let unprocessed = repo.find_need_geocode()?;
+
+        let count = unprocessed.len();
+        info!("Found {} photos to reverse geocode", count);
+
+        // Short-circuit before sending progress messages to stop
+        // banner from appearing and disappearing.
+        if count == 0 {
+            let _ = sender.output(PhotoGeocodeOutput::Completed(count));
+            return Ok(());
+        }
+
+        // The GeoNames dataset is optional when building Fotema, so it might not be installed.
+        let geocoder = match Geocoder::open(&geonames_dir) {
+            Ok(geocoder) => geocoder,
+            Err(e) => {
+                warn!("Cannot name places because GeoNames dataset failed to load: {:?}", e);
+                let _ = sender.output(PhotoGeocodeOutput::Completed(0));
+                return Ok(());
+            }
+        };
+
+        let _ = sender.output(PhotoGeocodeOutput::Started);
+
+        let places = unprocessed
+            .into_par_iter()
+            .take_any_while(|_| !stop.load(Ordering::Relaxed))
+            .map(|(picture_id, location)| (picture_id, geocoder.reverse(location)))
+            .collect();
+
+        repo.add_places(places)?;
+
+        info!("Reverse geocoded {} photos in {} seconds.", count, start.elapsed().as_secs());
+
+        if let Err(e) = sender.output(PhotoGeocodeOutput::Completed(count)) {
+            error!("Failed sending PhotoGeocodeOutput::Completed: {:?}", e);
+        }
+
+        Ok(())
+    }
+}
+
+impl Worker for PhotoGeocode {
+    type Init = (Arc<AtomicBool>, PathBuf, place::Repository);
+    type Input = PhotoGeocodeInput;
+    type Output = PhotoGeocodeOutput;
+
+    fn init((stop, geonames_dir, repo): Self::Init, _sender: ComponentSender<Self>) -> Self  {
+        PhotoGeocode {
+            stop,
+            geonames_dir,
+            repo,
+        }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            PhotoGeocodeInput::Start => {
+                info!("Reverse geocoding photos...");
+                let stop = self.stop.clone();
+                let geonames_dir = self.geonames_dir.clone();
+                let repo = self.repo.clone();
+
+                rayon::spawn(move || {
+                    if let Err(e) = PhotoGeocode::geocode(stop, geonames_dir, repo, &sender) {
+                        error!("Failed to reverse geocode photos: {}", e);
+                    }
+                });
+            }
+        };
+    }
+}
@@ -0,0 +1,136 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use relm4::prelude::*;
+use relm4::Worker;
+use relm4::Reducer;
+use rayon::prelude::*;
+use anyhow::*;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::result::Result::Ok;
+use tracing::{error, info};
+
+use fotema_core::duplicate;
+
+use crate::app::components::progress_monitor::{
+    ProgressMonitor,
+    ProgressMonitorInput,
+    TaskName,
+    MediaType
+};
+
+#[derive(Debug)]
+pub enum PhotoHashInput {
+    Start,
+}
+
+#[derive(Debug)]
+pub enum PhotoHashOutput {
+    // Content hashing has started.
+    Started,
+
+    // Content hashing has completed
+    Completed(usize),
+}
+
+pub struct PhotoHash {
+    // Stop flag
+    stop: Arc<AtomicBool>,
+
+    // Danger! Don't hold the repo mutex for too long as it blocks viewing images.
+    repo: fotema_core::photo::Repository,
+
+    duplicate_repo: duplicate::Repository,
+
+    progress_monitor: Arc<Reducer<ProgressMonitor>>,
+}
+
+impl PhotoHash {
+
+    fn hash(
+        stop: Arc<AtomicBool>,
+        repo: fotema_core::photo::Repository,
+        duplicate_repo: duplicate::Repository,
+        progress_monitor: Arc<Reducer<ProgressMonitor>>,
+        sender: ComponentSender<Self>) -> Result<()>
+    {
+        let start = std::time::Instant::now();
+
+        let unprocessed: Vec<fotema_core::photo::model::Picture> = repo
+            .find_need_content_hash()?
+            .into_iter()
+            .filter(|pic| pic.path.exists())
+            .collect();
+
+        let count = unprocessed.len();
+        info!("Found {} photos to hash", count);
+
+        // Short-circuit before sending progress messages to stop
+        // banner from appearing and disappearing.
+        if count == 0 {
+            let _ = sender.output(PhotoHashOutput::Completed(count));
+            return Ok(());
+        }
+
+        let _ = sender.output(PhotoHashOutput::Started);
+
+        progress_monitor.emit(ProgressMonitorInput::Start(TaskName::Hash(MediaType::Photo), count));
+
+        unprocessed
+            .par_iter()
+            .take_any_while(|_| !stop.load(Ordering::Relaxed))
+            .for_each(|pic| {
+                let result = duplicate::hash::hash(&pic.path)
+                    .and_then(|hash| duplicate_repo.clone().add_picture_hash(pic.picture_id, &hash));
+
+                if let Err(e) = result {
+                    error!("Failed to hash photo: {:?}: Photo path: {:?}", e, pic.path);
+                }
+
+                progress_monitor.emit(ProgressMonitorInput::Advance);
+            });
+
+        info!("Hashed {} photos in {} seconds.", count, start.elapsed().as_secs());
+
+        progress_monitor.emit(ProgressMonitorInput::Complete);
+
+        let _ = sender.output(PhotoHashOutput::Completed(count));
+
+        Ok(())
+    }
+}
+
+impl Worker for PhotoHash {
+    type Init = (Arc<AtomicBool>, fotema_core::photo::Repository, duplicate::Repository, Arc<Reducer<ProgressMonitor>>);
+    type Input = PhotoHashInput;
+    type Output = PhotoHashOutput;
+
+    fn init((stop, repo, duplicate_repo, progress_monitor): Self::Init, _sender: ComponentSender<Self>) -> Self  {
+        PhotoHash {
+            stop,
+            repo,
+            duplicate_repo,
+            progress_monitor,
+        }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            PhotoHashInput::Start => {
+                info!("Hashing photos...");
+                let stop = self.stop.clone();
+                let repo = self.repo.clone();
+                let duplicate_repo = self.duplicate_repo.clone();
+                let progress_monitor = self.progress_monitor.clone();
+
+                rayon::spawn(move || {
+                    if let Err(e) = PhotoHash::hash(stop, repo, duplicate_repo, progress_monitor, sender) {
+                        error!("Failed to hash photos: {}", e);
+                    }
+                });
+            }
+        };
+    }
+}
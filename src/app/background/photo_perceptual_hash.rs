@@ -0,0 +1,135 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use relm4::prelude::*;
+use relm4::Worker;
+use relm4::Reducer;
+use rayon::prelude::*;
+use anyhow::*;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::result::Result::Ok;
+use tracing::{error, info};
+
+use fotema_core::photo::perceptual_hash;
+
+use crate::app::components::progress_monitor::{
+    ProgressMonitor,
+    ProgressMonitorInput,
+    TaskName,
+};
+
+#[derive(Debug)]
+pub enum PhotoPerceptualHashInput {
+    Start,
+}
+
+#[derive(Debug)]
+pub enum PhotoPerceptualHashOutput {
+    // Perceptual hashing has started.
+    Started,
+
+    // Perceptual hashing has completed
+    Completed(usize),
+}
+
+pub struct PhotoPerceptualHash {
+    // Stop flag
+    stop: Arc<AtomicBool>,
+
+    // Danger! Don't hold the repo mutex for too long as it blocks viewing images.
+    repo: fotema_core::photo::Repository,
+
+    progress_monitor: Arc<Reducer<ProgressMonitor>>,
+}
+
+impl PhotoPerceptualHash {
+
+    fn hash(
+        stop: Arc<AtomicBool>,
+        repo: fotema_core::photo::Repository,
+        progress_monitor: Arc<Reducer<ProgressMonitor>>,
+        sender: ComponentSender<Self>) -> Result<()>
+    {
+        let start = std::time::Instant::now();
+
+        // Hashes are computed from thumbnails, so thumbnails must be generated first.
+        let unprocessed: Vec<fotema_core::photo::model::Picture> = repo
+            .find_need_perceptual_hash()?
+            .into_iter()
+            .filter(|pic| pic.thumbnail_path.as_ref().is_some_and(|p| p.exists()))
+            .collect();
+
+        let count = unprocessed.len();
+        info!("Found {} photos to perceptually hash", count);
+
+        // Short-circuit before sending progress messages to stop
+        // banner from appearing and disappearing.
+        if count == 0 {
+            let _ = sender.output(PhotoPerceptualHashOutput::Completed(count));
+            return Ok(());
+        }
+
+        let _ = sender.output(PhotoPerceptualHashOutput::Started);
+
+        progress_monitor.emit(ProgressMonitorInput::Start(TaskName::PerceptualHash, count));
+
+        unprocessed
+            .par_iter()
+            .take_any_while(|_| !stop.load(Ordering::Relaxed))
+            .for_each(|pic| {
+                let Some(ref thumbnail_path) = pic.thumbnail_path else {
+                    return;
+                };
+
+                let result = perceptual_hash::dhash(thumbnail_path)
+                    .and_then(|hash| repo.clone().add_perceptual_hash(pic.picture_id, &hash));
+
+                if let Err(e) = result {
+                    error!("Failed to perceptually hash photo: {:?}: Thumbnail path: {:?}", e, thumbnail_path);
+                }
+
+                progress_monitor.emit(ProgressMonitorInput::Advance);
+            });
+
+        info!("Perceptually hashed {} photos in {} seconds.", count, start.elapsed().as_secs());
+
+        progress_monitor.emit(ProgressMonitorInput::Complete);
+
+        let _ = sender.output(PhotoPerceptualHashOutput::Completed(count));
+
+        Ok(())
+    }
+}
+
+impl Worker for PhotoPerceptualHash {
+    type Init = (Arc<AtomicBool>, fotema_core::photo::Repository, Arc<Reducer<ProgressMonitor>>);
+    type Input = PhotoPerceptualHashInput;
+    type Output = PhotoPerceptualHashOutput;
+
+    fn init((stop, repo, progress_monitor): Self::Init, _sender: ComponentSender<Self>) -> Self  {
+        PhotoPerceptualHash {
+            stop,
+            repo,
+            progress_monitor,
+        }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            PhotoPerceptualHashInput::Start => {
+                info!("Perceptually hashing photos...");
+                let stop = self.stop.clone();
+                let repo = self.repo.clone();
+                let progress_monitor = self.progress_monitor.clone();
+
+                rayon::spawn(move || {
+                    if let Err(e) = PhotoPerceptualHash::hash(stop, repo, progress_monitor, sender) {
+                        error!("Failed to perceptually hash photos: {}", e);
+                    }
+                });
+            }
+        };
+    }
+}
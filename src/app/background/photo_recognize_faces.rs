@@ -10,7 +10,6 @@ use anyhow::*;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::result::Result::Ok;
-use std::path::PathBuf;
 use tracing::{error, info};
 
 use fotema_core::machine_learning::face_recognizer::FaceRecognizer;
@@ -48,8 +47,6 @@ pub struct PhotoRecognizeFaces {
     repo: people::Repository,
 
     progress_monitor: Arc<Reducer<ProgressMonitor>>,
-
-    cache_dir: PathBuf,
 }
 
 impl PhotoRecognizeFaces {
@@ -87,7 +84,7 @@ impl PhotoRecognizeFaces {
         let _ = sender.output(PhotoRecognizeFacesOutput::Started);
         self.progress_monitor.emit(ProgressMonitorInput::Start(TaskName::RecognizeFaces, unprocessed.len()));
 
-        let recognizer = FaceRecognizer::build(&self.cache_dir, people.clone())?;
+        let recognizer = FaceRecognizer::build(people.clone());
 
         unprocessed
             //.into_iter()
@@ -95,10 +92,10 @@ impl PhotoRecognizeFaces {
             .take_any_while(|_| !self.stop.load(Ordering::Relaxed))
             .for_each(|unknown_face| {
                 let is_match = recognizer.recognize(&unknown_face);
-                if let Ok(Some(person_id)) = is_match {
-                    info!("Face {} looks like person {}", unknown_face.face_id, person_id);
+                if let Some((person_id, similarity)) = is_match {
+                    info!("Face {} looks like person {} with similarity {}", unknown_face.face_id, person_id, similarity);
                     let mut repo = self.repo.clone();
-                    let result = repo.mark_as_person_unconfirmed(unknown_face.face_id, person_id);
+                    let result = repo.mark_as_person_unconfirmed(unknown_face.face_id, person_id, similarity);
                     if let Err(e) = result {
                         error!("Failed marking face {} as person: {:?}", unknown_face.face_id, e);
                     }
@@ -125,14 +122,13 @@ impl PhotoRecognizeFaces {
 }
 
 impl Worker for PhotoRecognizeFaces {
-    type Init = (Arc<AtomicBool>, PathBuf, people::Repository, Arc<Reducer<ProgressMonitor>>);
+    type Init = (Arc<AtomicBool>, people::Repository, Arc<Reducer<ProgressMonitor>>);
     type Input = PhotoRecognizeFacesInput;
     type Output = PhotoRecognizeFacesOutput;
 
-    fn init((stop, cache_dir, repo, progress_monitor): Self::Init, _sender: ComponentSender<Self>) -> Self  {
+    fn init((stop, repo, progress_monitor): Self::Init, _sender: ComponentSender<Self>) -> Self  {
         PhotoRecognizeFaces {
             stop,
-            cache_dir,
             repo,
             progress_monitor,
         }
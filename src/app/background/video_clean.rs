@@ -7,14 +7,28 @@ use relm4::Worker;
 use rayon::prelude::*;
 use anyhow::Result;
 
+use fotema_core::library::CleanPolicy;
+
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 #[derive(Debug)]
 pub enum VideoCleanInput {
     Start,
+
+    /// Clean only videos at or below these paths, such as those reported by the library watcher.
+    CleanPaths(Vec<PathBuf>),
+
+    /// Clean videos at these paths that the user has removed, such as extra copies of
+    /// duplicates moved to the trash. Doesn't ask for confirmation.
+    CleanPathsConfirmed(Vec<PathBuf>),
+
+    /// Clean all videos, even if a large share of the library is missing.
+    /// Sent after the user has confirmed.
+    StartConfirmed,
 }
 
 #[derive(Debug)]
@@ -25,6 +39,9 @@ pub enum VideoCleanOutput {
     // Thumbnail generation has completed
     Completed(usize),
 
+    /// Too many videos are missing to clean without confirmation.
+    /// Count of missing videos and count of videos in library.
+    ConfirmationRequired(usize, usize),
 }
 
 pub struct VideoClean {
@@ -33,20 +50,54 @@ pub struct VideoClean {
 
     // Danger! Don't hold the repo mutex for too long as it blocks viewing images.
     repo: fotema_core::video::Repository,
+
+    policy: CleanPolicy,
 }
 
 impl VideoClean {
 
-    fn cleanup(&mut self, sender: &ComponentSender<Self>) -> Result<()> {
+    fn cleanup(&mut self, removed: Option<Vec<PathBuf>>, is_confirmed: bool, sender: &ComponentSender<Self>) -> Result<()> {
 
         let start = std::time::Instant::now();
 
         // Scrub vids from database if they no longer exist on the file system.
         let vids: Vec<fotema_core::video::model::Video> = self.repo.all()?;
 
-        info!("Found {} videos as candidates for cleaning", vids.len());
+        // Files in an unmounted library root are missing, but not deleted.
+        let is_available = self.repo.roots().available_filter();
+        let vids: Vec<_> = vids.into_iter().filter(|x| is_available(&x.path)).collect();
+
+        let library_count = vids.len();
+
+        let candidates: Vec<&fotema_core::video::model::Video> = match removed {
+            Some(removed) => vids.iter()
+                .filter(|x| removed.iter().any(|r| x.path.starts_with(r)))
+                .collect(),
+            None => vids.iter().collect(),
+        };
+
+        info!("Found {} videos as candidates for cleaning", candidates.len());
+
+        let missing: Vec<_> = candidates.into_par_iter().filter(|v| !v.path.exists()).collect();
+
+        if !is_confirmed && self.policy.is_confirmation_required_per_root(
+            self.repo.roots(),
+            vids.iter().map(|x| x.path.as_path()),
+            missing.iter().map(|x| x.path.as_path()),
+        ) {
+            warn!("{} of {} videos are missing. Not cleaning without confirmation.", missing.len(), library_count);
+            let _ = sender.output(VideoCleanOutput::ConfirmationRequired(missing.len(), library_count));
+            let _ = sender.output(VideoCleanOutput::Completed(0));
+            return Ok(());
+        }
+
+        // Videos soft deleted by an earlier clean and not restored by a scan since.
+        let expired: Vec<_> = self.repo.find_deleted_before(self.policy.purge_before())?
+            .into_iter()
+            .filter(|x| is_available(&x.path) && !x.path.exists())
+            .collect();
 
-        let count = vids.par_iter().filter(|v| !v.path.exists()).count();
+        let count = missing.len() + expired.len();
 
         // Short-circuit before sending progress messages to stop
         // banner from appearing and disappearing.
@@ -59,30 +110,40 @@ impl VideoClean {
             error!("Failed sending cleanup started: {:?}", e);
         }
 
-        vids.par_iter()
+        // Hide missing videos, but keep their metadata and faces in case the files come back.
+        missing.iter()
+            .take_while(|_| !self.stop.load(Ordering::Relaxed))
+            .for_each(|vid| {
+                if let Err(e) = self.repo.mark_deleted(vid.video_id) {
+                    error!("Failed marking {} as deleted: {:?}", vid.video_id, e);
+                } else {
+                    info!("Marked {} as deleted", vid.video_id);
+                }
+            });
+
+        expired.par_iter()
             .take_any_while(|_| !self.stop.load(Ordering::Relaxed))
             .for_each(|vid| {
-                if !vid.path.exists() {
-                    let mut repo = self.repo.clone();
-                    if let Ok(paths) = repo.find_files_to_cleanup(vid.video_id) {
-                        for path in paths {
-                            debug!("Deleting {:?}", path);
-                            if let Err(e) = std::fs::remove_file(&path) {
-                                error!("Failed deleting {:?} with {}", path, e);
-                            }
+                let mut repo = self.repo.clone();
+                if let Ok(paths) = repo.find_files_to_cleanup(vid.video_id) {
+                    for path in paths {
+                        debug!("Deleting {:?}", path);
+                        if let Err(e) = std::fs::remove_file(&path) {
+                            error!("Failed deleting {:?} with {}", path, e);
                         }
                     }
+                }
 
-                    let result = repo.remove(vid.video_id);
-                    if let Err(e) = result {
-                        error!("Failed remove {}: {:?}", vid.video_id, e);
-                    } else {
-                        info!("Removed {}", vid.video_id);
-                    }
+                let result = repo.remove(vid.video_id);
+                if let Err(e) = result {
+                    error!("Failed remove {}: {:?}", vid.video_id, e);
+                } else {
+                    info!("Removed {}", vid.video_id);
                 }
             });
 
-        info!("Cleaned {} videos in {} seconds.", count, start.elapsed().as_secs());
+        info!("Marked {} videos as deleted and purged {} videos in {} seconds.",
+            missing.len(), expired.len(), start.elapsed().as_secs());
 
         if let Err(e) = sender.output(VideoCleanOutput::Completed(count)) {
             error!("Failed sending VideoCleanOutput::Completed: {:?}", e);
@@ -98,7 +159,7 @@ impl Worker for VideoClean {
     type Output = VideoCleanOutput;
 
     fn init((stop, repo): Self::Init, _sender: ComponentSender<Self>) -> Self {
-        Self { stop, repo }
+        Self { stop, repo, policy: CleanPolicy::default() }
     }
 
     fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
@@ -106,7 +167,28 @@ impl Worker for VideoClean {
             VideoCleanInput::Start => {
                 info!("Cleaning videos...");
 
-                if let Err(e) = self.cleanup(&sender) {
+                if let Err(e) = self.cleanup(None, false, &sender) {
+                    error!("Failed to clean videos: {}", e);
+                }
+            }
+            VideoCleanInput::CleanPaths(paths) => {
+                info!("Cleaning videos below {} paths...", paths.len());
+
+                if let Err(e) = self.cleanup(Some(paths), false, &sender) {
+                    error!("Failed to clean videos: {}", e);
+                }
+            }
+            VideoCleanInput::CleanPathsConfirmed(paths) => {
+                info!("Cleaning {} removed videos...", paths.len());
+
+                if let Err(e) = self.cleanup(Some(paths), true, &sender) {
+                    error!("Failed to clean videos: {}", e);
+                }
+            }
+            VideoCleanInput::StartConfirmed => {
+                info!("Cleaning videos after confirmation...");
+
+                if let Err(e) = self.cleanup(None, true, &sender) {
                     error!("Failed to clean videos: {}", e);
                 }
             }
@@ -0,0 +1,59 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use relm4::prelude::*;
+use relm4::Worker;
+
+use fotema_core::photo::gps::GPSLocation;
+use fotema_core::VideoId;
+
+use tracing::{error, info};
+
+#[derive(Debug)]
+pub enum VideoEditInput {
+    /// Set the location of videos.
+    SetLocation(Vec<VideoId>, GPSLocation),
+}
+
+#[derive(Debug)]
+pub enum VideoEditOutput {
+    // Metadata editing has started.
+    Started,
+
+    // Metadata editing has completed.
+    Completed(usize),
+}
+
+/// Videos are never written to, so changes are only saved to the repository.
+pub struct VideoEdit {
+    repo: fotema_core::video::Repository,
+}
+
+impl Worker for VideoEdit {
+    type Init = fotema_core::video::Repository;
+    type Input = VideoEditInput;
+    type Output = VideoEditOutput;
+
+    fn init(repo: Self::Init, _sender: ComponentSender<Self>) -> Self {
+        Self { repo }
+    }
+
+    fn update(&mut self, msg: VideoEditInput, sender: ComponentSender<Self>) {
+        let _ = sender.output(VideoEditOutput::Started);
+
+        let result = match msg {
+            VideoEditInput::SetLocation(video_ids, location) => {
+                info!("Setting location of {} videos", video_ids.len());
+                self.repo.set_location(&video_ids, &location)
+                    .map(|_| video_ids.len())
+            }
+        };
+
+        let count = result
+            .inspect_err(|e| error!("Failed editing metadata: {:?}", e))
+            .unwrap_or(0);
+
+        let _ = sender.output(VideoEditOutput::Completed(count));
+    }
+}
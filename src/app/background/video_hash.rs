@@ -0,0 +1,136 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use relm4::prelude::*;
+use relm4::Worker;
+use relm4::Reducer;
+use rayon::prelude::*;
+use anyhow::*;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::result::Result::Ok;
+use tracing::{error, info};
+
+use fotema_core::duplicate;
+
+use crate::app::components::progress_monitor::{
+    ProgressMonitor,
+    ProgressMonitorInput,
+    TaskName,
+    MediaType
+};
+
+#[derive(Debug)]
+pub enum VideoHashInput {
+    Start,
+}
+
+#[derive(Debug)]
+pub enum VideoHashOutput {
+    // Content hashing has started.
+    Started,
+
+    // Content hashing has completed
+    Completed(usize),
+}
+
+pub struct VideoHash {
+    // Stop flag
+    stop: Arc<AtomicBool>,
+
+    // Danger! Don't hold the repo mutex for too long as it blocks viewing images.
+    repo: fotema_core::video::Repository,
+
+    duplicate_repo: duplicate::Repository,
+
+    progress_monitor: Arc<Reducer<ProgressMonitor>>,
+}
+
+impl VideoHash {
+
+    fn hash(
+        stop: Arc<AtomicBool>,
+        repo: fotema_core::video::Repository,
+        duplicate_repo: duplicate::Repository,
+        progress_monitor: Arc<Reducer<ProgressMonitor>>,
+        sender: ComponentSender<Self>) -> Result<()>
+    {
+        let start = std::time::Instant::now();
+
+        let unprocessed: Vec<fotema_core::video::model::Video> = repo
+            .find_need_content_hash()?
+            .into_iter()
+            .filter(|vid| vid.path.exists())
+            .collect();
+
+        let count = unprocessed.len();
+        info!("Found {} videos to hash", count);
+
+        // Short-circuit before sending progress messages to stop
+        // banner from appearing and disappearing.
+        if count == 0 {
+            let _ = sender.output(VideoHashOutput::Completed(count));
+            return Ok(());
+        }
+
+        let _ = sender.output(VideoHashOutput::Started);
+
+        progress_monitor.emit(ProgressMonitorInput::Start(TaskName::Hash(MediaType::Video), count));
+
+        unprocessed
+            .par_iter()
+            .take_any_while(|_| !stop.load(Ordering::Relaxed))
+            .for_each(|vid| {
+                let result = duplicate::hash::hash(&vid.path)
+                    .and_then(|hash| duplicate_repo.clone().add_video_hash(vid.video_id, &hash));
+
+                if let Err(e) = result {
+                    error!("Failed to hash video: {:?}: Video path: {:?}", e, vid.path);
+                }
+
+                progress_monitor.emit(ProgressMonitorInput::Advance);
+            });
+
+        info!("Hashed {} videos in {} seconds.", count, start.elapsed().as_secs());
+
+        progress_monitor.emit(ProgressMonitorInput::Complete);
+
+        let _ = sender.output(VideoHashOutput::Completed(count));
+
+        Ok(())
+    }
+}
+
+impl Worker for VideoHash {
+    type Init = (Arc<AtomicBool>, fotema_core::video::Repository, duplicate::Repository, Arc<Reducer<ProgressMonitor>>);
+    type Input = VideoHashInput;
+    type Output = VideoHashOutput;
+
+    fn init((stop, repo, duplicate_repo, progress_monitor): Self::Init, _sender: ComponentSender<Self>) -> Self  {
+        VideoHash {
+            stop,
+            repo,
+            duplicate_repo,
+            progress_monitor,
+        }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            VideoHashInput::Start => {
+                info!("Hashing videos...");
+                let stop = self.stop.clone();
+                let repo = self.repo.clone();
+                let duplicate_repo = self.duplicate_repo.clone();
+                let progress_monitor = self.progress_monitor.clone();
+
+                rayon::spawn(move || {
+                    if let Err(e) = VideoHash::hash(stop, repo, duplicate_repo, progress_monitor, sender) {
+                        error!("Failed to hash videos: {}", e);
+                    }
+                });
+            }
+        };
+    }
+}
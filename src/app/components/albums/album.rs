@@ -4,6 +4,8 @@
 
 use gtk::prelude::OrientableExt;
 use fotema_core::VisualId;
+use fotema_core::user_album::{AlbumId, AlbumItem};
+use fotema_core::event::EventId;
 use fotema_core::YearMonth;
 use fotema_core::visual::model::PictureOrientation;
 use strum::IntoEnumIterator;
@@ -12,6 +14,7 @@ use relm4::gtk::gdk;
 use relm4::gtk::prelude::*;
 use relm4::gtk::prelude::AdjustmentExt;
 use relm4::gtk::gdk_pixbuf;
+use relm4::gtk::glib;
 use relm4::typed_view::grid::{RelmGridItem, TypedGridView};
 use relm4::*;
 use relm4::binding::*;
@@ -22,6 +25,7 @@ use crate::app::adaptive;
 use crate::app::SharedState;
 use crate::app::ActiveView;
 use crate::app::ViewName;
+use crate::fl;
 use super::album_filter::AlbumFilter;
 use super::album_sort::AlbumSort;
 
@@ -61,7 +65,22 @@ pub enum AlbumInput {
     ScrollOffset(f64),
 
     // Scroll to top of photo grid, regardless of sort order
-    ScrollToTop
+    ScrollToTop,
+
+    /// Start or stop selecting photos and videos.
+    SelectionMode(bool),
+
+    /// Add selected photos and videos to a user album.
+    AddSelectedToUserAlbum,
+
+    /// Remove selected photos and videos from the user album being shown.
+    RemoveSelectedFromUserAlbum,
+
+    /// Move selected photos and videos out of the event being shown into a new event.
+    SplitSelectedFromEvent,
+
+    /// Set the location of selected photos and videos by picking a point on the map.
+    SetLocationOfSelected,
 }
 
 #[derive(Debug)]
@@ -71,6 +90,18 @@ pub enum AlbumOutput {
 
     // Scroll offset, in pixels.
     ScrollOffset(f64),
+
+    /// User wants to add photos and videos to a user album.
+    AddToUserAlbum(Vec<AlbumItem>),
+
+    /// User wants to remove photos and videos from a user album.
+    RemoveFromUserAlbum(AlbumId, Vec<AlbumItem>),
+
+    /// User wants to move photos and videos out of an event into a new event.
+    SplitEvent(EventId, Vec<AlbumItem>),
+
+    /// User wants to set the location of photos and videos by picking a point on the map.
+    SetLocation(Vec<AlbumItem>),
 }
 
 #[derive(Debug)]
@@ -79,14 +110,26 @@ struct PhotoGridItem {
 
     // Length of thumbnail edge to allow for resizing when layout changes.
     edge_length: I32Binding,
+
+    // Show count of photos in stack. Only when other photos in the stack are hidden.
+    show_stack: bool,
+
+    // Has the item been selected in selection mode?
+    is_selected: BoolBinding,
 }
 
 struct PhotoGridItemWidgets {
     picture: gtk::Picture,
+    stack_overlay: gtk::Frame,
+    stack_label: gtk::Label,
     status_overlay: gtk::Frame,
     motion_type_icon: gtk::Image,
     duration_overlay: gtk::Frame,
     duration_label: gtk::Label,
+    selected_overlay: gtk::Frame,
+
+    // Binding of selected_overlay visibility to is_selected of the bound item.
+    selected_binding: Option<glib::Binding>,
 
     // If the gtk::Picture has been bound to edge_length.
     is_bound: bool,
@@ -101,6 +144,37 @@ impl RelmGridItem for PhotoGridItem {
             root = gtk::AspectFrame {
                 gtk::Frame {
                     gtk::Overlay {
+                        #[name(selected_overlay)]
+                        add_overlay =  &gtk::Frame {
+                            set_halign: gtk::Align::Start,
+                            set_valign: gtk::Align::Start,
+                            set_margin_all: 8,
+                            set_visible: false,
+                            add_css_class: "photo-grid-photo-status-frame",
+
+                            #[wrap(Some)]
+                            set_child = &gtk::Image {
+                                set_icon_name: Some("object-select-symbolic"),
+                                set_width_request: 16,
+                                set_height_request: 16,
+                                add_css_class: "photo-grid-photo-status-label",
+                            },
+                        },
+
+                        #[name(stack_overlay)]
+                        add_overlay =  &gtk::Frame {
+                            set_halign: gtk::Align::End,
+                            set_valign: gtk::Align::Start,
+                            set_margin_all: 8,
+                            add_css_class: "photo-grid-photo-status-frame",
+
+                            #[wrap(Some)]
+                            #[name(stack_label)]
+                            set_child = &gtk::Label{
+                                add_css_class: "photo-grid-photo-status-label",
+                            },
+                        },
+
                         #[name(status_overlay)]
                         add_overlay =  &gtk::Frame {
                             set_halign: gtk::Align::End,
@@ -145,10 +219,14 @@ impl RelmGridItem for PhotoGridItem {
 
         let widgets = PhotoGridItemWidgets {
             picture,
+            stack_overlay,
+            stack_label,
             status_overlay,
             motion_type_icon,
             duration_overlay,
             duration_label,
+            selected_overlay,
+            selected_binding: None,
             is_bound: false,
         };
 
@@ -181,6 +259,21 @@ impl RelmGridItem for PhotoGridItem {
             widgets.picture.set_paintable(Some(&img));
         }
 
+        // Unlike edge_length, each item has its own selection state, so the binding
+        // is removed when the widgets are unbound from the item.
+        widgets.selected_binding = Some(self.is_selected
+            .bind_property("value", &widgets.selected_overlay, "visible")
+            .sync_create()
+            .build());
+
+        if self.show_stack && self.visual.stack_count > 1 {
+            widgets.stack_overlay.set_visible(true);
+            widgets.stack_label.set_label(&self.visual.stack_count.to_string());
+        } else {
+            widgets.stack_overlay.set_visible(false);
+            widgets.stack_label.set_label("");
+        }
+
         if self.visual.is_motion_photo() {
             widgets.status_overlay.set_visible(true);
             widgets.duration_overlay.set_visible(false);
@@ -209,11 +302,17 @@ impl RelmGridItem for PhotoGridItem {
     }
 
     fn unbind(&mut self, widgets: &mut Self::Widgets, _root: &mut Self::Root) {
+        if let Some(binding) = widgets.selected_binding.take() {
+            binding.unbind();
+        }
+        widgets.selected_overlay.set_visible(false);
         widgets.picture.set_filename(None::<&Path>);
         widgets.motion_type_icon.set_icon_name(None);
         widgets.status_overlay.set_visible(false);
         widgets.duration_overlay.set_visible(false);
         widgets.duration_label.set_label("");
+        widgets.stack_overlay.set_visible(false);
+        widgets.stack_label.set_label("");
 
         // clear orientation transformation css classes
         for orient in PictureOrientation::iter() {
@@ -230,6 +329,12 @@ pub struct Album {
     filter: AlbumFilter,
     sort: AlbumSort,
     edge_length: I32Binding,
+
+    /// Is the user selecting items, rather than viewing them?
+    is_selecting: bool,
+
+    /// Items selected in selection mode.
+    selected: Vec<Arc<fotema_core::visual::Visual>>,
 }
 
 #[relm4::component(pub)]
@@ -239,27 +344,82 @@ impl SimpleComponent for Album {
     type Output = AlbumOutput;
 
     view! {
-        gtk::ScrolledWindow {
-            set_vexpand: true,
+        gtk::Box {
+            set_orientation: gtk::Orientation::Vertical,
+
+            gtk::ScrolledWindow {
+                set_vexpand: true,
 
-            #[local_ref]
-            grid_view -> gtk::GridView {
-                set_orientation: gtk::Orientation::Vertical,
-                set_single_click_activate: true,
+                #[local_ref]
+                grid_view -> gtk::GridView {
+                    set_orientation: gtk::Orientation::Vertical,
+                    set_single_click_activate: true,
 
-                connect_activate[sender] => move |_, idx| {
-                    sender.input(AlbumInput::Selected(idx))
+                    connect_activate[sender] => move |_, idx| {
+                        sender.input(AlbumInput::Selected(idx))
+                    },
+                },
+
+                #[wrap(Some)]
+                set_vadjustment = &gtk::Adjustment {
+                    // Emit scroll events so PersonAlbum can determine when to hide avatar.
+                    // FIXME maybe just emit one event at a boundary, instead of emitting an
+                    // event for every scroll?
+                    connect_value_changed[sender] => move |v| sender.input(AlbumInput::ScrollOffset(v.value())),
                 },
-            },
 
-            #[wrap(Some)]
-            set_vadjustment = &gtk::Adjustment {
-                // Emit scroll events so PersonAlbum can determine when to hide avatar.
-                // FIXME maybe just emit one event at a boundary, instead of emitting an
-                // event for every scroll?
-                connect_value_changed[sender] => move |v| sender.input(AlbumInput::ScrollOffset(v.value())),
             },
 
+            // Actions for items selected in selection mode.
+            gtk::ActionBar {
+                #[watch]
+                set_revealed: model.is_selecting,
+
+                pack_start = &gtk::Button {
+                    set_label: &fl!("album-selection", "cancel-button"),
+                    connect_clicked => AlbumInput::SelectionMode(false),
+                },
+
+                #[wrap(Some)]
+                set_center_widget = &gtk::Label {
+                    #[watch]
+                    set_label: &fl!("album-selection", "count", count = model.selected.len()),
+                },
+
+                pack_end = &gtk::Button {
+                    set_label: &fl!("album-selection", "add-button"),
+                    add_css_class: "suggested-action",
+                    #[watch]
+                    set_sensitive: !model.selected.is_empty(),
+                    connect_clicked => AlbumInput::AddSelectedToUserAlbum,
+                },
+
+                pack_end = &gtk::Button {
+                    set_label: &fl!("album-selection", "remove-button"),
+                    add_css_class: "destructive-action",
+                    #[watch]
+                    set_visible: matches!(model.filter, AlbumFilter::UserAlbum(_)),
+                    #[watch]
+                    set_sensitive: !model.selected.is_empty(),
+                    connect_clicked => AlbumInput::RemoveSelectedFromUserAlbum,
+                },
+
+                pack_end = &gtk::Button {
+                    set_label: &fl!("album-selection", "split-button"),
+                    #[watch]
+                    set_visible: matches!(model.filter, AlbumFilter::Event(_)),
+                    #[watch]
+                    set_sensitive: !model.selected.is_empty(),
+                    connect_clicked => AlbumInput::SplitSelectedFromEvent,
+                },
+
+                pack_end = &gtk::Button {
+                    set_label: &fl!("album-selection", "location-button"),
+                    #[watch]
+                    set_sensitive: !model.selected.is_empty(),
+                    connect_clicked => AlbumInput::SetLocationOfSelected,
+                },
+            },
         }
     }
 
@@ -279,6 +439,8 @@ impl SimpleComponent for Album {
             filter,
             sort: AlbumSort::default(),
             edge_length: I32Binding::new(NARROW_EDGE_LENGTH),
+            is_selecting: false,
+            selected: Vec::new(),
         };
 
         model.update_filter();
@@ -308,6 +470,11 @@ impl SimpleComponent for Album {
                 self.filter = filter;
                 self.update_filter();
                 //self.scroll();
+
+                // User albums have their own sort order, so items must be reordered.
+                if matches!(self.filter, AlbumFilter::UserAlbum(_)) {
+                    self.refresh();
+                }
             }
             AlbumInput::Sort(sort) => {
                 if self.sort != sort {
@@ -319,10 +486,31 @@ impl SimpleComponent for Album {
             AlbumInput::Selected(index) => {
                 // Albums are filters so must use get_visible(...) over get(...), otherwise
                 // wrong photo is displayed.
-                if let Some(item) = self.photo_grid.get_visible(index) {
-                    let visual_id = item.borrow().visual.visual_id.clone();
-                    debug!("index {} has visual_id {}", index, visual_id);
-                    let _ = sender.output(AlbumOutput::Selected(visual_id, self.filter.clone()));
+                if self.is_selecting {
+                    if let Some(item) = self.photo_grid.get_visible(index) {
+                        let item = item.borrow();
+                        let is_selected = !item.is_selected.value();
+                        item.is_selected.set_value(is_selected);
+                        if is_selected {
+                            self.selected.push(item.visual.clone());
+                        } else {
+                            self.selected.retain(|x| x.visual_id != item.visual.visual_id);
+                        }
+                    }
+                } else if let Some(item) = self.photo_grid.get_visible(index) {
+                    let visual = item.borrow().visual.clone();
+                    debug!("index {} has visual_id {}", index, visual.visual_id);
+
+                    // The other photos of a stack are hidden when showing all photos,
+                    // so the viewer steps through the stack instead.
+                    let filter = match visual.stack_id {
+                        Some(ref stack_id) if self.filter == AlbumFilter::All && visual.stack_count > 1 => {
+                            AlbumFilter::Stack(stack_id.clone())
+                        },
+                        _ => self.filter.clone(),
+                    };
+
+                    let _ = sender.output(AlbumOutput::Selected(visual.visual_id.clone(), filter));
                 }
             }
             AlbumInput::GoToMonth(ym) => {
@@ -354,6 +542,34 @@ impl SimpleComponent for Album {
             AlbumInput::ScrollOffset(offset) => {
                 let _ = sender.output(AlbumOutput::ScrollOffset(offset));
             },
+            AlbumInput::SelectionMode(is_selecting) => {
+                self.is_selecting = is_selecting;
+                self.clear_selection();
+            },
+            AlbumInput::AddSelectedToUserAlbum => {
+                let items = self.selected.iter().map(|x| AlbumItem::of(x)).collect();
+                let _ = sender.output(AlbumOutput::AddToUserAlbum(items));
+                sender.input(AlbumInput::SelectionMode(false));
+            },
+            AlbumInput::RemoveSelectedFromUserAlbum => {
+                if let AlbumFilter::UserAlbum(ref album) = self.filter {
+                    let items = self.selected.iter().map(|x| AlbumItem::of(x)).collect();
+                    let _ = sender.output(AlbumOutput::RemoveFromUserAlbum(album.album_id, items));
+                }
+                sender.input(AlbumInput::SelectionMode(false));
+            },
+            AlbumInput::SplitSelectedFromEvent => {
+                if let AlbumFilter::Event(ref event) = self.filter {
+                    let items = self.selected.iter().map(|x| AlbumItem::of(x)).collect();
+                    let _ = sender.output(AlbumOutput::SplitEvent(event.event_id, items));
+                }
+                sender.input(AlbumInput::SelectionMode(false));
+            },
+            AlbumInput::SetLocationOfSelected => {
+                let items = self.selected.iter().map(|x| AlbumItem::of(x)).collect();
+                let _ = sender.output(AlbumOutput::SetLocation(items));
+                sender.input(AlbumInput::SelectionMode(false));
+            },
         }
     }
 }
@@ -361,6 +577,9 @@ impl SimpleComponent for Album {
 impl Album {
 
     fn refresh(&mut self) {
+        // Stacked photos are only hidden when showing all photos.
+        let show_stack = self.filter == AlbumFilter::All;
+
         let mut all = {
             let data = self.state.read();
             data
@@ -368,12 +587,19 @@ impl Album {
                 .map(|visual| PhotoGridItem {
                     visual: visual.clone(),
                     edge_length: self.edge_length.clone(),
+                    show_stack,
+                    is_selected: BoolBinding::new(self.selected.iter().any(|x| x.visual_id == visual.visual_id)),
                 })
                 .collect::<Vec<PhotoGridItem>>()
         };
 
-        // State is always in ascending time order
-        self.sort.sort(&mut all);
+        if let AlbumFilter::UserAlbum(ref album) = self.filter {
+            // User albums are in the order chosen by the user, not in time order.
+            all.sort_by_cached_key(|item| album.position(&item.visual).unwrap_or(usize::MAX));
+        } else {
+            // State is always in ascending time order
+            self.sort.sort(&mut all);
+        }
 
         self.photo_grid.clear();
 
@@ -384,7 +610,19 @@ impl Album {
 
         // NOTE person album will in effect overide scrolling to the end
         // by sending a ScrollToTop command.
-        self.sort.scroll_to_end(&mut self.photo_grid);
+        // User albums start at the first item chosen by the user.
+        if !matches!(self.filter, AlbumFilter::UserAlbum(_)) {
+            self.sort.scroll_to_end(&mut self.photo_grid);
+        }
+    }
+
+    fn clear_selection(&mut self) {
+        self.selected.clear();
+        for index in 0..self.photo_grid.len() {
+            if let Some(item) = self.photo_grid.get(index) {
+                item.borrow().is_selected.set_value(false);
+            }
+        }
     }
 
     fn update_filter(&mut self) {
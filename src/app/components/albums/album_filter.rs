@@ -8,6 +8,10 @@ use fotema_core::Visual;
 use h3o::CellIndex;
 use fotema_core::VisualId;
 use fotema_core::PictureId;
+use fotema_core::user_album::UserAlbum;
+use fotema_core::event::Event;
+use fotema_core::smart_album::{MediaType, Query};
+use fotema_core::search::SearchResults;
 
 // An album is a view applied over the whole collection of messages.
 // An AlbumFilter defines the filter to apply to produce an album.
@@ -19,9 +23,12 @@ pub enum AlbumFilter {
     // Show a single photo
     One(VisualId),
 
-    // Show all photos
+    // Show all photos, with one photo for each stack of similar photos
     All,
 
+    /// Show all photos in a stack of similar photos, including those hidden by `All`.
+    Stack(VisualId),
+
     // Show only selfies
     Selfies,
 
@@ -31,6 +38,12 @@ pub enum AlbumFilter {
     // Show only motion photos (live photos)
     Motion,
 
+    /// Show only photos and videos marked as a favourite
+    Favourites,
+
+    /// Show only photos and videos rated with at least this many stars
+    MinRating(i32),
+
     // Show photos only for folder
     Folder(PathBuf),
 
@@ -39,28 +52,46 @@ pub enum AlbumFilter {
 
     /// Show photos who's picture_id is in a set. Used for person filtering.
     /// FIXME should probably be a Set of some kind... but that mucks up PartialEq and Eq.
-    Any(Vec<PictureId>)
+    Any(Vec<PictureId>),
+
+    /// Show photos and videos that are copies of other photos and videos.
+    Duplicates(Vec<VisualId>),
+
+    /// Show photos and videos the user has added to an album.
+    UserAlbum(UserAlbum),
+
+    /// Show photos and videos in an event.
+    Event(Event),
+
+    /// Show photos and videos matching a smart album query.
+    Smart(Query),
+
+    /// Show photos and videos found by a search.
+    Search(SearchResults),
 }
 
 impl AlbumFilter {
     pub fn filter(self, v: &Visual) -> bool {
+        // Criteria that smart albums can also use are evaluated by the smart album query,
+        // so that albums and smart albums always agree.
         match self {
             AlbumFilter::None => false,
             AlbumFilter::One(visual_id) => v.visual_id == visual_id,
-            AlbumFilter::All => true,
-            AlbumFilter::Folder(path) => v.parent_path == path,
-            AlbumFilter::Motion => v.is_motion_photo(),
-            AlbumFilter::Selfies => v.is_selfie(),
-            AlbumFilter::Videos => v.is_video_only() && !v.is_motion_photo(),
-            AlbumFilter::GeographicArea(cell_index) => {
-                if let Some(location) = v.location {
-                    let cell = location.to_cell(cell_index.resolution());
-                    cell == cell_index
-                } else {
-                    false
-                }
-            },
+            AlbumFilter::All => !v.is_hidden_in_stack(),
+            AlbumFilter::Stack(stack_id) => v.stack_id.as_ref() == Some(&stack_id),
+            AlbumFilter::Folder(path) => Query::Folder(path).matches(v),
+            AlbumFilter::Motion => Query::Media(MediaType::Motion).matches(v),
+            AlbumFilter::Selfies => Query::Selfie.matches(v),
+            AlbumFilter::Videos => Query::Media(MediaType::Video).matches(v),
+            AlbumFilter::Favourites => Query::Favourite.matches(v),
+            AlbumFilter::MinRating(stars) => Query::MinRating(stars).matches(v),
+            AlbumFilter::GeographicArea(cell_index) => Query::Place(cell_index).matches(v),
             AlbumFilter::Any(picture_ids) => v.picture_id.is_some_and(|id| picture_ids.contains(&id)),
+            AlbumFilter::Duplicates(visual_ids) => visual_ids.contains(&v.visual_id),
+            AlbumFilter::UserAlbum(album) => album.contains(v),
+            AlbumFilter::Event(event) => event.contains(v),
+            AlbumFilter::Smart(query) => query.matches(v),
+            AlbumFilter::Search(results) => results.matches(v),
         }
     }
 }
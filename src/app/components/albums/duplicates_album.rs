@@ -0,0 +1,296 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use gtk::prelude::OrientableExt;
+use fotema_core::VisualId;
+use fotema_core::duplicate;
+use relm4::gtk;
+use relm4::gtk::prelude::*;
+use relm4::*;
+use relm4::adw;
+use relm4::adw::prelude::*;
+
+use crate::app::adaptive;
+use crate::app::background::duplicates_trash::{
+    DuplicatesTrash,
+    DuplicatesTrashInput,
+    DuplicatesTrashOutput,
+};
+use crate::app::SharedState;
+use crate::app::ActiveView;
+use crate::app::ViewName;
+use crate::app::components::albums:: {
+    album::{Album, AlbumInput, AlbumOutput},
+    album_filter::AlbumFilter,
+    album_sort::AlbumSort,
+};
+
+use crate::fl;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use tracing::{error, info};
+
+#[derive(Debug)]
+pub enum DuplicatesAlbumInput {
+
+    /// Album is visible
+    Activate,
+
+    // State has been updated
+    Refresh,
+
+    /// Adapt to layout
+    Adapt(adaptive::Layout),
+
+    /// Underlying album has scrolled
+    ScrollOffset(f64),
+
+    /// Picture selected in underlying album
+    Selected(VisualId),
+
+    Sort(AlbumSort),
+
+    /// Start flow to keep one copy of each duplicate.
+    KeepOneDialog,
+
+    /// Actually move extra copies to the trash.
+    KeepOne,
+
+    /// Extra copies have been moved to the trash in the background.
+    Resolved(Vec<PathBuf>),
+
+    /// Ignore an event.
+    Ignore,
+}
+
+#[derive(Debug)]
+pub enum DuplicatesAlbumOutput {
+    /// User has selected photo or video in grid view
+    Selected(VisualId, AlbumFilter),
+
+    /// Extra copies have been moved to the trash.
+    Resolved(Vec<PathBuf>),
+}
+
+pub struct DuplicatesAlbum {
+    state: SharedState,
+    repo: duplicate::Repository,
+    groups: Vec<duplicate::DuplicateGroup>,
+    album: Controller<Album>,
+    trash: WorkerController<DuplicatesTrash>,
+    active_view: ActiveView,
+    status: adw::StatusPage,
+    content: gtk::Box,
+    summary: gtk::Label,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for DuplicatesAlbum {
+    type Init = (SharedState, duplicate::Repository, ActiveView);
+    type Input = DuplicatesAlbumInput;
+    type Output = DuplicatesAlbumOutput;
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Vertical,
+
+            #[local_ref]
+            content -> gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                set_vexpand: true,
+
+                model.album.widget(),
+
+                gtk::ActionBar {
+                    #[local_ref]
+                    pack_start = &summary -> gtk::Label,
+
+                    pack_end = &gtk::Button {
+                        set_label: &fl!("duplicates-album-keep-one-button"),
+                        add_css_class: "destructive-action",
+                        connect_clicked => DuplicatesAlbumInput::KeepOneDialog,
+                    },
+                },
+            },
+
+            #[local_ref]
+            status -> adw::StatusPage {
+                set_valign: gtk::Align::Start,
+                set_vexpand: true,
+
+                set_visible: false,
+                set_icon_name: Some("edit-copy-symbolic"),
+                set_title: &fl!("duplicates-album-status-none", "title"),
+                set_description: Some(&fl!("duplicates-album-status-none", "description")),
+            },
+        }
+    }
+
+    fn init(
+        (state, repo, active_view): Self::Init,
+        _root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let album = Album::builder()
+            .launch((state.clone(), active_view.clone(), ViewName::Duplicates, AlbumFilter::None))
+            .forward(sender.input_sender(), |msg| match msg {
+                AlbumOutput::Selected(id, _) => DuplicatesAlbumInput::Selected(id),
+                AlbumOutput::ScrollOffset(offset) => DuplicatesAlbumInput::ScrollOffset(offset),
+                // Duplicates can't be selected.
+                AlbumOutput::AddToUserAlbum(_) => DuplicatesAlbumInput::Ignore,
+                AlbumOutput::SetLocation(_) => DuplicatesAlbumInput::Ignore,
+                AlbumOutput::RemoveFromUserAlbum(_, _) => DuplicatesAlbumInput::Ignore,
+                AlbumOutput::SplitEvent(_, _) => DuplicatesAlbumInput::Ignore,
+            });
+
+        // Comparing and trashing files is slow, so is done off the main thread.
+        let trash = DuplicatesTrash::builder()
+            .detach_worker(())
+            .forward(sender.input_sender(), |msg| match msg {
+                DuplicatesTrashOutput::Resolved(trashed) => DuplicatesAlbumInput::Resolved(trashed),
+            });
+
+        let status = adw::StatusPage::new();
+        let content = gtk::Box::new(gtk::Orientation::Vertical, 0);
+        let summary = gtk::Label::new(None);
+
+        let model = DuplicatesAlbum {
+            state,
+            repo,
+            groups: vec![],
+            album,
+            trash,
+            active_view,
+            status: status.clone(),
+            content: content.clone(),
+            summary: summary.clone(),
+        };
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            DuplicatesAlbumInput::Activate => {
+                *self.active_view.write() = ViewName::Duplicates;
+                self.refresh();
+                self.album.sender().emit(AlbumInput::Activate);
+            }
+            DuplicatesAlbumInput::Refresh => {
+                if *self.active_view.read() == ViewName::Duplicates {
+                    self.refresh();
+                }
+                self.album.sender().emit(AlbumInput::Refresh);
+            }
+            DuplicatesAlbumInput::Sort(sort) => {
+                self.album.sender().emit(AlbumInput::Sort(sort));
+            },
+            DuplicatesAlbumInput::Adapt(layout) => {
+                self.album.sender().emit(AlbumInput::Adapt(layout));
+            },
+            DuplicatesAlbumInput::Ignore => {},
+            DuplicatesAlbumInput::ScrollOffset(_) => {
+                // Nothing to hide or show when scrolling.
+            },
+            DuplicatesAlbumInput::Selected(visual_id) => {
+                let _ = sender.output(DuplicatesAlbumOutput::Selected(visual_id, self.filter()));
+            },
+            DuplicatesAlbumInput::KeepOneDialog => {
+                let copy_count = self.extras().len();
+                if copy_count == 0 {
+                    return;
+                }
+
+                let dialog = adw::AlertDialog::builder()
+                    .heading(fl!("duplicates-keep-one-dialog", "heading"))
+                    .body(fl!("duplicates-keep-one-dialog", "body", copy_count = copy_count))
+                    .build();
+
+                dialog.add_response("cancel", &fl!("duplicates-keep-one-dialog", "cancel-button"));
+                dialog.set_default_response(Some("cancel"));
+                dialog.set_close_response("cancel");
+
+                dialog.add_response("trash", &fl!("duplicates-keep-one-dialog", "trash-button"));
+                dialog.set_response_appearance("trash", adw::ResponseAppearance::Destructive);
+
+                dialog.connect_response(None, move |_, response| {
+                    if response == "trash" {
+                        sender.input(DuplicatesAlbumInput::KeepOne);
+                    }
+                });
+
+                if let Some(root) = gtk::Widget::root(self.content.widget_ref()) {
+                    dialog.present(Some(&root));
+                } else {
+                    error!("Couldn't get root widget!");
+                }
+            },
+            DuplicatesAlbumInput::KeepOne => {
+                self.trash.emit(DuplicatesTrashInput::Trash(self.copies()));
+            },
+            DuplicatesAlbumInput::Resolved(trashed) => {
+                let _ = sender.output(DuplicatesAlbumOutput::Resolved(trashed));
+            },
+        }
+    }
+}
+
+impl DuplicatesAlbum {
+    fn refresh(&mut self) {
+        self.groups = self.repo.find_duplicates().unwrap_or_default();
+
+        let copy_count = self.extras().len();
+        info!("Found {} duplicated items with {} extra copies", self.groups.len(), copy_count);
+
+        self.status.set_visible(self.groups.is_empty());
+        self.content.set_visible(!self.groups.is_empty());
+        self.summary.set_label(&fl!("duplicates-album-summary", copy_count = copy_count));
+
+        self.album.sender().emit(AlbumInput::Filter(self.filter()));
+    }
+
+    fn filter(&self) -> AlbumFilter {
+        let visual_ids = self.groups.iter()
+            .flat_map(|group| group.visual_ids.clone())
+            .collect();
+        AlbumFilter::Duplicates(visual_ids)
+    }
+
+    /// Copies that will be moved to the trash when keeping one copy of each duplicate.
+    fn extras(&self) -> Vec<VisualId> {
+        self.groups.iter()
+            .flat_map(|group| group.extras().to_vec())
+            .collect()
+    }
+
+    /// Path of the file to keep and path of each extra copy of it.
+    /// Only the file that was hashed is included, so the video of a live photo
+    /// is never trashed because its picture is a copy.
+    fn copies(&self) -> Vec<(PathBuf, PathBuf)> {
+        let data = self.state.read();
+        let hashed_paths: HashMap<VisualId, PathBuf> = data.iter()
+            .filter_map(|v| {
+                let path = v.picture_path.clone().or_else(|| v.video_path.clone())?;
+                Some((v.visual_id.clone(), path))
+            })
+            .collect();
+
+        self.groups.iter()
+            .filter_map(|group| {
+                let keep = hashed_paths.get(group.keep())?;
+                let extras = group.extras()
+                    .iter()
+                    .filter_map(|visual_id| hashed_paths.get(visual_id))
+                    .map(|path| (keep.clone(), path.clone()))
+                    .collect::<Vec<_>>();
+                Some(extras)
+            })
+            .flatten()
+            .collect()
+    }
+}
@@ -0,0 +1,415 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use gtk::prelude::OrientableExt;
+use fotema_core::VisualId;
+use relm4::gtk;
+use relm4::gtk::prelude::*;
+use relm4::*;
+use relm4::adw;
+use relm4::adw::prelude::*;
+use relm4::actions::{RelmAction, RelmActionGroup};
+
+use crate::app::adaptive;
+use crate::app::SharedState;
+use crate::app::ActiveView;
+use crate::app::ViewName;
+use crate::app::components::albums:: {
+    album::{Album, AlbumInput, AlbumOutput},
+    album_filter::AlbumFilter,
+    events_album,
+};
+
+use fotema_core::event::{self, Event, EventId};
+use fotema_core::user_album::AlbumItem;
+use crate::fl;
+
+use tracing::{error, info};
+
+relm4::new_action_group!(EventActionGroup, "event");
+
+// Rename an event
+relm4::new_stateless_action!(RenameAction, EventActionGroup, "rename");
+
+// Merge an event with the event before it
+relm4::new_stateless_action!(MergePreviousAction, EventActionGroup, "merge_previous");
+
+// Merge an event with the event after it
+relm4::new_stateless_action!(MergeNextAction, EventActionGroup, "merge_next");
+
+#[derive(Debug)]
+pub enum EventInput {
+
+    /// Event is visible
+    Activate,
+
+    /// Event has changed, or state has been updated
+    Refresh,
+
+    /// View an event
+    View(Event),
+
+    /// Adapt to layout
+    Adapt(adaptive::Layout),
+
+    /// Picture selected in underlying album
+    Selected(VisualId),
+
+    /// Start selecting photos and videos.
+    SelectionMode,
+
+    /// Add selected photos and videos to a user album.
+    AddToUserAlbum(Vec<AlbumItem>),
+
+    /// Set the location of selected photos and videos.
+    SetLocation(Vec<AlbumItem>),
+
+    /// Move selected photos and videos into a new event.
+    Split(EventId, Vec<AlbumItem>),
+
+    /// Merge this event with the event before it.
+    MergePrevious,
+
+    /// Merge this event with the event after it.
+    MergeNext,
+
+    /// Start rename event flow
+    RenameDialog,
+
+    /// Actually rename event
+    Rename(String),
+
+    /// Ignore an event.
+    Ignore,
+}
+
+#[derive(Debug)]
+pub enum EventOutput {
+    /// User has selected photo or video in grid view
+    Selected(VisualId, AlbumFilter),
+
+    /// Event renamed, merged, or split.
+    Changed,
+
+    /// User wants to add photos and videos to a user album.
+    AddToUserAlbum(Vec<AlbumItem>),
+
+    /// User wants to set the location of photos and videos by picking a point on the map.
+    SetLocation(Vec<AlbumItem>),
+}
+
+pub struct EventPage {
+    repo: event::Repository,
+    event: Option<Event>,
+    album: Controller<Album>,
+    title: adw::WindowTitle,
+    active_view: ActiveView,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for EventPage {
+    type Init = (SharedState, event::Repository, ActiveView);
+    type Input = EventInput;
+    type Output = EventOutput;
+
+    menu! {
+        primary_menu: {
+            section! {
+                &fl!("event-menu-rename") => RenameAction,
+            },
+            section! {
+                &fl!("event-menu-merge-previous") => MergePreviousAction,
+                &fl!("event-menu-merge-next") => MergeNextAction,
+            }
+        }
+    }
+
+    view! {
+        adw::ToolbarView {
+            add_top_bar = &adw::HeaderBar {
+                #[wrap(Some)]
+                #[local_ref]
+                set_title_widget = &title -> adw::WindowTitle {},
+
+                pack_end = &gtk::MenuButton {
+                    set_icon_name: "open-menu-symbolic",
+                    set_menu_model: Some(&primary_menu),
+                },
+
+                pack_end = &gtk::Button {
+                    set_icon_name: "object-select-symbolic",
+                    set_tooltip_text: Some(&fl!("album-select-button", "tooltip")),
+                    connect_clicked => EventInput::SelectionMode,
+                },
+            },
+
+            #[wrap(Some)]
+            set_content = &gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                set_vexpand: true,
+
+                model.album.widget(),
+            }
+        }
+    }
+
+    fn init(
+        (state, repo, active_view): Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let album = Album::builder()
+            .launch((state.clone(), active_view.clone(), ViewName::Event, AlbumFilter::None))
+            .forward(sender.input_sender(), |msg| match msg {
+                AlbumOutput::Selected(id, _) => EventInput::Selected(id),
+                AlbumOutput::ScrollOffset(_) => EventInput::Ignore,
+                AlbumOutput::AddToUserAlbum(items) => EventInput::AddToUserAlbum(items),
+                AlbumOutput::SetLocation(items) => EventInput::SetLocation(items),
+                AlbumOutput::RemoveFromUserAlbum(_, _) => EventInput::Ignore,
+                AlbumOutput::SplitEvent(event_id, items) => EventInput::Split(event_id, items),
+            });
+
+        let title = adw::WindowTitle::builder()
+            .build();
+
+        let model = EventPage {
+            repo,
+            event: None,
+            title: title.clone(),
+            album,
+            active_view,
+        };
+
+        let widgets = view_output!();
+
+        let mut actions = RelmActionGroup::<EventActionGroup>::new();
+
+        let rename_action = {
+            let sender = sender.clone();
+            RelmAction::<RenameAction>::new_stateless(move |_| {
+                sender.input(EventInput::RenameDialog);
+            })
+        };
+
+        let merge_previous_action = {
+            let sender = sender.clone();
+            RelmAction::<MergePreviousAction>::new_stateless(move |_| {
+                sender.input(EventInput::MergePrevious);
+            })
+        };
+
+        let merge_next_action = {
+            let sender = sender.clone();
+            RelmAction::<MergeNextAction>::new_stateless(move |_| {
+                sender.input(EventInput::MergeNext);
+            })
+        };
+
+        actions.add_action(rename_action);
+        actions.add_action(merge_previous_action);
+        actions.add_action(merge_next_action);
+        actions.register_for_widget(&root);
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            EventInput::Activate => {
+                *self.active_view.write() = ViewName::Event;
+                self.album.sender().emit(AlbumInput::Activate);
+            }
+            EventInput::Refresh => {
+                let Some(ref event) = self.event else {
+                    return;
+                };
+
+                // Reload event so membership, dates, and name are current.
+                let event_id = event.event_id;
+                let event = self.repo.all()
+                    .map(|events| events.into_iter().find(|x| x.event_id == event_id));
+
+                match event {
+                    Ok(Some(event)) => {
+                        self.set_title(&event);
+                        self.album.sender().emit(AlbumInput::Filter(AlbumFilter::Event(event.clone())));
+                        self.event = Some(event);
+                    },
+                    Ok(None) => {
+                        info!("Event {} no longer exists", event_id);
+                        self.event = None;
+                    },
+                    Err(e) => {
+                        error!("Failed loading event {}: {}", event_id, e);
+                    },
+                }
+            }
+            EventInput::View(event) => {
+                info!("Viewing event: {}", event.event_id);
+                self.album.sender().emit(AlbumInput::Activate);
+                self.album.sender().emit(AlbumInput::Filter(AlbumFilter::Event(event.clone())));
+                self.album.sender().emit(AlbumInput::ScrollToTop);
+
+                self.set_title(&event);
+                self.event = Some(event);
+            }
+            EventInput::SelectionMode => {
+                self.album.sender().emit(AlbumInput::SelectionMode(true));
+            },
+            EventInput::AddToUserAlbum(items) => {
+                let _ = sender.output(EventOutput::AddToUserAlbum(items));
+            },
+            EventInput::SetLocation(items) => {
+                let _ = sender.output(EventOutput::SetLocation(items));
+            },
+            EventInput::Split(event_id, items) => {
+                info!("Splitting {} items from event {}", items.len(), event_id);
+                if let Err(e) = self.repo.split(event_id, &items) {
+                    error!("Failed splitting event: {}", e);
+                    return;
+                }
+                sender.input(EventInput::Refresh);
+                let _ = sender.output(EventOutput::Changed);
+            },
+            EventInput::MergePrevious | EventInput::MergeNext => {
+                let Some(ref event) = self.event else {
+                    info!("Asked to merge event, but no event to merge");
+                    return;
+                };
+
+                let events = match self.repo.all() {
+                    Ok(events) => events,
+                    Err(e) => {
+                        error!("Failed loading events: {}", e);
+                        return;
+                    },
+                };
+
+                // Events are in ascending time order.
+                let Some(index) = events.iter().position(|x| x.event_id == event.event_id) else {
+                    return;
+                };
+
+                let other = if matches!(msg, EventInput::MergePrevious) {
+                    index.checked_sub(1).and_then(|i| events.get(i))
+                } else {
+                    events.get(index + 1)
+                };
+
+                let Some(other) = other else {
+                    info!("No event to merge with event {}", event.event_id);
+                    return;
+                };
+
+                info!("Merging event {} into event {}", other.event_id, event.event_id);
+                if let Err(e) = self.repo.merge(event.event_id, other.event_id) {
+                    error!("Failed merging events: {}", e);
+                    return;
+                }
+                sender.input(EventInput::Refresh);
+                let _ = sender.output(EventOutput::Changed);
+            },
+            EventInput::Ignore => {},
+            EventInput::Selected(visual_id) => {
+                if let Some(ref event) = self.event {
+                    let _ = sender.output(EventOutput::Selected(visual_id, AlbumFilter::Event(event.clone())));
+                }
+            },
+            EventInput::Adapt(layout) => {
+                // FIXME album should directly subscribe to layout state.
+                self.album.sender().emit(AlbumInput::Adapt(layout));
+            },
+            EventInput::RenameDialog => {
+                let Some(ref event) = self.event else {
+                    info!("Asked to rename event, but no event to rename");
+                    return;
+                };
+                info!("Renaming event {}", event.event_id);
+
+                let event_name = gtk::Entry::builder()
+                    .placeholder_text(fl!("event-rename-dialog", "placeholder"))
+                    .text(event.name.as_deref().unwrap_or_default())
+                    .build();
+
+                let dialog = adw::AlertDialog::builder()
+                    .heading(fl!("event-rename-dialog", "heading"))
+                    .close_response("cancel")
+                    .default_response("rename")
+                    .extra_child(&event_name)
+                    .build();
+
+                dialog.add_response("cancel", &fl!("event-rename-dialog", "cancel-button"));
+                dialog.set_default_response(Some("cancel"));
+                dialog.set_close_response("cancel");
+
+                dialog.add_response("rename", &fl!("event-rename-dialog", "rename-button"));
+                dialog.set_response_appearance("rename", adw::ResponseAppearance::Suggested);
+
+                {
+                    let event_name = event_name.clone();
+                    let sender = sender.clone();
+                    dialog.connect_response(None, move |_, response| {
+                        if response == "rename" {
+                            let name = event_name.text();
+                            sender.input(EventInput::Rename(name.into()));
+                        }
+                    });
+                }
+
+                {
+                    let event_name = event_name.clone();
+                    let sender = sender.clone();
+                    let dialog = dialog.clone();
+                    event_name.clone().connect_activate(move |_| {
+                        dialog.close();
+                        let name = event_name.text();
+                        sender.input(EventInput::Rename(name.into()));
+                    });
+                }
+
+                if let Some(root) = gtk::Widget::root(self.title.widget_ref()) {
+                    dialog.present(Some(&root));
+                    event_name.grab_focus();
+                } else {
+                    error!("Couldn't get root widget!");
+                }
+            },
+            EventInput::Rename(name) => {
+                let Some(ref mut event) = self.event else {
+                    info!("Asked to rename event, but no event to rename");
+                    return;
+                };
+
+                let name = name.trim().to_string();
+                if name.is_empty() {
+                    return;
+                }
+
+                info!("Renaming event {} to {}", event.event_id, name);
+
+                if let Err(e) = self.repo.rename(event.event_id, &name) {
+                    error!("Failed to rename event: {}", e);
+                    return;
+                }
+                event.name = Some(name);
+                let event = event.clone();
+                self.set_title(&event);
+                let _ = sender.output(EventOutput::Changed);
+            },
+        }
+    }
+}
+
+impl EventPage {
+    fn set_title(&self, event: &Event) {
+        let dates = events_album::date_range(event);
+        if let Some(ref name) = event.name {
+            self.title.set_title(name);
+            self.title.set_subtitle(&dates);
+        } else {
+            self.title.set_title(&dates);
+            self.title.set_subtitle("");
+        }
+    }
+}
@@ -0,0 +1,333 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use gtk::prelude::OrientableExt;
+
+use chrono::{Datelike, Local};
+use fotema_core::event;
+use fotema_core::visual::model::PictureOrientation;
+use strum::IntoEnumIterator;
+
+use relm4::adw;
+use relm4::gtk;
+use relm4::gtk::gdk;
+use relm4::gtk::gdk_pixbuf;
+use relm4::gtk::prelude::WidgetExt;
+use relm4::typed_view::grid::{RelmGridItem, TypedGridView};
+use relm4::*;
+use relm4::binding::*;
+
+use std::path;
+use std::sync::Arc;
+
+use crate::adaptive;
+use crate::app::SharedState;
+use crate::app::ActiveView;
+use crate::app::ViewName;
+use crate::fl;
+
+use tracing::{debug, error, info};
+
+const NARROW_EDGE_LENGTH: i32 = 170;
+const WIDE_EDGE_LENGTH: i32 = 200;
+
+#[derive(Debug)]
+struct EventGridItem {
+    event: event::Event,
+
+    // Event cover. None if the library hasn't loaded.
+    cover: Option<Arc<fotema_core::visual::Visual>>,
+
+    // Length of thumbnail edge to allow for resizing when layout changes.
+    edge_length: I32Binding,
+}
+
+struct Widgets {
+    picture: gtk::Picture,
+    label: gtk::Label,
+    dates_label: gtk::Label,
+    count_label: gtk::Label,
+
+    // If the gtk::Picture has been bound to edge_length.
+    is_bound: bool,
+}
+
+#[derive(Debug)]
+pub enum EventsAlbumInput {
+    Activate,
+
+    // Reload events from database
+    Refresh,
+
+    EventSelected(u32), // Index into event grid vector
+
+    // Adapt to layout
+    Adapt(adaptive::Layout),
+}
+
+#[derive(Debug)]
+pub enum EventsAlbumOutput {
+    EventSelected(event::Event),
+}
+
+impl RelmGridItem for EventGridItem {
+    type Root = gtk::Box;
+    type Widgets = Widgets;
+
+    fn setup(_item: &gtk::ListItem) -> (gtk::Box, Widgets) {
+        relm4::view! {
+           my_box = gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                gtk::AspectFrame {
+                    gtk::Frame {
+                        #[name(picture)]
+                        gtk::Picture {
+                            set_can_shrink: true,
+                            set_width_request: NARROW_EDGE_LENGTH,
+                            set_height_request: NARROW_EDGE_LENGTH,
+                        }
+                    }
+                },
+
+                #[name(label)]
+                gtk::Label {
+                    add_css_class: "caption-heading",
+                    set_margin_top: 4,
+                },
+
+                #[name(dates_label)]
+                gtk::Label {
+                    add_css_class: "caption",
+                },
+
+                #[name(count_label)]
+                gtk::Label {
+                    add_css_class: "caption",
+                    add_css_class: "dim-label",
+                    set_margin_bottom: 12,
+                },
+            }
+        }
+
+        let widgets = Widgets {
+            picture,
+            label,
+            dates_label,
+            count_label,
+            is_bound: false,
+        };
+
+        (my_box, widgets)
+    }
+
+    fn bind(&mut self, widgets: &mut Self::Widgets, _root: &mut Self::Root) {
+        let dates = date_range(&self.event);
+        widgets.label.set_text(self.event.name.as_ref().unwrap_or(&dates));
+        widgets.dates_label.set_text(&dates);
+        widgets.dates_label.set_visible(self.event.name.is_some());
+        widgets.count_label.set_text(&fl!("events-album-count", count = self.event.len()));
+
+        // If we repeatedly bind, then Fotema will die with the following error:
+        // (fotema:2): GLib-GObject-CRITICAL **: 13:26:14.297: Too many GWeakRef registered
+        // GLib-GObject:ERROR:../gobject/gbinding.c:805:g_binding_constructed: assertion failed: (source != NULL)
+        // Bail out! GLib-GObject:ERROR:../gobject/gbinding.c:805:g_binding_constructed: assertion failed: (source != NULL)
+        if !widgets.is_bound {
+            widgets.picture.add_write_only_binding(&self.edge_length, "width-request");
+            widgets.picture.add_write_only_binding(&self.edge_length, "height-request");
+            widgets.is_bound = true;
+        }
+
+        let thumbnail_path = self.cover
+            .as_ref()
+            .and_then(|x| x.thumbnail_path.clone())
+            .filter(|x| x.exists());
+
+        if thumbnail_path.is_some() {
+            widgets.picture.set_filename(thumbnail_path);
+        } else {
+            let pb = gdk_pixbuf::Pixbuf::from_resource_at_scale(
+                "/app/fotema/Fotema/icons/scalable/actions/image-missing-symbolic.svg",
+                200, 200, true
+            ).unwrap();
+            let img = gdk::Texture::for_pixbuf(&pb);
+            widgets.picture.set_paintable(Some(&img));
+        }
+    }
+
+    fn unbind(&mut self, widgets: &mut Self::Widgets, _root: &mut Self::Root) {
+        widgets.picture.set_filename(None::<&path::Path>);
+        // clear orientation transformation css classes
+        for orient in PictureOrientation::iter() {
+            widgets.picture.remove_css_class(orient.as_ref());
+        }
+    }
+}
+
+pub struct EventsAlbum {
+    state: SharedState,
+    repo: event::Repository,
+    active_view: ActiveView,
+    event_grid: TypedGridView<EventGridItem, gtk::SingleSelection>,
+    edge_length: I32Binding,
+    status: adw::StatusPage,
+    scrolled: gtk::ScrolledWindow,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for EventsAlbum {
+    type Init = (SharedState, event::Repository, ActiveView);
+    type Input = EventsAlbumInput;
+    type Output = EventsAlbumOutput;
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Vertical,
+
+            #[local_ref]
+            scrolled -> gtk::ScrolledWindow {
+                set_vexpand: true,
+
+                #[local_ref]
+                events_box -> gtk::GridView {
+                    set_orientation: gtk::Orientation::Vertical,
+                    set_single_click_activate: true,
+
+                    connect_activate[sender] => move |_, idx| {
+                        sender.input(EventsAlbumInput::EventSelected(idx))
+                    }
+                }
+            },
+
+            #[local_ref]
+            status -> adw::StatusPage {
+                set_valign: gtk::Align::Start,
+                set_vexpand: true,
+
+                set_visible: false,
+                set_icon_name: Some("x-office-calendar-symbolic"),
+                set_title: &fl!("events-album-status-none", "title"),
+                set_description: Some(&fl!("events-album-status-none", "description")),
+            },
+        }
+    }
+
+    fn init(
+        (state, repo, active_view): Self::Init,
+        _root: Self::Root,
+        _sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let event_grid = TypedGridView::new();
+        let status = adw::StatusPage::new();
+        let scrolled = gtk::ScrolledWindow::new();
+
+        let model = EventsAlbum {
+            state,
+            repo,
+            active_view,
+            event_grid,
+            edge_length: I32Binding::new(NARROW_EDGE_LENGTH),
+            status: status.clone(),
+            scrolled: scrolled.clone(),
+        };
+
+        let events_box = &model.event_grid.view;
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            EventsAlbumInput::Activate => {
+                *self.active_view.write() = ViewName::Events;
+                self.refresh();
+            },
+            EventsAlbumInput::Refresh => {
+                // Also refresh when viewing a single event, so the grid is
+                // current when navigating back.
+                let active_view = *self.active_view.read();
+                if active_view == ViewName::Events || active_view == ViewName::Event {
+                    info!("Events view is active so refreshing");
+                    self.refresh();
+                } else {
+                    info!("Events view is inactive so clearing");
+                    self.event_grid.clear();
+                }
+            },
+            EventsAlbumInput::EventSelected(index) => {
+                debug!("Event selected index: {}", index);
+                if let Some(item) = self.event_grid.get_visible(index) {
+                    let item = item.borrow();
+                    let _ = sender.output(EventsAlbumOutput::EventSelected(item.event.clone()));
+                }
+            },
+            EventsAlbumInput::Adapt(adaptive::Layout::Narrow) => {
+                self.edge_length.set_value(NARROW_EDGE_LENGTH);
+            },
+            EventsAlbumInput::Adapt(adaptive::Layout::Wide) => {
+                self.edge_length.set_value(WIDE_EDGE_LENGTH);
+            },
+        }
+    }
+}
+
+impl EventsAlbum {
+    fn refresh(&mut self) {
+        let events = match self.repo.all() {
+            Ok(events) => events,
+            Err(e) => {
+                error!("Failed loading events: {}", e);
+                return;
+            }
+        };
+
+        // Most recent events first.
+        let items: Vec<EventGridItem> = {
+            let data = self.state.read();
+            events.into_iter()
+                .rev()
+                .map(|event| {
+                    let cover = event.cover()
+                        .and_then(|cover| data.iter().find(|v| cover.matches(v)))
+                        .cloned();
+
+                    EventGridItem {
+                        event,
+                        cover,
+                        edge_length: self.edge_length.clone(),
+                    }
+                })
+                .collect()
+        };
+
+        self.status.set_visible(items.is_empty());
+        self.scrolled.set_visible(!items.is_empty());
+
+        self.event_grid.clear();
+        self.event_grid.extend_from_iter(items);
+
+        // NOTE events are sorted most recent first, so don't scroll to end.
+    }
+}
+
+/// Dates an event spans, such as "3–5 May 2024".
+pub fn date_range(event: &event::Event) -> String {
+    let start = event.start_ts.with_timezone(&Local).date_naive();
+    let end = event.end_ts.with_timezone(&Local).date_naive();
+
+    let day_month = |date: chrono::NaiveDate| fl!("event-day-month", day = date.day(), month = date.month());
+
+    if start == end {
+        fl!("event-date", date = day_month(start), year = start.year().to_string())
+    } else if start.year() == end.year() && start.month() == end.month() {
+        fl!("event-date-range-month", start = start.day(), end = day_month(end), year = end.year().to_string())
+    } else if start.year() == end.year() {
+        fl!("event-date-range-year", start = day_month(start), end = day_month(end), year = end.year().to_string())
+    } else {
+        let start = fl!("event-date", date = day_month(start), year = start.year().to_string());
+        let end = fl!("event-date", date = day_month(end), year = end.year().to_string());
+        fl!("event-date-range", start = start, end = end)
+    }
+}
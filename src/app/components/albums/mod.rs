@@ -5,9 +5,19 @@
 pub mod album;
 pub mod album_filter;
 pub mod album_sort;
+pub mod duplicates_album;
+pub mod event;
+pub mod events_album;
 pub mod folders_album;
 pub mod months_album;
 pub mod people_album;
 pub mod person_album;
+pub mod person_review;
+pub mod person_split;
 pub mod places_album;
+pub mod search_album;
+pub mod smart_album;
+pub mod smart_albums_album;
+pub mod user_album;
+pub mod user_albums_album;
 pub mod years_album;
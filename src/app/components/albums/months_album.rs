@@ -272,6 +272,8 @@ impl MonthsAlbum {
             let data = self.state.read();
             data
                 .iter()
+                // Don't use a hidden photo from a stack as the cover.
+                .filter(|x| !x.is_hidden_in_stack())
                 .dedup_by(|x, y| x.year_month() == y.year_month())
                 .map(|picture| PhotoGridItem {
                     picture: picture.clone(),
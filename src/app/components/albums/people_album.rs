@@ -19,12 +19,14 @@ use crate::app::ActiveView;
 use crate::app::ViewName;
 use crate::app::SettingsState;
 use crate::app::FaceDetectionMode;
+use crate::app::components::viewer::person_select::{PersonSelect, PersonSelectInput, PersonSelectOutput};
 use crate::fl;
 
-use tracing::{debug, info};
+use tracing::{debug, error, info};
 
 const NARROW_EDGE_LENGTH: i32 = 170;
 const WIDE_EDGE_LENGTH: i32 = 200;
+const CLUSTER_EDGE_LENGTH: i32 = 100;
 
 #[derive(Debug)]
 struct PhotoGridItem {
@@ -59,6 +61,9 @@ pub enum PeopleAlbumInput {
     SettingsChanged,
 
     EnableFaceDetection,
+
+    /// The person selection dialog has named or merged a group and should be dismissed.
+    ClusterNamed,
 }
 
 #[derive(Debug)]
@@ -137,6 +142,13 @@ pub struct PeopleAlbum {
     avatars: gtk::ScrolledWindow,
     status: adw::StatusPage,
     edge_length: I32Binding,
+
+    /// Groups of unknown faces that look like the same person.
+    clusters: gtk::Box,
+    cluster_avatars: gtk::Box,
+
+    person_dialog: adw::Dialog,
+    person_select: AsyncController<PersonSelect>,
 }
 
 #[relm4::component(pub)]
@@ -149,6 +161,30 @@ impl SimpleComponent for PeopleAlbum {
         gtk::Box {
             set_orientation: gtk::Orientation::Vertical,
 
+            #[local_ref]
+            clusters -> gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                set_spacing: 8,
+                set_margin_all: 12,
+                set_visible: false,
+
+                gtk::Label {
+                    set_label: &fl!("people-page-unnamed-groups"),
+                    set_halign: gtk::Align::Start,
+                    add_css_class: "heading",
+                },
+
+                gtk::ScrolledWindow {
+                    set_vscrollbar_policy: gtk::PolicyType::Never,
+
+                    #[local_ref]
+                    cluster_avatars -> gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+                        set_spacing: 8,
+                    },
+                },
+            },
+
             #[local_ref]
             avatars -> gtk::ScrolledWindow {
                 set_vexpand: true,
@@ -207,6 +243,21 @@ impl SimpleComponent for PeopleAlbum {
 
         let avatars = gtk::ScrolledWindow::builder().build();
 
+        let clusters = gtk::Box::builder().build();
+
+        let cluster_avatars = gtk::Box::builder().build();
+
+        let person_select = PersonSelect::builder()
+            .launch(repo.clone())
+            .forward(sender.input_sender(), |msg| match msg {
+                PersonSelectOutput::Done => PeopleAlbumInput::ClusterNamed,
+            });
+
+        let person_dialog = adw::Dialog::builder()
+            .child(person_select.widget())
+            .presentation_mode(adw::DialogPresentationMode::BottomSheet)
+            .build();
+
         let model = PeopleAlbum {
             repo,
             active_view,
@@ -215,6 +266,10 @@ impl SimpleComponent for PeopleAlbum {
             avatars: avatars.clone(),
             status: status.clone(),
             edge_length: I32Binding::new(NARROW_EDGE_LENGTH),
+            clusters: clusters.clone(),
+            cluster_avatars: cluster_avatars.clone(),
+            person_dialog,
+            person_select,
         };
 
         let pictures_box = &model.photo_grid.view;
@@ -260,6 +315,11 @@ impl SimpleComponent for PeopleAlbum {
                 self.refresh();
                 let _ = sender.output(PeopleAlbumOutput::EnableFaceDetection);
             },
+            PeopleAlbumInput::ClusterNamed => {
+                debug!("Dismissing dialog.");
+                self.person_dialog.close();
+                self.refresh();
+            },
         }
     }
 }
@@ -268,6 +328,7 @@ impl PeopleAlbum {
     fn refresh(&mut self) {
 
         if self.settings_state.read().face_detection_mode == FaceDetectionMode::Off {
+            self.clusters.set_visible(false);
             self.avatars.set_visible(false);
             self.status.set_visible(true);
             self.status.set_title(&fl!("people-page-status-off", "title"));
@@ -294,7 +355,10 @@ impl PeopleAlbum {
             items.push(item);
         }
 
-        self.status.set_visible(items.is_empty());
+        let clusters = self.repo.all_clusters().unwrap_or_default();
+        self.refresh_clusters(&clusters);
+
+        self.status.set_visible(items.is_empty() && clusters.is_empty());
         self.avatars.set_visible(!items.is_empty());
 
         if items.is_empty() {
@@ -308,4 +372,57 @@ impl PeopleAlbum {
         self.photo_grid.extend_from_iter(items);
 
     }
+
+    fn refresh_clusters(&self, clusters: &[people::FaceCluster]) {
+        self.cluster_avatars.remove_all();
+        self.clusters.set_visible(!clusters.is_empty());
+
+        for cluster in clusters {
+            let avatar = adw::Avatar::builder()
+                .size(CLUSTER_EDGE_LENGTH)
+                .show_initials(false)
+                .build();
+
+            let img = gdk::Texture::from_filename(&cluster.thumbnail_path).ok();
+            avatar.set_custom_image(img.as_ref());
+
+            let label = gtk::Label::builder()
+                .label(fl!("people-page-unnamed-group-count", count = cluster.face_ids.len()))
+                .css_classes(["caption"])
+                .build();
+
+            let content = gtk::Box::builder()
+                .orientation(gtk::Orientation::Vertical)
+                .spacing(4)
+                .build();
+
+            content.append(&avatar);
+            content.append(&label);
+
+            let button = gtk::Button::builder()
+                .child(&content)
+                .css_classes(["flat"])
+                .tooltip_text(fl!("people-page-unnamed-group-name", "tooltip"))
+                .build();
+
+            {
+                let sender = self.person_select.sender().clone();
+                let dialog = self.person_dialog.clone();
+                let cluster_id = cluster.cluster_id;
+                let thumbnail = cluster.thumbnail_path.clone();
+                // Name the group as a new person, or merge it with an existing person.
+                button.connect_clicked(move |button| {
+                    debug!("Name cluster {}", cluster_id);
+                    if let Some(root) = gtk::Widget::root(button.widget_ref()) {
+                        sender.emit(PersonSelectInput::ActivateCluster(cluster_id, thumbnail.clone()));
+                        dialog.present(Some(&root));
+                    } else {
+                        error!("Couldn't get root widget!");
+                    }
+                });
+            }
+
+            self.cluster_avatars.append(&button);
+        }
+    }
 }
@@ -10,7 +10,7 @@ use relm4::*;
 use relm4::binding::*;
 use relm4::adw;
 use relm4::adw::prelude::*;
-use relm4::gtk::gdk;
+use relm4::gtk::{gdk, gio};
 use relm4::actions::{RelmAction, RelmActionGroup};
 
 use crate::app::adaptive;
@@ -21,16 +21,22 @@ use crate::app::components::albums:: {
     album::{Album, AlbumInput, AlbumOutput},
     album_filter::AlbumFilter,
     album_sort::AlbumSort,
+    person_review::{PersonReview, PersonReviewInput, PersonReviewOutput},
+    person_split::{PersonSplit, PersonSplitInput, PersonSplitOutput},
 };
 
 use fotema_core::people;
+use fotema_core::FaceId;
+use fotema_core::PersonId;
 use fotema_core::PictureId;
+use fotema_core::user_album::AlbumItem;
 use crate::fl;
 
 use tracing::{error, info};
 
 const NARROW_EDGE_LENGTH: i32 = 50;
 const WIDE_EDGE_LENGTH: i32 = 200;
+const SUGGESTION_EDGE_LENGTH: i32 = 80;
 
 relm4::new_action_group!(PersonActionGroup, "person");
 
@@ -40,6 +46,23 @@ relm4::new_stateless_action!(RenameAction, PersonActionGroup, "rename");
 // Delete a person
 relm4::new_stateless_action!(DeleteAction, PersonActionGroup, "delete");
 
+// Review suggested faces of a person
+relm4::new_stateless_action!(ReviewAction, PersonActionGroup, "review");
+
+// Merge another person into a person
+relm4::new_stateless_action!(MergeAction, PersonActionGroup, "merge");
+
+// Move some faces of a person to another person
+relm4::new_stateless_action!(SplitAction, PersonActionGroup, "split");
+
+relm4::new_action_group!(SuggestionActionGroup, "suggestion");
+
+// Confirm a suggested face is the person.
+relm4::new_stateless_action!(ConfirmSuggestionAction, SuggestionActionGroup, "confirm");
+
+// Reject a suggested face as not being the person.
+relm4::new_stateless_action!(RejectSuggestionAction, SuggestionActionGroup, "reject");
+
 #[derive(Debug)]
 pub enum PersonAlbumInput {
 
@@ -74,6 +97,42 @@ pub enum PersonAlbumInput {
     Delete,
 
     Sort(AlbumSort),
+
+    /// Start selecting photos and videos.
+    SelectionMode,
+
+    /// Add selected photos and videos to a user album.
+    AddToUserAlbum(Vec<AlbumItem>),
+
+    /// Set the location of selected photos and videos.
+    SetLocation(Vec<AlbumItem>),
+
+    /// Confirm a face suggested by face recognition is the person.
+    ConfirmSuggestion(FaceId),
+
+    /// Reject a face suggested by face recognition.
+    RejectSuggestion(FaceId),
+
+    /// Review all faces suggested by face recognition.
+    Review,
+
+    /// Suggested faces have been confirmed or rejected.
+    SuggestionsChanged,
+
+    /// Start merge person flow.
+    MergeDialog,
+
+    /// Actually merge another person into this person.
+    Merge(PersonId),
+
+    /// Start split person flow.
+    SplitDialog,
+
+    /// Faces have been moved to another person.
+    Split,
+
+    /// Ignore an event.
+    Ignore,
 }
 
 #[derive(Debug)]
@@ -86,6 +145,15 @@ pub enum PersonAlbumOutput {
 
     /// Person renamed.
     Renamed,
+
+    /// Faces moved between people, or people merged.
+    PeopleChanged,
+
+    /// User wants to add photos and videos to a user album.
+    AddToUserAlbum(Vec<AlbumItem>),
+
+    /// User wants to set the location of photos and videos by picking a point on the map.
+    SetLocation(Vec<AlbumItem>),
 }
 
 pub struct PersonAlbum {
@@ -97,6 +165,13 @@ pub struct PersonAlbum {
     title: gtk::Label,
     active_view: ActiveView,
     edge_length: I32Binding,
+
+    /// Faces recognized as the person, that the user hasn't confirmed.
+    suggestions: gtk::Box,
+    suggestion_avatars: gtk::Box,
+
+    review: Controller<PersonReview>,
+    split: Controller<PersonSplit>,
 }
 
 #[relm4::component(pub)]
@@ -111,6 +186,11 @@ impl SimpleComponent for PersonAlbum {
                 // FIXME I would like to have the person's name in these menu items.
                 &fl!("person-menu-rename") => RenameAction,
                 &fl!("person-menu-delete") => DeleteAction,
+            },
+            section! {
+                &fl!("person-menu-review") => ReviewAction,
+                &fl!("person-menu-merge") => MergeAction,
+                &fl!("person-menu-split") => SplitAction,
             }
         }
     }
@@ -128,6 +208,12 @@ impl SimpleComponent for PersonAlbum {
                     set_icon_name: "open-menu-symbolic",
                     set_menu_model: Some(&primary_menu),
                 },
+
+                pack_end = &gtk::Button {
+                    set_icon_name: "object-select-symbolic",
+                    set_tooltip_text: Some(&fl!("album-select-button", "tooltip")),
+                    connect_clicked => PersonAlbumInput::SelectionMode,
+                },
             },
 
             #[wrap(Some)]
@@ -139,6 +225,42 @@ impl SimpleComponent for PersonAlbum {
                 #[local_ref]
                 avatar -> adw::Avatar,
 
+                #[local_ref]
+                suggestions -> gtk::Box {
+                    set_orientation: gtk::Orientation::Vertical,
+                    set_spacing: 8,
+                    set_margin_start: 12,
+                    set_margin_end: 12,
+                    set_visible: false,
+
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+
+                        gtk::Label {
+                            set_label: &fl!("person-suggestions"),
+                            set_halign: gtk::Align::Start,
+                            set_hexpand: true,
+                            add_css_class: "heading",
+                        },
+
+                        gtk::Button {
+                            set_label: &fl!("person-suggestions-review-button"),
+                            add_css_class: "flat",
+                            connect_clicked => PersonAlbumInput::Review,
+                        },
+                    },
+
+                    gtk::ScrolledWindow {
+                        set_vscrollbar_policy: gtk::PolicyType::Never,
+
+                        #[local_ref]
+                        suggestion_avatars -> gtk::Box {
+                            set_orientation: gtk::Orientation::Horizontal,
+                            set_spacing: 8,
+                        },
+                    },
+                },
+
                 model.album.widget(),
             }
         }
@@ -159,11 +281,31 @@ impl SimpleComponent for PersonAlbum {
             .forward(sender.input_sender(), |msg| match msg {
                 AlbumOutput::Selected(id, _) => PersonAlbumInput::Selected(id),
                 AlbumOutput::ScrollOffset(offset) => PersonAlbumInput::ScrollOffset(offset),
+                AlbumOutput::AddToUserAlbum(items) => PersonAlbumInput::AddToUserAlbum(items),
+                AlbumOutput::SetLocation(items) => PersonAlbumInput::SetLocation(items),
+                AlbumOutput::RemoveFromUserAlbum(_, _) => PersonAlbumInput::Ignore,
+                AlbumOutput::SplitEvent(_, _) => PersonAlbumInput::Ignore,
             });
 
         let title = gtk::Label::builder()
             .build();
 
+        let suggestions = gtk::Box::builder().build();
+
+        let suggestion_avatars = gtk::Box::builder().build();
+
+        let review = PersonReview::builder()
+            .launch(repo.clone())
+            .forward(sender.input_sender(), |msg| match msg {
+                PersonReviewOutput::Changed => PersonAlbumInput::SuggestionsChanged,
+            });
+
+        let split = PersonSplit::builder()
+            .launch(repo.clone())
+            .forward(sender.input_sender(), |msg| match msg {
+                PersonSplitOutput::Changed => PersonAlbumInput::Split,
+            });
+
         let model = PersonAlbum {
             repo,
             person: None,
@@ -173,6 +315,10 @@ impl SimpleComponent for PersonAlbum {
             active_view,
             picture_ids: vec![],
             edge_length: I32Binding::new(NARROW_EDGE_LENGTH),
+            suggestions: suggestions.clone(),
+            suggestion_avatars: suggestion_avatars.clone(),
+            review,
+            split,
         };
 
         model.avatar.add_write_only_binding(&model.edge_length, "size");
@@ -194,8 +340,32 @@ impl SimpleComponent for PersonAlbum {
             })
         };
 
+        let review_action = {
+            let sender = sender.clone();
+            RelmAction::<ReviewAction>::new_stateless(move |_| {
+                sender.input(PersonAlbumInput::Review);
+            })
+        };
+
         actions.add_action(rename_action);
         actions.add_action(delete_action);
+        let merge_action = {
+            let sender = sender.clone();
+            RelmAction::<MergeAction>::new_stateless(move |_| {
+                sender.input(PersonAlbumInput::MergeDialog);
+            })
+        };
+
+        let split_action = {
+            let sender = sender.clone();
+            RelmAction::<SplitAction>::new_stateless(move |_| {
+                sender.input(PersonAlbumInput::SplitDialog);
+            })
+        };
+
+        actions.add_action(review_action);
+        actions.add_action(merge_action);
+        actions.add_action(split_action);
         actions.register_for_widget(&root);
 
         ComponentParts { model, widgets }
@@ -209,6 +379,7 @@ impl SimpleComponent for PersonAlbum {
             }
             PersonAlbumInput::Refresh => {
                 self.album.sender().emit(AlbumInput::Refresh);
+                self.refresh_suggestions(&sender);
             }
             PersonAlbumInput::Sort(sort) => {
                 self.album.sender().emit(AlbumInput::Sort(sort));
@@ -234,7 +405,164 @@ impl SimpleComponent for PersonAlbum {
 
                 self.title.set_label(&person.name);
                 self.person = Some(person);
+                self.refresh_suggestions(&sender);
             }
+            PersonAlbumInput::SelectionMode => {
+                self.album.sender().emit(AlbumInput::SelectionMode(true));
+            },
+            PersonAlbumInput::AddToUserAlbum(items) => {
+                let _ = sender.output(PersonAlbumOutput::AddToUserAlbum(items));
+            },
+            PersonAlbumInput::SetLocation(items) => {
+                let _ = sender.output(PersonAlbumOutput::SetLocation(items));
+            },
+            PersonAlbumInput::ConfirmSuggestion(face_id) => {
+                let Some(ref person) = self.person else {
+                    return;
+                };
+                info!("Confirming face {} is person {}", face_id, person.person_id);
+                if let Err(e) = self.repo.mark_as_person(face_id, person.person_id) {
+                    error!("Failed confirming face: {}", e);
+                }
+                sender.input(PersonAlbumInput::SuggestionsChanged);
+            },
+            PersonAlbumInput::RejectSuggestion(face_id) => {
+                let Some(ref person) = self.person else {
+                    return;
+                };
+                info!("Rejecting face {} for person {}", face_id, person.person_id);
+                if let Err(e) = self.repo.mark_not_person(face_id) {
+                    error!("Failed rejecting face: {}", e);
+                }
+                sender.input(PersonAlbumInput::SuggestionsChanged);
+            },
+            PersonAlbumInput::Review => {
+                let Some(ref person) = self.person else {
+                    info!("Asked to review suggestions, but no person for album");
+                    return;
+                };
+
+                self.review.emit(PersonReviewInput::View(person.clone()));
+
+                if let Some(root) = gtk::Widget::root(self.avatar.widget_ref()) {
+                    self.review.widget().present(Some(&root));
+                } else {
+                    error!("Couldn't get root widget!");
+                }
+            },
+            PersonAlbumInput::MergeDialog => {
+                let Some(ref person) = self.person else {
+                    info!("Asked to merge person, but no person for album");
+                    return;
+                };
+                info!("Starting merge flow for person: {}", person.person_id);
+
+                let others: Vec<people::Person> = self.repo.all_people()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|x| x.person_id != person.person_id)
+                    .collect();
+
+                if others.is_empty() {
+                    info!("No other people to merge with {}", person.person_id);
+                    return;
+                }
+
+                let names: Vec<&str> = others.iter().map(|x| x.name.as_str()).collect();
+                let other_people = gtk::DropDown::from_strings(&names);
+
+                let dialog = adw::AlertDialog::builder()
+                    .heading(fl!("person-merge-dialog", "heading"))
+                    .body(fl!("person-merge-dialog", "body", name = person.name.clone()))
+                    .close_response("cancel")
+                    .default_response("merge")
+                    .extra_child(&other_people)
+                    .build();
+
+                dialog.add_response("cancel", &fl!("person-merge-dialog", "cancel-button"));
+                dialog.set_default_response(Some("cancel"));
+                dialog.set_close_response("cancel");
+
+                dialog.add_response("merge", &fl!("person-merge-dialog", "merge-button"));
+                dialog.set_response_appearance("merge", adw::ResponseAppearance::Destructive);
+
+                {
+                    let other_people = other_people.clone();
+                    dialog.connect_response(None, move |_, response| {
+                        if response == "merge" {
+                            let index = other_people.selected() as usize;
+                            if let Some(other) = others.get(index) {
+                                sender.input(PersonAlbumInput::Merge(other.person_id));
+                            }
+                        }
+                    });
+                }
+
+                if let Some(root) = gtk::Widget::root(self.avatar.widget_ref()) {
+                    dialog.present(Some(&root));
+                } else {
+                    error!("Couldn't get root widget!");
+                }
+            },
+            PersonAlbumInput::Merge(other_person_id) => {
+                let Some(ref person) = self.person else {
+                    info!("Asked to merge person, but no person for album");
+                    return;
+                };
+
+                info!("Merging person {} into person {}", other_person_id, person.person_id);
+                if let Err(e) = self.repo.merge_people(other_person_id, person.person_id) {
+                    error!("Failed to merge people: {}", e);
+                    return;
+                }
+
+                sender.input(PersonAlbumInput::SuggestionsChanged);
+                let _ = sender.output(PersonAlbumOutput::PeopleChanged);
+            },
+            PersonAlbumInput::SplitDialog => {
+                let Some(ref person) = self.person else {
+                    info!("Asked to split person, but no person for album");
+                    return;
+                };
+
+                self.split.emit(PersonSplitInput::View(person.clone()));
+
+                if let Some(root) = gtk::Widget::root(self.avatar.widget_ref()) {
+                    self.split.widget().present(Some(&root));
+                } else {
+                    error!("Couldn't get root widget!");
+                }
+            },
+            PersonAlbumInput::Split => {
+                let Some(ref person) = self.person else {
+                    return;
+                };
+
+                // Moving the thumbnail face gives the person a new thumbnail.
+                match self.repo.get_person(person.person_id) {
+                    Ok(Some(person)) => {
+                        let img = gdk::Texture::from_filename(&person.thumbnail_path).ok();
+                        self.avatar.set_custom_image(img.as_ref());
+                        self.person = Some(person);
+                    },
+                    Ok(None) => {},
+                    Err(e) => error!("Failed loading person: {}", e),
+                }
+
+                sender.input(PersonAlbumInput::SuggestionsChanged);
+                let _ = sender.output(PersonAlbumOutput::PeopleChanged);
+            },
+            PersonAlbumInput::SuggestionsChanged => {
+                let Some(ref person) = self.person else {
+                    return;
+                };
+
+                // Rejected faces may have been the only faces of the person in a picture.
+                self.picture_ids = self.repo.find_pictures_for_person(person.person_id).unwrap_or_default();
+                self.album.sender().emit(AlbumInput::Filter(AlbumFilter::Any(self.picture_ids.clone())));
+                self.refresh_suggestions(&sender);
+            },
+            PersonAlbumInput::Ignore => {},
             PersonAlbumInput::Selected(visual_id) => {
                 let _ = sender.output(PersonAlbumOutput::Selected(visual_id, AlbumFilter::Any(self.picture_ids.clone())));
             },
@@ -377,3 +705,101 @@ impl SimpleComponent for PersonAlbum {
     }
 }
 
+impl PersonAlbum {
+    /// Show faces suggested by face recognition, most similar first.
+    fn refresh_suggestions(&self, sender: &ComponentSender<Self>) {
+        self.suggestion_avatars.remove_all();
+
+        let Some(ref person) = self.person else {
+            self.suggestions.set_visible(false);
+            return;
+        };
+
+        let suggestions = match self.repo.find_suggested_faces(person.person_id) {
+            Ok(suggestions) => suggestions,
+            Err(e) => {
+                error!("Failed getting suggested faces: {}", e);
+                vec![]
+            },
+        };
+
+        let suggestions: Vec<people::SuggestedFace> = suggestions
+            .into_iter()
+            .filter(|x| x.face.thumbnail_path.exists())
+            .collect();
+
+        info!("Person {} has {} suggested faces.", person.person_id, suggestions.len());
+
+        self.suggestions.set_visible(!suggestions.is_empty());
+
+        for suggestion in suggestions {
+            let face_id = suggestion.face.face_id;
+
+            let mut group = RelmActionGroup::<SuggestionActionGroup>::new();
+
+            let confirm: RelmAction<ConfirmSuggestionAction> = {
+                let sender = sender.clone();
+                RelmAction::new_stateless(move |_| {
+                    sender.input(PersonAlbumInput::ConfirmSuggestion(face_id));
+                })
+            };
+            group.add_action(confirm);
+
+            let reject: RelmAction<RejectSuggestionAction> = {
+                let sender = sender.clone();
+                RelmAction::new_stateless(move |_| {
+                    sender.input(PersonAlbumInput::RejectSuggestion(face_id));
+                })
+            };
+            group.add_action(reject);
+
+            let menu_model = gio::Menu::new();
+            menu_model.append(Some(&fl!("person-suggestion-confirm")), Some("suggestion.confirm"));
+            menu_model.append(Some(&fl!("people-not-this-person", name = person.name.clone())), Some("suggestion.reject"));
+
+            let pop = gtk::PopoverMenu::builder()
+                .menu_model(&menu_model)
+                .build();
+
+            let avatar = adw::Avatar::builder()
+                .size(SUGGESTION_EDGE_LENGTH)
+                .show_initials(false)
+                .build();
+
+            let img = gdk::Texture::from_filename(&suggestion.face.thumbnail_path).ok();
+            avatar.set_custom_image(img.as_ref());
+
+            let content = gtk::Box::builder()
+                .orientation(gtk::Orientation::Vertical)
+                .spacing(4)
+                .build();
+
+            content.append(&avatar);
+
+            // Faces matched before similarities were recorded have no score.
+            if let Some(similarity) = suggestion.similarity {
+                let percent = (similarity * 100.0).round() as i32;
+                let label = gtk::Label::builder()
+                    .label(fl!("person-suggestion-similarity", percent = percent))
+                    .css_classes(["caption"])
+                    .build();
+                content.append(&label);
+            }
+
+            content.append(&pop);
+
+            let button = gtk::Button::builder()
+                .child(&content)
+                .css_classes(["flat"])
+                .build();
+
+            group.register_for_widget(&button);
+
+            button.connect_clicked(move |_| {
+                pop.popup();
+            });
+
+            self.suggestion_avatars.append(&button);
+        }
+    }
+}
@@ -0,0 +1,288 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use relm4::adw::prelude::*;
+use relm4::gtk::{self, gdk};
+use relm4::*;
+
+use crate::fl;
+use fotema_core::people;
+use fotema_core::FaceId;
+
+use tracing::{error, info};
+
+const FACE_EDGE_LENGTH: i32 = 100;
+
+/// Dialog to review all faces that face recognition has matched with a person,
+/// and confirm or reject many of them at once.
+pub struct PersonReview {
+    repo: people::Repository,
+    person: Option<people::Person>,
+
+    /// Toggle button for each suggested face, in same order as face grid.
+    faces: Vec<(FaceId, gtk::ToggleButton)>,
+
+    face_grid: gtk::FlowBox,
+    face_grid_scroll: gtk::ScrolledWindow,
+    status: adw::StatusPage,
+    title: adw::WindowTitle,
+    confirm_button: gtk::Button,
+    reject_button: gtk::Button,
+}
+
+#[derive(Debug)]
+pub enum PersonReviewInput {
+    /// Review suggested faces of a person.
+    View(people::Person),
+
+    /// A face has been selected or unselected.
+    SelectionChanged,
+
+    /// Select all faces.
+    SelectAll,
+
+    /// Confirm selected faces are the person.
+    Confirm,
+
+    /// Reject selected faces as not the person.
+    Reject,
+}
+
+#[derive(Debug)]
+pub enum PersonReviewOutput {
+    /// Faces have been confirmed or rejected.
+    Changed,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for PersonReview {
+    type Init = people::Repository;
+    type Input = PersonReviewInput;
+    type Output = PersonReviewOutput;
+
+    view! {
+        adw::Dialog {
+            set_content_width: 600,
+            set_content_height: 500,
+
+            #[wrap(Some)]
+            set_child = &adw::ToolbarView {
+                add_top_bar = &adw::HeaderBar {
+                    #[wrap(Some)]
+                    #[local_ref]
+                    set_title_widget = &title -> adw::WindowTitle {},
+
+                    pack_start = &gtk::Button {
+                        set_label: &fl!("person-review-dialog", "select-all-button"),
+                        connect_clicked => PersonReviewInput::SelectAll,
+                    },
+                },
+
+                #[wrap(Some)]
+                set_content = &gtk::Box {
+                    set_orientation: gtk::Orientation::Vertical,
+
+                    #[local_ref]
+                    status -> adw::StatusPage {
+                        set_vexpand: true,
+                        set_visible: false,
+                        set_icon_name: Some("object-select-symbolic"),
+                        set_title: &fl!("person-review-dialog", "empty-title"),
+                        set_description: Some(&fl!("person-review-dialog", "empty-description")),
+                    },
+
+                    #[local_ref]
+                    face_grid_scroll -> gtk::ScrolledWindow {
+                        set_vexpand: true,
+
+                        #[local_ref]
+                        face_grid -> gtk::FlowBox {
+                            set_valign: gtk::Align::Start,
+                            set_selection_mode: gtk::SelectionMode::None,
+                            set_homogeneous: true,
+                            set_row_spacing: 8,
+                            set_column_spacing: 8,
+                            set_margin_all: 12,
+                        },
+                    },
+                },
+
+                add_bottom_bar = &gtk::ActionBar {
+                    #[local_ref]
+                    pack_start = &reject_button -> gtk::Button {
+                        set_sensitive: false,
+                        add_css_class: "destructive-action",
+                        connect_clicked => PersonReviewInput::Reject,
+                    },
+
+                    #[local_ref]
+                    pack_end = &confirm_button -> gtk::Button {
+                        set_label: &fl!("person-review-dialog", "confirm-button"),
+                        set_sensitive: false,
+                        add_css_class: "suggested-action",
+                        connect_clicked => PersonReviewInput::Confirm,
+                    },
+                },
+            },
+        }
+    }
+
+    fn init(
+        repo: Self::Init,
+        root: Self::Root,
+        _sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let face_grid = gtk::FlowBox::new();
+        let face_grid_scroll = gtk::ScrolledWindow::new();
+        let status = adw::StatusPage::new();
+        let title = adw::WindowTitle::builder().build();
+        let confirm_button = gtk::Button::new();
+        let reject_button = gtk::Button::new();
+
+        let model = PersonReview {
+            repo,
+            person: None,
+            faces: vec![],
+            face_grid: face_grid.clone(),
+            face_grid_scroll: face_grid_scroll.clone(),
+            status: status.clone(),
+            title: title.clone(),
+            confirm_button: confirm_button.clone(),
+            reject_button: reject_button.clone(),
+        };
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            PersonReviewInput::View(person) => {
+                info!("Reviewing suggested faces for person {}", person.person_id);
+                self.title.set_title(&fl!("person-review-dialog", "title", name = person.name.clone()));
+                self.reject_button.set_label(&fl!("people-not-this-person", name = person.name.clone()));
+                self.person = Some(person);
+                self.refresh(&sender);
+            },
+            PersonReviewInput::SelectionChanged => {
+                let count = self.selected().len();
+                self.confirm_button.set_sensitive(count > 0);
+                self.reject_button.set_sensitive(count > 0);
+
+                if count > 0 {
+                    self.title.set_subtitle(&fl!("person-review-dialog", "selected", count = count));
+                } else {
+                    self.title.set_subtitle("");
+                }
+            },
+            PersonReviewInput::SelectAll => {
+                for (_, toggle) in &self.faces {
+                    toggle.set_active(true);
+                }
+            },
+            PersonReviewInput::Confirm => {
+                let Some(ref person) = self.person else {
+                    return;
+                };
+                let face_ids = self.selected();
+                info!("Confirming {} faces are person {}", face_ids.len(), person.person_id);
+                if let Err(e) = self.repo.confirm_faces(person.person_id, &face_ids) {
+                    error!("Failed confirming faces: {}", e);
+                }
+                self.refresh(&sender);
+                let _ = sender.output(PersonReviewOutput::Changed);
+            },
+            PersonReviewInput::Reject => {
+                let Some(ref person) = self.person else {
+                    return;
+                };
+                let face_ids = self.selected();
+                info!("Rejecting {} faces for person {}", face_ids.len(), person.person_id);
+                if let Err(e) = self.repo.reject_faces(person.person_id, &face_ids) {
+                    error!("Failed rejecting faces: {}", e);
+                }
+                self.refresh(&sender);
+                let _ = sender.output(PersonReviewOutput::Changed);
+            },
+        }
+    }
+}
+
+impl PersonReview {
+    /// IDs of selected faces.
+    fn selected(&self) -> Vec<FaceId> {
+        self.faces
+            .iter()
+            .filter(|(_, toggle)| toggle.is_active())
+            .map(|(face_id, _)| *face_id)
+            .collect()
+    }
+
+    /// Show faces suggested by face recognition, most similar first.
+    fn refresh(&mut self, sender: &ComponentSender<Self>) {
+        self.face_grid.remove_all();
+        self.faces.clear();
+
+        let Some(ref person) = self.person else {
+            return;
+        };
+
+        let suggestions = match self.repo.find_suggested_faces(person.person_id) {
+            Ok(suggestions) => suggestions,
+            Err(e) => {
+                error!("Failed getting suggested faces: {}", e);
+                vec![]
+            },
+        };
+
+        for suggestion in suggestions.into_iter().filter(|x| x.face.thumbnail_path.exists()) {
+            let avatar = adw::Avatar::builder()
+                .size(FACE_EDGE_LENGTH)
+                .show_initials(false)
+                .build();
+
+            let img = gdk::Texture::from_filename(&suggestion.face.thumbnail_path).ok();
+            avatar.set_custom_image(img.as_ref());
+
+            let content = gtk::Box::builder()
+                .orientation(gtk::Orientation::Vertical)
+                .spacing(4)
+                .build();
+
+            content.append(&avatar);
+
+            if let Some(similarity) = suggestion.similarity {
+                let percent = (similarity * 100.0).round() as i32;
+                let label = gtk::Label::builder()
+                    .label(fl!("person-suggestion-similarity", percent = percent))
+                    .css_classes(["caption"])
+                    .build();
+                content.append(&label);
+            }
+
+            let toggle = gtk::ToggleButton::builder()
+                .child(&content)
+                .css_classes(["flat"])
+                .build();
+
+            {
+                let sender = sender.clone();
+                toggle.connect_toggled(move |_| {
+                    sender.input(PersonReviewInput::SelectionChanged);
+                });
+            }
+
+            self.face_grid.append(&toggle);
+            self.faces.push((suggestion.face.face_id, toggle));
+        }
+
+        info!("Person {} has {} faces to review.", person.person_id, self.faces.len());
+
+        self.status.set_visible(self.faces.is_empty());
+        self.face_grid_scroll.set_visible(!self.faces.is_empty());
+
+        sender.input(PersonReviewInput::SelectionChanged);
+    }
+}
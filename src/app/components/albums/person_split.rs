@@ -0,0 +1,240 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use relm4::adw::prelude::*;
+use relm4::gtk::{self, gdk};
+use relm4::*;
+
+use crate::app::components::viewer::person_select::{PersonSelect, PersonSelectInput, PersonSelectOutput};
+use crate::fl;
+use fotema_core::people;
+use fotema_core::FaceId;
+
+use tracing::{error, info};
+
+use std::path::PathBuf;
+
+const FACE_EDGE_LENGTH: i32 = 100;
+
+/// Dialog to split a person by moving some of their faces to a new or existing person.
+pub struct PersonSplit {
+    repo: people::Repository,
+    person: Option<people::Person>,
+
+    /// Toggle button and thumbnail for each face, in same order as face grid.
+    faces: Vec<(FaceId, PathBuf, gtk::ToggleButton)>,
+
+    dialog: adw::Dialog,
+    face_grid: gtk::FlowBox,
+    title: adw::WindowTitle,
+    move_button: gtk::Button,
+
+    person_dialog: adw::Dialog,
+    person_select: AsyncController<PersonSelect>,
+}
+
+#[derive(Debug)]
+pub enum PersonSplitInput {
+    /// Choose faces of a person to move.
+    View(people::Person),
+
+    /// A face has been selected or unselected.
+    SelectionChanged,
+
+    /// Choose a person to move selected faces to.
+    Move,
+
+    /// The person selection dialog has moved the faces and should be dismissed.
+    PersonSelected,
+}
+
+#[derive(Debug)]
+pub enum PersonSplitOutput {
+    /// Faces have been moved to another person.
+    Changed,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for PersonSplit {
+    type Init = people::Repository;
+    type Input = PersonSplitInput;
+    type Output = PersonSplitOutput;
+
+    view! {
+        adw::Dialog {
+            set_content_width: 600,
+            set_content_height: 500,
+
+            #[wrap(Some)]
+            set_child = &adw::ToolbarView {
+                add_top_bar = &adw::HeaderBar {
+                    #[wrap(Some)]
+                    #[local_ref]
+                    set_title_widget = &title -> adw::WindowTitle {},
+                },
+
+                #[wrap(Some)]
+                set_content = &gtk::ScrolledWindow {
+                    set_vexpand: true,
+
+                    #[local_ref]
+                    face_grid -> gtk::FlowBox {
+                        set_valign: gtk::Align::Start,
+                        set_selection_mode: gtk::SelectionMode::None,
+                        set_homogeneous: true,
+                        set_row_spacing: 8,
+                        set_column_spacing: 8,
+                        set_margin_all: 12,
+                    },
+                },
+
+                add_bottom_bar = &gtk::ActionBar {
+                    #[local_ref]
+                    pack_end = &move_button -> gtk::Button {
+                        set_label: &fl!("person-split-dialog", "move-button"),
+                        set_sensitive: false,
+                        add_css_class: "suggested-action",
+                        connect_clicked => PersonSplitInput::Move,
+                    },
+                },
+            },
+        }
+    }
+
+    fn init(
+        repo: Self::Init,
+        dialog: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let face_grid = gtk::FlowBox::new();
+        let title = adw::WindowTitle::builder().build();
+        let move_button = gtk::Button::new();
+
+        let person_select = PersonSelect::builder()
+            .launch(repo.clone())
+            .forward(sender.input_sender(), |msg| match msg {
+                PersonSelectOutput::Done => PersonSplitInput::PersonSelected,
+            });
+
+        let person_dialog = adw::Dialog::builder()
+            .child(person_select.widget())
+            .presentation_mode(adw::DialogPresentationMode::BottomSheet)
+            .build();
+
+        let model = PersonSplit {
+            repo,
+            person: None,
+            faces: vec![],
+            dialog: dialog.clone(),
+            face_grid: face_grid.clone(),
+            title: title.clone(),
+            move_button: move_button.clone(),
+            person_dialog,
+            person_select,
+        };
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            PersonSplitInput::View(person) => {
+                info!("Choosing faces to split from person {}", person.person_id);
+                self.title.set_title(&fl!("person-split-dialog", "title", name = person.name.clone()));
+                self.person = Some(person);
+                self.refresh(&sender);
+            },
+            PersonSplitInput::SelectionChanged => {
+                let count = self.selected().len();
+                self.move_button.set_sensitive(count > 0);
+
+                if count > 0 {
+                    self.title.set_subtitle(&fl!("person-split-dialog", "selected", count = count));
+                } else {
+                    self.title.set_subtitle("");
+                }
+            },
+            PersonSplitInput::Move => {
+                let selected = self.selected();
+                let Some((_, thumbnail)) = selected.first() else {
+                    return;
+                };
+
+                let face_ids = selected.iter().map(|(face_id, _)| *face_id).collect();
+                self.person_select.emit(PersonSelectInput::ActivateFaces(face_ids, thumbnail.clone()));
+
+                if let Some(root) = gtk::Widget::root(self.face_grid.widget_ref()) {
+                    self.person_dialog.present(Some(&root));
+                } else {
+                    error!("Couldn't get root widget!");
+                }
+            },
+            PersonSplitInput::PersonSelected => {
+                self.person_dialog.close();
+                self.dialog.close();
+                let _ = sender.output(PersonSplitOutput::Changed);
+            },
+        }
+    }
+}
+
+impl PersonSplit {
+    /// IDs and thumbnails of selected faces.
+    fn selected(&self) -> Vec<(FaceId, PathBuf)> {
+        self.faces
+            .iter()
+            .filter(|(_, _, toggle)| toggle.is_active())
+            .map(|(face_id, thumbnail, _)| (*face_id, thumbnail.clone()))
+            .collect()
+    }
+
+    /// Show all faces of person.
+    fn refresh(&mut self, sender: &ComponentSender<Self>) {
+        self.face_grid.remove_all();
+        self.faces.clear();
+
+        let Some(ref person) = self.person else {
+            return;
+        };
+
+        let faces = match self.repo.find_faces_for_person(person.person_id) {
+            Ok(faces) => faces,
+            Err(e) => {
+                error!("Failed getting faces for person: {}", e);
+                vec![]
+            },
+        };
+
+        for face in faces.into_iter().filter(|x| x.thumbnail_path.exists()) {
+            let avatar = adw::Avatar::builder()
+                .size(FACE_EDGE_LENGTH)
+                .show_initials(false)
+                .build();
+
+            let img = gdk::Texture::from_filename(&face.thumbnail_path).ok();
+            avatar.set_custom_image(img.as_ref());
+
+            let toggle = gtk::ToggleButton::builder()
+                .child(&avatar)
+                .css_classes(["flat"])
+                .build();
+
+            {
+                let sender = sender.clone();
+                toggle.connect_toggled(move |_| {
+                    sender.input(PersonSplitInput::SelectionChanged);
+                });
+            }
+
+            self.face_grid.append(&toggle);
+            self.faces.push((face.face_id, face.thumbnail_path, toggle));
+        }
+
+        info!("Person {} has {} faces to split.", person.person_id, self.faces.len());
+
+        sender.input(PersonSplitInput::SelectionChanged);
+    }
+}
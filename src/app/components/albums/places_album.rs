@@ -12,6 +12,8 @@ use relm4::gtk::prelude::WidgetExt;
 use relm4::gtk::gdk_pixbuf;
 use relm4::gtk::gdk;
 use relm4::*;
+use relm4::adw;
+use relm4::adw::prelude::*;
 use relm4::binding::*;
 
 use tracing::{debug,error,info};
@@ -20,7 +22,11 @@ use crate::adaptive;
 use crate::app::SharedState;
 use crate::app::ActiveView;
 use crate::app::ViewName;
+use crate::fl;
 use fotema_core::{Visual, VisualId};
+use fotema_core::photo::gps::GPSLocation;
+use fotema_core::smart_album::Query;
+use fotema_core::user_album::AlbumItem;
 
 use h3o;
 use h3o::CellIndex;
@@ -30,7 +36,7 @@ use shumate::prelude::*;
 use shumate::MAP_SOURCE_OSM_MAPNIK;
 
 use std::sync::Arc;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 const NARROW_EDGE_LENGTH: i32 = 60;
 const WIDE_EDGE_LENGTH: i32 = 100;
@@ -55,6 +61,15 @@ pub enum PlacesAlbumInput {
 
     // Map has been dragged
     Move,
+
+    /// Set the location of photos and videos with the next click on the map.
+    PickLocation(Vec<AlbumItem>),
+
+    /// Stop picking a location.
+    CancelPickLocation,
+
+    /// Map clicked at widget coordinates.
+    MapClicked(f64, f64),
 }
 
 #[derive(Debug)]
@@ -64,6 +79,13 @@ pub enum PlacesAlbumOutput {
 
     // User has selected a group of items grouped in a cell index to view as an album
     GeographicArea(CellIndex),
+
+    /// User has selected a named country or city from the places list.
+    /// Query to match items at place, and place name.
+    Place(Query, String),
+
+    /// User has picked the location of photos and videos on the map.
+    SetLocation(Vec<AlbumItem>, GPSLocation),
 }
 
 /// Item to represent all photos in a cell
@@ -98,6 +120,21 @@ pub struct PlacesAlbum {
     /// Cell nearest centre of map
     centre_cell: h3o::CellIndex,
 
+    /// Named places grouped by country, as an alternative to the map.
+    place_list: gtk::ListBox,
+
+    /// Shown instead of the places list when no places have been named.
+    place_list_status: adw::StatusPage,
+
+    /// Pages for the map and the places list.
+    view_stack: adw::ViewStack,
+
+    /// Shown while picking a location.
+    pick_banner: adw::Banner,
+
+    /// Items to set the location of when the map is clicked.
+    picking: Vec<AlbumItem>,
+
     need_refresh: bool,
 }
 
@@ -108,18 +145,55 @@ impl SimpleComponent for PlacesAlbum {
     type Output = PlacesAlbumOutput;
 
     view! {
-       gtk::Box {
-       #[local_ref]
-        map_widget -> shumate::SimpleMap{
-            set_vexpand: true,
-            set_hexpand: true,
+        adw::ViewStack {
+            add_titled_with_icon[Some("map"), &fl!("places-page", "map"), "mark-location-symbolic"] = &gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+
+                #[local_ref]
+                pick_banner -> adw::Banner {
+                    set_button_label: Some(&fl!("places-page-pick-location", "cancel-button")),
+                    connect_button_clicked => PlacesAlbumInput::CancelPickLocation,
+                },
+
+                #[local_ref]
+                map_widget -> shumate::SimpleMap{
+                    set_vexpand: true,
+                    set_hexpand: true,
+                },
+            },
+
+            add_titled_with_icon[Some("list"), &fl!("places-page", "list"), "view-list-symbolic"] = &gtk::ScrolledWindow {
+                set_vexpand: true,
+                set_hscrollbar_policy: gtk::PolicyType::Never,
+
+                adw::Clamp {
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Vertical,
+                        set_margin_all: 12,
+
+                        #[local_ref]
+                        place_list -> gtk::ListBox {
+                            set_valign: gtk::Align::Start,
+                            set_selection_mode: gtk::SelectionMode::None,
+                            add_css_class: "boxed-list",
+                        },
+
+                        #[local_ref]
+                        place_list_status -> adw::StatusPage {
+                            set_vexpand: true,
+                            set_icon_name: Some("mark-location-symbolic"),
+                            set_title: &fl!("places-page-status-none", "title"),
+                            set_description: Some(&fl!("places-page-status-none", "description")),
+                        },
+                    },
+                },
+            },
         },
-       },
     }
 
     fn init(
         (state, active_view): Self::Init,
-        _root: Self::Root,
+        root: Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
 
@@ -162,11 +236,23 @@ impl SimpleComponent for PlacesAlbum {
         let gesture = gtk::GestureClick::new();
         map_widget.add_controller(gesture.clone());
 
+        {
+            let sender = sender.clone();
+            gesture.connect_released(move |_, _, x, y| sender.input(PlacesAlbumInput::MapClicked(x, y)));
+        }
+
+        // If we get a stop message, then the map is being dragged rather than clicked.
+        gesture.connect_stopped(|click| click.reset());
+
         let marker_layer: shumate::MarkerLayer =
             shumate::MarkerLayer::new_full(&viewport, gtk::SelectionMode::Single);
 
         map.add_layer(&marker_layer);
 
+        let place_list = gtk::ListBox::new();
+        let place_list_status = adw::StatusPage::new();
+        let pick_banner = adw::Banner::new("");
+
         let model = PlacesAlbum {
             state,
             active_view,
@@ -185,6 +271,12 @@ impl SimpleComponent for PlacesAlbum {
             centre_cell: h3o::LatLng::new(0.0, 0.0)
                 .expect("0/0 is a valid lat/lng")
                 .to_cell(h3o::Resolution::Zero),
+
+            place_list: place_list.clone(),
+            place_list_status: place_list_status.clone(),
+            view_stack: root.clone(),
+            pick_banner: pick_banner.clone(),
+            picking: Vec::new(),
         };
 
         let widgets = view_output!();
@@ -206,6 +298,7 @@ impl SimpleComponent for PlacesAlbum {
                 } else {
                     info!("Places view is inactive so clearing");
                     self.marker_layer.remove_all();
+                    self.place_list.remove_all();
                     self.need_refresh = true;
                 }
             }
@@ -225,6 +318,35 @@ impl SimpleComponent for PlacesAlbum {
             PlacesAlbumInput::Move => {
                 self.update_on_move(&sender);
             },
+            PlacesAlbumInput::PickLocation(items) => {
+                info!("Picking location of {} items", items.len());
+                self.pick_banner.set_title(&fl!("places-page-pick-location", "title", count = items.len()));
+                self.pick_banner.set_revealed(!items.is_empty());
+                self.view_stack.set_visible_child_name("map");
+                self.picking = items;
+            },
+            PlacesAlbumInput::CancelPickLocation => {
+                self.pick_banner.set_revealed(false);
+                self.picking.clear();
+            },
+            PlacesAlbumInput::MapClicked(x, y) => {
+                if self.picking.is_empty() {
+                    return;
+                }
+
+                let map = self.map.map().expect("Must have map");
+                let (latitude, longitude) = self.viewport.widget_coords_to_location(&map, x, y);
+
+                let Some(location) = GPSLocation::from_f64(latitude, longitude) else {
+                    error!("Invalid picked location: lat={}, lng={}", latitude, longitude);
+                    return;
+                };
+
+                info!("Setting location of {} items to lat={}, lng={}", self.picking.len(), latitude, longitude);
+                let items = std::mem::take(&mut self.picking);
+                self.pick_banner.set_revealed(false);
+                let _ = sender.output(PlacesAlbumOutput::SetLocation(items, location));
+            },
         }
     }
 }
@@ -384,9 +506,82 @@ impl PlacesAlbum {
         self.viewport.set_zoom_level(DEFAULT_ZOOM_LEVEL);
         self.update_on_zoom(&PlacesAlbum::zoom_to_resolution(DEFAULT_ZOOM_LEVEL));
         self.update_on_move(sender);
+        self.update_place_list(sender);
         self.need_refresh = false;
     }
 
+    /// List named places with a row for each country that expands to show its cities.
+    fn update_place_list(&mut self, sender: &ComponentSender<Self>) {
+        self.place_list.remove_all();
+
+        // Count of items for each city in each country, sorted by name.
+        let mut countries: BTreeMap<String, BTreeMap<String, usize>> = BTreeMap::new();
+        for place in self.state.read().iter().filter_map(|v| v.place.as_ref()) {
+            *countries
+                .entry(place.country.clone())
+                .or_default()
+                .entry(place.city.clone())
+                .or_default() += 1;
+        }
+
+        info!("{} countries with named places", countries.len());
+
+        self.place_list.set_visible(!countries.is_empty());
+        self.place_list_status.set_visible(countries.is_empty());
+
+        for (country, cities) in countries {
+            let count: usize = cities.values().sum();
+
+            let country_row = adw::ExpanderRow::builder()
+                .title(&country)
+                .subtitle(fl!("places-page-place-count", count = count))
+                .use_markup(false)
+                .build();
+
+            let view_country = gtk::Button::builder()
+                .icon_name("go-next-symbolic")
+                .tooltip_text(fl!("places-page-view-country", "tooltip"))
+                .valign(gtk::Align::Center)
+                .css_classes(["flat"])
+                .build();
+
+            {
+                let sender = sender.clone();
+                let country = country.clone();
+                view_country.connect_clicked(move |_| {
+                    let _ = sender.output(PlacesAlbumOutput::Place(Query::Country(country.clone()), country.clone()));
+                });
+            }
+
+            country_row.add_suffix(&view_country);
+
+            for (city, count) in cities {
+                let city_row = adw::ActionRow::builder()
+                    .title(&city)
+                    .subtitle(fl!("places-page-place-count", count = count))
+                    .use_markup(false)
+                    .activatable(true)
+                    .build();
+
+                city_row.add_suffix(&gtk::Image::from_icon_name("go-next-symbolic"));
+
+                {
+                    let sender = sender.clone();
+                    let country = country.clone();
+                    city_row.connect_activated(move |_| {
+                        let query = Query::City(country.clone(), city.clone());
+                        let name = format!("{}, {}", city, country);
+                        let _ = sender.output(PlacesAlbumOutput::Place(query, name));
+                    });
+                }
+
+                country_row.add_row(&city_row);
+            }
+
+            self.place_list.append(&country_row);
+        }
+    }
+
     /// Make thumbnail to put onto map
     fn to_pin_thumbnail(&self, visual: &Visual, count: Option<usize>, sender: &ComponentSender<PlacesAlbum>) -> gtk::Frame {
         let picture = if visual.thumbnail_path.as_ref().is_some_and(|x| x.exists()) {
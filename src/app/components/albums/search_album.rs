@@ -0,0 +1,259 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use gtk::prelude::OrientableExt;
+use fotema_core::VisualId;
+use relm4::gtk;
+use relm4::gtk::prelude::*;
+use relm4::*;
+use relm4::adw;
+use relm4::adw::prelude::*;
+
+use crate::app::adaptive;
+use crate::app::SharedState;
+use crate::app::ActiveView;
+use crate::app::ViewName;
+use crate::app::components::albums:: {
+    album::{Album, AlbumInput, AlbumOutput},
+    album_filter::AlbumFilter,
+    album_sort::AlbumSort,
+};
+
+use fotema_core::search::{self, SearchQuery, SearchResults};
+use fotema_core::user_album::AlbumItem;
+use crate::fl;
+
+use tracing::{error, info};
+
+#[derive(Debug)]
+pub enum SearchAlbumInput {
+
+    /// Album is visible
+    Activate,
+
+    /// Search text has changed
+    Search(String),
+
+    /// Library has been reloaded, so search again.
+    Refresh,
+
+    /// Adapt to layout
+    Adapt(adaptive::Layout),
+
+    Sort(AlbumSort),
+
+    /// Picture selected in underlying album
+    Selected(VisualId),
+
+    /// Start selecting photos and videos.
+    SelectionMode,
+
+    /// Add selected photos and videos to a user album.
+    AddToUserAlbum(Vec<AlbumItem>),
+
+    /// Set the location of selected photos and videos.
+    SetLocation(Vec<AlbumItem>),
+
+    /// Ignore an event.
+    Ignore,
+}
+
+#[derive(Debug)]
+pub enum SearchAlbumOutput {
+    /// User has selected photo or video in grid view
+    Selected(VisualId, AlbumFilter),
+
+    /// User wants to add photos and videos to a user album.
+    AddToUserAlbum(Vec<AlbumItem>),
+
+    /// User wants to set the location of photos and videos by picking a point on the map.
+    SetLocation(Vec<AlbumItem>),
+}
+
+pub struct SearchAlbum {
+    state: SharedState,
+    repo: search::Repository,
+    album: Controller<Album>,
+    active_view: ActiveView,
+
+    /// Search text typed by user.
+    text: String,
+
+    /// Results of last search. None if search text is empty.
+    results: Option<SearchResults>,
+
+    entry: gtk::SearchEntry,
+    status: adw::StatusPage,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for SearchAlbum {
+    type Init = (SharedState, search::Repository, ActiveView);
+    type Input = SearchAlbumInput;
+    type Output = SearchAlbumOutput;
+
+    view! {
+        adw::ToolbarView {
+            add_top_bar = &adw::HeaderBar {
+                #[wrap(Some)]
+                set_title_widget = &adw::Clamp {
+                    set_maximum_size: 400,
+
+                    #[local_ref]
+                    entry -> gtk::SearchEntry {
+                        set_hexpand: true,
+                        set_placeholder_text: Some(&fl!("search-album", "placeholder")),
+                        connect_search_changed[sender] => move |entry| {
+                            sender.input(SearchAlbumInput::Search(entry.text().to_string()));
+                        },
+                    },
+                },
+
+                pack_end = &gtk::Button {
+                    set_icon_name: "object-select-symbolic",
+                    set_tooltip_text: Some(&fl!("album-select-button", "tooltip")),
+                    connect_clicked => SearchAlbumInput::SelectionMode,
+                },
+            },
+
+            #[wrap(Some)]
+            set_content = &gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                set_vexpand: true,
+
+                model.album.widget(),
+
+                #[local_ref]
+                status -> adw::StatusPage {
+                    set_valign: gtk::Align::Start,
+                    set_vexpand: true,
+                    set_icon_name: Some("system-search-symbolic"),
+                },
+            }
+        }
+    }
+
+    fn init(
+        (state, repo, active_view): Self::Init,
+        _root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let album = Album::builder()
+            .launch((state.clone(), active_view.clone(), ViewName::Search, AlbumFilter::None))
+            .forward(sender.input_sender(), |msg| match msg {
+                AlbumOutput::Selected(id, _) => SearchAlbumInput::Selected(id),
+                AlbumOutput::ScrollOffset(_) => SearchAlbumInput::Ignore,
+                AlbumOutput::AddToUserAlbum(items) => SearchAlbumInput::AddToUserAlbum(items),
+                AlbumOutput::SetLocation(items) => SearchAlbumInput::SetLocation(items),
+                AlbumOutput::RemoveFromUserAlbum(_, _) => SearchAlbumInput::Ignore,
+                AlbumOutput::SplitEvent(_, _) => SearchAlbumInput::Ignore,
+            });
+
+        let entry = gtk::SearchEntry::new();
+        let status = adw::StatusPage::new();
+
+        let model = SearchAlbum {
+            state,
+            repo,
+            album,
+            active_view,
+            text: String::new(),
+            results: None,
+            entry: entry.clone(),
+            status: status.clone(),
+        };
+
+        let widgets = view_output!();
+
+        model.update_status();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            SearchAlbumInput::Activate => {
+                *self.active_view.write() = ViewName::Search;
+                self.album.sender().emit(AlbumInput::Activate);
+                self.entry.grab_focus();
+            }
+            SearchAlbumInput::Search(text) => {
+                if text.trim() == self.text.trim() {
+                    return;
+                }
+                self.text = text;
+                self.search();
+                self.album.sender().emit(AlbumInput::ScrollToTop);
+            }
+            SearchAlbumInput::Refresh => {
+                if *self.active_view.read() == ViewName::Search {
+                    info!("Search view is active so refreshing");
+                    self.search();
+                    self.album.sender().emit(AlbumInput::Refresh);
+                }
+            }
+            SearchAlbumInput::Sort(sort) => {
+                self.album.sender().emit(AlbumInput::Sort(sort));
+            },
+            SearchAlbumInput::SelectionMode => {
+                self.album.sender().emit(AlbumInput::SelectionMode(true));
+            },
+            SearchAlbumInput::AddToUserAlbum(items) => {
+                let _ = sender.output(SearchAlbumOutput::AddToUserAlbum(items));
+            },
+            SearchAlbumInput::SetLocation(items) => {
+                let _ = sender.output(SearchAlbumOutput::SetLocation(items));
+            },
+            SearchAlbumInput::Ignore => {},
+            SearchAlbumInput::Selected(visual_id) => {
+                if let Some(ref results) = self.results {
+                    let _ = sender.output(SearchAlbumOutput::Selected(visual_id, AlbumFilter::Search(results.clone())));
+                }
+            },
+            SearchAlbumInput::Adapt(layout) => {
+                // FIXME album should directly subscribe to layout state.
+                self.album.sender().emit(AlbumInput::Adapt(layout));
+            },
+        }
+    }
+}
+
+impl SearchAlbum {
+    fn search(&mut self) {
+        let query = SearchQuery::parse(&self.text);
+
+        self.results = if query.is_empty() {
+            None
+        } else {
+            self.repo.search(&query)
+                .inspect_err(|e| error!("Failed searching for '{}': {}", self.text, e))
+                .ok()
+        };
+
+        let filter = self.results.clone()
+            .map(AlbumFilter::Search)
+            .unwrap_or(AlbumFilter::None);
+
+        self.album.sender().emit(AlbumInput::Filter(filter));
+        self.update_status();
+    }
+
+    /// Show help when there is no search text, or a message when nothing is found.
+    fn update_status(&self) {
+        let count = self.results.as_ref()
+            .map(|results| self.state.read().iter().filter(|v| results.matches(v)).count())
+            .unwrap_or(0);
+
+        if self.results.is_none() {
+            self.status.set_title(&fl!("search-album-status-help", "title"));
+            self.status.set_description(Some(&fl!("search-album-status-help", "description")));
+        } else {
+            self.status.set_title(&fl!("search-album-status-none", "title"));
+            self.status.set_description(Some(&fl!("search-album-status-none", "description")));
+        }
+
+        self.status.set_visible(count == 0);
+        self.album.widget().set_visible(count > 0);
+    }
+}
@@ -0,0 +1,320 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use gtk::prelude::OrientableExt;
+use fotema_core::VisualId;
+use relm4::gtk;
+use relm4::gtk::prelude::*;
+use relm4::*;
+use relm4::adw;
+use relm4::adw::prelude::*;
+use relm4::actions::{RelmAction, RelmActionGroup};
+
+use crate::app::adaptive;
+use crate::app::SharedState;
+use crate::app::ActiveView;
+use crate::app::ViewName;
+use crate::app::components::albums:: {
+    album::{Album, AlbumInput, AlbumOutput},
+    album_filter::AlbumFilter,
+    album_sort::AlbumSort,
+};
+
+use fotema_core::smart_album::{self, SmartAlbum};
+use fotema_core::user_album::AlbumItem;
+use crate::fl;
+
+use tracing::{error, info};
+
+relm4::new_action_group!(SmartAlbumActionGroup, "smartalbum");
+
+// Edit name and query of a smart album
+relm4::new_stateless_action!(EditAction, SmartAlbumActionGroup, "edit");
+
+// Delete a smart album
+relm4::new_stateless_action!(DeleteAction, SmartAlbumActionGroup, "delete");
+
+#[derive(Debug)]
+pub enum SmartAlbumInput {
+
+    /// Album is visible
+    Activate,
+
+    /// Smart album has been edited, or state has been updated
+    Refresh,
+
+    /// View a smart album
+    View(SmartAlbum),
+
+    /// Adapt to layout
+    Adapt(adaptive::Layout),
+
+    Sort(AlbumSort),
+
+    /// Picture selected in underlying album
+    Selected(VisualId),
+
+    /// Start selecting photos and videos.
+    SelectionMode,
+
+    /// Add selected photos and videos to a user album.
+    AddToUserAlbum(Vec<AlbumItem>),
+
+    /// Set the location of selected photos and videos.
+    SetLocation(Vec<AlbumItem>),
+
+    /// Start edit smart album flow
+    Edit,
+
+    /// Start delete smart album flow.
+    DeleteDialog,
+
+    /// Actually delete smart album.
+    Delete,
+
+    /// Ignore an event.
+    Ignore,
+}
+
+#[derive(Debug)]
+pub enum SmartAlbumOutput {
+    /// User has selected photo or video in grid view
+    Selected(VisualId, AlbumFilter),
+
+    /// User wants to edit smart album.
+    Edit(SmartAlbum),
+
+    /// Smart album deleted.
+    Deleted,
+
+    /// User wants to add photos and videos to a user album.
+    AddToUserAlbum(Vec<AlbumItem>),
+
+    /// User wants to set the location of photos and videos by picking a point on the map.
+    SetLocation(Vec<AlbumItem>),
+}
+
+pub struct SmartAlbumPage {
+    repo: smart_album::Repository,
+    smart_album: Option<SmartAlbum>,
+    album: Controller<Album>,
+    title: gtk::Label,
+    active_view: ActiveView,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for SmartAlbumPage {
+    type Init = (SharedState, smart_album::Repository, ActiveView);
+    type Input = SmartAlbumInput;
+    type Output = SmartAlbumOutput;
+
+    menu! {
+        primary_menu: {
+            section! {
+                &fl!("smart-album-menu-edit") => EditAction,
+                &fl!("smart-album-menu-delete") => DeleteAction,
+            }
+        }
+    }
+
+    view! {
+        adw::ToolbarView {
+            add_top_bar = &adw::HeaderBar {
+                #[wrap(Some)]
+                #[local_ref]
+                set_title_widget = &title -> gtk::Label {
+                    add_css_class: "title",
+                },
+
+                pack_end = &gtk::MenuButton {
+                    set_icon_name: "open-menu-symbolic",
+                    set_menu_model: Some(&primary_menu),
+                },
+
+                pack_end = &gtk::Button {
+                    set_icon_name: "object-select-symbolic",
+                    set_tooltip_text: Some(&fl!("album-select-button", "tooltip")),
+                    connect_clicked => SmartAlbumInput::SelectionMode,
+                },
+            },
+
+            #[wrap(Some)]
+            set_content = &gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                set_vexpand: true,
+
+                model.album.widget(),
+            }
+        }
+    }
+
+    fn init(
+        (state, repo, active_view): Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let album = Album::builder()
+            .launch((state.clone(), active_view.clone(), ViewName::SmartAlbum, AlbumFilter::None))
+            .forward(sender.input_sender(), |msg| match msg {
+                AlbumOutput::Selected(id, _) => SmartAlbumInput::Selected(id),
+                AlbumOutput::ScrollOffset(_) => SmartAlbumInput::Ignore,
+                AlbumOutput::AddToUserAlbum(items) => SmartAlbumInput::AddToUserAlbum(items),
+                AlbumOutput::SetLocation(items) => SmartAlbumInput::SetLocation(items),
+                AlbumOutput::RemoveFromUserAlbum(_, _) => SmartAlbumInput::Ignore,
+                AlbumOutput::SplitEvent(_, _) => SmartAlbumInput::Ignore,
+            });
+
+        let title = gtk::Label::builder()
+            .build();
+
+        let model = SmartAlbumPage {
+            repo,
+            smart_album: None,
+            title: title.clone(),
+            album,
+            active_view,
+        };
+
+        let widgets = view_output!();
+
+        let mut actions = RelmActionGroup::<SmartAlbumActionGroup>::new();
+
+        let edit_action = {
+            let sender = sender.clone();
+            RelmAction::<EditAction>::new_stateless(move |_| {
+                sender.input(SmartAlbumInput::Edit);
+            })
+        };
+
+        let delete_action = {
+            let sender = sender.clone();
+            RelmAction::<DeleteAction>::new_stateless(move |_| {
+                sender.input(SmartAlbumInput::DeleteDialog);
+            })
+        };
+
+        actions.add_action(edit_action);
+        actions.add_action(delete_action);
+        actions.register_for_widget(&root);
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            SmartAlbumInput::Activate => {
+                *self.active_view.write() = ViewName::SmartAlbum;
+                self.album.sender().emit(AlbumInput::Activate);
+            }
+            SmartAlbumInput::Refresh => {
+                let Some(ref smart_album) = self.smart_album else {
+                    return;
+                };
+
+                // Reload smart album in case name or query have been edited.
+                let smart_album_id = smart_album.smart_album_id;
+                let smart_album = self.repo.all()
+                    .map(|albums| albums.into_iter().find(|x| x.smart_album_id == smart_album_id));
+
+                match smart_album {
+                    Ok(Some(smart_album)) => {
+                        self.title.set_label(&smart_album.name);
+                        self.album.sender().emit(AlbumInput::Filter(AlbumFilter::Smart(smart_album.query.clone())));
+                        self.album.sender().emit(AlbumInput::Refresh);
+                        self.smart_album = Some(smart_album);
+                    },
+                    Ok(None) => {
+                        info!("Smart album {} no longer exists", smart_album_id);
+                        self.smart_album = None;
+                    },
+                    Err(e) => {
+                        error!("Failed loading smart album {}: {}", smart_album_id, e);
+                    },
+                }
+            }
+            SmartAlbumInput::View(smart_album) => {
+                info!("Viewing smart album: {}", smart_album.smart_album_id);
+                self.album.sender().emit(AlbumInput::Activate);
+                self.album.sender().emit(AlbumInput::Filter(AlbumFilter::Smart(smart_album.query.clone())));
+                self.album.sender().emit(AlbumInput::ScrollToTop);
+
+                self.title.set_label(&smart_album.name);
+                self.smart_album = Some(smart_album);
+            }
+            SmartAlbumInput::Sort(sort) => {
+                self.album.sender().emit(AlbumInput::Sort(sort));
+            },
+            SmartAlbumInput::SelectionMode => {
+                self.album.sender().emit(AlbumInput::SelectionMode(true));
+            },
+            SmartAlbumInput::AddToUserAlbum(items) => {
+                let _ = sender.output(SmartAlbumOutput::AddToUserAlbum(items));
+            },
+            SmartAlbumInput::SetLocation(items) => {
+                let _ = sender.output(SmartAlbumOutput::SetLocation(items));
+            },
+            SmartAlbumInput::Ignore => {},
+            SmartAlbumInput::Selected(visual_id) => {
+                if let Some(ref smart_album) = self.smart_album {
+                    let _ = sender.output(SmartAlbumOutput::Selected(visual_id, AlbumFilter::Smart(smart_album.query.clone())));
+                }
+            },
+            SmartAlbumInput::Adapt(layout) => {
+                // FIXME album should directly subscribe to layout state.
+                self.album.sender().emit(AlbumInput::Adapt(layout));
+            },
+            SmartAlbumInput::Edit => {
+                if let Some(ref smart_album) = self.smart_album {
+                    let _ = sender.output(SmartAlbumOutput::Edit(smart_album.clone()));
+                }
+            },
+            SmartAlbumInput::DeleteDialog => {
+                let Some(ref smart_album) = self.smart_album else {
+                    info!("Asked to delete smart album, but no album to delete");
+                    return;
+                };
+                info!("Starting delete flow for smart album: {}", smart_album.smart_album_id);
+
+                let dialog = adw::AlertDialog::builder()
+                    .heading(fl!("smart-album-delete-dialog", "heading"))
+                    .body(fl!("smart-album-delete-dialog", "body"))
+                    .close_response("cancel")
+                    .default_response("delete")
+                    .build();
+
+                dialog.add_response("cancel", &fl!("smart-album-delete-dialog", "cancel-button"));
+                dialog.set_default_response(Some("cancel"));
+                dialog.set_close_response("cancel");
+
+                dialog.add_response("delete", &fl!("smart-album-delete-dialog", "delete-button"));
+                dialog.set_response_appearance("delete", adw::ResponseAppearance::Destructive);
+
+                dialog.connect_response(None, move |_, response| {
+                    if response == "delete" {
+                       sender.input(SmartAlbumInput::Delete);
+                    }
+                });
+
+                if let Some(root) = gtk::Widget::root(self.title.widget_ref()) {
+                    dialog.present(Some(&root));
+                } else {
+                    error!("Couldn't get root widget!");
+                }
+            },
+            SmartAlbumInput::Delete => {
+                let Some(ref smart_album) = self.smart_album else {
+                    info!("Asked to delete smart album, but no album to delete");
+                    return;
+                };
+                info!("Deleting smart album: {}", smart_album.smart_album_id);
+                if let Err(e) = self.repo.delete(smart_album.smart_album_id) {
+                    error!("Failed to delete smart album: {}", e);
+                    return;
+                }
+                self.smart_album = None;
+                let _ = sender.output(SmartAlbumOutput::Deleted);
+            },
+        }
+    }
+}
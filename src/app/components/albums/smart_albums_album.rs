@@ -0,0 +1,170 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use gtk::prelude::OrientableExt;
+
+use fotema_core::smart_album::{self, SmartAlbum};
+
+use relm4::adw;
+use relm4::adw::prelude::*;
+use relm4::gtk;
+use relm4::*;
+
+use crate::app::SharedState;
+use crate::app::ActiveView;
+use crate::app::ViewName;
+use crate::fl;
+
+use tracing::{error, info};
+
+#[derive(Debug)]
+pub enum SmartAlbumsAlbumInput {
+    Activate,
+
+    // Reload smart albums from database and re-evaluate queries
+    Refresh,
+
+    AlbumSelected(usize), // Index into smart albums vector
+}
+
+#[derive(Debug)]
+pub enum SmartAlbumsAlbumOutput {
+    AlbumSelected(SmartAlbum),
+}
+
+pub struct SmartAlbumsAlbum {
+    state: SharedState,
+    repo: smart_album::Repository,
+    active_view: ActiveView,
+    albums: Vec<SmartAlbum>,
+    list_box: gtk::ListBox,
+    status: adw::StatusPage,
+    scrolled: gtk::ScrolledWindow,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for SmartAlbumsAlbum {
+    type Init = (SharedState, smart_album::Repository, ActiveView);
+    type Input = SmartAlbumsAlbumInput;
+    type Output = SmartAlbumsAlbumOutput;
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Vertical,
+
+            #[local_ref]
+            scrolled -> gtk::ScrolledWindow {
+                set_vexpand: true,
+
+                adw::Clamp {
+                    set_margin_all: 12,
+
+                    #[local_ref]
+                    list_box -> gtk::ListBox {
+                        set_valign: gtk::Align::Start,
+                        set_selection_mode: gtk::SelectionMode::None,
+                        add_css_class: "boxed-list",
+
+                        connect_row_activated[sender] => move |_, row| {
+                            if let Ok(index) = usize::try_from(row.index()) {
+                                sender.input(SmartAlbumsAlbumInput::AlbumSelected(index));
+                            }
+                        },
+                    },
+                },
+            },
+
+            #[local_ref]
+            status -> adw::StatusPage {
+                set_valign: gtk::Align::Start,
+                set_vexpand: true,
+
+                set_visible: false,
+                set_icon_name: Some("system-search-symbolic"),
+                set_title: &fl!("smart-albums-album-status-none", "title"),
+                set_description: Some(&fl!("smart-albums-album-status-none", "description")),
+            },
+        }
+    }
+
+    fn init(
+        (state, repo, active_view): Self::Init,
+        _root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let list_box = gtk::ListBox::new();
+        let status = adw::StatusPage::new();
+        let scrolled = gtk::ScrolledWindow::new();
+
+        let model = SmartAlbumsAlbum {
+            state,
+            repo,
+            active_view,
+            albums: Vec::new(),
+            list_box: list_box.clone(),
+            status: status.clone(),
+            scrolled: scrolled.clone(),
+        };
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            SmartAlbumsAlbumInput::Activate => {
+                *self.active_view.write() = ViewName::SmartAlbums;
+                self.refresh();
+            },
+            SmartAlbumsAlbumInput::Refresh => {
+                // Also refresh when viewing a single smart album, so the list is
+                // current when navigating back.
+                let active_view = *self.active_view.read();
+                if active_view == ViewName::SmartAlbums || active_view == ViewName::SmartAlbum {
+                    info!("Smart albums view is active so refreshing");
+                    self.refresh();
+                }
+            },
+            SmartAlbumsAlbumInput::AlbumSelected(index) => {
+                if let Some(album) = self.albums.get(index) {
+                    let _ = sender.output(SmartAlbumsAlbumOutput::AlbumSelected(album.clone()));
+                }
+            },
+        }
+    }
+}
+
+impl SmartAlbumsAlbum {
+    fn refresh(&mut self) {
+        self.albums = match self.repo.all() {
+            Ok(albums) => albums,
+            Err(e) => {
+                error!("Failed loading smart albums: {}", e);
+                return;
+            }
+        };
+
+        self.list_box.remove_all();
+
+        {
+            let data = self.state.read();
+            for album in &self.albums {
+                let count = data.iter().filter(|v| album.query.matches(v)).count();
+
+                let row = adw::ActionRow::builder()
+                    .title(&album.name)
+                    .subtitle(fl!("smart-albums-album-count", count = count))
+                    .activatable(true)
+                    .build();
+
+                row.add_suffix(&gtk::Image::from_icon_name("go-next-symbolic"));
+                self.list_box.append(&row);
+            }
+        }
+
+        self.status.set_visible(self.albums.is_empty());
+        self.scrolled.set_visible(!self.albums.is_empty());
+    }
+}
@@ -0,0 +1,398 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use gtk::prelude::OrientableExt;
+use fotema_core::VisualId;
+use relm4::gtk;
+use relm4::gtk::prelude::*;
+use relm4::*;
+use relm4::adw;
+use relm4::adw::prelude::*;
+use relm4::actions::{RelmAction, RelmActionGroup};
+
+use crate::app::adaptive;
+use crate::app::SharedState;
+use crate::app::ActiveView;
+use crate::app::ViewName;
+use crate::app::components::albums:: {
+    album::{Album, AlbumInput, AlbumOutput},
+    album_filter::AlbumFilter,
+};
+
+use fotema_core::user_album::{self, AlbumId, AlbumItem, UserAlbum};
+use crate::fl;
+
+use tracing::{error, info};
+
+relm4::new_action_group!(UserAlbumActionGroup, "useralbum");
+
+// Rename a user album
+relm4::new_stateless_action!(RenameAction, UserAlbumActionGroup, "rename");
+
+// Delete a user album
+relm4::new_stateless_action!(DeleteAction, UserAlbumActionGroup, "delete");
+
+#[derive(Debug)]
+pub enum UserAlbumInput {
+
+    /// Album is visible
+    Activate,
+
+    /// Album has changed, or state has been updated
+    Refresh,
+
+    /// View a user album
+    View(UserAlbum),
+
+    /// Adapt to layout
+    Adapt(adaptive::Layout),
+
+    /// Picture selected in underlying album
+    Selected(VisualId),
+
+    /// Start selecting photos and videos.
+    SelectionMode,
+
+    /// Add selected photos and videos to a user album.
+    AddToUserAlbum(Vec<AlbumItem>),
+
+    /// Set the location of selected photos and videos.
+    SetLocation(Vec<AlbumItem>),
+
+    /// Remove selected photos and videos from this album.
+    RemoveFromUserAlbum(AlbumId, Vec<AlbumItem>),
+
+    /// Start rename album flow
+    RenameDialog,
+
+    /// Actually rename album
+    Rename(String),
+
+    /// Start delete album flow.
+    DeleteDialog,
+
+    /// Actually delete album.
+    Delete,
+
+    /// Ignore an event.
+    Ignore,
+}
+
+#[derive(Debug)]
+pub enum UserAlbumOutput {
+    /// User has selected photo or video in grid view
+    Selected(VisualId, AlbumFilter),
+
+    /// Album deleted.
+    Deleted,
+
+    /// Album renamed, or items added or removed.
+    Changed,
+
+    /// User wants to add photos and videos to a user album.
+    AddToUserAlbum(Vec<AlbumItem>),
+
+    /// User wants to set the location of photos and videos by picking a point on the map.
+    SetLocation(Vec<AlbumItem>),
+}
+
+pub struct UserAlbumPage {
+    repo: user_album::Repository,
+    user_album: Option<UserAlbum>,
+    album: Controller<Album>,
+    title: gtk::Label,
+    active_view: ActiveView,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for UserAlbumPage {
+    type Init = (SharedState, user_album::Repository, ActiveView);
+    type Input = UserAlbumInput;
+    type Output = UserAlbumOutput;
+
+    menu! {
+        primary_menu: {
+            section! {
+                &fl!("user-album-menu-rename") => RenameAction,
+                &fl!("user-album-menu-delete") => DeleteAction,
+            }
+        }
+    }
+
+    view! {
+        adw::ToolbarView {
+            add_top_bar = &adw::HeaderBar {
+                #[wrap(Some)]
+                #[local_ref]
+                set_title_widget = &title -> gtk::Label {
+                    add_css_class: "title",
+                },
+
+                pack_end = &gtk::MenuButton {
+                    set_icon_name: "open-menu-symbolic",
+                    set_menu_model: Some(&primary_menu),
+                },
+
+                pack_end = &gtk::Button {
+                    set_icon_name: "object-select-symbolic",
+                    set_tooltip_text: Some(&fl!("album-select-button", "tooltip")),
+                    connect_clicked => UserAlbumInput::SelectionMode,
+                },
+            },
+
+            #[wrap(Some)]
+            set_content = &gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                set_vexpand: true,
+
+                model.album.widget(),
+            }
+        }
+    }
+
+    fn init(
+        (state, repo, active_view): Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let album = Album::builder()
+            .launch((state.clone(), active_view.clone(), ViewName::UserAlbum, AlbumFilter::None))
+            .forward(sender.input_sender(), |msg| match msg {
+                AlbumOutput::Selected(id, _) => UserAlbumInput::Selected(id),
+                AlbumOutput::ScrollOffset(_) => UserAlbumInput::Ignore,
+                AlbumOutput::AddToUserAlbum(items) => UserAlbumInput::AddToUserAlbum(items),
+                AlbumOutput::SetLocation(items) => UserAlbumInput::SetLocation(items),
+                AlbumOutput::RemoveFromUserAlbum(album_id, items) => UserAlbumInput::RemoveFromUserAlbum(album_id, items),
+                AlbumOutput::SplitEvent(_, _) => UserAlbumInput::Ignore,
+            });
+
+        let title = gtk::Label::builder()
+            .build();
+
+        let model = UserAlbumPage {
+            repo,
+            user_album: None,
+            title: title.clone(),
+            album,
+            active_view,
+        };
+
+        let widgets = view_output!();
+
+        let mut actions = RelmActionGroup::<UserAlbumActionGroup>::new();
+
+        let rename_action = {
+            let sender = sender.clone();
+            RelmAction::<RenameAction>::new_stateless(move |_| {
+                sender.input(UserAlbumInput::RenameDialog);
+            })
+        };
+
+        let delete_action = {
+            let sender = sender.clone();
+            RelmAction::<DeleteAction>::new_stateless(move |_| {
+                sender.input(UserAlbumInput::DeleteDialog);
+            })
+        };
+
+        actions.add_action(rename_action);
+        actions.add_action(delete_action);
+        actions.register_for_widget(&root);
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            UserAlbumInput::Activate => {
+                *self.active_view.write() = ViewName::UserAlbum;
+                self.album.sender().emit(AlbumInput::Activate);
+            }
+            UserAlbumInput::Refresh => {
+                let Some(ref user_album) = self.user_album else {
+                    return;
+                };
+
+                // Reload album so membership, order, and name are current.
+                let album_id = user_album.album_id;
+                let user_album = self.repo.all()
+                    .map(|albums| albums.into_iter().find(|x| x.album_id == album_id));
+
+                match user_album {
+                    Ok(Some(user_album)) => {
+                        self.title.set_label(&user_album.name);
+                        self.album.sender().emit(AlbumInput::Filter(AlbumFilter::UserAlbum(user_album.clone())));
+                        self.user_album = Some(user_album);
+                    },
+                    Ok(None) => {
+                        info!("User album {} no longer exists", album_id);
+                        self.user_album = None;
+                    },
+                    Err(e) => {
+                        error!("Failed loading user album {}: {}", album_id, e);
+                    },
+                }
+            }
+            UserAlbumInput::View(user_album) => {
+                info!("Viewing user album: {}", user_album.album_id);
+                self.album.sender().emit(AlbumInput::Activate);
+                self.album.sender().emit(AlbumInput::Filter(AlbumFilter::UserAlbum(user_album.clone())));
+                self.album.sender().emit(AlbumInput::ScrollToTop);
+
+                self.title.set_label(&user_album.name);
+                self.user_album = Some(user_album);
+            }
+            UserAlbumInput::SelectionMode => {
+                self.album.sender().emit(AlbumInput::SelectionMode(true));
+            },
+            UserAlbumInput::AddToUserAlbum(items) => {
+                let _ = sender.output(UserAlbumOutput::AddToUserAlbum(items));
+            },
+            UserAlbumInput::SetLocation(items) => {
+                let _ = sender.output(UserAlbumOutput::SetLocation(items));
+            },
+            UserAlbumInput::RemoveFromUserAlbum(album_id, items) => {
+                info!("Removing {} items from user album {}", items.len(), album_id);
+                if let Err(e) = self.repo.remove(album_id, &items) {
+                    error!("Failed removing items from user album: {}", e);
+                    return;
+                }
+                sender.input(UserAlbumInput::Refresh);
+                let _ = sender.output(UserAlbumOutput::Changed);
+            },
+            UserAlbumInput::Ignore => {},
+            UserAlbumInput::Selected(visual_id) => {
+                if let Some(ref user_album) = self.user_album {
+                    let _ = sender.output(UserAlbumOutput::Selected(visual_id, AlbumFilter::UserAlbum(user_album.clone())));
+                }
+            },
+            UserAlbumInput::Adapt(layout) => {
+                // FIXME album should directly subscribe to layout state.
+                self.album.sender().emit(AlbumInput::Adapt(layout));
+            },
+            UserAlbumInput::RenameDialog => {
+                let Some(ref user_album) = self.user_album else {
+                    info!("Asked to rename user album, but no album to rename");
+                    return;
+                };
+                info!("Renaming user album {}", user_album.album_id);
+
+                let album_name = gtk::Entry::builder()
+                    .placeholder_text(fl!("user-album-rename-dialog", "placeholder"))
+                    .text(&user_album.name)
+                    .build();
+
+                let dialog = adw::AlertDialog::builder()
+                    .heading(fl!("user-album-rename-dialog", "heading"))
+                    .close_response("cancel")
+                    .default_response("rename")
+                    .extra_child(&album_name)
+                    .build();
+
+                dialog.add_response("cancel", &fl!("user-album-rename-dialog", "cancel-button"));
+                dialog.set_default_response(Some("cancel"));
+                dialog.set_close_response("cancel");
+
+                dialog.add_response("rename", &fl!("user-album-rename-dialog", "rename-button"));
+                dialog.set_response_appearance("rename", adw::ResponseAppearance::Suggested);
+
+                {
+                    let album_name = album_name.clone();
+                    let sender = sender.clone();
+                    dialog.connect_response(None, move |_, response| {
+                        if response == "rename" {
+                            let name = album_name.text();
+                            sender.input(UserAlbumInput::Rename(name.into()));
+                        }
+                    });
+                }
+
+                {
+                    let album_name = album_name.clone();
+                    let sender = sender.clone();
+                    let dialog = dialog.clone();
+                    album_name.clone().connect_activate(move |_| {
+                        dialog.close();
+                        let name = album_name.text();
+                        sender.input(UserAlbumInput::Rename(name.into()));
+                    });
+                }
+
+                if let Some(root) = gtk::Widget::root(self.title.widget_ref()) {
+                    dialog.present(Some(&root));
+                    album_name.grab_focus();
+                } else {
+                    error!("Couldn't get root widget!");
+                }
+            },
+            UserAlbumInput::Rename(name) => {
+                let Some(ref mut user_album) = self.user_album else {
+                    info!("Asked to rename user album, but no album to rename");
+                    return;
+                };
+
+                let name = name.trim().to_string();
+                if name.is_empty() {
+                    return;
+                }
+
+                info!("Renaming {} to {}", user_album.name, name);
+
+                if let Err(e) = self.repo.rename(user_album.album_id, &name) {
+                    error!("Failed to rename user album: {}", e);
+                    return;
+                }
+                self.title.set_label(&name);
+                user_album.name = name;
+                let _ = sender.output(UserAlbumOutput::Changed);
+            },
+            UserAlbumInput::DeleteDialog => {
+                let Some(ref user_album) = self.user_album else {
+                    info!("Asked to delete user album, but no album to delete");
+                    return;
+                };
+                info!("Starting delete flow for user album: {}", user_album.album_id);
+
+                let dialog = adw::AlertDialog::builder()
+                    .heading(fl!("user-album-delete-dialog", "heading"))
+                    .body(fl!("user-album-delete-dialog", "body"))
+                    .close_response("cancel")
+                    .default_response("delete")
+                    .build();
+
+                dialog.add_response("cancel", &fl!("user-album-delete-dialog", "cancel-button"));
+                dialog.set_default_response(Some("cancel"));
+                dialog.set_close_response("cancel");
+
+                dialog.add_response("delete", &fl!("user-album-delete-dialog", "delete-button"));
+                dialog.set_response_appearance("delete", adw::ResponseAppearance::Destructive);
+
+                dialog.connect_response(None, move |_, response| {
+                    if response == "delete" {
+                       sender.input(UserAlbumInput::Delete);
+                    }
+                });
+
+                if let Some(root) = gtk::Widget::root(self.title.widget_ref()) {
+                    dialog.present(Some(&root));
+                } else {
+                    error!("Couldn't get root widget!");
+                }
+            },
+            UserAlbumInput::Delete => {
+                let Some(ref user_album) = self.user_album else {
+                    info!("Asked to delete user album, but no album to delete");
+                    return;
+                };
+                info!("Deleting user album: {}", user_album.album_id);
+                if let Err(e) = self.repo.delete(user_album.album_id) {
+                    error!("Failed to delete user album: {}", e);
+                    return;
+                }
+                self.user_album = None;
+                let _ = sender.output(UserAlbumOutput::Deleted);
+            },
+        }
+    }
+}
@@ -0,0 +1,300 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use gtk::prelude::OrientableExt;
+
+use fotema_core::user_album;
+use fotema_core::visual::model::PictureOrientation;
+use strum::IntoEnumIterator;
+
+use relm4::adw;
+use relm4::gtk;
+use relm4::gtk::gdk;
+use relm4::gtk::gdk_pixbuf;
+use relm4::gtk::prelude::WidgetExt;
+use relm4::typed_view::grid::{RelmGridItem, TypedGridView};
+use relm4::*;
+use relm4::binding::*;
+
+use std::path;
+use std::sync::Arc;
+
+use crate::adaptive;
+use crate::app::SharedState;
+use crate::app::ActiveView;
+use crate::app::ViewName;
+use crate::fl;
+
+use tracing::{error, event, info, Level};
+
+const NARROW_EDGE_LENGTH: i32 = 170;
+const WIDE_EDGE_LENGTH: i32 = 200;
+
+#[derive(Debug)]
+struct AlbumGridItem {
+    album: user_album::UserAlbum,
+
+    // Album cover. None if album is empty.
+    cover: Option<Arc<fotema_core::visual::Visual>>,
+
+    // Length of thumbnail edge to allow for resizing when layout changes.
+    edge_length: I32Binding,
+}
+
+struct Widgets {
+    picture: gtk::Picture,
+    label: gtk::Label,
+    count_label: gtk::Label,
+
+    // If the gtk::Picture has been bound to edge_length.
+    is_bound: bool,
+}
+
+#[derive(Debug)]
+pub enum UserAlbumsAlbumInput {
+    Activate,
+
+    // Reload albums from database
+    Refresh,
+
+    AlbumSelected(u32), // Index into album grid vector
+
+    // Adapt to layout
+    Adapt(adaptive::Layout),
+}
+
+#[derive(Debug)]
+pub enum UserAlbumsAlbumOutput {
+    AlbumSelected(user_album::UserAlbum),
+}
+
+impl RelmGridItem for AlbumGridItem {
+    type Root = gtk::Box;
+    type Widgets = Widgets;
+
+    fn setup(_item: &gtk::ListItem) -> (gtk::Box, Widgets) {
+        relm4::view! {
+           my_box = gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                gtk::AspectFrame {
+                    gtk::Frame {
+                        #[name(picture)]
+                        gtk::Picture {
+                            set_can_shrink: true,
+                            set_width_request: NARROW_EDGE_LENGTH,
+                            set_height_request: NARROW_EDGE_LENGTH,
+                        }
+                    }
+                },
+
+                #[name(label)]
+                gtk::Label {
+                    add_css_class: "caption-heading",
+                    set_margin_top: 4,
+                },
+
+                #[name(count_label)]
+                gtk::Label {
+                    add_css_class: "caption",
+                    add_css_class: "dim-label",
+                    set_margin_bottom: 12,
+                },
+            }
+        }
+
+        let widgets = Widgets {
+            picture,
+            label,
+            count_label,
+            is_bound: false,
+        };
+
+        (my_box, widgets)
+    }
+
+    fn bind(&mut self, widgets: &mut Self::Widgets, _root: &mut Self::Root) {
+        widgets.label.set_text(&self.album.name);
+        widgets.count_label.set_text(&fl!("user-albums-album-count", count = self.album.len()));
+
+        // If we repeatedly bind, then Fotema will die with the following error:
+        // (fotema:2): GLib-GObject-CRITICAL **: 13:26:14.297: Too many GWeakRef registered
+        // GLib-GObject:ERROR:../gobject/gbinding.c:805:g_binding_constructed: assertion failed: (source != NULL)
+        // Bail out! GLib-GObject:ERROR:../gobject/gbinding.c:805:g_binding_constructed: assertion failed: (source != NULL)
+        if !widgets.is_bound {
+            widgets.picture.add_write_only_binding(&self.edge_length, "width-request");
+            widgets.picture.add_write_only_binding(&self.edge_length, "height-request");
+            widgets.is_bound = true;
+        }
+
+        let thumbnail_path = self.cover
+            .as_ref()
+            .and_then(|x| x.thumbnail_path.clone())
+            .filter(|x| x.exists());
+
+        if thumbnail_path.is_some() {
+            widgets.picture.set_filename(thumbnail_path);
+        } else {
+            let pb = gdk_pixbuf::Pixbuf::from_resource_at_scale(
+                "/app/fotema/Fotema/icons/scalable/actions/image-missing-symbolic.svg",
+                200, 200, true
+            ).unwrap();
+            let img = gdk::Texture::for_pixbuf(&pb);
+            widgets.picture.set_paintable(Some(&img));
+        }
+    }
+
+    fn unbind(&mut self, widgets: &mut Self::Widgets, _root: &mut Self::Root) {
+        widgets.picture.set_filename(None::<&path::Path>);
+        // clear orientation transformation css classes
+        for orient in PictureOrientation::iter() {
+            widgets.picture.remove_css_class(orient.as_ref());
+        }
+    }
+}
+
+pub struct UserAlbumsAlbum {
+    state: SharedState,
+    repo: user_album::Repository,
+    active_view: ActiveView,
+    album_grid: TypedGridView<AlbumGridItem, gtk::SingleSelection>,
+    edge_length: I32Binding,
+    status: adw::StatusPage,
+    scrolled: gtk::ScrolledWindow,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for UserAlbumsAlbum {
+    type Init = (SharedState, user_album::Repository, ActiveView);
+    type Input = UserAlbumsAlbumInput;
+    type Output = UserAlbumsAlbumOutput;
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Vertical,
+
+            #[local_ref]
+            scrolled -> gtk::ScrolledWindow {
+                set_vexpand: true,
+
+                #[local_ref]
+                albums_box -> gtk::GridView {
+                    set_orientation: gtk::Orientation::Vertical,
+                    set_single_click_activate: true,
+
+                    connect_activate[sender] => move |_, idx| {
+                        sender.input(UserAlbumsAlbumInput::AlbumSelected(idx))
+                    }
+                }
+            },
+
+            #[local_ref]
+            status -> adw::StatusPage {
+                set_valign: gtk::Align::Start,
+                set_vexpand: true,
+
+                set_visible: false,
+                set_icon_name: Some("folder-pictures-symbolic"),
+                set_title: &fl!("user-albums-album-status-none", "title"),
+                set_description: Some(&fl!("user-albums-album-status-none", "description")),
+            },
+        }
+    }
+
+    fn init(
+        (state, repo, active_view): Self::Init,
+        _root: Self::Root,
+        _sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let album_grid = TypedGridView::new();
+        let status = adw::StatusPage::new();
+        let scrolled = gtk::ScrolledWindow::new();
+
+        let model = UserAlbumsAlbum {
+            state,
+            repo,
+            active_view,
+            album_grid,
+            edge_length: I32Binding::new(NARROW_EDGE_LENGTH),
+            status: status.clone(),
+            scrolled: scrolled.clone(),
+        };
+
+        let albums_box = &model.album_grid.view;
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            UserAlbumsAlbumInput::Activate => {
+                *self.active_view.write() = ViewName::UserAlbums;
+                self.refresh();
+            },
+            UserAlbumsAlbumInput::Refresh => {
+                // Also refresh when viewing a single user album, so the grid is
+                // current when navigating back.
+                let active_view = *self.active_view.read();
+                if active_view == ViewName::UserAlbums || active_view == ViewName::UserAlbum {
+                    info!("User albums view is active so refreshing");
+                    self.refresh();
+                } else {
+                    info!("User albums view is inactive so clearing");
+                    self.album_grid.clear();
+                }
+            },
+            UserAlbumsAlbumInput::AlbumSelected(index) => {
+                event!(Level::DEBUG, "User album selected index: {}", index);
+                if let Some(item) = self.album_grid.get_visible(index) {
+                    let item = item.borrow();
+                    let _ = sender.output(UserAlbumsAlbumOutput::AlbumSelected(item.album.clone()));
+                }
+            },
+            UserAlbumsAlbumInput::Adapt(adaptive::Layout::Narrow) => {
+                self.edge_length.set_value(NARROW_EDGE_LENGTH);
+            },
+            UserAlbumsAlbumInput::Adapt(adaptive::Layout::Wide) => {
+                self.edge_length.set_value(WIDE_EDGE_LENGTH);
+            },
+        }
+    }
+}
+
+impl UserAlbumsAlbum {
+    fn refresh(&mut self) {
+        let albums = match self.repo.all() {
+            Ok(albums) => albums,
+            Err(e) => {
+                error!("Failed loading user albums: {}", e);
+                return;
+            }
+        };
+
+        let items: Vec<AlbumGridItem> = {
+            let data = self.state.read();
+            albums.into_iter()
+                .map(|album| {
+                    let cover = album.cover()
+                        .and_then(|cover| data.iter().find(|v| cover.matches(v)))
+                        .cloned();
+
+                    AlbumGridItem {
+                        album,
+                        cover,
+                        edge_length: self.edge_length.clone(),
+                    }
+                })
+                .collect()
+        };
+
+        self.status.set_visible(items.is_empty());
+        self.scrolled.set_visible(!items.is_empty());
+
+        self.album_grid.clear();
+        self.album_grid.extend_from_iter(items);
+
+        // NOTE user albums are sorted by name, so don't scroll to end.
+    }
+}
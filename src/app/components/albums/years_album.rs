@@ -252,6 +252,8 @@ impl YearsAlbum {
             let data = self.state.read();
             data
                 .iter()
+                // Don't use a hidden photo from a stack as the cover.
+                .filter(|x| !x.is_hidden_in_stack())
                 .dedup_by(|x, y| x.year() == y.year())
                 .map(|picture| PhotoGridItem {
                     picture: picture.clone(),
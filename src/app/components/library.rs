@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use fotema_core::{VisualId, YearMonth};
+use fotema_core::user_album::AlbumItem;
 
 use relm4::*;
 use relm4::adw;
@@ -42,14 +43,27 @@ pub enum LibraryInput {
     // Scroll to first photo in year
     GoToYear(i32),
 
-    View(VisualId),
+    View(VisualId, AlbumFilter),
 
     Sort(AlbumSort),
+
+    /// Start selecting photos and videos in the all photos view.
+    SelectionMode,
+
+    /// Add selected photos and videos to a user album.
+    AddToUserAlbum(Vec<AlbumItem>),
+
+    /// Set the location of selected photos and videos.
+    SetLocation(Vec<AlbumItem>),
 }
 
 #[derive(Debug)]
 pub enum LibraryOutput {
-    View(VisualId),
+    View(VisualId, AlbumFilter),
+
+    AddToUserAlbum(Vec<AlbumItem>),
+
+    SetLocation(Vec<AlbumItem>),
 }
 
 
@@ -95,8 +109,12 @@ impl SimpleComponent for Library {
         let all_album = Album::builder()
             .launch((state.clone(), active_view.clone(), ViewName::All, AlbumFilter::All))
             .forward(sender.input_sender(), |msg| match msg {
-                AlbumOutput::Selected(id, _) => LibraryInput::View(id),
+                AlbumOutput::Selected(id, filter) => LibraryInput::View(id, filter),
                 AlbumOutput::ScrollOffset(_) => LibraryInput::Ignore,
+                AlbumOutput::AddToUserAlbum(items) => LibraryInput::AddToUserAlbum(items),
+                AlbumOutput::SetLocation(items) => LibraryInput::SetLocation(items),
+                AlbumOutput::RemoveFromUserAlbum(_, _) => LibraryInput::Ignore,
+                AlbumOutput::SplitEvent(_, _) => LibraryInput::Ignore,
             });
 
         state.subscribe(all_album.sender(), |_| AlbumInput::Refresh);
@@ -163,8 +181,20 @@ impl SimpleComponent for Library {
                 self.months_album.emit(MonthsAlbumInput::Activate);
                 self.months_album.emit(MonthsAlbumInput::GoToYear(year));
             },
-            LibraryInput::View(id) => {
-                let _ = sender.output(LibraryOutput::View(id));
+            LibraryInput::View(id, filter) => {
+                let _ = sender.output(LibraryOutput::View(id, filter));
+            },
+            LibraryInput::SelectionMode => {
+                // Only the all photos view shows individual items.
+                self.stack.set_visible_child_name(LibraryViewName::All.into());
+                self.all_album.emit(AlbumInput::Activate);
+                self.all_album.emit(AlbumInput::SelectionMode(true));
+            },
+            LibraryInput::AddToUserAlbum(items) => {
+                let _ = sender.output(LibraryOutput::AddToUserAlbum(items));
+            },
+            LibraryInput::SetLocation(items) => {
+                let _ = sender.output(LibraryOutput::SetLocation(items));
             },
             LibraryInput::Sort(sort) => {
                 self.all_album.emit(AlbumInput::Sort(sort));
@@ -9,4 +9,5 @@ pub mod library;
 pub mod onboard;
 pub mod progress_monitor;
 pub mod progress_panel;
+pub mod smart_album_editor;
 pub mod viewer;
@@ -19,10 +19,25 @@ use crate::app::{Settings, SettingsState};
 use crate::app::FaceDetectionMode;
 use crate::app::AlbumSort;
 
+use fotema_core::photo::WriteTarget;
+
+use fotema_core::library;
+
 pub struct PreferencesDialog {
     parent: adw::ApplicationWindow,
+
+    /// Library roots known to the database, including roots no longer configured.
+    library_repo: library::Repository,
+
     dialog: adw::PreferencesDialog,
     album_sort: adw::ComboRow,
+    metadata_write_target: adw::ComboRow,
+
+    /// Group listing library roots.
+    library_group: adw::PreferencesGroup,
+
+    /// One row per library root. Rebuilt when settings change.
+    library_rows: Vec<adw::ActionRow>,
 
     settings_state: SettingsState,
 
@@ -35,11 +50,66 @@ impl PreferencesDialog {
         self.settings.face_detection_mode == FaceDetectionMode::On
     }
 
-    pub fn picture_base_dir_name(&self) -> String {
-        self.settings.pictures_base_dir
-            .file_name()
-            .map(|s| String::from(s.to_string_lossy()))
-            .unwrap_or(String::from(""))
+    /// Rebuild library root rows from settings.
+    fn refresh_library_rows(&mut self, sender: &AsyncComponentSender<Self>) {
+        for row in self.library_rows.drain(..) {
+            self.library_group.remove(&row);
+        }
+
+        // Always keep at least one library root.
+        let is_removable = self.settings.library_roots.len() > 1;
+
+        for (index, root) in self.settings.library_roots.iter().enumerate() {
+            let row = adw::ActionRow::builder()
+                .title(&root.name)
+                .subtitle(root.path.to_string_lossy())
+                .build();
+
+            let remove_button = gtk::Button::builder()
+                .valign(gtk::Align::Center)
+                .icon_name("user-trash-symbolic")
+                .tooltip_text(fl!("prefs-library-section-remove-root", "tooltip"))
+                .sensitive(is_removable)
+                .build();
+            remove_button.add_css_class("flat");
+
+            let sender = sender.clone();
+            remove_button.connect_clicked(move |_| sender.input(PreferencesInput::RemoveLibraryRoot(index)));
+
+            row.add_suffix(&remove_button);
+            self.library_group.add(&row);
+            self.library_rows.push(row);
+        }
+    }
+
+    /// Add a library root named after its directory. Names must be unique across
+    /// every root in the database, not just configured roots, so a number is appended
+    /// if another root has the same name. Directories that overlap a configured root
+    /// are rejected with a toast.
+    fn add_library_root(&mut self, path: std::path::PathBuf) -> bool {
+        if self.settings.library_roots.iter().any(|root| root.path == path) {
+            return false;
+        }
+
+        // Overlapping roots would scan the same files twice.
+        if let Some(root) = self.settings.library_roots.iter().find(|root| root.overlaps(&path)) {
+            error!("Library root {:?} overlaps library root {}: {:?}", path, root.name, root.path);
+            let message = fl!("prefs-library-section-overlapping-root", name = root.name.clone());
+            self.dialog.add_toast(adw::Toast::new(&message));
+            return false;
+        }
+
+        let root = match self.library_repo.new_root_config(&path, &self.settings.library_roots) {
+            Ok(root) => root,
+            Err(e) => {
+                error!("Failed naming library root {:?}: {}", path, e);
+                return false;
+            }
+        };
+
+        info!("Adding library root {}: {:?}", root.name, root.path);
+        self.settings.library_roots.push(root);
+        true
     }
 }
 
@@ -58,12 +128,18 @@ pub enum PreferencesInput {
 
     Sort(AlbumSort),
 
-    ChoosePicturesDir,
+    UpdateMetadataWriteTarget(WriteTarget),
+
+    /// Choose directories to add as library roots.
+    AddLibraryRoot,
+
+    /// Remove library root at index.
+    RemoveLibraryRoot(usize),
 }
 
 #[relm4::component(pub async)]
 impl SimpleAsyncComponent for PreferencesDialog {
-    type Init = (SettingsState, adw::ApplicationWindow);
+    type Init = (SettingsState, adw::ApplicationWindow, library::Repository);
     type Input = PreferencesInput;
     type Output = ();
 
@@ -123,29 +199,41 @@ impl SimpleAsyncComponent for PreferencesDialog {
                 },
 
                 add = &adw::PreferencesGroup {
-                    set_title: &fl!("prefs-library-section", "title"),
-                    set_description: Some(&fl!("prefs-library-section", "description")),
+                    set_title: &fl!("prefs-metadata-section"),
+                    set_description: Some(&fl!("prefs-metadata-section", "description")),
 
-                    adw::ActionRow {
-                        set_title: &fl!("prefs-library-section-pictures-dir", "title"),
-
-                        #[watch]
-                        set_subtitle: &model.picture_base_dir_name(),
+                    #[local_ref]
+                    metadata_write_target_row -> adw::ComboRow {
+                        set_title: &fl!("prefs-metadata-write-target"),
+                        set_subtitle: &fl!("prefs-metadata-write-target", "subtitle"),
 
-                        add_suffix = &gtk::Button {
-                            set_valign: gtk::Align::Center,
-                            set_icon_name: "folder-open-symbolic",
-                            set_tooltip_text: Some(&fl!("prefs-library-section-pictures-dir", "tooltip")),
-                            connect_clicked => PreferencesInput::ChoosePicturesDir,
+                        connect_selected_item_notify[sender] => move |row| {
+                            let target = WriteTarget::from_repr(row.selected()).unwrap_or_default();
+                            let _ = sender.input_sender().send(PreferencesInput::UpdateMetadataWriteTarget(target));
                         }
                     }
                 },
+
+                #[local_ref]
+                library_group -> adw::PreferencesGroup {
+                    set_title: &fl!("prefs-library-section", "title"),
+                    set_description: Some(&fl!("prefs-library-section", "description")),
+
+                    #[wrap(Some)]
+                    set_header_suffix = &gtk::Button {
+                        set_valign: gtk::Align::Center,
+                        set_icon_name: "folder-new-symbolic",
+                        set_tooltip_text: Some(&fl!("prefs-library-section-add-root", "tooltip")),
+                        add_css_class: "flat",
+                        connect_clicked => PreferencesInput::AddLibraryRoot,
+                    },
+                },
             }
         }
     }
 
     async fn init(
-        (settings_state, parent): Self::Init,
+        (settings_state, parent, library_repo): Self::Init,
         dialog: Self::Root,
         sender: AsyncComponentSender<Self>,
     ) -> AsyncComponentParts<Self> {
@@ -163,12 +251,25 @@ impl SimpleAsyncComponent for PreferencesDialog {
         ]);
         album_sort_row.set_model(Some(&list));
 
+        let metadata_write_target_row = adw::ComboRow::new();
+        let list = gtk::StringList::new(&[
+            &fl!("prefs-metadata-write-target", "sidecar"),
+            &fl!("prefs-metadata-write-target", "file"),
+        ]);
+        metadata_write_target_row.set_model(Some(&list));
+
+        let library_group = adw::PreferencesGroup::new();
+
         let model = Self {
             settings_state: settings_state.clone(),
             parent,
+            library_repo,
             dialog: dialog.clone(),
             settings: settings_state.read().clone(),
             album_sort: album_sort_row.clone(),
+            metadata_write_target: metadata_write_target_row.clone(),
+            library_group: library_group.clone(),
+            library_rows: Vec::new(),
         };
 
         let widgets = view_output!();
@@ -178,7 +279,7 @@ impl SimpleAsyncComponent for PreferencesDialog {
         AsyncComponentParts { model, widgets }
     }
 
-    async fn update(&mut self, msg: Self::Input, _sender: AsyncComponentSender<Self>) {
+    async fn update(&mut self, msg: Self::Input, sender: AsyncComponentSender<Self>) {
         match msg {
             PreferencesInput::Present => {
                 self.settings = self.settings_state.read().clone();
@@ -194,6 +295,15 @@ impl SimpleAsyncComponent for PreferencesDialog {
                 };
 
                 self.album_sort.set_selected(index);
+
+                let index = match self.settings.metadata_write_target {
+                    WriteTarget::Sidecar => 0,
+                    WriteTarget::File => 1,
+                };
+
+                self.metadata_write_target.set_selected(index);
+
+                self.refresh_library_rows(&sender);
             },
             PreferencesInput::UpdateShowSelfies(show_selfies) => {
                 info!("Update show selfies: {}", show_selfies);
@@ -210,8 +320,20 @@ impl SimpleAsyncComponent for PreferencesDialog {
                 self.settings.album_sort = mode;
                 *self.settings_state.write() = self.settings.clone();
             },
-            PreferencesInput::ChoosePicturesDir => {
-                info!("Presenting select pictures directory file chooser");
+            PreferencesInput::UpdateMetadataWriteTarget(target) => {
+                info!("Update metadata write target: {:?}", target);
+                self.settings.metadata_write_target = target;
+                *self.settings_state.write() = self.settings.clone();
+            },
+            PreferencesInput::RemoveLibraryRoot(index) => {
+                if self.settings.library_roots.len() > 1 && index < self.settings.library_roots.len() {
+                    let root = self.settings.library_roots.remove(index);
+                    info!("Removing library root {}: {:?}", root.name, root.path);
+                    *self.settings_state.write() = self.settings.clone();
+                }
+            },
+            PreferencesInput::AddLibraryRoot => {
+                info!("Presenting add library root file chooser");
                 if let Some(root) = gtk::Widget::root(self.parent.widget_ref()) {
                     let identifier = WindowIdentifier::from_native(&root).await;
                     let request = OpenFileRequest::default()
@@ -223,16 +345,13 @@ impl SimpleAsyncComponent for PreferencesDialog {
                     match request.send().await.and_then(|r| r.response()) {
                         Ok(files) => {
                             info!("Open: {:?}", files);
-                            if let Some(first) = files.uris().first() {
-                                info!("User has chosen picture library at: {:?}", first.path());
-                                if let Some(pictures_base_dir) = files.uris().first().and_then(|uri| uri.to_file_path().ok()) {
-                                    info!("User has chosen picture library at: {:?}", pictures_base_dir);
-                                    if self.settings.pictures_base_dir != pictures_base_dir {
-                                        info!("New pictures base director is: {:?}", pictures_base_dir);
-                                        self.settings.pictures_base_dir = pictures_base_dir;
-                                        *self.settings_state.write() = self.settings.clone();
-                                    }
-                                }
+                            let mut is_changed = false;
+                            for dir in files.uris().iter().flat_map(|uri| uri.to_file_path().ok()) {
+                                info!("User has chosen library root at: {:?}", dir);
+                                is_changed |= self.add_library_root(dir);
+                            }
+                            if is_changed {
+                                *self.settings_state.write() = self.settings.clone();
                             }
                         }
                         Err(err) => {
@@ -17,6 +17,8 @@ pub enum MediaType {
 pub enum TaskName {
     Enrich(MediaType),
     Thumbnail(MediaType),
+    Hash(MediaType),
+    PerceptualHash,
     Transcode,
     MotionPhoto,
     DetectFaces,
@@ -71,6 +71,15 @@ impl SimpleComponent for ProgressPanel {
                         TaskName::Thumbnail(MediaType::Video) => {
                             self.progress_bar.set_text(Some(&fl!("progress-thumbnails-videos")));
                         },
+                        TaskName::Hash(MediaType::Photo) => {
+                            self.progress_bar.set_text(Some(&fl!("progress-hash-photos")));
+                        },
+                        TaskName::Hash(MediaType::Video) => {
+                            self.progress_bar.set_text(Some(&fl!("progress-hash-videos")));
+                        },
+                        TaskName::PerceptualHash => {
+                            self.progress_bar.set_text(Some(&fl!("progress-perceptual-hash-photos")));
+                        },
                         TaskName::Transcode => {
                             self.progress_bar.set_text(Some(&fl!("progress-convert-videos")));
                         },
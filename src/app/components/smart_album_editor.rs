@@ -0,0 +1,470 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use relm4::adw::prelude::*;
+use relm4::gtk;
+use relm4::*;
+
+use chrono::NaiveDate;
+
+use tracing::{error, info};
+
+use crate::fl;
+
+use fotema_core::people;
+use fotema_core::rating;
+use fotema_core::smart_album::{self, MediaType, Query, SmartAlbum, SmartAlbumId};
+
+/// Date used for a date range with no start date.
+fn earliest_date() -> NaiveDate {
+    NaiveDate::from_ymd_opt(1, 1, 1).unwrap()
+}
+
+/// Date used for a date range with no end date.
+fn latest_date() -> NaiveDate {
+    NaiveDate::from_ymd_opt(9999, 12, 31).unwrap()
+}
+
+/// Dialog to create or edit a smart album.
+/// The dialog edits common criteria, combined with "and" or "or". Other criteria,
+/// such as a folder or a place, are kept unchanged when saving.
+pub struct SmartAlbumEditor {
+    repo: smart_album::Repository,
+    people_repo: people::Repository,
+    parent: adw::ApplicationWindow,
+    dialog: adw::Dialog,
+
+    /// Smart album being edited. None if creating a new smart album.
+    smart_album_id: Option<SmartAlbumId>,
+
+    /// People that can be chosen, in same order as person row.
+    people: Vec<people::Person>,
+
+    /// Criteria that can't be edited with the rows of this dialog.
+    other_criteria: Vec<Query>,
+
+    name_row: adw::EntryRow,
+    match_row: adw::ComboRow,
+    person_row: adw::ComboRow,
+    from_row: adw::EntryRow,
+    to_row: adw::EntryRow,
+    media_row: adw::ComboRow,
+    selfie_row: adw::ComboRow,
+    rating_row: adw::ComboRow,
+    favourite_row: adw::SwitchRow,
+    tag_row: adw::EntryRow,
+    other_row: adw::ActionRow,
+}
+
+#[derive(Debug)]
+pub enum SmartAlbumEditorInput {
+    /// Create a new smart album, optionally starting from a query and a name.
+    New(Option<Query>, String),
+
+    /// Edit an existing smart album.
+    Edit(SmartAlbum),
+
+    /// Save smart album and close dialog.
+    Save,
+
+    /// Close dialog without saving.
+    Cancel,
+}
+
+#[derive(Debug)]
+pub enum SmartAlbumEditorOutput {
+    Saved(SmartAlbum),
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for SmartAlbumEditor {
+    type Init = (smart_album::Repository, people::Repository, adw::ApplicationWindow);
+    type Input = SmartAlbumEditorInput;
+    type Output = SmartAlbumEditorOutput;
+
+    view! {
+        adw::Dialog {
+            set_title: &fl!("smart-album-editor", "title"),
+            set_content_width: 420,
+
+            #[wrap(Some)]
+            set_child = &adw::ToolbarView {
+                add_top_bar = &adw::HeaderBar {
+                    set_show_start_title_buttons: false,
+                    set_show_end_title_buttons: false,
+
+                    pack_start = &gtk::Button {
+                        set_label: &fl!("smart-album-editor", "cancel-button"),
+                        connect_clicked => SmartAlbumEditorInput::Cancel,
+                    },
+
+                    pack_end = &gtk::Button {
+                        set_label: &fl!("smart-album-editor", "save-button"),
+                        add_css_class: "suggested-action",
+                        connect_clicked => SmartAlbumEditorInput::Save,
+                    },
+                },
+
+                #[wrap(Some)]
+                set_content = &adw::PreferencesPage {
+                    add = &adw::PreferencesGroup {
+                        #[local_ref]
+                        name_row -> adw::EntryRow {
+                            set_title: &fl!("smart-album-editor", "name"),
+                        },
+
+                        #[local_ref]
+                        match_row -> adw::ComboRow {
+                            set_title: &fl!("smart-album-editor", "match"),
+                        },
+                    },
+
+                    add = &adw::PreferencesGroup {
+                        set_title: &fl!("smart-album-editor", "criteria"),
+
+                        #[local_ref]
+                        person_row -> adw::ComboRow {
+                            set_title: &fl!("smart-album-editor", "person"),
+                        },
+
+                        #[local_ref]
+                        from_row -> adw::EntryRow {
+                            set_title: &fl!("smart-album-editor", "from-date"),
+                        },
+
+                        #[local_ref]
+                        to_row -> adw::EntryRow {
+                            set_title: &fl!("smart-album-editor", "to-date"),
+                        },
+
+                        #[local_ref]
+                        media_row -> adw::ComboRow {
+                            set_title: &fl!("smart-album-editor", "media"),
+                        },
+
+                        #[local_ref]
+                        selfie_row -> adw::ComboRow {
+                            set_title: &fl!("smart-album-editor", "selfies"),
+                        },
+
+                        #[local_ref]
+                        rating_row -> adw::ComboRow {
+                            set_title: &fl!("smart-album-editor", "rating"),
+                        },
+
+                        #[local_ref]
+                        favourite_row -> adw::SwitchRow {
+                            set_title: &fl!("smart-album-editor", "favourite"),
+                        },
+
+                        #[local_ref]
+                        tag_row -> adw::EntryRow {
+                            set_title: &fl!("smart-album-editor", "tag"),
+                        },
+
+                        #[local_ref]
+                        other_row -> adw::ActionRow {
+                            set_visible: false,
+                            set_title: &fl!("smart-album-editor", "other-criteria"),
+                        },
+                    },
+                },
+            },
+        }
+    }
+
+    fn init(
+        (repo, people_repo, parent): Self::Init,
+        dialog: Self::Root,
+        _sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let name_row = adw::EntryRow::new();
+
+        let match_row = adw::ComboRow::new();
+        let list = gtk::StringList::new(&[
+            &fl!("smart-album-editor", "match-all"),
+            &fl!("smart-album-editor", "match-any"),
+        ]);
+        match_row.set_model(Some(&list));
+
+        let person_row = adw::ComboRow::new();
+
+        let from_row = adw::EntryRow::new();
+        let to_row = adw::EntryRow::new();
+
+        let media_row = adw::ComboRow::new();
+        let list = gtk::StringList::new(&[
+            &fl!("smart-album-editor", "any"),
+            &fl!("smart-album-editor", "media-photos"),
+            &fl!("smart-album-editor", "media-videos"),
+            &fl!("smart-album-editor", "media-animated"),
+        ]);
+        media_row.set_model(Some(&list));
+
+        let selfie_row = adw::ComboRow::new();
+        let list = gtk::StringList::new(&[
+            &fl!("smart-album-editor", "any"),
+            &fl!("smart-album-editor", "selfies-only"),
+            &fl!("smart-album-editor", "selfies-exclude"),
+        ]);
+        selfie_row.set_model(Some(&list));
+
+        let rating_row = adw::ComboRow::new();
+        let mut options = vec![fl!("smart-album-editor", "any")];
+        for stars in 1..=rating::MAX_RATING {
+            options.push(fl!("favourites-album-filter", "min-rating", stars = stars));
+        }
+        let options: Vec<&str> = options.iter().map(|x| x.as_str()).collect();
+        rating_row.set_model(Some(&gtk::StringList::new(&options)));
+
+        let favourite_row = adw::SwitchRow::new();
+        let tag_row = adw::EntryRow::new();
+        let other_row = adw::ActionRow::new();
+
+        let model = Self {
+            repo,
+            people_repo,
+            parent,
+            dialog: dialog.clone(),
+            smart_album_id: None,
+            people: Vec::new(),
+            other_criteria: Vec::new(),
+            name_row: name_row.clone(),
+            match_row: match_row.clone(),
+            person_row: person_row.clone(),
+            from_row: from_row.clone(),
+            to_row: to_row.clone(),
+            media_row: media_row.clone(),
+            selfie_row: selfie_row.clone(),
+            rating_row: rating_row.clone(),
+            favourite_row: favourite_row.clone(),
+            tag_row: tag_row.clone(),
+            other_row: other_row.clone(),
+        };
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            SmartAlbumEditorInput::New(query, name) => {
+                info!("Creating smart album");
+                self.smart_album_id = None;
+                self.load(&name, &query.unwrap_or(Query::And(vec![])));
+                self.dialog.present(Some(&self.parent));
+            },
+            SmartAlbumEditorInput::Edit(smart_album) => {
+                info!("Editing smart album {}", smart_album.smart_album_id);
+                self.smart_album_id = Some(smart_album.smart_album_id);
+                self.load(&smart_album.name, &smart_album.query);
+                self.dialog.present(Some(&self.parent));
+            },
+            SmartAlbumEditorInput::Cancel => {
+                self.dialog.close();
+            },
+            SmartAlbumEditorInput::Save => {
+                let name = self.name_row.text().trim().to_string();
+                if name.is_empty() {
+                    self.name_row.add_css_class("error");
+                    return;
+                }
+                self.name_row.remove_css_class("error");
+
+                let Some(query) = self.query() else {
+                    return;
+                };
+
+                let result = match self.smart_album_id {
+                    Some(smart_album_id) => self.repo
+                        .update(smart_album_id, &name, &query)
+                        .map(|_| smart_album_id),
+                    None => self.repo.create(&name, &query),
+                };
+
+                match result {
+                    Ok(smart_album_id) => {
+                        info!("Saved smart album {}: {}", smart_album_id, query);
+                        let smart_album = SmartAlbum {
+                            smart_album_id,
+                            name,
+                            query,
+                        };
+                        let _ = sender.output(SmartAlbumEditorOutput::Saved(smart_album));
+                        self.dialog.close();
+                    },
+                    Err(e) => error!("Failed saving smart album: {}", e),
+                }
+            },
+        }
+    }
+}
+
+impl SmartAlbumEditor {
+    /// Populate rows from a query.
+    fn load(&mut self, name: &str, query: &Query) {
+        self.people = self.people_repo.all_people().unwrap_or_else(|e| {
+            error!("Failed loading people: {}", e);
+            vec![]
+        });
+
+        let mut options = vec![fl!("smart-album-editor", "anyone")];
+        options.extend(self.people.iter().map(|person| person.name.clone()));
+        let options: Vec<&str> = options.iter().map(|x| x.as_str()).collect();
+        self.person_row.set_model(Some(&gtk::StringList::new(&options)));
+
+        self.name_row.set_text(name);
+        self.name_row.remove_css_class("error");
+        self.person_row.set_selected(0);
+        self.from_row.set_text("");
+        self.from_row.remove_css_class("error");
+        self.to_row.set_text("");
+        self.to_row.remove_css_class("error");
+        self.media_row.set_selected(0);
+        self.selfie_row.set_selected(0);
+        self.rating_row.set_selected(0);
+        self.favourite_row.set_active(false);
+        self.tag_row.set_text("");
+        self.other_criteria.clear();
+
+        let (is_all, criteria) = match query {
+            Query::And(criteria) => (true, criteria.clone()),
+            Query::Or(criteria) => (false, criteria.clone()),
+            criterion => (true, vec![criterion.clone()]),
+        };
+
+        self.match_row.set_selected(if is_all { 0 } else { 1 });
+
+        // Each row can only show one criterion, so any repeated criteria are kept as other criteria.
+        for criterion in criteria {
+            let person_index = match criterion {
+                Query::Person(person_id) => self.people.iter().position(|p| p.person_id == person_id),
+                _ => None,
+            };
+
+            match criterion {
+                Query::Person(_) if person_index.is_some() && self.person_row.selected() == 0 => {
+                    self.person_row.set_selected(person_index.map(|x| x as u32 + 1).unwrap_or(0));
+                },
+                Query::DateRange(from, to) if self.from_row.text().is_empty() && self.to_row.text().is_empty() => {
+                    if from != earliest_date() {
+                        self.from_row.set_text(&from.to_string());
+                    }
+                    if to != latest_date() {
+                        self.to_row.set_text(&to.to_string());
+                    }
+                },
+                Query::Media(media_type) if self.media_row.selected() == 0 => {
+                    let index = match media_type {
+                        MediaType::Photo => 1,
+                        MediaType::Video => 2,
+                        MediaType::Motion => 3,
+                    };
+                    self.media_row.set_selected(index);
+                },
+                Query::Selfie if self.selfie_row.selected() == 0 => {
+                    self.selfie_row.set_selected(1);
+                },
+                Query::Not(ref negated) if **negated == Query::Selfie && self.selfie_row.selected() == 0 => {
+                    self.selfie_row.set_selected(2);
+                },
+                Query::MinRating(stars) if self.rating_row.selected() == 0
+                    && (1..=i32::from(rating::MAX_RATING)).contains(&stars) => {
+                    self.rating_row.set_selected(stars as u32);
+                },
+                Query::Favourite if !self.favourite_row.is_active() => {
+                    self.favourite_row.set_active(true);
+                },
+                Query::Tag(ref tag) if self.tag_row.text().is_empty() => {
+                    self.tag_row.set_text(tag);
+                },
+                criterion => self.other_criteria.push(criterion),
+            }
+        }
+
+        self.other_row.set_visible(!self.other_criteria.is_empty());
+        self.other_row.set_subtitle(&fl!("smart-album-editor", "other-criteria-count",
+            count = self.other_criteria.len()));
+    }
+
+    /// Build query from rows. None if a row is invalid.
+    fn query(&self) -> Option<Query> {
+        let mut criteria = Vec::new();
+
+        let person = (self.person_row.selected() as usize)
+            .checked_sub(1)
+            .and_then(|index| self.people.get(index));
+
+        if let Some(person) = person {
+            criteria.push(Query::Person(person.person_id));
+        }
+
+        let from = Self::date(&self.from_row, earliest_date());
+        let to = Self::date(&self.to_row, latest_date());
+        let (Some(from), Some(to)) = (from, to) else {
+            return None;
+        };
+
+        if !self.from_row.text().is_empty() || !self.to_row.text().is_empty() {
+            criteria.push(Query::DateRange(from, to));
+        }
+
+        match self.media_row.selected() {
+            1 => criteria.push(Query::Media(MediaType::Photo)),
+            2 => criteria.push(Query::Media(MediaType::Video)),
+            3 => criteria.push(Query::Media(MediaType::Motion)),
+            _ => {},
+        }
+
+        match self.selfie_row.selected() {
+            1 => criteria.push(Query::Selfie),
+            2 => criteria.push(Query::Not(Box::new(Query::Selfie))),
+            _ => {},
+        }
+
+        let stars = self.rating_row.selected();
+        if stars > 0 {
+            criteria.push(Query::MinRating(stars as i32));
+        }
+
+        if self.favourite_row.is_active() {
+            criteria.push(Query::Favourite);
+        }
+
+        let tag = self.tag_row.text().trim().to_string();
+        if !tag.is_empty() {
+            criteria.push(Query::Tag(tag));
+        }
+
+        criteria.extend(self.other_criteria.iter().cloned());
+
+        if self.match_row.selected() == 0 {
+            Some(Query::And(criteria))
+        } else {
+            Some(Query::Or(criteria))
+        }
+    }
+
+    /// Parse a date row, or use a default date if empty. None if invalid.
+    fn date(row: &adw::EntryRow, default: NaiveDate) -> Option<NaiveDate> {
+        let text = row.text();
+        let text = text.trim();
+        if text.is_empty() {
+            row.remove_css_class("error");
+            return Some(default);
+        }
+
+        match NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+            Ok(date) => {
+                row.remove_css_class("error");
+                Some(date)
+            },
+            Err(_) => {
+                row.add_css_class("error");
+                None
+            },
+        }
+    }
+}
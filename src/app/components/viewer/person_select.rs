@@ -10,18 +10,25 @@ use relm4::prelude::*;
 
 use crate::fl;
 use fotema_core::people;
+use fotema_core::people::ClusterId;
 use fotema_core::FaceId;
 use fotema_core::PersonId;
 
 use tracing::{debug, error};
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug)]
 pub enum PersonSelectInput {
     /// Present person selector for a give face.
     Activate(FaceId, PathBuf),
 
+    /// Present person selector for a group of unknown faces.
+    ActivateCluster(ClusterId, PathBuf),
+
+    /// Present person selector for faces to move from one person to another.
+    ActivateFaces(Vec<FaceId>, PathBuf),
+
     /// Create a new person to associate with a face.
     NewPerson,
 
@@ -57,6 +64,12 @@ pub struct PersonSelect {
 
     /// ID of face to associate with person,
     face_id: Option<FaceId>,
+
+    /// ID of group of unknown faces to associate with person.
+    cluster_id: Option<ClusterId>,
+
+    /// IDs of faces to move to another person.
+    face_ids: Vec<FaceId>,
 }
 
 #[relm4::component(pub async)]
@@ -107,6 +120,7 @@ impl SimpleAsyncComponent for PersonSelect {
             .build();
 
         {
+            let sender = sender.clone();
             let people_list2 = people_list.clone();
             people_list.connect_row_activated(move |_, row| {
                 debug!("activated = {:?}", row);
@@ -124,6 +138,14 @@ impl SimpleAsyncComponent for PersonSelect {
             debug!("selected = {:?}", row);
         });
 
+        {
+            let sender = sender.clone();
+            face_name.connect_activate(move |_| {
+                debug!("Face name entry activated.");
+                sender.input(PersonSelectInput::NewPerson);
+            });
+        }
+
         let widgets = view_output!();
 
         let model = Self {
@@ -133,6 +155,8 @@ impl SimpleAsyncComponent for PersonSelect {
             people_list,
             all_people: vec![],
             face_id: None,
+            cluster_id: None,
+            face_ids: vec![],
         };
 
         AsyncComponentParts { model, widgets }
@@ -142,81 +166,53 @@ impl SimpleAsyncComponent for PersonSelect {
         match msg {
             PersonSelectInput::Activate(face_id, thumbnail) => {
                 debug!("Set person for face {}", face_id);
-
-                self.people_list.remove_all();
-                self.all_people.clear();
-                self.face_name.set_text("");
                 self.face_id = Some(face_id);
-
-                {
-                    let sender = sender.clone();
-                    self.face_name.connect_activate(move |_| {
-                        debug!("Face name entry activated.");
-                        sender.input(PersonSelectInput::NewPerson);
-                    });
-                }
-
-                let img = gdk::Texture::from_filename(&thumbnail).ok();
-                self.avatar.set_custom_image(img.as_ref());
-
-                let people = self.people_repo.all_people().unwrap_or_default();
-
-                for person in people {
-                    let avatar = adw::Avatar::builder()
-                        .size(50)
-                        .name(&person.name)
-                        .build();
-
-                    let img = gdk::Texture::from_filename(&person.thumbnail_path).ok();
-                    avatar.set_custom_image(img.as_ref());
-
-                    let row = adw::ActionRow::builder()
-                        .title(person.name)
-                        .activatable(true)
-                        .build();
-
-                    row.add_prefix(&avatar);
-
-                    {
-                        let sender = sender.clone();
-                        row.connect_activate(move |_| {
-                            sender.input(PersonSelectInput::Associate(person.person_id));
-                        });
-                    }
-
-                    self.people_list.append(&row);
-                    self.all_people.push(person.person_id);
-                }
+                self.cluster_id = None;
+                self.face_ids.clear();
+                self.show_people(&thumbnail, &sender);
+            },
+            PersonSelectInput::ActivateCluster(cluster_id, thumbnail) => {
+                debug!("Set person for cluster {}", cluster_id);
+                self.face_id = None;
+                self.cluster_id = Some(cluster_id);
+                self.face_ids.clear();
+                self.show_people(&thumbnail, &sender);
+            },
+            PersonSelectInput::ActivateFaces(face_ids, thumbnail) => {
+                debug!("Set person for {} faces", face_ids.len());
+                self.face_id = None;
+                self.cluster_id = None;
+                self.face_ids = face_ids;
+                self.show_people(&thumbnail, &sender);
             },
             PersonSelectInput::Associate(person_id) => {
-                if let Some(face_id) = self.face_id {
-                    debug!("Associating face {} with person {}", face_id, person_id);
-                    if let Err(e) = self.people_repo.mark_as_person(face_id, person_id) {
-                        error!("Failed associating face with person: {:?}", e);
-                    }
-                }
-                self.people_list.remove_all();
-                self.all_people.clear();
+                self.associate(person_id);
                 let _ = sender.output(PersonSelectOutput::Done);
             },
             PersonSelectInput::AssociateByIndex(person_id_index) => {
-                if let (Some(face_id), Some(person_id)) = (self.face_id, self.all_people.get(person_id_index)) {
-                    debug!("Associating face {} with person {} by idnex", face_id, person_id);
-                    if let Err(e) = self.people_repo.mark_as_person(face_id, *person_id) {
-                        error!("Failed associating face with person: {:?}", e);
-                    }
+                if let Some(person_id) = self.all_people.get(person_id_index) {
+                    debug!("Associating with person {} by index", person_id);
+                    self.associate(*person_id);
                 }
-                self.people_list.remove_all();
-                self.all_people.clear();
                 let _ = sender.output(PersonSelectOutput::Done);
             },
             PersonSelectInput::NewPerson => {
+                let name = self.face_name.text().to_string();
                 if let Some(face_id) = self.face_id {
                     debug!("Face {} is a new person", face_id);
-                    let name = self.face_name.text().to_string();
                     if let Err(e) = self.people_repo.add_person(face_id, &name) {
                         error!("Failed adding new person: {:?}", e);
                     }
+                } else if let Some(cluster_id) = self.cluster_id {
+                    debug!("Cluster {} is a new person", cluster_id);
+                    if let Err(e) = self.people_repo.add_person_from_cluster(cluster_id, &name) {
+                        error!("Failed adding new person: {:?}", e);
+                    }
+                } else if !self.face_ids.is_empty() {
+                    debug!("{} faces are a new person", self.face_ids.len());
+                    if let Err(e) = self.people_repo.add_person_from_faces(&self.face_ids, &name) {
+                        error!("Failed adding new person: {:?}", e);
+                    }
                 }
                 self.people_list.remove_all();
                 self.all_people.clear();
@@ -225,3 +221,65 @@ impl SimpleAsyncComponent for PersonSelect {
         }
     }
 }
+
+impl PersonSelect {
+    fn show_people(&mut self, thumbnail: &Path, sender: &AsyncComponentSender<Self>) {
+        self.people_list.remove_all();
+        self.all_people.clear();
+        self.face_name.set_text("");
+
+        let img = gdk::Texture::from_filename(thumbnail).ok();
+        self.avatar.set_custom_image(img.as_ref());
+
+        let people = self.people_repo.all_people().unwrap_or_default();
+
+        for person in people {
+            let avatar = adw::Avatar::builder()
+                .size(50)
+                .name(&person.name)
+                .build();
+
+            let img = gdk::Texture::from_filename(&person.thumbnail_path).ok();
+            avatar.set_custom_image(img.as_ref());
+
+            let row = adw::ActionRow::builder()
+                .title(person.name)
+                .activatable(true)
+                .build();
+
+            row.add_prefix(&avatar);
+
+            {
+                let sender = sender.clone();
+                row.connect_activate(move |_| {
+                    sender.input(PersonSelectInput::Associate(person.person_id));
+                });
+            }
+
+            self.people_list.append(&row);
+            self.all_people.push(person.person_id);
+        }
+    }
+
+    /// Associate the face, group of faces, or faces to move with a person.
+    fn associate(&mut self, person_id: PersonId) {
+        if let Some(face_id) = self.face_id {
+            debug!("Associating face {} with person {}", face_id, person_id);
+            if let Err(e) = self.people_repo.mark_as_person(face_id, person_id) {
+                error!("Failed associating face with person: {:?}", e);
+            }
+        } else if let Some(cluster_id) = self.cluster_id {
+            debug!("Associating cluster {} with person {}", cluster_id, person_id);
+            if let Err(e) = self.people_repo.mark_cluster_as_person(cluster_id, person_id) {
+                error!("Failed associating cluster with person: {:?}", e);
+            }
+        } else if !self.face_ids.is_empty() {
+            debug!("Moving {} faces to person {}", self.face_ids.len(), person_id);
+            if let Err(e) = self.people_repo.move_faces(&self.face_ids, person_id) {
+                error!("Failed moving faces to person: {:?}", e);
+            }
+        }
+        self.people_list.remove_all();
+        self.all_people.clear();
+    }
+}
@@ -29,14 +29,30 @@ pub enum ViewInfoInput {
     Photo(VisualId, ImageInfo),
     Video(VisualId),
     OpenFolder,
+
+    /// Rating or favourite of item currently shown has changed.
+    Refresh,
 }
 
 pub struct ViewInfo {
     state: SharedState,
 
     path: Option<PathBuf>,
+
+    /// Item currently shown
+    visual_id: Option<VisualId>,
+
     folder: adw::ActionRow,
     file_name: adw::ActionRow,
+    place: adw::ActionRow,
+
+    // Descriptive metadata, such as title and rating
+    xmp_details: adw::PreferencesGroup,
+    title: adw::ActionRow,
+    description: adw::ActionRow,
+    keywords: adw::ActionRow,
+    rating: adw::ActionRow,
+    people: adw::ActionRow,
 
     // FIXME what timestamps to show for live photos that have an image an a video?
     date_time_details: adw::PreferencesGroup,
@@ -99,6 +115,51 @@ impl SimpleComponent for ViewInfo {
                         add_css_class: "property",
                         set_subtitle_selectable: true,
                     },
+
+                    #[local_ref]
+                    place -> adw::ActionRow {
+                        set_title: &fl!("infobar-place"),
+                        add_css_class: "property",
+                        set_subtitle_selectable: true,
+                    },
+                },
+
+                #[local_ref]
+                xmp_details -> adw::PreferencesGroup {
+                    #[local_ref]
+                    title -> adw::ActionRow {
+                        set_title: &fl!("infobar-title"),
+                        add_css_class: "property",
+                        set_subtitle_selectable: true,
+                    },
+
+                    #[local_ref]
+                    description -> adw::ActionRow {
+                        set_title: &fl!("infobar-description"),
+                        add_css_class: "property",
+                        set_subtitle_selectable: true,
+                    },
+
+                    #[local_ref]
+                    keywords -> adw::ActionRow {
+                        set_title: &fl!("infobar-keywords"),
+                        add_css_class: "property",
+                        set_subtitle_selectable: true,
+                    },
+
+                    #[local_ref]
+                    rating -> adw::ActionRow {
+                        set_title: &fl!("infobar-rating"),
+                        add_css_class: "property",
+                        set_subtitle_selectable: true,
+                    },
+
+                    #[local_ref]
+                    people -> adw::ActionRow {
+                        set_title: &fl!("infobar-people"),
+                        add_css_class: "property",
+                        set_subtitle_selectable: true,
+                    },
                 },
 
                 #[local_ref]
@@ -223,6 +284,14 @@ impl SimpleComponent for ViewInfo {
 
         let folder = adw::ActionRow::new();
         let file_name = adw::ActionRow::new();
+        let place = adw::ActionRow::new();
+
+        let xmp_details = adw::PreferencesGroup::new();
+        let title = adw::ActionRow::new();
+        let description = adw::ActionRow::new();
+        let keywords = adw::ActionRow::new();
+        let rating = adw::ActionRow::new();
+        let people = adw::ActionRow::new();
 
         let date_time_details = adw::PreferencesGroup::new();
         let created_at = adw::ActionRow::new();
@@ -251,7 +320,16 @@ impl SimpleComponent for ViewInfo {
 
             folder: folder.clone(),
             file_name: file_name.clone(),
+            place: place.clone(),
             path: None,
+            visual_id: None,
+
+            xmp_details: xmp_details.clone(),
+            title: title.clone(),
+            description: description.clone(),
+            keywords: keywords.clone(),
+            rating: rating.clone(),
+            people: people.clone(),
 
             date_time_details: date_time_details.clone(),
             created_at: created_at.clone(),
@@ -303,6 +381,7 @@ impl SimpleComponent for ViewInfo {
                 };
 
                 self.video_details.set_visible(false);
+                self.visual_id = Some(vis.visual_id.clone());
 
                 let _ = self.update_file_details(vis.clone());
 
@@ -310,6 +389,20 @@ impl SimpleComponent for ViewInfo {
                     let _ = self.update_photo_details(vis.clone(), image_info);
                 }
             },
+            ViewInfoInput::Refresh => {
+                let Some(ref visual_id) = self.visual_id else {
+                    return;
+                };
+
+                let result = {
+                    let data = self.state.read();
+                    data.iter().find(|&x| x.visual_id == *visual_id).cloned()
+                };
+
+                if let Some(vis) = result {
+                    self.update_descriptive_details(&vis);
+                }
+            },
             ViewInfoInput::Video(ref visual_id) => {
                 let result = {
                     let data = self.state.read();
@@ -323,6 +416,7 @@ impl SimpleComponent for ViewInfo {
 
                 self.image_details.set_visible(false);
                 self.exif_details.set_visible(false);
+                self.visual_id = Some(vis.visual_id.clone());
 
                 let _ = self.update_file_details(vis.clone());
 
@@ -346,8 +440,21 @@ impl ViewInfo {
 
         Self::update_row(&self.folder, vis.folder_name());
         Self::update_row(&self.file_name, path.file_name().map(|x| x.to_string_lossy().to_string()));
+
+        // City, region, and country. Region is skipped if it has the same name as the city.
+        let place = vis.place.as_ref().map(|place| {
+            [Some(&place.city), place.region.as_ref().filter(|r| **r != place.city), Some(&place.country)]
+                .into_iter()
+                .flatten()
+                .cloned()
+                .collect::<Vec<String>>()
+                .join(", ")
+        });
+        Self::update_row(&self.place, place);
         self.path = Some(path.to_path_buf());
 
+        self.update_descriptive_details(&vis);
+
         // FIXME duplicated from Scanner
         let file = fs::File::open(path).map_err(|e| e.to_string())?;
 
@@ -378,6 +485,36 @@ impl ViewInfo {
         Ok(())
     }
 
+    fn update_descriptive_details(&mut self, vis: &fotema_core::visual::Visual) {
+        let keywords = Some(vis.keywords.join(", ")).filter(|x| !x.is_empty());
+
+        let rating = match vis.rating {
+            Some(-1) => Some(fl!("infobar-rating", "rejected")),
+            Some(stars @ 1..=5) => Some("★".repeat(stars as usize) + &"☆".repeat(5 - stars as usize)),
+            _ => None,
+        };
+
+        let people = vis.regions
+            .iter()
+            .filter(|x| x.is_face())
+            .filter_map(|x| x.name.clone())
+            .collect::<Vec<String>>()
+            .join(", ");
+        let people = Some(people).filter(|x| !x.is_empty());
+
+        let has_xmp_details = [
+            Self::update_row(&self.title, vis.title.as_ref()),
+            Self::update_row(&self.description, vis.description.as_ref()),
+            Self::update_row(&self.keywords, keywords),
+            Self::update_row(&self.rating, rating),
+            Self::update_row(&self.people, people),
+        ]
+        .into_iter()
+        .any(|x| x);
+
+        self.xmp_details.set_visible(has_xmp_details);
+    }
+
     fn update_photo_details(&mut self, vis: Arc<fotema_core::visual::Visual>, image_info: &ImageInfo) -> Result<(), String> {
         let Some(ref picture_path) = vis.picture_path else {
             return Err("No picture path".to_string());
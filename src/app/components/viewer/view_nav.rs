@@ -18,6 +18,8 @@ use crate::fl;
 
 use fotema_core::Visual;
 use fotema_core::people;
+use fotema_core::rating;
+use fotema_core::user_album::{AlbumId, AlbumItem};
 use fotema_core::PictureId;
 use fotema_core::VisualId;
 
@@ -38,6 +40,27 @@ relm4::new_stateless_action!(IgnoreUnknownFacesAction, ViewNavActionGroup, "igno
 // Scan file for faces again using the most thorough scan possible.
 relm4::new_stateless_action!(ScanForFacesAction, ViewNavActionGroup, "scan_faces");
 
+// Set star rating. Target is number of stars, with 0 for unrated.
+relm4::new_stateful_action!(RateAction, ViewNavActionGroup, "rate", i32, ());
+
+// Mark or unmark item as a favourite.
+relm4::new_stateless_action!(ToggleFavouriteAction, ViewNavActionGroup, "toggle_favourite");
+
+// Add item to a user album.
+relm4::new_stateless_action!(AddToUserAlbumAction, ViewNavActionGroup, "add_to_album");
+
+// Remove item from the user album being viewed.
+relm4::new_stateless_action!(RemoveFromUserAlbumAction, ViewNavActionGroup, "remove_from_album");
+
+// Show item as the cover of the user album being viewed.
+relm4::new_stateless_action!(SetUserAlbumCoverAction, ViewNavActionGroup, "set_album_cover");
+
+// Move item one place earlier in the user album being viewed.
+relm4::new_stateless_action!(MoveEarlierAction, ViewNavActionGroup, "move_earlier");
+
+// Move item one place later in the user album being viewed.
+relm4::new_stateless_action!(MoveLaterAction, ViewNavActionGroup, "move_later");
+
 #[derive(Debug)]
 pub enum ViewNavInput {
     /// View an item after applying an album filter.
@@ -78,12 +101,34 @@ pub enum ViewNavInput {
 
     /// Scan for more faces.
     ScanForFaces,
+
+    /// Set star rating of item. 0 for unrated.
+    Rate(u8),
+
+    /// Mark or unmark item as a favourite.
+    ToggleFavourite,
+
+    /// Add item to a user album.
+    AddToUserAlbum,
+
+    /// Remove item from the user album being viewed.
+    RemoveFromUserAlbum,
+
+    /// Show item as the cover of the user album being viewed.
+    SetUserAlbumCover,
+
+    /// Move item earlier (negative) or later (positive) in the user album being viewed.
+    Move(isize),
 }
 
 #[derive(Debug)]
 pub enum ViewNavOutput {
     TranscodeAll,
     ScanForFaces(PictureId),
+    AddToUserAlbum(Vec<AlbumItem>),
+    RemoveFromUserAlbum(AlbumId, Vec<AlbumItem>),
+    SetUserAlbumCover(AlbumId, AlbumItem),
+    ReorderUserAlbum(AlbumId, Vec<AlbumItem>),
 }
 
 pub struct ViewNav {
@@ -91,6 +136,8 @@ pub struct ViewNav {
 
     people_repo: people::Repository,
 
+    rating_repo: rating::Repository,
+
     /// Carousel for swiping through items
     carousel: adw::Carousel,
 
@@ -121,7 +168,7 @@ pub struct ViewNav {
 
 #[relm4::component(pub async)]
 impl SimpleAsyncComponent for ViewNav {
-    type Init = (SharedState, Arc<Reducer<ProgressMonitor>>, Arc<adaptive::LayoutState>, people::Repository);
+    type Init = (SharedState, Arc<Reducer<ProgressMonitor>>, Arc<adaptive::LayoutState>, people::Repository, rating::Repository);
     type Input = ViewNavInput;
     type Output = ViewNavOutput;
 
@@ -132,6 +179,35 @@ impl SimpleAsyncComponent for ViewNav {
                 &fl!("viewer-faces-menu", "ignore-unknown") => IgnoreUnknownFacesAction,
                 &fl!("viewer-faces-menu", "scan") => ScanForFacesAction,
             }
+        },
+
+        rating_menu: {
+            section! {
+                &fl!("viewer-rating-menu", "unrated") => RateAction(0),
+                &fl!("viewer-rating-menu", "stars", stars = 1) => RateAction(1),
+                &fl!("viewer-rating-menu", "stars", stars = 2) => RateAction(2),
+                &fl!("viewer-rating-menu", "stars", stars = 3) => RateAction(3),
+                &fl!("viewer-rating-menu", "stars", stars = 4) => RateAction(4),
+                &fl!("viewer-rating-menu", "stars", stars = 5) => RateAction(5),
+            }
+        },
+
+        album_menu: {
+            section! {
+                &fl!("viewer-album-menu", "add") => AddToUserAlbumAction,
+            }
+        },
+
+        user_album_menu: {
+            section! {
+                &fl!("viewer-album-menu", "add") => AddToUserAlbumAction,
+                &fl!("viewer-album-menu", "remove") => RemoveFromUserAlbumAction,
+                &fl!("viewer-album-menu", "set-cover") => SetUserAlbumCoverAction,
+            },
+            section! {
+                &fl!("viewer-album-menu", "move-earlier") => MoveEarlierAction,
+                &fl!("viewer-album-menu", "move-later") => MoveLaterAction,
+            }
         }
     }
 
@@ -141,6 +217,37 @@ impl SimpleAsyncComponent for ViewNav {
                 pack_end = &gtk::Box {
                     set_orientation: gtk::Orientation::Horizontal,
 
+                    gtk::Button {
+                        #[watch]
+                        set_icon_name: if model.is_favourite() { "starred-symbolic" } else { "non-starred-symbolic" },
+                        set_tooltip_text: Some(&fl!("viewer-favourite-tooltip")),
+                        connect_clicked => ViewNavInput::ToggleFavourite,
+                    },
+
+                    gtk::MenuButton {
+                        #[watch]
+                        set_label: &model.rating_label(),
+                        set_tooltip_text: Some(&fl!("viewer-rating-menu", "tooltip")),
+                        set_menu_model: Some(&rating_menu),
+                    },
+
+                    // Menu depends on whether a user album is being viewed.
+                    gtk::MenuButton {
+                        set_icon_name: "folder-pictures-symbolic",
+                        set_tooltip_text: Some(&fl!("viewer-album-menu", "tooltip")),
+                        set_menu_model: Some(&album_menu),
+                        #[watch]
+                        set_visible: !model.is_user_album(),
+                    },
+
+                    gtk::MenuButton {
+                        set_icon_name: "folder-pictures-symbolic",
+                        set_tooltip_text: Some(&fl!("viewer-album-menu", "tooltip")),
+                        set_menu_model: Some(&user_album_menu),
+                        #[watch]
+                        set_visible: model.is_user_album(),
+                    },
+
                     gtk::MenuButton {
                         set_icon_name: "sentiment-very-satisfied-symbolic",
                         set_menu_model: Some(&viewnav_menu),
@@ -226,7 +333,7 @@ impl SimpleAsyncComponent for ViewNav {
     }
 
     async fn init(
-        (state, transcode_progress_monitor, layout_state, people_repo): Self::Init,
+        (state, transcode_progress_monitor, layout_state, people_repo, rating_repo): Self::Init,
         root: Self::Root,
         sender: AsyncComponentSender<Self>,
     ) -> AsyncComponentParts<Self>  {
@@ -269,6 +376,7 @@ impl SimpleAsyncComponent for ViewNav {
         let model = ViewNav {
             state,
             people_repo,
+            rating_repo,
             carousel: carousel.clone(),
             carousel_pages,
             carousel_last_page_index: 0,
@@ -301,12 +409,77 @@ impl SimpleAsyncComponent for ViewNav {
             })
         };
 
+        let rate_action = {
+            let sender = sender.clone();
+            RelmAction::<RateAction>::new_with_target_value(move |_, stars: i32| {
+                if let Ok(stars) = u8::try_from(stars) {
+                    sender.input(ViewNavInput::Rate(stars));
+                }
+            })
+        };
+
+        let toggle_favourite_action = {
+            let sender = sender.clone();
+            RelmAction::<ToggleFavouriteAction>::new_stateless(move |_| {
+                sender.input(ViewNavInput::ToggleFavourite);
+            })
+        };
+
+        let add_to_album_action = {
+            let sender = sender.clone();
+            RelmAction::<AddToUserAlbumAction>::new_stateless(move |_| {
+                sender.input(ViewNavInput::AddToUserAlbum);
+            })
+        };
+
+        let remove_from_album_action = {
+            let sender = sender.clone();
+            RelmAction::<RemoveFromUserAlbumAction>::new_stateless(move |_| {
+                sender.input(ViewNavInput::RemoveFromUserAlbum);
+            })
+        };
+
+        let set_album_cover_action = {
+            let sender = sender.clone();
+            RelmAction::<SetUserAlbumCoverAction>::new_stateless(move |_| {
+                sender.input(ViewNavInput::SetUserAlbumCover);
+            })
+        };
+
+        let move_earlier_action = {
+            let sender = sender.clone();
+            RelmAction::<MoveEarlierAction>::new_stateless(move |_| {
+                sender.input(ViewNavInput::Move(-1));
+            })
+        };
+
+        let move_later_action = {
+            let sender = sender.clone();
+            RelmAction::<MoveLaterAction>::new_stateless(move |_| {
+                sender.input(ViewNavInput::Move(1));
+            })
+        };
+
         let mut actions = RelmActionGroup::<ViewNavActionGroup>::new();
         actions.add_action(restore_action);
         actions.add_action(ignore_unknown_faces_action);
         actions.add_action(scan_faces_action);
+        actions.add_action(rate_action);
+        actions.add_action(toggle_favourite_action);
+        actions.add_action(add_to_album_action);
+        actions.add_action(remove_from_album_action);
+        actions.add_action(set_album_cover_action);
+        actions.add_action(move_earlier_action);
+        actions.add_action(move_later_action);
         actions.register_for_widget(&root);
 
+        // Keyboard shortcuts for rating. Only active when viewing an item.
+        let app = relm4::main_application();
+        for stars in 0..=rating::MAX_RATING {
+            app.set_accels_for_action(&format!("viewnav.rate({})", stars), &[&stars.to_string()]);
+        }
+        app.set_accels_for_action("viewnav.toggle_favourite", &["f"]);
+
         let widgets = view_output!();
         AsyncComponentParts { model, widgets }
     }
@@ -333,6 +506,11 @@ impl SimpleAsyncComponent for ViewNav {
                         .filter(|v| album_filter.clone().filter(v))
                         .cloned()
                         .collect();
+
+                    // User albums are in the order chosen by the user, not in time order.
+                    if let AlbumFilter::UserAlbum(ref user_album) = album_filter {
+                        self.album.sort_by_cached_key(|v| user_album.position(v).unwrap_or(usize::MAX));
+                    }
                 }
 
                 self.album_index = self.album
@@ -588,11 +766,133 @@ impl SimpleAsyncComponent for ViewNav {
                     let _ = sender.output(ViewNavOutput::ScanForFaces(picture_id));
                 }
             },
+            ViewNavInput::Rate(stars) => {
+                let Some(index) = self.album_index else {
+                    return;
+                };
+
+                info!("Rating {} with {} stars", self.album[index].visual_id, stars);
+
+                if let Err(e) = self.rating_repo.set_rating(&self.album[index], stars) {
+                    error!("Failed setting rating: {}", e);
+                    return;
+                }
+
+                let mut visual = (*self.album[index]).clone();
+                visual.rating = Some(stars as i32);
+                self.replace(index, visual);
+            },
+            ViewNavInput::ToggleFavourite => {
+                let Some(index) = self.album_index else {
+                    return;
+                };
+
+                let is_favourite = !self.album[index].is_favourite;
+
+                info!("Marking {} as favourite? {}", self.album[index].visual_id, is_favourite);
+
+                if let Err(e) = self.rating_repo.set_favourite(&self.album[index], is_favourite) {
+                    error!("Failed setting favourite: {}", e);
+                    return;
+                }
+
+                let mut visual = (*self.album[index]).clone();
+                visual.is_favourite = is_favourite;
+                self.replace(index, visual);
+            },
+            ViewNavInput::AddToUserAlbum => {
+                let Some(index) = self.album_index else {
+                    return;
+                };
+
+                let item = AlbumItem::of(&self.album[index]);
+                let _ = sender.output(ViewNavOutput::AddToUserAlbum(vec![item]));
+            },
+            ViewNavInput::RemoveFromUserAlbum => {
+                let (Some(index), AlbumFilter::UserAlbum(ref user_album)) = (self.album_index, &self.album_filter) else {
+                    return;
+                };
+
+                let item = AlbumItem::of(&self.album[index]);
+                let _ = sender.output(ViewNavOutput::RemoveFromUserAlbum(user_album.album_id, vec![item]));
+            },
+            ViewNavInput::SetUserAlbumCover => {
+                let (Some(index), AlbumFilter::UserAlbum(ref user_album)) = (self.album_index, &self.album_filter) else {
+                    return;
+                };
+
+                let item = AlbumItem::of(&self.album[index]);
+                let _ = sender.output(ViewNavOutput::SetUserAlbumCover(user_album.album_id, item));
+            },
+            ViewNavInput::Move(offset) => {
+                let (Some(index), AlbumFilter::UserAlbum(ref user_album)) = (self.album_index, &self.album_filter) else {
+                    return;
+                };
+
+                let visual = self.album[index].clone();
+                let Some(from) = user_album.position(&visual) else {
+                    return;
+                };
+
+                let Some(to) = from.checked_add_signed(offset).filter(|to| *to < user_album.len()) else {
+                    return;
+                };
+
+                let mut user_album = user_album.clone();
+                user_album.items.swap(from, to);
+
+                let _ = sender.output(ViewNavOutput::ReorderUserAlbum(user_album.album_id, user_album.items.clone()));
+
+                // Force album to be rebuilt in the new order.
+                self.album_filter = AlbumFilter::None;
+                sender.input(ViewNavInput::View(visual.visual_id.clone(), AlbumFilter::UserAlbum(user_album)));
+            },
         }
     }
 }
 
 impl ViewNav {
+    fn is_user_album(&self) -> bool {
+        matches!(self.album_filter, AlbumFilter::UserAlbum(_))
+    }
+
+    fn is_favourite(&self) -> bool {
+        self.album_index
+            .and_then(|index| self.album.get(index))
+            .is_some_and(|visual| visual.is_favourite)
+    }
+
+    /// Stars for rating of current item, or a single empty star if unrated.
+    fn rating_label(&self) -> String {
+        let stars = self.album_index
+            .and_then(|index| self.album.get(index))
+            .and_then(|visual| visual.rating)
+            .filter(|rating| *rating > 0)
+            .unwrap_or(0);
+
+        if stars == 0 {
+            "☆".to_string()
+        } else {
+            "★".repeat(stars as usize)
+        }
+    }
+
+    /// Replace an updated item in the album and in the shared state, so that
+    /// albums and the info bar show the change without reloading the library.
+    fn replace(&mut self, index: usize, visual: Visual) {
+        let visual = Arc::new(visual);
+        self.album[index] = visual.clone();
+
+        {
+            let mut items = self.state.write();
+            if let Some(item) = items.iter_mut().find(|x| x.visual_id == visual.visual_id) {
+                *item = visual.clone();
+            }
+        }
+
+        self.view_info.emit(ViewInfoInput::Refresh);
+    }
+
     fn is_left_button_sensitive(&self) -> bool {
         self.album_index.is_some_and(|index| index > 0)
     }
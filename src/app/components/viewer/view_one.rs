@@ -19,6 +19,7 @@ use crate::app::components::progress_monitor::ProgressMonitor;
 use crate::app::components::progress_panel::ProgressPanel;
 use crate::fl;
 use fotema_core::people;
+use fotema_core::photo::raw;
 use super::face_thumbnails::{FaceThumbnails, FaceThumbnailsInput};
 
 use std::sync::Arc;
@@ -350,7 +351,28 @@ impl SimpleAsyncComponent for ViewOne {
                         .unwrap_or(PictureOrientation::North);
                     self.picture.add_css_class(orientation.as_ref());
 
-                    let file = gio::File::for_path(visual_path);
+                    // Glycin can't decode camera RAW files, so show the embedded preview instead.
+                    // The preview file must outlive loading.
+                    let preview_file = if visual.is_raw_only() {
+                        match raw::extract_preview_file(visual_path) {
+                            Ok(preview_file) => Some(preview_file),
+                            Err(e) => {
+                                event!(Level::ERROR, "Failed extracting RAW preview: {:?}", e);
+                                self.broken_status.set_icon_name(Some("sad-computer-symbolic"));
+                                self.broken_status.set_description(Some(&fl!("viewer-error-failed-to-load")));
+                                self.broken_status.set_visible(true);
+                                return;
+                            }
+                        }
+                    } else {
+                        None
+                    };
+
+                    let load_path = preview_file.as_ref()
+                        .map(|x| x.path())
+                        .unwrap_or(visual_path);
+
+                    let file = gio::File::for_path(load_path);
 
                     let mut loader = glycin::Loader::new(file);
                     loader.sandbox_selector(glycin::SandboxSelector::FlatpakSpawn);